@@ -0,0 +1,126 @@
+use crate::{error::{Error, Result}, obu::header::ObuHeader, utils::bits::{BitsReader, FromBitsReader}};
+
+
+
+fn read_size(data: &[u8], position: &mut usize) -> Result<usize> {
+    let mut reader = BitsReader::from(&data[*position..]);
+    let value = reader.read_leb128()?;
+    *position += reader.read_position() >> 3;
+    Ok(value)
+}
+
+fn split<'a>(data: &'a [u8], position: &mut usize, size: usize) -> Result<&'a [u8]> {
+    let available = data.len() - *position;
+    if size > available {
+        return Err(Error::Truncated { needed: size * 8, available: available * 8 });
+    }
+    let res = &data[*position..*position + size];
+    *position += size;
+    Ok(res)
+}
+
+
+/// B.2 Length delimited bitstream syntax, open_bitstream_unit( obu_length )
+pub fn read_obu(data: &[u8]) -> Result<(ObuHeader, &[u8])> {
+    let mut reader = BitsReader::from(data);
+    let mut header = ObuHeader::from_bits_reader(&mut reader)?;
+    let header_len = reader.read_position() >> 3;
+    let available = data.len() - header_len;
+    // obu_length 已经给出了长度，带 size 字段时两者必须一致
+    if !header.obu_has_size_field {
+        header.obu_size = available;
+    }
+    if header.obu_size != available {
+        return Err(Error::Conformance { section: "B.3", reason: "obu_size does not match obu_length" });
+    }
+    let payload = &data[header_len..header_len + header.obu_size];
+    Ok((header, payload))
+}
+
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FrameUnit<'a> {
+    pub obus: Vec<(ObuHeader, &'a [u8])>,
+}
+
+impl<'a> FrameUnit<'a> {
+    /// B.2 frame_unit( sz )
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        let mut obus = Vec::new();
+        let mut position = 0;
+        while position < data.len() {
+            let obu_length = read_size(data, &mut position)?;
+            let obu = split(data, &mut position, obu_length)
+                .map_err(|_| Error::Conformance { section: "B.3", reason: "obu_length exceeds frame_unit_size" })?;
+            obus.push(read_obu(obu)?);
+        }
+        Ok(Self { obus })
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TemporalUnit<'a> {
+    pub frame_units: Vec<FrameUnit<'a>>,
+}
+
+impl<'a> TemporalUnit<'a> {
+    /// B.2 temporal_unit( sz )
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        let mut frame_units = Vec::new();
+        let mut position = 0;
+        while position < data.len() {
+            let frame_unit_size = read_size(data, &mut position)?;
+            let frame_unit = split(data, &mut position, frame_unit_size)
+                .map_err(|_| Error::Conformance { section: "B.3", reason: "frame_unit_size exceeds temporal_unit_size" })?;
+            frame_units.push(FrameUnit::parse(frame_unit)?);
+        }
+        Ok(Self { frame_units })
+    }
+
+    pub fn obus(&self) -> impl Iterator<Item = &(ObuHeader, &'a [u8])> {
+        self.frame_units.iter().flat_map(|f| f.obus.iter())
+    }
+}
+
+
+/// B.2 Length delimited bitstream syntax, yields one temporal unit at a time
+pub struct AnnexBReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> From<&'a [u8]> for AnnexBReader<'a> {
+    fn from(value: &'a [u8]) -> Self {
+        Self { data: value, position: 0, offset: 0, failed: false }
+    }
+}
+
+impl<'a> AnnexBReader<'a> {
+    /// Byte offset of the temporal unit most recently returned, or of the one that failed
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn read_temporal_unit(&mut self) -> Result<TemporalUnit<'a>> {
+        let temporal_unit_size = read_size(self.data, &mut self.position)?;
+        let data = split(self.data, &mut self.position, temporal_unit_size)?;
+        TemporalUnit::parse(data)
+    }
+}
+
+impl<'a> Iterator for AnnexBReader<'a> {
+    type Item = Result<TemporalUnit<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.position >= self.data.len() {
+            return None;
+        }
+        self.offset = self.position;
+        let res = self.read_temporal_unit();
+        self.failed = res.is_err();
+        Some(res)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::error::{Error, Result};
+
+
+
+const IVF_SIGNATURE: &[u8; 4] = b"DKIF";
+const IVF_FOURCC_AV1: &[u8; 4] = b"AV01";
+const IVF_HEADER_SIZE: usize = 32;
+const IVF_FRAME_HEADER_SIZE: usize = 12;
+
+
+fn le_u16(data: &[u8], start: usize) -> u16 {
+    u16::from_le_bytes([data[start], data[start + 1]])
+}
+
+fn le_u32(data: &[u8], start: usize) -> u32 {
+    u32::from_le_bytes(data[start..start + 4].try_into().unwrap())
+}
+
+fn le_u64(data: &[u8], start: usize) -> u64 {
+    u64::from_le_bytes(data[start..start + 8].try_into().unwrap())
+}
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IvfHeader {
+    pub version: u16,
+    pub header_size: u16,
+    pub fourcc: [u8; 4],
+    pub width: u16,
+    pub height: u16,
+    // 时间基，时间戳乘以 timebase_num / timebase_den 得到秒
+    pub timebase_num: u32,
+    pub timebase_den: u32,
+    // 文件头记录的帧数，写入方不一定填对
+    pub frame_count: u32,
+}
+
+impl IvfHeader {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < IVF_HEADER_SIZE {
+            return Err(Error::Truncated { needed: IVF_HEADER_SIZE * 8, available: data.len() * 8 });
+        }
+        if &data[0..4] != IVF_SIGNATURE {
+            return Err(Error::InvalidValue { name: "ivf_signature", value: le_u32(data, 0) });
+        }
+        let header_size = le_u16(data, 6);
+        if (header_size as usize) < IVF_HEADER_SIZE {
+            return Err(Error::InvalidValue { name: "ivf_header_size", value: header_size as u32 });
+        }
+        let fourcc: [u8; 4] = data[8..12].try_into().unwrap();
+        if &fourcc != IVF_FOURCC_AV1 {
+            return Err(Error::InvalidValue { name: "ivf_fourcc", value: le_u32(data, 8) });
+        }
+        Ok(Self {
+            version: le_u16(data, 4),
+            header_size,
+            fourcc,
+            width: le_u16(data, 12),
+            height: le_u16(data, 14),
+            timebase_den: le_u32(data, 16),
+            timebase_num: le_u32(data, 20),
+            frame_count: le_u32(data, 24),
+        })
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IvfFrame<'a> {
+    pub timestamp: u64,
+    // 一个时间单元的 OBU 数据，可直接交给 ObuIter
+    pub data: &'a [u8],
+}
+
+
+pub struct IvfReader<'a> {
+    pub header: IvfHeader,
+    data: &'a [u8],
+    position: usize,
+    failed: bool,
+}
+
+impl<'a> IvfReader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        let header = IvfHeader::parse(data)?;
+        let position = (header.header_size as usize).min(data.len());
+        Ok(Self { header, data, position, failed: false })
+    }
+
+    fn read_frame(&mut self) -> Result<IvfFrame<'a>> {
+        let data = &self.data[self.position..];
+        if data.len() < IVF_FRAME_HEADER_SIZE {
+            return Err(Error::Truncated { needed: IVF_FRAME_HEADER_SIZE * 8, available: data.len() * 8 });
+        }
+        let size = le_u32(data, 0) as usize;
+        let timestamp = le_u64(data, 4);
+        let available = data.len() - IVF_FRAME_HEADER_SIZE;
+        if size > available {
+            return Err(Error::Truncated { needed: size * 8, available: available * 8 });
+        }
+        self.position += IVF_FRAME_HEADER_SIZE + size;
+        Ok(IvfFrame { timestamp, data: &data[IVF_FRAME_HEADER_SIZE..IVF_FRAME_HEADER_SIZE + size] })
+    }
+}
+
+impl<'a> Iterator for IvfReader<'a> {
+    type Item = Result<IvfFrame<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.position >= self.data.len() {
+            return None;
+        }
+        let res = self.read_frame();
+        self.failed = res.is_err();
+        Some(res)
+    }
+}
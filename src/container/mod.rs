@@ -0,0 +1,33 @@
+use crate::{obu::ObuIter, utils::enums::ObuType};
+
+pub mod ivf;
+pub mod annexb;
+
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BitstreamFormat {
+    // 5.2 Low overhead bitstream format
+    Section5,
+    // Annex B Length delimited bitstream format
+    AnnexB,
+}
+
+impl BitstreamFormat {
+    // 码流都以时间分隔符开头，按两种格式分别尝试解析第一个 OBU，时间分隔符没有 payload
+    pub fn detect(data: &[u8]) -> Self {
+        if let Some(Ok((header, _))) = ObuIter::from(data).next()
+            && header.obu_type == ObuType::TemporalDelimiter
+            && header.obu_has_size_field
+            && header.obu_size == 0
+        {
+            return Self::Section5;
+        }
+        if let Some(Ok(tu)) = annexb::AnnexBReader::from(data).next()
+            && tu.obus().next().is_some_and(|(header, _)| header.obu_type == ObuType::TemporalDelimiter)
+        {
+            return Self::AnnexB;
+        }
+        Self::Section5
+    }
+}
@@ -0,0 +1,440 @@
+// ! 7.11.2 Intra prediction process and 7.11.5 chroma from luma
+
+use crate::{
+    decoder::picture::Plane,
+    utils::{
+        consts::{
+            DR_INTRA_DERIVATIVE, INTRA_EDGE_KERNEL, INTRA_FILTER_SCALE_BITS, INTRA_FILTER_TAPS, MODE_TO_ANGLE, SM_WEIGHTS_TX_16X16, SM_WEIGHTS_TX_32X32, SM_WEIGHTS_TX_4X4,
+            SM_WEIGHTS_TX_64X64, SM_WEIGHTS_TX_8X8, TX_HEIGHT_LOG2, TX_WIDTH_LOG2,
+        },
+        enums::{FilterIntraMode, IntraFrameYMode, TxSize},
+    },
+};
+
+
+
+/// Inputs of 7.11.2 for one transform block of an intra block, as worked out by transform_block
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct IntraPrediction {
+    pub plane: u8,
+    pub start_x: u32,
+    pub start_y: u32,
+    pub tx_size: TxSize,
+    // 色度的 UVMode 转成相同编号的 YMode
+    pub mode: IntraFrameYMode,
+    pub angle_delta: i8,
+    pub use_filter_intra: bool,
+    pub filter_intra_mode: FilterIntraMode,
+    pub have_left: bool,
+    pub have_above: bool,
+    pub have_above_right: bool,
+    pub have_below_left: bool,
+    // get_filter_type，上方或左边的块使用 SMOOTH 类预测
+    pub smooth_neighbor: bool,
+    // 平面中 mode info 覆盖的最后一列和最后一行
+    pub max_x: u32,
+    pub max_y: u32,
+    // uv_mode 为 UV_CFL 时在 DC 预测之上加亮度的交流分量
+    pub cfl: Option<ChromaFromLuma>,
+}
+
+/// Inputs of 7.11.5 for a chroma transform block, the luma of the block is taken from the
+/// reconstructed luma plane
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct ChromaFromLuma {
+    // CflAlphaU 或 CflAlphaV
+    pub alpha: i8,
+    pub subsampling_x: bool,
+    pub subsampling_y: bool,
+    // MaxLumaW / MaxLumaH，已解码的亮度变换块的右边界和下边界，超出的部分复制最后的样本
+    pub max_luma_w: u32,
+    pub max_luma_h: u32,
+}
+
+
+// AboveRow 和 LeftCol 允许的最小下标是 -16
+const EDGE_OFFSET: isize = 16;
+
+fn at(edge: &[i32], i: isize) -> i32 {
+    edge[(i + EDGE_OFFSET) as usize]
+}
+
+fn set(edge: &mut [i32], i: isize, value: i32) {
+    edge[(i + EDGE_OFFSET) as usize] = value;
+}
+
+fn round2(x: i32, n: u32) -> i32 {
+    (x + (1 << (n - 1))) >> n
+}
+
+fn round2_signed(x: i32, n: u32) -> i32 {
+    if x >= 0 { round2(x, n) } else { -round2(-x, n) }
+}
+
+fn sm_weights(log2: u32) -> &'static [u32] {
+    match log2 {
+        2 => &SM_WEIGHTS_TX_4X4,
+        3 => &SM_WEIGHTS_TX_8X8,
+        4 => &SM_WEIGHTS_TX_16X16,
+        5 => &SM_WEIGHTS_TX_32X32,
+        _ => &SM_WEIGHTS_TX_64X64,
+    }
+}
+
+/// 7.11.2 Intra prediction process, frame is the plane being reconstructed and must cover the
+/// mode info area of the frame, the output is the w*h prediction in raster order
+pub fn predict_intra(frame: &Plane, block: &IntraPrediction, bit_depth: u8, enable_intra_edge_filter: bool) -> Vec<u16> {
+    let log2w = TX_WIDTH_LOG2[block.tx_size as usize] as u32;
+    let log2h = TX_HEIGHT_LOG2[block.tx_size as usize] as u32;
+    let (w, h) = (1usize << log2w, 1usize << log2h);
+    let (x, y) = (block.start_x as usize, block.start_y as usize);
+    let (max_x, max_y) = (block.max_x as usize, block.max_y as usize);
+    let (have_left, have_above) = (block.have_left, block.have_above);
+    let mid = 1 << (bit_depth - 1);
+
+    // 上采样后最多用到 2 * (w + h) 个样本
+    let len = 2 * (w + h) + 2 * EDGE_OFFSET as usize;
+    let mut above_row = vec![0; len];
+    let mut left_col = vec![0; len];
+    let sample = |x: usize, y: usize| frame.get(x, y) as i32;
+    let above_limit = max_x.min(x + if block.have_above_right { 2 * w } else { w } - 1);
+    let left_limit = max_y.min(y + if block.have_below_left { 2 * h } else { h } - 1);
+    for i in 0..w + h {
+        let above = match (have_above, have_left) {
+            (false, true) => sample(x - 1, y),
+            (false, false) => mid - 1,
+            _ => sample(above_limit.min(x + i), y - 1),
+        };
+        let left = match (have_left, have_above) {
+            (false, true) => sample(x, y - 1),
+            (false, false) => mid + 1,
+            _ => sample(x - 1, left_limit.min(y + i)),
+        };
+        set(&mut above_row, i as isize, above);
+        set(&mut left_col, i as isize, left);
+    }
+    let corner = match (have_above, have_left) {
+        (true, true) => sample(x - 1, y - 1),
+        (true, false) => sample(x, y - 1),
+        (false, true) => sample(x - 1, y),
+        _ => mid,
+    };
+    set(&mut above_row, -1, corner);
+    set(&mut left_col, -1, corner);
+
+    let mode = block.mode as u8;
+    let pred = if block.plane == 0 && block.use_filter_intra {
+        recursive_intra_prediction(&above_row, &left_col, w, h, block.filter_intra_mode, bit_depth)
+    } else if (IntraFrameYMode::V as u8..=IntraFrameYMode::D67 as u8).contains(&mode) {
+        let mut edges = Edges { above_row, left_col, w, h, bit_depth };
+        let p_angle = MODE_TO_ANGLE[mode as usize] + block.angle_delta as i32 * 3;
+        if enable_intra_edge_filter {
+            let max_above = (max_x - x + 1).min(w);
+            let max_left = (max_y - y + 1).min(h);
+            edges.prepare(p_angle, block.smooth_neighbor, have_above, have_left, max_above, max_left)
+        } else {
+            edges.directional(p_angle, false, false)
+        }
+    } else {
+        match block.mode {
+            IntraFrameYMode::Smooth | IntraFrameYMode::SmoothV | IntraFrameYMode::SmoothH => smooth(&above_row, &left_col, log2w, log2h, block.mode),
+            IntraFrameYMode::Dc => dc(&above_row, &left_col, log2w, log2h, have_above, have_left, mid),
+            _ => paeth(&above_row, &left_col, w, h),
+        }
+    };
+    pred.into_iter().map(|p| p as u16).collect()
+}
+
+/// 7.11.5 Predict chroma from luma process, pred is the DC prediction of the chroma block from
+/// predict_intra and receives the scaled AC contribution of the co-located luma samples
+pub fn predict_chroma_from_luma(luma: &Plane, block: &IntraPrediction, cfl: &ChromaFromLuma, pred: &mut [u16], bit_depth: u8) {
+    let log2w = TX_WIDTH_LOG2[block.tx_size as usize] as u32;
+    let log2h = TX_HEIGHT_LOG2[block.tx_size as usize] as u32;
+    let (w, h) = (1usize << log2w, 1usize << log2h);
+    let (sub_x, sub_y) = (cfl.subsampling_x as usize, cfl.subsampling_y as usize);
+    // 超出 MaxLumaW / MaxLumaH 的位置复制最后一组亮度样本
+    let last_x = cfl.max_luma_w as usize - (1 << sub_x);
+    let last_y = cfl.max_luma_h as usize - (1 << sub_y);
+    let mut l = Vec::with_capacity(w * h);
+    for i in 0..h {
+        let luma_y = ((block.start_y as usize + i) << sub_y).min(last_y);
+        for j in 0..w {
+            let luma_x = ((block.start_x as usize + j) << sub_x).min(last_x);
+            let mut t = 0;
+            for dy in 0..=sub_y {
+                for dx in 0..=sub_x {
+                    t += luma.get(luma_x + dx, luma_y + dy) as i32;
+                }
+            }
+            // 统一放大到 8 倍的亮度
+            l.push(t << (3 - sub_x - sub_y));
+        }
+    }
+    let luma_avg = round2(l.iter().sum(), log2w + log2h);
+    let max = (1 << bit_depth) - 1;
+    for (p, v) in pred.iter_mut().zip(l) {
+        let scaled_luma = round2_signed(cfl.alpha as i32 * (v - luma_avg), 6);
+        *p = (*p as i32 + scaled_luma).clamp(0, max) as u16;
+    }
+}
+
+/// 7.11.2.2 Basic intra prediction process
+fn paeth(above_row: &[i32], left_col: &[i32], w: usize, h: usize) -> Vec<i32> {
+    let top_left = at(above_row, -1);
+    let mut pred = vec![0; w * h];
+    for i in 0..h {
+        for j in 0..w {
+            let (above, left) = (at(above_row, j as isize), at(left_col, i as isize));
+            let base = above + left - top_left;
+            let p_left = (base - left).abs();
+            let p_top = (base - above).abs();
+            let p_top_left = (base - top_left).abs();
+            pred[i * w + j] = if p_left <= p_top && p_left <= p_top_left {
+                left
+            } else if p_top <= p_top_left {
+                above
+            } else {
+                top_left
+            };
+        }
+    }
+    pred
+}
+
+/// 7.11.2.3 Recursive intra prediction process, filter intra works on 4x2 blocks
+fn recursive_intra_prediction(above_row: &[i32], left_col: &[i32], w: usize, h: usize, filter_mode: FilterIntraMode, bit_depth: u8) -> Vec<i32> {
+    let max = (1 << bit_depth) - 1;
+    let taps = &INTRA_FILTER_TAPS[filter_mode as usize];
+    let mut pred = vec![0; w * h];
+    for i2 in 0..h >> 1 {
+        for j4 in 0..w >> 2 {
+            let (row, col) = (i2 << 1, j4 << 2);
+            let mut p = [0; 7];
+            for (i, v) in p.iter_mut().enumerate() {
+                *v = if i < 5 {
+                    if i2 == 0 {
+                        at(above_row, (col + i) as isize - 1)
+                    } else if j4 == 0 && i == 0 {
+                        at(left_col, row as isize - 1)
+                    } else {
+                        pred[(row - 1) * w + col + i - 1]
+                    }
+                } else if j4 == 0 {
+                    at(left_col, (row + i - 5) as isize)
+                } else {
+                    pred[(row + i - 5) * w + col - 1]
+                };
+            }
+            for (i, tap) in taps.iter().enumerate() {
+                let pr: i32 = tap.iter().zip(p).map(|(&t, v)| t * v).sum();
+                pred[(row + (i >> 2)) * w + col + (i & 3)] = round2_signed(pr, INTRA_FILTER_SCALE_BITS as u32).clamp(0, max);
+            }
+        }
+    }
+    pred
+}
+
+/// 7.11.2.5 DC intra prediction process
+fn dc(above_row: &[i32], left_col: &[i32], log2w: u32, log2h: u32, have_above: bool, have_left: bool, mid: i32) -> Vec<i32> {
+    let (w, h) = (1 << log2w, 1 << log2h);
+    let sum_above: i32 = (0..w).map(|j| at(above_row, j)).sum();
+    let sum_left: i32 = (0..h).map(|i| at(left_col, i)).sum();
+    let avg = match (have_above, have_left) {
+        (true, true) => (sum_above + sum_left + ((w + h) >> 1) as i32) / (w + h) as i32,
+        (true, false) => (sum_above + (w >> 1) as i32) >> log2w,
+        (false, true) => (sum_left + (h >> 1) as i32) >> log2h,
+        _ => mid,
+    };
+    vec![avg; (w * h) as usize]
+}
+
+/// 7.11.2.6 Smooth intra prediction process
+fn smooth(above_row: &[i32], left_col: &[i32], log2w: u32, log2h: u32, mode: IntraFrameYMode) -> Vec<i32> {
+    let (w, h) = (1usize << log2w, 1usize << log2h);
+    let (weights_x, weights_y) = (sm_weights(log2w), sm_weights(log2h));
+    let bottom = at(left_col, h as isize - 1) as u32;
+    let right = at(above_row, w as isize - 1) as u32;
+    let mut pred = vec![0; w * h];
+    for i in 0..h {
+        for j in 0..w {
+            let (above, left) = (at(above_row, j as isize) as u32, at(left_col, i as isize) as u32);
+            let vertical = weights_y[i] * above + (256 - weights_y[i]) * bottom;
+            let horizontal = weights_x[j] * left + (256 - weights_x[j]) * right;
+            pred[i * w + j] = match mode {
+                IntraFrameYMode::Smooth => (vertical + horizontal + 256) >> 9,
+                IntraFrameYMode::SmoothV => (vertical + 128) >> 8,
+                _ => (horizontal + 128) >> 8,
+            } as i32;
+        }
+    }
+    pred
+}
+
+
+// 7.11.2.9 Intra edge filter strength selection process
+fn edge_filter_strength(w: usize, h: usize, smooth: bool, delta: i32) -> usize {
+    let d = delta.abs();
+    let blk_wh = w + h;
+    let thresholds: &[i32] = match (smooth, blk_wh) {
+        (false, 0..=8) => &[56],
+        (false, 9..=16) => &[40],
+        (false, 17..=24) => &[8, 16, 32],
+        (false, 25..=32) => &[1, 4, 32],
+        (false, _) => &[1, 1, 1],
+        (true, 0..=8) => &[40, 64],
+        (true, 9..=16) => &[20, 48],
+        (true, 17..=24) => &[4, 4, 4],
+        (true, _) => &[1, 1, 1],
+    };
+    thresholds.iter().filter(|&&t| d >= t).count()
+}
+
+// 7.11.2.10 Intra edge upsample selection process
+fn use_upsample(w: usize, h: usize, smooth: bool, delta: i32) -> bool {
+    let d = delta.abs();
+    let blk_wh = w + h;
+    if d <= 0 || d >= 40 {
+        false
+    } else if smooth {
+        blk_wh <= 8
+    } else {
+        blk_wh <= 16
+    }
+}
+
+// 方向预测用到的 AboveRow 和 LeftCol
+struct Edges {
+    above_row: Vec<i32>,
+    left_col: Vec<i32>,
+    w: usize,
+    h: usize,
+    bit_depth: u8,
+}
+
+impl Edges {
+    // 7.11.2.4 中 enable_intra_edge_filter 为 1 时的边缘滤波和上采样
+    fn prepare(&mut self, p_angle: i32, smooth: bool, have_above: bool, have_left: bool, max_above: usize, max_left: usize) -> Vec<i32> {
+        let (w, h) = (self.w, self.h);
+        if p_angle != 90 && p_angle != 180 {
+            if p_angle > 90 && p_angle < 180 && w + h >= 24 {
+                let s = at(&self.left_col, 0) * 5 + at(&self.above_row, -1) * 6 + at(&self.above_row, 0) * 5;
+                let corner = round2(s, 4);
+                set(&mut self.above_row, -1, corner);
+                set(&mut self.left_col, -1, corner);
+            }
+            if have_above {
+                let strength = edge_filter_strength(w, h, smooth, p_angle - 90);
+                let num_px = max_above + if p_angle < 90 { h } else { 0 } + 1;
+                filter_edge(&mut self.above_row, num_px, strength);
+            }
+            if have_left {
+                let strength = edge_filter_strength(w, h, smooth, p_angle - 180);
+                let num_px = max_left + if p_angle > 180 { w } else { 0 } + 1;
+                filter_edge(&mut self.left_col, num_px, strength);
+            }
+        }
+        let upsample_above = use_upsample(w, h, smooth, p_angle - 90);
+        if upsample_above {
+            let num_px = w + if p_angle < 90 { h } else { 0 };
+            upsample_edge(&mut self.above_row, num_px, self.bit_depth);
+        }
+        let upsample_left = use_upsample(w, h, smooth, p_angle - 180);
+        if upsample_left {
+            let num_px = h + if p_angle > 180 { w } else { 0 };
+            upsample_edge(&mut self.left_col, num_px, self.bit_depth);
+        }
+        self.directional(p_angle, upsample_above, upsample_left)
+    }
+
+    /// 7.11.2.4 Directional intra prediction process
+    fn directional(&self, p_angle: i32, upsample_above: bool, upsample_left: bool) -> Vec<i32> {
+        let (w, h) = (self.w, self.h);
+        let (up_a, up_l) = (upsample_above as i32, upsample_left as i32);
+        let dx = if p_angle < 90 {
+            DR_INTRA_DERIVATIVE[p_angle as usize]
+        } else if p_angle > 90 && p_angle < 180 {
+            DR_INTRA_DERIVATIVE[(180 - p_angle) as usize]
+        } else {
+            0
+        };
+        let dy = if p_angle > 90 && p_angle < 180 {
+            DR_INTRA_DERIVATIVE[(p_angle - 90) as usize]
+        } else if p_angle > 180 {
+            DR_INTRA_DERIVATIVE[(270 - p_angle) as usize]
+        } else {
+            0
+        };
+        let interpolate = |edge: &[i32], base: i32, shift: i32| round2(at(edge, base as isize) * (32 - shift) + at(edge, base as isize + 1) * shift, 5);
+        let mut pred = vec![0; w * h];
+        for i in 0..h as i32 {
+            for j in 0..w as i32 {
+                pred[i as usize * w + j as usize] = if p_angle < 90 {
+                    let idx = (i + 1) * dx;
+                    let base = (idx >> (6 - up_a)) + (j << up_a);
+                    let shift = ((idx << up_a) >> 1) & 0x1F;
+                    let max_base_x = ((w + h - 1) as i32) << up_a;
+                    if base < max_base_x {
+                        interpolate(&self.above_row, base, shift)
+                    } else {
+                        at(&self.above_row, max_base_x as isize)
+                    }
+                } else if p_angle > 90 && p_angle < 180 {
+                    let idx = (j << 6) - (i + 1) * dx;
+                    let base = idx >> (6 - up_a);
+                    if base >= -(1 << up_a) {
+                        let shift = ((idx << up_a) >> 1) & 0x1F;
+                        interpolate(&self.above_row, base, shift)
+                    } else {
+                        let idx = (i << 6) - (j + 1) * dy;
+                        let base = idx >> (6 - up_l);
+                        let shift = ((idx << up_l) >> 1) & 0x1F;
+                        interpolate(&self.left_col, base, shift)
+                    }
+                } else if p_angle > 180 {
+                    let idx = (j + 1) * dy;
+                    let base = (idx >> (6 - up_l)) + (i << up_l);
+                    let shift = ((idx << up_l) >> 1) & 0x1F;
+                    interpolate(&self.left_col, base, shift)
+                } else if p_angle == 90 {
+                    at(&self.above_row, j as isize)
+                } else {
+                    at(&self.left_col, i as isize)
+                };
+            }
+        }
+        pred
+    }
+}
+
+// 7.11.2.12 Intra edge filter process，edge[i] 对应 AboveRow[i - 1] 或 LeftCol[i - 1]
+fn filter_edge(buf: &mut [i32], size: usize, strength: usize) {
+    if strength == 0 {
+        return;
+    }
+    let edge: Vec<i32> = (0..size).map(|i| at(buf, i as isize - 1)).collect();
+    for i in 1..size {
+        let s: i32 = INTRA_EDGE_KERNEL[strength - 1]
+            .iter()
+            .enumerate()
+            .map(|(j, &tap)| tap * edge[(i + j).saturating_sub(2).min(size - 1)])
+            .sum();
+        set(buf, i as isize - 1, (s + 8) >> 4);
+    }
+}
+
+// 7.11.2.11 Intra edge upsample process
+fn upsample_edge(buf: &mut [i32], num_px: usize, bit_depth: u8) {
+    let mut dup = vec![0; num_px + 3];
+    dup[0] = at(buf, -1);
+    for i in -1..num_px as isize {
+        dup[(i + 2) as usize] = at(buf, i);
+    }
+    dup[num_px + 2] = at(buf, num_px as isize - 1);
+    set(buf, -2, dup[0]);
+    let max = (1 << bit_depth) - 1;
+    for i in 0..num_px {
+        let s = -dup[i] + 9 * dup[i + 1] + 9 * dup[i + 2] - dup[i + 3];
+        set(buf, 2 * i as isize - 1, round2(s, 4).clamp(0, max));
+        set(buf, 2 * i as isize, dup[i + 2]);
+    }
+}
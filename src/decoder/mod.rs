@@ -0,0 +1,408 @@
+use std::collections::VecDeque;
+
+use crate::{
+    decoder::{intra_pred::IntraPrediction, motion_field::{motion_field_estimation, save_motion_field}, palette::PalettePrediction},
+    entropy::CdfContext,
+    error::{Error, Result},
+    obu::{frame::{FrameHeader, RefFrameState}, header::ObuHeader, metadata::Metadata, sequence::SequenceHeader, tile_group::TileGroup, ObuIter},
+    utils::{bits::{BitsReader, FromBitsReader}, consts::{MI_SIZE, NUM_REF_FRAMES, PRIMARY_REF_NONE}, enums::{FrameRestorationType, FrameType, ObuType}, math::floor_log2},
+};
+
+pub mod picture;
+pub mod intra_pred;
+pub mod mode_info;
+pub mod motion_field;
+pub mod mvpred;
+pub mod palette;
+pub mod quant;
+pub mod recon;
+pub mod tile;
+pub mod transform;
+
+pub use picture::{Picture, Plane};
+pub use mode_info::{ModeInfo, ModeInfoGrid};
+pub use motion_field::MotionFieldMvs;
+pub use mvpred::{MvPredictor, MvStack};
+pub use tile::{residual::TransformBlock, restoration::RestorationUnit, TileDecoder};
+
+
+
+// 正在解码的帧，等待剩余的 tile group
+struct FrameState {
+    header: FrameHeader,
+    picture: Picture,
+    cdf: CdfContext,
+    // context_update_tile_id 对应 tile 结束时的 CDF
+    saved_cdf: Option<CdfContext>,
+    // use_ref_frame_mvs 为 1 时的 7.9 投影结果
+    motion_field: Option<MotionFieldMvs>,
+    mi: ModeInfoGrid,
+    // 所有 tile 的输出，按解码顺序
+    residuals: Vec<TransformBlock>,
+    intra_predictions: Vec<IntraPrediction>,
+    palette_predictions: Vec<PalettePrediction>,
+}
+
+impl FrameState {
+    // 7.11.2 / 7.13.3 CurrFrame 覆盖所有超级块，重建帧内块后裁剪到图像大小
+    fn reconstruct(&mut self, seq: &SequenceHeader) {
+        let sb_size = if seq.use_128x128_superblock { 128 } else { 64 };
+        let width = (self.header.mi_cols as usize * MI_SIZE as usize).next_multiple_of(sb_size);
+        let height = (self.header.mi_rows as usize * MI_SIZE as usize).next_multiple_of(sb_size);
+        let color_config = &seq.color_config;
+        let mid = 1 << (color_config.bit_depth - 1);
+        let mut frame: Vec<_> = (0..self.picture.planes.len()).map(|plane| {
+            let (ss_x, ss_y) = if plane > 0 { (color_config.subsampling_x as usize, color_config.subsampling_y as usize) } else { (0, 0) };
+            Plane::new(width >> ss_x, height >> ss_y, mid)
+        }).collect();
+        recon::reconstruct_intra(&mut frame, &self.intra_predictions, &self.palette_predictions, &self.residuals, color_config.bit_depth, seq.enable_intra_edge_filter);
+        for (dst, src) in self.picture.planes.iter_mut().zip(&frame) {
+            let w = dst.width.min(src.width);
+            for y in 0..dst.height.min(src.height) {
+                dst.data[y * dst.stride..y * dst.stride + w].copy_from_slice(&src.row(y)[..w]);
+            }
+        }
+        self.picture.reconstructed = self.is_complete(seq);
+    }
+
+    // 没有帧间块和 intrabc 块，并且 7.14 - 7.18 的滤波、超分辨率和 film grain 都不改变样本时，
+    // 帧内重建的结果就是最终图像
+    fn is_complete(&self, seq: &SequenceHeader) -> bool {
+        let fh = &self.header;
+        let cdef = &fh.cdef;
+        let no_loop_filter = fh.loop_filter.loop_filter_level == [0; 4] && !fh.delta.delta_lf_present;
+        let no_cdef = !seq.enable_cdef || fh.coded_lossless || fh.allow_intrabc
+            || (cdef.cdef_bits == 0 && [cdef.cdef_y_pri_strength, cdef.cdef_y_sec_strength, cdef.cdef_uv_pri_strength, cdef.cdef_uv_sec_strength].iter().all(|s| s[0] == 0));
+        let no_lr = fh.lr.frame_restoration_type.iter().all(|&t| t == FrameRestorationType::None);
+        let intra_only = (0..fh.mi_rows).all(|row| (0..fh.mi_cols).all(|col| {
+            let info = self.mi.get(row, col);
+            !info.is_inter && !info.use_intrabc
+        }));
+        no_loop_filter && no_cdef && no_lr && !fh.use_superres && !fh.film_grain.apply_grain && intra_only
+    }
+}
+
+
+/// 7.5 Ordering of OBUs, decodes temporal units into pictures
+///
+/// Tiles are decoded and intra blocks reconstructed. Inter prediction, the loop filters, superres
+/// and film grain are not implemented yet, pictures that need them are not marked as reconstructed.
+#[derive(Default)]
+pub struct Decoder {
+    data: Vec<u8>,
+    temporal_unit: Vec<(ObuHeader, Vec<u8>)>,
+    sequence_header: Option<SequenceHeader>,
+    operating_point: usize,
+    // 输出所有空间层，否则每个 temporal unit 只输出最高层
+    all_layers: bool,
+    ref_state: RefFrameState,
+    ref_pictures: [Option<Picture>; NUM_REF_FRAMES],
+    frame: Option<FrameState>,
+    // 这个 temporal unit 里已经有帧解码完成，之后没有帧在解码时的 redundant frame header 是它的副本
+    frame_finished: bool,
+    // 当前 temporal unit 的 metadata，带扩展头时记录 spatial_id
+    metadata: Vec<(Option<u8>, Metadata)>,
+    // 解析失败被跳过的 metadata OBU，由调用者取走
+    metadata_errors: Vec<Error>,
+    // 等待更高空间层的图像，temporal unit 结束时输出
+    pending: Option<Picture>,
+    pictures: VecDeque<Picture>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Operating point to decode, checked against each sequence header
+    pub fn set_operating_point(&mut self, operating_point: usize) {
+        self.operating_point = operating_point;
+    }
+
+    /// Outputs every spatial layer of the operating point instead of only the highest one
+    pub fn set_all_layers(&mut self, all_layers: bool) {
+        self.all_layers = all_layers;
+    }
+
+    pub fn sequence_header(&self) -> Option<&SequenceHeader> {
+        self.sequence_header.as_ref()
+    }
+
+    /// Feeds low overhead format data, chunks may split OBUs anywhere
+    pub fn send_data(&mut self, data: &[u8]) -> Result<()> {
+        self.data.extend_from_slice(data);
+        let mut position = 0;
+        let res = loop {
+            let (header, header_len) = match Self::next_obu(&self.data[position..]) {
+                Ok(Some(obu)) => obu,
+                Ok(None) => break Ok(()),
+                Err(e) => {
+                    // 头部无效，跳过一个字节
+                    position += 1;
+                    break Err(e);
+                }
+            };
+            let start = position + header_len;
+            let payload = self.data[start..start + header.obu_size].to_vec();
+            position = start + header.obu_size;
+            if let Err(e) = self.push_obu(header, payload) {
+                break Err(e);
+            }
+        };
+        // 出错时只丢掉已经处理的部分，剩下的数据留给下一次调用重新同步
+        self.data.drain(..position);
+        res
+    }
+
+    /// Feeds one complete OBU, used for Annex B input
+    pub fn send_obu(&mut self, header: ObuHeader, payload: &[u8]) -> Result<()> {
+        self.push_obu(header, payload.to_vec())
+    }
+
+    /// Decodes everything still buffered, call at the end of the stream
+    pub fn flush(&mut self) -> Result<()> {
+        let data = std::mem::take(&mut self.data);
+        for obu in ObuIter::from(data.as_slice()) {
+            let (header, payload) = obu?;
+            self.push_obu(header, payload.to_vec())?;
+        }
+        self.decode_temporal_unit()
+    }
+
+    /// Errors of the metadata OBUs skipped since the last call, they do not stop decoding
+    pub fn take_metadata_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.metadata_errors)
+    }
+
+    /// Next picture in output order
+    pub fn get_picture(&mut self) -> Option<Picture> {
+        self.pictures.pop_front()
+    }
+
+    // 只有整个 OBU 都已收到才返回，不带 obu_size 的 OBU 留到 flush
+    fn next_obu(data: &[u8]) -> Result<Option<(ObuHeader, usize)>> {
+        let mut reader = BitsReader::from(data);
+        let header = match ObuHeader::from_bits_reader(&mut reader) {
+            Ok(header) => header,
+            Err(Error::Truncated { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let header_len = reader.read_position() >> 3;
+        if !header.obu_has_size_field || header.obu_size > data.len() - header_len {
+            return Ok(None);
+        }
+        Ok(Some((header, header_len)))
+    }
+
+    fn push_obu(&mut self, header: ObuHeader, payload: Vec<u8>) -> Result<()> {
+        // 上一个 temporal unit 解码失败时仍然保留新的时间分隔符
+        let res = if header.obu_type == ObuType::TemporalDelimiter && !self.temporal_unit.is_empty() {
+            self.decode_temporal_unit()
+        } else {
+            Ok(())
+        };
+        self.temporal_unit.push((header, payload));
+        res
+    }
+
+    fn decode_temporal_unit(&mut self) -> Result<()> {
+        let temporal_unit = std::mem::take(&mut self.temporal_unit);
+        for (header, payload) in temporal_unit.iter() {
+            if let Err(e) = self.decode_obu(header, payload) {
+                self.frame = None;
+                self.pending = None;
+                return Err(e);
+            }
+        }
+        if let Some(picture) = self.pending.take() {
+            self.pictures.push_back(picture);
+        }
+        Ok(())
+    }
+
+    fn decode_obu(&mut self, obu: &ObuHeader, payload: &[u8]) -> Result<()> {
+        if self.drop_obu(obu) {
+            return Ok(());
+        }
+        let mut reader = BitsReader::from(payload);
+        match obu.obu_type {
+            ObuType::TemporalDelimiter => {
+                self.frame = None;
+                self.frame_finished = false;
+                self.metadata.clear();
+            }
+            ObuType::SequenceHeader => {
+                let seq = SequenceHeader::from_bits_reader(&mut reader)?;
+                if self.operating_point >= seq.operating_points.len() {
+                    return Err(Error::InvalidValue { name: "operating_point", value: self.operating_point as u32 });
+                }
+                self.sequence_header = Some(seq);
+            }
+            // SeenFrameHeader 为 1 时是 frame_header_copy，直接忽略
+            ObuType::FrameHeader if self.frame.is_none() => {
+                self.frame_header(&mut reader, obu)?;
+            }
+            ObuType::RedundantFrameHeader if self.frame.is_none() && !self.frame_finished => {
+                self.frame_header(&mut reader, obu)?;
+            }
+            ObuType::Frame => {
+                if !self.frame_header(&mut reader, obu)? {
+                    return Err(Error::Conformance { section: "6.8.2", reason: "show_existing_frame in a frame OBU" });
+                }
+                reader.read_alignment()?;
+                self.tile_group(&payload[reader.read_position() >> 3..])?;
+            }
+            ObuType::TileGroup => self.tile_group(payload)?,
+            ObuType::Metadata => {
+                let spatial_id = obu.obu_extension_flag.then_some(obu.spatial_id);
+                // metadata 不影响解码，无效时记下错误后跳过
+                match Metadata::parse(payload) {
+                    Ok(metadata) => self.metadata.push((spatial_id, metadata)),
+                    Err(e) => self.metadata_errors.push(e),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // 返回 false 表示 show_existing_frame，后面没有 tile group
+    fn frame_header(&mut self, reader: &mut BitsReader, obu: &ObuHeader) -> Result<bool> {
+        let Some(seq) = &self.sequence_header else {
+            return Err(Error::Conformance { section: "7.5", reason: "frame header before the sequence header" });
+        };
+        let fh = FrameHeader::from_bits_reader(reader, seq, obu, &mut self.ref_state)?;
+        if fh.show_existing_frame {
+            let Some(picture) = self.ref_pictures[fh.frame_to_show_map_idx as usize].clone() else {
+                return Err(Error::Conformance { section: "6.8.2", reason: "frame_to_show_map_idx refers to an empty slot" });
+            };
+            // 7.21 显示已有的 key frame 时要刷新全部参考帧
+            if fh.frame_type == FrameType::Key {
+                let cdf = self.ref_state.load_cdfs(fh.frame_to_show_map_idx as usize)?;
+                self.finish_frame(&fh, &picture, &cdf, None);
+            }
+            self.output_picture(picture);
+            self.frame_finished = true;
+            return Ok(false);
+        }
+        let picture = Picture::new(&fh, &seq.color_config, obu.temporal_id, obu.spatial_id);
+        let cdf = if fh.primary_ref_frame == PRIMARY_REF_NONE {
+            CdfContext::new(fh.quantization.base_q_idx)
+        } else {
+            self.ref_state.load_cdfs(fh.ref_frame_idx[fh.primary_ref_frame])?
+        };
+        let motion_field = fh.use_ref_frame_mvs.then(|| motion_field_estimation(seq, &fh, &self.ref_state));
+        let mi = ModeInfoGrid::new(fh.mi_rows, fh.mi_cols);
+        self.frame = Some(FrameState {
+            header: fh,
+            picture,
+            cdf,
+            saved_cdf: None,
+            motion_field,
+            mi,
+            residuals: Vec::new(),
+            intra_predictions: Vec::new(),
+            palette_predictions: Vec::new(),
+        });
+        Ok(true)
+    }
+
+    fn tile_group(&mut self, data: &[u8]) -> Result<()> {
+        let Some(frame) = &mut self.frame else {
+            return Err(Error::Conformance { section: "7.5", reason: "tile group without a frame header" });
+        };
+        let Some(seq) = &self.sequence_header else {
+            return Err(Error::Conformance { section: "7.5", reason: "frame header before the sequence header" });
+        };
+        let tile_group = TileGroup::parse(data, &frame.header)?;
+        for tile in tile_group.tiles.iter() {
+            // 每个 tile 从帧的 CDF 开始解码
+            let mut td = TileDecoder::new(seq, &frame.header, tile, frame.cdf.clone(), &mut frame.mi)?;
+            if !frame.header.frame_is_intra {
+                td.set_ref_frames(&self.ref_state);
+            }
+            if let Some(motion_field) = &frame.motion_field {
+                td.set_motion_field(motion_field);
+            }
+            td.decode_tile()?;
+            frame.residuals.extend_from_slice(td.residuals());
+            frame.intra_predictions.extend_from_slice(td.intra_predictions());
+            frame.palette_predictions.extend_from_slice(td.palette_predictions());
+            let cdf = td.finish()?;
+            if tile.tile_num == frame.header.tile_info.context_update_tile_id {
+                frame.saved_cdf = Some(cdf);
+            }
+        }
+        if tile_group.is_last(&frame.header) {
+            let mut frame = self.frame.take().unwrap();
+            frame.reconstruct(seq);
+            // 7.4 frame_end_update_cdf
+            if !frame.header.disable_frame_end_update_cdf && let Some(mut cdf) = frame.saved_cdf.take() {
+                cdf.reset_counters();
+                frame.cdf = cdf;
+            }
+            self.finish_frame(&frame.header, &frame.picture, &frame.cdf, Some(&frame.mi));
+            if frame.header.show_frame {
+                self.output_picture(frame.picture);
+            }
+            self.frame_finished = true;
+        }
+        Ok(())
+    }
+
+    // 7.5 不在所选 operating point 里的层直接丢弃
+    fn drop_obu(&self, obu: &ObuHeader) -> bool {
+        let Some(seq) = &self.sequence_header else {
+            return false;
+        };
+        let idc = seq.operating_point_idc(self.operating_point);
+        if obu.obu_type == ObuType::SequenceHeader || obu.obu_type == ObuType::TemporalDelimiter || idc == 0 || !obu.obu_extension_flag {
+            return false;
+        }
+        let in_temporal_layer = (idc >> obu.temporal_id) & 1 == 1;
+        let in_spatial_layer = (idc >> (obu.spatial_id + 8)) & 1 == 1;
+        !in_temporal_layer || !in_spatial_layer
+    }
+
+    fn output_picture(&mut self, mut picture: Picture) {
+        picture.metadata = self.metadata.iter()
+            .filter(|(spatial_id, _)| spatial_id.is_none_or(|id| id == picture.spatial_id))
+            .map(|(_, metadata)| metadata.clone())
+            .collect();
+        let spatial_layers = self.sequence_header.as_ref()
+            .map_or(0, |seq| seq.operating_point_idc(self.operating_point) >> 8);
+        if self.all_layers || spatial_layers == 0 || picture.spatial_id >= floor_log2(spatial_layers) {
+            self.pending = None;
+            self.pictures.push_back(picture);
+        } else {
+            // 缺少更高层时输出这个 temporal unit 里最高的一层
+            self.pending = Some(picture);
+        }
+    }
+
+    /// 7.20 Reference frame update process, mi is None for show_existing_frame
+    fn finish_frame(&mut self, fh: &FrameHeader, picture: &Picture, cdf: &CdfContext, mi: Option<&ModeInfoGrid>) {
+        let segment_ids = mi.map(|mi| self.segment_ids(fh, mi));
+        self.ref_state.update(fh);
+        for (i, slot) in self.ref_pictures.iter_mut().enumerate() {
+            if (fh.refresh_frame_flags >> i) & 1 == 1 {
+                *slot = Some(picture.clone());
+                self.ref_state.save_cdfs(i, cdf);
+            }
+        }
+        if let (Some(seq), Some(mi), Some(segment_ids)) = (&self.sequence_header, mi, segment_ids) {
+            save_motion_field(seq, fh, mi, &mut self.ref_state);
+            self.ref_state.save_segment_ids(fh, &segment_ids);
+        }
+    }
+
+    // 7.4 SegmentIds，不更新 segmentation map 时沿用 PrevSegmentIds
+    fn segment_ids(&self, fh: &FrameHeader, mi: &ModeInfoGrid) -> Vec<u8> {
+        let seg = &fh.segmentation;
+        if seg.segmentation_enabled && !seg.segmentation_update_map {
+            let len = (fh.mi_rows * fh.mi_cols) as usize;
+            return self.ref_state.load_previous_segment_ids(fh).map_or_else(|| vec![0; len], <[u8]>::to_vec);
+        }
+        (0..fh.mi_rows).flat_map(|row| (0..fh.mi_cols).map(move |col| mi.get(row, col).segment_id)).collect()
+    }
+}
@@ -0,0 +1,104 @@
+use crate::utils::{consts::FRAME_LF_COUNT, enums::{CompoundType, FilterIntraMode, InterMode, InterintraMode, Interpolationfilter, IntraFrameYMode, MaskType, MotionMode, SubSize, TxSize, UVMode}};
+
+
+
+/// Mode info of one block, copied into every 4x4 unit the block covers
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ModeInfo {
+    pub mi_size: SubSize,
+    pub segment_id: u8,
+    pub skip: bool,
+    pub skip_mode: bool,
+    pub is_inter: bool,
+    pub use_intrabc: bool,
+    pub y_mode: IntraFrameYMode,
+    pub uv_mode: UVMode,
+    pub angle_delta_y: i8,
+    pub angle_delta_uv: i8,
+    pub cfl_alpha_u: i8,
+    pub cfl_alpha_v: i8,
+    pub use_filter_intra: bool,
+    pub filter_intra_mode: FilterIntraMode,
+    pub palette_size_y: u8,
+    pub palette_size_uv: u8,
+    pub palette_colors_y: [u16; 8],
+    pub palette_colors_u: [u16; 8],
+    pub palette_colors_v: [u16; 8],
+    // 以下为 inter 块的信息，ref_frame 按 RefFrame 取值，NONE_FRAME 表示没有第二个参考帧
+    pub ref_frame: [i8; 2],
+    pub mv: [[i32; 2]; 2],
+    pub inter_mode: InterMode,
+    pub ref_mv_idx: u8,
+    pub seg_id_predicted: bool,
+    pub interp_filter: [Interpolationfilter; 2],
+    pub motion_mode: MotionMode,
+    pub interintra: bool,
+    pub interintra_mode: InterintraMode,
+    pub wedge_interintra: bool,
+    pub wedge_index: u8,
+    pub wedge_sign: u8,
+    pub mask_type: MaskType,
+    pub compound_type: CompoundType,
+    pub comp_group_idx: u8,
+    pub compound_idx: u8,
+    pub tx_size: TxSize,
+    // InterTxSizes，每个 4x4 可以不同
+    pub inter_tx_size: TxSize,
+    // 解码这个块时的 CurrentQIndex 和 DeltaLF
+    pub qindex: u8,
+    pub delta_lf: [i8; FRAME_LF_COUNT],
+}
+
+
+/// Per 4x4 mode info of a frame plus cdef_idx of every 64x64 block
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModeInfoGrid {
+    pub mi_rows: u32,
+    pub mi_cols: u32,
+    data: Vec<ModeInfo>,
+    // 本帧已经解码过的 4x4
+    decoded: Vec<bool>,
+    // -1 表示还没有读到
+    cdef_idx: Vec<i8>,
+}
+
+impl ModeInfoGrid {
+    pub fn new(mi_rows: u32, mi_cols: u32) -> Self {
+        let cdef_len = (mi_rows.div_ceil(16) * mi_cols.div_ceil(16)) as usize;
+        Self { mi_rows, mi_cols, data: vec![ModeInfo::default(); (mi_rows * mi_cols) as usize], decoded: vec![false; (mi_rows * mi_cols) as usize], cdef_idx: vec![-1; cdef_len] }
+    }
+
+    pub fn get(&self, row: u32, col: u32) -> &ModeInfo {
+        &self.data[(row * self.mi_cols + col) as usize]
+    }
+
+    pub fn get_mut(&mut self, row: u32, col: u32) -> &mut ModeInfo {
+        &mut self.data[(row * self.mi_cols + col) as usize]
+    }
+
+    // 块可能超出帧的右边和下边，只保存帧内的部分
+    pub fn fill(&mut self, row: u32, col: u32, bw4: u32, bh4: u32, info: &ModeInfo) {
+        for y in row..(row + bh4).min(self.mi_rows) {
+            for x in col..(col + bw4).min(self.mi_cols) {
+                *self.get_mut(y, x) = info.clone();
+                self.decoded[(y * self.mi_cols + x) as usize] = true;
+            }
+        }
+    }
+
+    pub fn is_decoded(&self, row: u32, col: u32) -> bool {
+        self.decoded[(row * self.mi_cols + col) as usize]
+    }
+
+    /// cdef_idx of the 64x64 block containing the 4x4 unit
+    pub fn cdef_idx(&self, row: u32, col: u32) -> i8 {
+        self.cdef_idx[((row >> 4) * self.mi_cols.div_ceil(16) + (col >> 4)) as usize]
+    }
+
+    pub fn set_cdef_idx(&mut self, row: u32, col: u32, value: i8) {
+        if row < self.mi_rows && col < self.mi_cols {
+            let stride = self.mi_cols.div_ceil(16);
+            self.cdef_idx[((row >> 4) * stride + (col >> 4)) as usize] = value;
+        }
+    }
+}
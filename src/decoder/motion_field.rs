@@ -0,0 +1,178 @@
+// ! 7.9 Motion field estimation process and the 7.19 motion vectors saved for it
+
+use crate::{
+    decoder::mode_info::ModeInfoGrid,
+    obu::{frame::{FrameHeader, RefFrameState}, sequence::SequenceHeader},
+    utils::{
+        consts::{DIV_MULT, MAX_FRAME_DISTANCE, MAX_OFFSET_HEIGHT, MAX_OFFSET_WIDTH, MFMV_STACK_SIZE, MI_SIZE_LOG2, NONE_FRAME, REFMVS_LIMIT, TOTAL_REFS_PER_FRAME},
+        enums::{FrameType, RefFrame},
+        math::{clip3, round2signed},
+    },
+};
+
+
+
+const INTRA_FRAME: i8 = RefFrame::Intra as i8;
+
+// MotionFieldMvs 中表示无效的值
+const INVALID_MV: i32 = -(1 << 15);
+
+
+/// MotionFieldMvs, the projected motion of every 8x8 unit towards each reference frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotionFieldMvs {
+    pub rows: u32,
+    pub cols: u32,
+    // 按 LAST_FRAME..=ALTREF_FRAME 分开保存
+    mvs: Vec<[i32; 2]>,
+}
+
+impl MotionFieldMvs {
+    /// Every unit starts out invalid
+    pub fn new(mi_rows: u32, mi_cols: u32) -> Self {
+        let (rows, cols) = (mi_rows >> 1, mi_cols >> 1);
+        let len = (TOTAL_REFS_PER_FRAME as u32 - 1) * rows * cols;
+        Self { rows, cols, mvs: vec![[INVALID_MV, 0]; len as usize] }
+    }
+
+    fn index(&self, ref_frame: i8, row: u32, col: u32) -> usize {
+        (((ref_frame as u32 - 1) * self.rows + row) * self.cols + col) as usize
+    }
+
+    pub fn get(&self, ref_frame: i8, row: u32, col: u32) -> Option<[i32; 2]> {
+        let mv = self.mvs[self.index(ref_frame, row, col)];
+        (mv[0] != INVALID_MV).then_some(mv)
+    }
+
+    pub fn set(&mut self, ref_frame: i8, row: u32, col: u32, mv: Option<[i32; 2]>) {
+        let idx = self.index(ref_frame, row, col);
+        self.mvs[idx] = mv.unwrap_or([INVALID_MV, 0]);
+    }
+}
+
+
+
+
+/// 7.19 Motion field motion vector storage process, keeps one reference and motion vector per 8x8 in
+/// the slots refreshed by the frame
+pub fn save_motion_field(seq: &SequenceHeader, fh: &FrameHeader, mi: &ModeInfoGrid, refs: &mut RefFrameState) {
+    let (rows, cols) = (fh.mi_rows >> 1, fh.mi_cols >> 1);
+    let mut ref_frames = vec![NONE_FRAME; (rows * cols) as usize];
+    let mut mvs = vec![[0; 2]; (rows * cols) as usize];
+    let limit = REFMVS_LIMIT as i32;
+    for y8 in 0..rows {
+        for x8 in 0..cols {
+            // 每个 8x8 取右下角的 4x4
+            let info = mi.get(2 * y8 + 1, 2 * x8 + 1);
+            let idx = (y8 * cols + x8) as usize;
+            for list in 0..2 {
+                let r = info.ref_frame[list];
+                if r <= INTRA_FRAME {
+                    continue;
+                }
+                // 只保存指向过去帧的运动
+                if seq.get_relative_dist(fh.order_hints[r as usize], fh.order_hint) >= 0 {
+                    continue;
+                }
+                let mv = info.mv[list];
+                if mv[0].abs() <= limit && mv[1].abs() <= limit {
+                    ref_frames[idx] = r;
+                    mvs[idx] = mv;
+                }
+            }
+        }
+    }
+    refs.save_motion_field(fh, &ref_frames, &mvs);
+}
+
+
+/// 7.9 Motion field estimation process, every unit is invalid unless use_ref_frame_mvs is set
+pub fn motion_field_estimation(seq: &SequenceHeader, fh: &FrameHeader, refs: &RefFrameState) -> MotionFieldMvs {
+    let mut mf = MotionFieldMvs::new(fh.mi_rows, fh.mi_cols);
+    if !fh.use_ref_frame_mvs {
+        return mf;
+    }
+    let last_idx = fh.ref_frame_idx[0];
+    let cur_gold_hint = fh.order_hints[RefFrame::Golden as usize];
+    let last_alt_hint = refs.slots[last_idx].saved_order_hints[RefFrame::Altref as usize];
+    // LAST 是 ALTREF 的 overlay 时不投影
+    if last_alt_hint != cur_gold_hint {
+        projection(&mut mf, seq, fh, refs, RefFrame::Last, -1);
+    }
+    let mut ref_stamp = MFMV_STACK_SIZE as i32 - 2;
+    for src in [RefFrame::Bwdref, RefFrame::Altref2, RefFrame::Altref] {
+        if seq.get_relative_dist(fh.order_hints[src as usize], fh.order_hint) > 0
+            && (src != RefFrame::Altref || ref_stamp >= 0)
+            && projection(&mut mf, seq, fh, refs, src, 1)
+        {
+            ref_stamp -= 1;
+        }
+    }
+    if ref_stamp >= 0 {
+        projection(&mut mf, seq, fh, refs, RefFrame::Last2, -1);
+    }
+    mf
+}
+
+/// 7.9.2 Projection process, false when the reference can not be projected
+fn projection(mf: &mut MotionFieldMvs, seq: &SequenceHeader, fh: &FrameHeader, refs: &RefFrameState, src: RefFrame, dst_sign: i32) -> bool {
+    let src_idx = fh.ref_frame_idx[src as usize - 1];
+    let slot = &refs.slots[src_idx];
+    let (h8, w8) = (fh.mi_rows >> 1, fh.mi_cols >> 1);
+    if slot.mi_rows != fh.mi_rows || slot.mi_cols != fh.mi_cols
+        || matches!(slot.frame_type, FrameType::Key | FrameType::Intra)
+        || slot.saved_ref_frames.len() != (h8 * w8) as usize
+    {
+        return false;
+    }
+    let max_distance = MAX_FRAME_DISTANCE as i32;
+    let ref_to_cur = seq.get_relative_dist(fh.order_hints[src as usize], fh.order_hint);
+    for y8 in 0..h8 {
+        for x8 in 0..w8 {
+            let idx = (y8 * w8 + x8) as usize;
+            let src_ref = slot.saved_ref_frames[idx];
+            if src_ref <= INTRA_FRAME {
+                continue;
+            }
+            let ref_offset = seq.get_relative_dist(fh.order_hints[src as usize], slot.saved_order_hints[src_ref as usize]);
+            if ref_to_cur.abs() > max_distance || ref_offset.abs() > max_distance || ref_offset <= 0 {
+                continue;
+            }
+            let mv = slot.saved_mvs[idx];
+            let proj_mv = get_mv_projection(mv, ref_to_cur * dst_sign, ref_offset);
+            let pos_y8 = project(y8 as i32, proj_mv[0], dst_sign, h8 as i32, MAX_OFFSET_HEIGHT as i32);
+            let pos_x8 = project(x8 as i32, proj_mv[1], dst_sign, w8 as i32, MAX_OFFSET_WIDTH as i32);
+            let (Some(pos_y8), Some(pos_x8)) = (pos_y8, pos_x8) else {
+                continue;
+            };
+            for dst in RefFrame::Last as i8..=RefFrame::Altref as i8 {
+                let ref_to_dst = seq.get_relative_dist(fh.order_hint, fh.order_hints[dst as usize]);
+                mf.set(dst, pos_y8, pos_x8, Some(get_mv_projection(mv, ref_to_dst, ref_offset)));
+            }
+        }
+    }
+    true
+}
+
+/// 7.9.3 Get MV projection process
+pub fn get_mv_projection(mv: [i32; 2], numerator: i32, denominator: i32) -> [i32; 2] {
+    let max_distance = MAX_FRAME_DISTANCE as i32;
+    let clipped_denominator = denominator.min(max_distance);
+    let clipped_numerator = clip3(-max_distance, max_distance, numerator);
+    mv.map(|v| {
+        let scaled = round2signed(v * clipped_numerator * DIV_MULT[clipped_denominator as usize], 14);
+        clip3(-(1 << 14) + 1, (1 << 14) - 1, scaled)
+    })
+}
+
+/// 7.9.4 Project process, the 8x8 position the motion lands on if it stays close enough
+fn project(v8: i32, delta: i32, dst_sign: i32, max8: i32, max_off8: i32) -> Option<u32> {
+    let base8 = (v8 >> 3) << 3;
+    let shift = 3 + 1 + MI_SIZE_LOG2;
+    let offset8 = if delta >= 0 { delta >> shift } else { -((-delta) >> shift) };
+    let v8 = v8 + dst_sign * offset8;
+    if v8 < 0 || v8 >= max8 || v8 < base8 - max_off8 || v8 >= base8 + 8 + max_off8 {
+        return None;
+    }
+    Some(v8 as u32)
+}
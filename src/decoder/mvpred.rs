@@ -0,0 +1,540 @@
+// ! 7.10.2 Find MV stack process, builds the motion vector candidates of an inter block from the mode info grid
+
+use crate::{
+    decoder::{mode_info::{ModeInfo, ModeInfoGrid}, motion_field::MotionFieldMvs},
+    obu::frame::FrameHeader,
+    utils::{
+        consts::{BLOCK_HEIGHT, BLOCK_WIDTH, IDENTITY, MAX_REF_MV_STACK_SIZE, MI_SIZE, MV_BORDER, NUM_4X4_BLOCKS_HIGH, NUM_4X4_BLOCKS_WIDE, REF_CAT_LEVEL, TRANSLATION, WARPEDMODEL_PREC_BITS},
+        enums::{InterMode, RefFrame, SubSize},
+        math::{clip3, round2signed},
+    },
+};
+
+
+
+const INTRA_FRAME: i8 = RefFrame::Intra as i8;
+
+/// Output of 7.10.2 find_mv_stack used by the inter syntax
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MvStack {
+    pub num_mv_found: u8,
+    pub new_mv_context: u8,
+    pub ref_mv_context: u8,
+    pub zero_mv_context: u8,
+    pub drl_ctx_stack: [u8; MAX_REF_MV_STACK_SIZE as usize],
+    pub ref_stack_mv: [[[i32; 2]; 2]; MAX_REF_MV_STACK_SIZE as usize],
+    pub weight_stack: [u32; MAX_REF_MV_STACK_SIZE as usize],
+    pub global_mvs: [[i32; 2]; 2],
+}
+
+
+/// Position, size and references of the block whose candidates are searched
+#[derive(Debug, Clone, Copy)]
+pub struct MvBlock {
+    pub mi_row: u32,
+    pub mi_col: u32,
+    pub mi_size: SubSize,
+    pub ref_frame: [i8; 2],
+}
+
+
+/// Everything 7.10.2 reads besides the block itself
+pub struct MvPredictor<'a> {
+    pub fh: &'a FrameHeader,
+    pub mi: &'a ModeInfoGrid,
+    /// 7.9 output, None when use_ref_frame_mvs is off or no projection was made
+    pub motion_field: Option<&'a MotionFieldMvs>,
+    /// MiRowStart, MiRowEnd, MiColStart, MiColEnd of the tile
+    pub tile: [u32; 4],
+}
+
+impl MvPredictor<'_> {
+    /// 7.10.2 Find MV stack process
+    pub fn find_mv_stack(&self, block: &MvBlock) -> MvStack {
+        let mut s = Search::new(self, block);
+        s.run();
+        s.stack
+    }
+
+    /// 7.10.2.1 Setup global mv process
+    pub fn setup_global_mv(&self, block: &MvBlock, ref_list: usize) -> [i32; 2] {
+        let ref_frame = block.ref_frame[ref_list];
+        let gm = &self.fh.global_motion;
+        let gm_type = if ref_frame > INTRA_FRAME { gm.gm_type[ref_frame as usize] } else { IDENTITY };
+        let mut mv = if gm_type == IDENTITY {
+            [0, 0]
+        } else if gm_type == TRANSLATION {
+            let params = &gm.gm_params[ref_frame as usize];
+            [params[0] >> (WARPEDMODEL_PREC_BITS - 3), params[1] >> (WARPEDMODEL_PREC_BITS - 3)]
+        } else {
+            let params = &gm.gm_params[ref_frame as usize];
+            let mi_size = block.mi_size as usize;
+            let x = (block.mi_col * MI_SIZE as u32) as i32 + BLOCK_WIDTH[mi_size] as i32 / 2 - 1;
+            let y = (block.mi_row * MI_SIZE as u32) as i32 + BLOCK_HEIGHT[mi_size] as i32 / 2 - 1;
+            let xc = (params[2] - (1 << WARPEDMODEL_PREC_BITS)) * x + params[3] * y + params[0];
+            let yc = params[4] * x + (params[5] - (1 << WARPEDMODEL_PREC_BITS)) * y + params[1];
+            if self.fh.allow_high_precision_mv {
+                [round2signed(yc, WARPEDMODEL_PREC_BITS - 3), round2signed(xc, WARPEDMODEL_PREC_BITS - 3)]
+            } else {
+                [round2signed(yc, WARPEDMODEL_PREC_BITS - 2) * 2, round2signed(xc, WARPEDMODEL_PREC_BITS - 2) * 2]
+            }
+        };
+        lower_mv_precision(self.fh, &mut mv);
+        mv
+    }
+
+    // is_inside，位置是否在当前 tile 内
+    fn is_inside(&self, cand_r: i64, cand_c: i64) -> bool {
+        let [row_start, row_end, col_start, col_end] = self.tile.map(i64::from);
+        cand_c >= col_start && cand_c < col_end && cand_r >= row_start && cand_r < row_end
+    }
+}
+
+
+/// 7.10.2.10 Lower precision process
+pub fn lower_mv_precision(fh: &FrameHeader, cand_mv: &mut [i32; 2]) {
+    if fh.allow_high_precision_mv {
+        return;
+    }
+    for v in cand_mv.iter_mut() {
+        if fh.force_integer_mv {
+            let a_int = (v.abs() + 3) >> 3;
+            *v = if *v > 0 { a_int << 3 } else { -(a_int << 3) };
+        } else if *v & 1 == 1 {
+            *v += if *v > 0 { -1 } else { 1 };
+        }
+    }
+}
+
+
+// find_mv_stack 过程中的临时变量
+struct Search<'p, 'a> {
+    p: &'p MvPredictor<'a>,
+    b: MvBlock,
+    is_compound: bool,
+    bw4: u32,
+    bh4: u32,
+    stack: MvStack,
+    new_mv_count: u8,
+    found_match: bool,
+    // extra_search 的 RefIdMvs / RefDiffMvs
+    ref_id_mvs: [Vec<[i32; 2]>; 2],
+    ref_diff_mvs: [Vec<[i32; 2]>; 2],
+}
+
+impl<'p, 'a> Search<'p, 'a> {
+    fn new(p: &'p MvPredictor<'a>, b: &MvBlock) -> Self {
+        Self {
+            p,
+            b: *b,
+            is_compound: b.ref_frame[1] > INTRA_FRAME,
+            bw4: NUM_4X4_BLOCKS_WIDE[b.mi_size as usize] as u32,
+            bh4: NUM_4X4_BLOCKS_HIGH[b.mi_size as usize] as u32,
+            stack: MvStack::default(),
+            new_mv_count: 0,
+            found_match: false,
+            ref_id_mvs: Default::default(),
+            ref_diff_mvs: Default::default(),
+        }
+    }
+
+    fn run(&mut self) {
+        self.stack.global_mvs[0] = self.p.setup_global_mv(&self.b, 0);
+        if self.is_compound {
+            self.stack.global_mvs[1] = self.p.setup_global_mv(&self.b, 1);
+        }
+        // 最近的一行、一列和右上角
+        self.found_match = false;
+        self.scan_row(-1);
+        let mut found_above_match = self.found_match;
+        self.found_match = false;
+        self.scan_col(-1);
+        let mut found_left_match = self.found_match;
+        self.found_match = false;
+        if self.bw4.max(self.bh4) <= 16 {
+            self.scan_point(-1, self.bw4 as i64);
+        }
+        found_above_match |= self.found_match;
+        let close_matches = found_above_match as u8 + found_left_match as u8;
+        let num_nearest = self.stack.num_mv_found as usize;
+        let num_new = self.new_mv_count;
+        for weight in &mut self.stack.weight_stack[..num_nearest] {
+            *weight += REF_CAT_LEVEL as u32;
+        }
+        self.stack.zero_mv_context = 0;
+        if self.p.fh.use_ref_frame_mvs {
+            self.temporal_scan();
+        }
+        // 外圈
+        self.found_match = false;
+        self.scan_point(-1, -1);
+        found_above_match |= self.found_match;
+        self.found_match = false;
+        self.scan_row(-3);
+        found_above_match |= self.found_match;
+        self.found_match = false;
+        self.scan_col(-3);
+        found_left_match |= self.found_match;
+        self.found_match = false;
+        if self.bh4 > 1 {
+            self.scan_row(-5);
+        }
+        found_above_match |= self.found_match;
+        self.found_match = false;
+        if self.bw4 > 1 {
+            self.scan_col(-5);
+        }
+        found_left_match |= self.found_match;
+        let total_matches = found_above_match as u8 + found_left_match as u8;
+        self.sorting(0, num_nearest);
+        self.sorting(num_nearest, self.stack.num_mv_found as usize);
+        if self.stack.num_mv_found < 2 {
+            self.extra_search();
+        }
+        self.context_and_clamping(num_new, close_matches, total_matches);
+    }
+
+    /// 7.10.2.2 Scan row process
+    fn scan_row(&mut self, delta_row: i64) {
+        let (bw4, mi_row, mi_col) = (self.bw4, self.b.mi_row as i64, self.b.mi_col as i64);
+        let end4 = bw4.min(self.p.fh.mi_cols - self.b.mi_col).min(16);
+        let mut delta_row = delta_row;
+        let mut delta_col = 0;
+        let use_step16 = bw4 >= 16;
+        if delta_row.abs() > 1 {
+            delta_row += mi_row & 1;
+            delta_col = 1 - (mi_col & 1);
+        }
+        let mut i = 0;
+        while i < end4 {
+            let mv_row = mi_row + delta_row;
+            let mv_col = mi_col + delta_col + i as i64;
+            if !self.p.is_inside(mv_row, mv_col) {
+                break;
+            }
+            let cand = self.p.mi.get(mv_row as u32, mv_col as u32);
+            let mut len = bw4.min(NUM_4X4_BLOCKS_WIDE[cand.mi_size as usize] as u32);
+            if delta_row.abs() > 1 {
+                len = len.max(2);
+            }
+            if use_step16 {
+                len = len.max(4);
+            }
+            self.add_ref_mv_candidate(cand, len * 2);
+            i += len;
+        }
+    }
+
+    /// 7.10.2.3 Scan col process
+    fn scan_col(&mut self, delta_col: i64) {
+        let (bh4, mi_row, mi_col) = (self.bh4, self.b.mi_row as i64, self.b.mi_col as i64);
+        let end4 = bh4.min(self.p.fh.mi_rows - self.b.mi_row).min(16);
+        let mut delta_col = delta_col;
+        let mut delta_row = 0;
+        let use_step16 = bh4 >= 16;
+        if delta_col.abs() > 1 {
+            delta_row = 1 - (mi_row & 1);
+            delta_col += mi_col & 1;
+        }
+        let mut i = 0;
+        while i < end4 {
+            let mv_row = mi_row + delta_row + i as i64;
+            let mv_col = mi_col + delta_col;
+            if !self.p.is_inside(mv_row, mv_col) {
+                break;
+            }
+            let cand = self.p.mi.get(mv_row as u32, mv_col as u32);
+            let mut len = bh4.min(NUM_4X4_BLOCKS_HIGH[cand.mi_size as usize] as u32);
+            if delta_col.abs() > 1 {
+                len = len.max(2);
+            }
+            if use_step16 {
+                len = len.max(4);
+            }
+            self.add_ref_mv_candidate(cand, len * 2);
+            i += len;
+        }
+    }
+
+    /// 7.10.2.4 Scan point process
+    fn scan_point(&mut self, delta_row: i64, delta_col: i64) {
+        let mv_row = self.b.mi_row as i64 + delta_row;
+        let mv_col = self.b.mi_col as i64 + delta_col;
+        // 右上角可能还没有解码
+        if self.p.is_inside(mv_row, mv_col) && self.p.mi.is_decoded(mv_row as u32, mv_col as u32) {
+            let cand = self.p.mi.get(mv_row as u32, mv_col as u32);
+            self.add_ref_mv_candidate(cand, 4);
+        }
+    }
+
+    /// 7.10.2.5 Temporal scan process
+    fn temporal_scan(&mut self) {
+        let (bw4, bh4) = (self.bw4 as i64, self.bh4 as i64);
+        let step_w4 = if bw4 >= 16 { 4 } else { 2 };
+        let step_h4 = if bh4 >= 16 { 4 } else { 2 };
+        for delta_row in (0..bh4.min(16)).step_by(step_h4) {
+            for delta_col in (0..bw4.min(16)).step_by(step_w4) {
+                self.add_tpl_ref_mv(delta_row, delta_col);
+            }
+        }
+        let allow_extension = (2..16).contains(&bh4) && (2..16).contains(&bw4);
+        if allow_extension {
+            let tpl_sample_pos = [[bh4, -2], [bh4, bw4], [bh4 - 2, bw4]];
+            for [delta_row, delta_col] in tpl_sample_pos {
+                if self.check_sb_border(delta_row, delta_col) {
+                    self.add_tpl_ref_mv(delta_row, delta_col);
+                }
+            }
+        }
+    }
+
+    /// 7.10.2.6 Temporal sample process
+    fn add_tpl_ref_mv(&mut self, delta_row: i64, delta_col: i64) {
+        let mv_row = (self.b.mi_row as i64 + delta_row) | 1;
+        let mv_col = (self.b.mi_col as i64 + delta_col) | 1;
+        if !self.p.is_inside(mv_row, mv_col) {
+            return;
+        }
+        let (y8, x8) = ((mv_row >> 1) as u32, (mv_col >> 1) as u32);
+        let at_origin = delta_row == 0 && delta_col == 0;
+        if at_origin {
+            self.stack.zero_mv_context = 1;
+        }
+        let lists = 1 + self.is_compound as usize;
+        let mut cand_mvs = [[0; 2]; 2];
+        for (list, cand_mv) in cand_mvs.iter_mut().enumerate().take(lists) {
+            let ref_frame = self.b.ref_frame[list];
+            let Some(mut mv) = self.p.motion_field.and_then(|mf| mf.get(ref_frame, y8, x8)) else {
+                return;
+            };
+            lower_mv_precision(self.p.fh, &mut mv);
+            *cand_mv = mv;
+        }
+        if at_origin {
+            let global_mvs = self.stack.global_mvs;
+            let far = (0..lists).any(|list| (0..2).any(|comp| (cand_mvs[list][comp] - global_mvs[list][comp]).abs() >= 16));
+            self.stack.zero_mv_context = far as u8;
+        }
+        let num = self.stack.num_mv_found as usize;
+        let matches = |stack_mv: &[[i32; 2]; 2]| (0..lists).all(|list| stack_mv[list] == cand_mvs[list]);
+        if let Some(idx) = self.stack.ref_stack_mv[..num].iter().position(matches) {
+            self.stack.weight_stack[idx] += 2;
+        } else if num < MAX_REF_MV_STACK_SIZE as usize {
+            self.stack.ref_stack_mv[num] = cand_mvs;
+            self.stack.weight_stack[num] = 2;
+            self.stack.num_mv_found += 1;
+        }
+    }
+
+    // check_sb_border，时域候选不能超出当前 64x64
+    fn check_sb_border(&self, delta_row: i64, delta_col: i64) -> bool {
+        let row = (self.b.mi_row & 15) as i64 + delta_row;
+        let col = (self.b.mi_col & 15) as i64 + delta_col;
+        (0..16).contains(&row) && (0..16).contains(&col)
+    }
+
+    /// 7.10.2.7 Add reference motion vector process
+    fn add_ref_mv_candidate(&mut self, cand: &ModeInfo, weight: u32) {
+        if !cand.is_inter {
+            return;
+        }
+        if !self.is_compound {
+            for cand_list in 0..2 {
+                if cand.ref_frame[cand_list] == self.b.ref_frame[0] {
+                    self.search_stack(cand, cand_list, weight);
+                }
+            }
+        } else if cand.ref_frame == self.b.ref_frame {
+            self.compound_search_stack(cand, weight);
+        }
+    }
+
+    // 候选块是否使用全局运动，这时用 GlobalMvs 代替它保存的 mv
+    fn uses_global_mv(&self, cand: &ModeInfo, ref_list: usize) -> bool {
+        let cand_size = cand.mi_size as usize;
+        let large = BLOCK_WIDTH[cand_size].min(BLOCK_HEIGHT[cand_size]) >= 8;
+        let ref_frame = self.b.ref_frame[ref_list] as usize;
+        matches!(cand.inter_mode, InterMode::GlobalMv | InterMode::GlobalGlobalMv)
+            && self.p.fh.global_motion.gm_type[ref_frame] > TRANSLATION
+            && large
+    }
+
+    /// 7.10.2.8 Search stack process
+    fn search_stack(&mut self, cand: &ModeInfo, cand_list: usize, weight: u32) {
+        let mut cand_mv = if self.uses_global_mv(cand, 0) { self.stack.global_mvs[0] } else { cand.mv[cand_list] };
+        lower_mv_precision(self.p.fh, &mut cand_mv);
+        if cand.inter_mode.has_newmv() {
+            self.new_mv_count += 1;
+        }
+        self.found_match = true;
+        let num = self.stack.num_mv_found as usize;
+        if let Some(idx) = self.stack.ref_stack_mv[..num].iter().position(|mvs| mvs[0] == cand_mv) {
+            self.stack.weight_stack[idx] += weight;
+        } else if num < MAX_REF_MV_STACK_SIZE as usize {
+            self.stack.ref_stack_mv[num][0] = cand_mv;
+            self.stack.weight_stack[num] = weight;
+            self.stack.num_mv_found += 1;
+        }
+    }
+
+    /// 7.10.2.9 Compound search stack process
+    fn compound_search_stack(&mut self, cand: &ModeInfo, weight: u32) {
+        let mut cand_mvs = cand.mv;
+        for (list, cand_mv) in cand_mvs.iter_mut().enumerate() {
+            if self.uses_global_mv(cand, list) {
+                *cand_mv = self.stack.global_mvs[list];
+            }
+            lower_mv_precision(self.p.fh, cand_mv);
+        }
+        self.found_match = true;
+        let num = self.stack.num_mv_found as usize;
+        if let Some(idx) = self.stack.ref_stack_mv[..num].iter().position(|mvs| *mvs == cand_mvs) {
+            self.stack.weight_stack[idx] += weight;
+        } else if num < MAX_REF_MV_STACK_SIZE as usize {
+            self.stack.ref_stack_mv[num] = cand_mvs;
+            self.stack.weight_stack[num] = weight;
+            self.stack.num_mv_found += 1;
+        }
+        if cand.inter_mode.has_newmv() {
+            self.new_mv_count += 1;
+        }
+    }
+
+    /// 7.10.2.11 Sorting process, stable and by descending weight
+    fn sorting(&mut self, start: usize, end: usize) {
+        let mut end = end;
+        while end > start {
+            let mut new_end = start;
+            for idx in start + 1..end {
+                if self.stack.weight_stack[idx - 1] < self.stack.weight_stack[idx] {
+                    self.stack.weight_stack.swap(idx - 1, idx);
+                    self.stack.ref_stack_mv.swap(idx - 1, idx);
+                    new_end = idx;
+                }
+            }
+            end = new_end;
+        }
+    }
+
+    /// 7.10.2.12 Extra search process
+    fn extra_search(&mut self) {
+        let w4 = self.bw4.min(16).min(self.p.fh.mi_cols - self.b.mi_col);
+        let h4 = self.bh4.min(16).min(self.p.fh.mi_rows - self.b.mi_row);
+        let num4x4 = w4.min(h4);
+        let (mi_row, mi_col) = (self.b.mi_row as i64, self.b.mi_col as i64);
+        for pass in 0..2 {
+            let mut idx = 0;
+            while idx < num4x4 && self.stack.num_mv_found < 2 {
+                let (mv_row, mv_col) = if pass == 0 { (mi_row - 1, mi_col + idx as i64) } else { (mi_row + idx as i64, mi_col - 1) };
+                if !self.p.is_inside(mv_row, mv_col) {
+                    break;
+                }
+                let cand = self.p.mi.get(mv_row as u32, mv_col as u32);
+                self.add_extra_mv_candidate(cand);
+                idx += if pass == 0 { NUM_4X4_BLOCKS_WIDE[cand.mi_size as usize] } else { NUM_4X4_BLOCKS_HIGH[cand.mi_size as usize] } as u32;
+            }
+        }
+        if self.is_compound {
+            let mut combined_mvs = [[[0; 2]; 2]; 2];
+            for (list, global_mv) in self.stack.global_mvs.iter().enumerate() {
+                let cands = self.ref_id_mvs[list].iter().chain(&self.ref_diff_mvs[list]).chain([global_mv; 2]);
+                for (comp_count, mv) in cands.take(2).enumerate() {
+                    combined_mvs[comp_count][list] = *mv;
+                }
+            }
+            let num = self.stack.num_mv_found as usize;
+            if num == 1 {
+                self.stack.ref_stack_mv[1] = if combined_mvs[0] == self.stack.ref_stack_mv[0] { combined_mvs[1] } else { combined_mvs[0] };
+                self.stack.weight_stack[1] = 2;
+            } else {
+                for (idx, mvs) in combined_mvs.into_iter().enumerate() {
+                    self.stack.ref_stack_mv[idx] = mvs;
+                    self.stack.weight_stack[idx] = 2;
+                }
+            }
+            self.stack.num_mv_found = 2;
+        } else {
+            // 不增加 NumMvFound，只给 NEARESTMV / NEARMV 提供 GlobalMvs
+            for idx in self.stack.num_mv_found as usize..2 {
+                self.stack.ref_stack_mv[idx][0] = self.stack.global_mvs[0];
+            }
+        }
+    }
+
+    /// 7.10.2.13 Add extra mv candidate process
+    fn add_extra_mv_candidate(&mut self, cand: &ModeInfo) {
+        let sign_bias = &self.p.fh.ref_frame_sign_bias;
+        for cand_list in 0..2 {
+            let cand_ref = cand.ref_frame[cand_list];
+            if cand_ref <= INTRA_FRAME {
+                continue;
+            }
+            if self.is_compound {
+                for list in 0..2 {
+                    let mut cand_mv = cand.mv[cand_list];
+                    let ref_frame = self.b.ref_frame[list];
+                    if cand_ref == ref_frame && self.ref_id_mvs[list].len() < 2 {
+                        self.ref_id_mvs[list].push(cand_mv);
+                    } else if self.ref_diff_mvs[list].len() < 2 {
+                        if sign_bias[cand_ref as usize] != sign_bias[ref_frame as usize] {
+                            cand_mv = cand_mv.map(|v| -v);
+                        }
+                        self.ref_diff_mvs[list].push(cand_mv);
+                    }
+                }
+            } else {
+                let mut cand_mv = cand.mv[cand_list];
+                if sign_bias[cand_ref as usize] != sign_bias[self.b.ref_frame[0] as usize] {
+                    cand_mv = cand_mv.map(|v| -v);
+                }
+                let num = self.stack.num_mv_found as usize;
+                if !self.stack.ref_stack_mv[..num].iter().any(|mvs| mvs[0] == cand_mv) {
+                    self.stack.ref_stack_mv[num][0] = cand_mv;
+                    self.stack.weight_stack[num] = 2;
+                    self.stack.num_mv_found += 1;
+                }
+            }
+        }
+    }
+
+    /// 7.10.2.14 Context and clamping process
+    fn context_and_clamping(&mut self, num_new: u8, close_matches: u8, total_matches: u8) {
+        let num = self.stack.num_mv_found as usize;
+        let level = REF_CAT_LEVEL as u32;
+        for idx in 0..num.saturating_sub(1) {
+            let (w0, w1) = (self.stack.weight_stack[idx], self.stack.weight_stack[idx + 1]);
+            self.stack.drl_ctx_stack[idx] = if w0 >= level && w1 < level {
+                1
+            } else if w0 < level && w1 < level {
+                2
+            } else {
+                0
+            };
+        }
+        let fh = self.p.fh;
+        let (mi_row, mi_col) = (self.b.mi_row as i32, self.b.mi_col as i32);
+        let (bw4, bh4) = (self.bw4 as i32, self.bh4 as i32);
+        let mi_size = MI_SIZE as i32;
+        // clamp_mv_row / clamp_mv_col
+        let row_border = MV_BORDER as i32 + bh4 * 4 * 8;
+        let col_border = MV_BORDER as i32 + bw4 * 4 * 8;
+        let mb_to_top_edge = -(mi_row * mi_size * 8);
+        let mb_to_bottom_edge = (fh.mi_rows as i32 - bh4 - mi_row) * mi_size * 8;
+        let mb_to_left_edge = -(mi_col * mi_size * 8);
+        let mb_to_right_edge = (fh.mi_cols as i32 - bw4 - mi_col) * mi_size * 8;
+        for list in 0..1 + self.is_compound as usize {
+            for mvs in &mut self.stack.ref_stack_mv[..num] {
+                let mv = &mut mvs[list];
+                mv[0] = clip3(mb_to_top_edge - row_border, mb_to_bottom_edge + row_border, mv[0]);
+                mv[1] = clip3(mb_to_left_edge - col_border, mb_to_right_edge + col_border, mv[1]);
+            }
+        }
+        let (new_mv_context, ref_mv_context) = match close_matches {
+            0 => (total_matches.min(1), total_matches),
+            1 => (3 - num_new.min(1), 2 + total_matches),
+            _ => (5 - num_new.min(1), 5),
+        };
+        self.stack.new_mv_context = new_mv_context;
+        self.stack.ref_mv_context = ref_mv_context;
+    }
+}
@@ -0,0 +1,131 @@
+// ! 5.11.46 - 5.11.50 Palette cache and color index map, 7.11.4 palette prediction
+
+use crate::utils::{
+    consts::{PALETTE_COLORS, PALETTE_COLOR_CONTEXT, PALETTE_COLOR_HASH_MULTIPLIERS, PALETTE_NUM_NEIGHBORS, TX_HEIGHT, TX_WIDTH},
+    enums::TxSize,
+};
+
+
+
+/// get_palette_cache, merges the sorted palettes of the above and left blocks into one sorted
+/// list without duplicates
+pub fn get_palette_cache(above: &[u16], left: &[u16]) -> Vec<u16> {
+    let mut cache: Vec<u16> = Vec::with_capacity(above.len() + left.len());
+    let mut push = |color: u16| {
+        if cache.last() != Some(&color) {
+            cache.push(color);
+        }
+    };
+    let (mut above_idx, mut left_idx) = (0, 0);
+    while above_idx < above.len() && left_idx < left.len() {
+        let (above_c, left_c) = (above[above_idx], left[left_idx]);
+        if left_c < above_c {
+            push(left_c);
+            left_idx += 1;
+        } else {
+            push(above_c);
+            above_idx += 1;
+            if left_c == above_c {
+                left_idx += 1;
+            }
+        }
+    }
+    // 只有一边还有剩余
+    above[above_idx..].iter().chain(&left[left_idx..]).for_each(|&color| push(color));
+    cache
+}
+
+/// 5.11.49 order of the color indices, anti-diagonals from the top left corner, each one from its
+/// top right end to its bottom left end, as (row, column)
+pub fn wavefront(width: usize, height: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..width + height - 1).flat_map(move |i| {
+        let first = i.saturating_sub(height - 1);
+        (first..=i.min(width - 1)).rev().map(move |j| (i - j, j))
+    })
+}
+
+
+/// ColorMapY or ColorMapUV of a palette block, one palette index per sample in raster order
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ColorMap {
+    pub width: usize,
+    pub height: usize,
+    pub indices: Vec<u8>,
+}
+
+impl ColorMap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, indices: vec![0; width * height] }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> u8 {
+        self.indices[row * self.width + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, index: u8) {
+        self.indices[row * self.width + col] = index;
+    }
+
+    /// 5.11.50 get_palette_color_context, returns the context of palette_color_idx at (row, col)
+    /// and ColorOrder, the palette indices sorted by the scores of the decoded neighbours
+    pub fn color_context(&self, row: usize, col: usize, n: usize) -> (usize, [u8; PALETTE_COLORS as usize]) {
+        let mut scores = [0u8; PALETTE_COLORS as usize];
+        let mut color_order: [u8; PALETTE_COLORS as usize] = std::array::from_fn(|i| i as u8);
+        if col > 0 {
+            scores[self.get(row, col - 1) as usize] += 2;
+        }
+        if row > 0 && col > 0 {
+            scores[self.get(row - 1, col - 1) as usize] += 1;
+        }
+        if row > 0 {
+            scores[self.get(row - 1, col) as usize] += 2;
+        }
+        // 把得分最高的三个移到前面，得分相同时保持原来的顺序
+        for i in 0..PALETTE_NUM_NEIGHBORS as usize {
+            let mut max_idx = i;
+            for j in i + 1..n {
+                if scores[j] > scores[max_idx] {
+                    max_idx = j;
+                }
+            }
+            scores[i..=max_idx].rotate_right(1);
+            color_order[i..=max_idx].rotate_right(1);
+        }
+        let hash: u8 = scores.iter().zip(PALETTE_COLOR_HASH_MULTIPLIERS).map(|(&score, multiplier)| score * multiplier).sum();
+        (PALETTE_COLOR_CONTEXT[hash as usize] as usize, color_order)
+    }
+
+    /// 5.11.49 the part of the block outside the frame repeats the last column and row inside it
+    pub fn extend(&mut self, onscreen_width: usize, onscreen_height: usize) {
+        for i in 0..onscreen_height {
+            for j in onscreen_width..self.width {
+                self.set(i, j, self.get(i, onscreen_width - 1));
+            }
+        }
+        for i in onscreen_height..self.height {
+            for j in 0..self.width {
+                self.set(i, j, self.get(onscreen_height - 1, j));
+            }
+        }
+    }
+}
+
+
+/// 7.11.4 Palette prediction process of one transform block
+#[derive(Debug, PartialEq, Clone)]
+pub struct PalettePrediction {
+    pub plane: u8,
+    pub start_x: u32,
+    pub start_y: u32,
+    pub tx_size: TxSize,
+    // w*h 的预测值
+    pub pred: Vec<u16>,
+}
+
+/// 7.11.4 predict_palette, x and y are the position of the transform block in samples from the
+/// top left corner of the color map
+pub fn predict_palette(palette: &[u16], map: &ColorMap, x: usize, y: usize, tx_size: TxSize) -> Vec<u16> {
+    let w = TX_WIDTH[tx_size as usize] as usize;
+    let h = TX_HEIGHT[tx_size as usize] as usize;
+    (0..h).flat_map(|i| (0..w).map(move |j| palette[map.get(y + i, x + j) as usize])).collect()
+}
@@ -0,0 +1,107 @@
+use crate::{obu::{frame::FrameHeader, metadata::Metadata, sequence::ColorConfig}, utils::enums::FrameType};
+
+
+
+/// One plane of samples, 8-bit streams are stored widened to u16
+#[derive(Debug, PartialEq, Clone)]
+pub struct Plane {
+    pub width: usize,
+    pub height: usize,
+    pub stride: usize,
+    pub data: Vec<u16>,
+}
+
+impl Plane {
+    pub fn new(width: usize, height: usize, value: u16) -> Self {
+        Self { width, height, stride: width, data: vec![value; width * height] }
+    }
+
+    pub fn row(&self, y: usize) -> &[u16] {
+        &self.data[y * self.stride..y * self.stride + self.width]
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u16 {
+        self.data[y * self.stride + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: u16) {
+        self.data[y * self.stride + x] = value;
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Picture {
+    pub frame_type: FrameType,
+    pub width: u32,
+    pub height: u32,
+    pub render_width: u32,
+    pub render_height: u32,
+    pub order_hint: u32,
+    pub temporal_id: u8,
+    pub spatial_id: u8,
+    pub color_config: ColorConfig,
+    // Y, U, V; 单色时只有 Y
+    pub planes: Vec<Plane>,
+    // 同一 temporal unit 里适用于这一层的 metadata OBU
+    pub metadata: Vec<Metadata>,
+    // 样本已经由解码过程重建，否则还是 new 填的中间值
+    pub reconstructed: bool,
+}
+
+impl Picture {
+    // 按帧头分配图像，样本先填成中间值
+    pub fn new(fh: &FrameHeader, color_config: &ColorConfig, temporal_id: u8, spatial_id: u8) -> Self {
+        let width = fh.upscaled_width as usize;
+        let height = fh.frame_height as usize;
+        let value = 1 << (color_config.bit_depth - 1);
+        let mut planes = vec![Plane::new(width, height, value)];
+        if !color_config.mono_chrome {
+            let ss_x = color_config.subsampling_x as usize;
+            let ss_y = color_config.subsampling_y as usize;
+            let plane = Plane::new((width + ss_x) >> ss_x, (height + ss_y) >> ss_y, value);
+            planes.push(plane.clone());
+            planes.push(plane);
+        }
+        Self {
+            frame_type: fh.frame_type,
+            width: fh.upscaled_width,
+            height: fh.frame_height,
+            render_width: fh.render_width,
+            render_height: fh.render_height,
+            order_hint: fh.order_hint,
+            temporal_id,
+            spatial_id,
+            color_config: color_config.clone(),
+            planes,
+            metadata: Vec::new(),
+            reconstructed: false,
+        }
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        self.color_config.bit_depth
+    }
+
+    /// Copy of the picture with samples scaled to another bit depth, reductions are rounded
+    pub fn with_bit_depth(&self, bit_depth: u8) -> Self {
+        let mut picture = self.clone();
+        let from = self.bit_depth();
+        let max = (1u32 << bit_depth) - 1;
+        for plane in picture.planes.iter_mut() {
+            for sample in plane.data.iter_mut() {
+                *sample = if bit_depth >= from {
+                    *sample << (bit_depth - from)
+                } else {
+                    let shift = from - bit_depth;
+                    ((*sample as u32 + (1 << (shift - 1))) >> shift).min(max) as u16
+                };
+            }
+        }
+        let cc = &mut picture.color_config;
+        cc.bit_depth = bit_depth;
+        cc.high_bitdepth = bit_depth > 8;
+        cc.twelve_bit = bit_depth == 12;
+        picture
+    }
+}
@@ -0,0 +1,96 @@
+// ! 7.12.2 / 7.12.3 量化参数和反量化
+
+use crate::{
+    error::{Error, Result},
+    obu::frame::FrameHeader,
+    utils::{
+        consts::{AC_QLOOKUP, AOM_QM_BITS, DC_QLOOKUP, QM_OFFSET, QUANTIZER_MATRIX, TX_HEIGHT, TX_WIDTH},
+        enums::{TxSize, TxType},
+        math::round2,
+    },
+};
+
+
+
+/// 7.12.2 dc_q, b is clipped to a valid qindex
+pub fn dc_q(b: i32, bit_depth: u8) -> i32 {
+    DC_QLOOKUP[((bit_depth - 8) >> 1) as usize][b.clamp(0, 255) as usize]
+}
+
+/// 7.12.2 ac_q
+pub fn ac_q(b: i32, bit_depth: u8) -> i32 {
+    AC_QLOOKUP[((bit_depth - 8) >> 1) as usize][b.clamp(0, 255) as usize]
+}
+
+/// Quantizer_Matrix[level][chroma] weights of one transform size, in the layout of
+/// `TransformBlock::quant`
+pub fn quantizer_matrix(level: u8, chroma: bool, tx_size: TxSize) -> Result<&'static [u8]> {
+    let start = QM_OFFSET[tx_size as usize] as usize;
+    let len = TX_WIDTH[tx_size as usize].min(32) as usize * TX_HEIGHT[tx_size as usize].min(32) as usize;
+    // 表中缺少的 level 不能解码
+    let matrix = QUANTIZER_MATRIX.get(level as usize).ok_or(Error::Unsupported("quantizer matrix tables"))?;
+    Ok(&matrix[chroma as usize][start..start + len])
+}
+
+
+/// Quantizers of one plane of a block, 7.12.2 get_dc_quant / get_ac_quant and the SegQMLevel in use
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Quantizer {
+    pub plane: u8,
+    pub bit_depth: u8,
+    pub dc: i32,
+    pub ac: i32,
+    // 15 表示不使用量化矩阵
+    pub qm_level: u8,
+}
+
+impl Quantizer {
+    /// current_q_index is CurrentQIndex after the delta_qindex of the superblock
+    pub fn new(fh: &FrameHeader, plane: u8, segment_id: u8, current_q_index: u8, bit_depth: u8) -> Self {
+        let qindex = fh.get_qindex(false, segment_id as usize, current_q_index as i32);
+        let q = &fh.quantization;
+        let (dc_delta, ac_delta) = match plane {
+            0 => (q.delta_q_y_dc, 0),
+            1 => (q.delta_q_u_dc, q.delta_q_u_ac),
+            _ => (q.delta_q_v_dc, q.delta_q_v_ac),
+        };
+        let qm_level = if q.using_qmatrix { fh.seg_qm_level[plane as usize][segment_id as usize] } else { 15 };
+        Self {
+            plane,
+            bit_depth,
+            dc: dc_q(qindex + dc_delta, bit_depth),
+            ac: ac_q(qindex + ac_delta, bit_depth),
+            qm_level,
+        }
+    }
+
+    /// 7.12.3 Dequantization functions, quant is in the layout of `TransformBlock::quant`
+    pub fn dequantize(&self, quant: &[i32], tx_size: TxSize, tx_type: TxType) -> Result<Vec<i32>> {
+        // 量化矩阵只用于二维变换
+        let qm = if self.qm_level < 15 && (tx_type as u8) < TxType::Idtx as u8 {
+            Some(quantizer_matrix(self.qm_level, self.plane > 0, tx_size)?)
+        } else {
+            None
+        };
+        Ok(self.dequantize_with_matrix(quant, tx_size, qm))
+    }
+
+    /// 7.12.3 with the weights of the quantizer matrix, None for the flat matrix
+    pub fn dequantize_with_matrix(&self, quant: &[i32], tx_size: TxSize, qm: Option<&[u8]>) -> Vec<i32> {
+        let area = TX_WIDTH[tx_size as usize] as u32 * TX_HEIGHT[tx_size as usize] as u32;
+        // dqDenom，大于 256 个像素除以 2，大于 1024 个除以 4
+        let dq_shift = (area > 256) as u32 + (area > 1024) as u32;
+        let max = (1 << (7 + self.bit_depth)) - 1;
+        quant
+            .iter()
+            .enumerate()
+            .map(|(pos, &coeff)| {
+                let q = if pos == 0 { self.dc } else { self.ac };
+                let q = qm.map_or(q, |qm| round2(q * qm[pos] as i32, AOM_QM_BITS));
+                let dq = (((coeff.unsigned_abs() as u64 * q as u64) & 0xFFFFFF) >> dq_shift) as i32;
+                let dq = if coeff < 0 { -dq } else { dq };
+                dq.clamp(-max - 1, max)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,56 @@
+// ! 7.11.2 / 7.11.4 prediction and 7.13.3 reconstruction of the intra coded transform blocks
+
+use std::collections::HashMap;
+
+use crate::{
+    decoder::{
+        intra_pred::{predict_chroma_from_luma, predict_intra, IntraPrediction},
+        palette::PalettePrediction,
+        picture::Plane,
+        tile::residual::TransformBlock,
+        transform::inverse_transform_block,
+    },
+    utils::consts::TX_WIDTH,
+};
+
+
+
+// 预测加上残差，裁剪到位深后写回 CurrFrame
+fn write_block(frame: &mut Plane, x: usize, y: usize, w: usize, pred: &[u16], residual: Option<&[i32]>, bit_depth: u8) {
+    let max = (1 << bit_depth) - 1;
+    for (k, &p) in pred.iter().enumerate() {
+        let r = residual.map_or(0, |residual| residual[k]);
+        frame.set(x + k % w, y + k / w, (p as i32 + r).clamp(0, max) as u16);
+    }
+}
+
+
+/// Reconstructs the palette and intra predicted transform blocks of a frame into CurrFrame
+///
+/// frame holds one plane per color plane and must cover every block, including the parts of the
+/// superblocks outside the frame. Residuals are matched to the predictions by plane and position,
+/// inter blocks are not predicted so their samples are left as they are.
+pub fn reconstruct_intra(frame: &mut [Plane], intra: &[IntraPrediction], palette: &[PalettePrediction], residuals: &[TransformBlock], bit_depth: u8, enable_intra_edge_filter: bool) {
+    let residuals: HashMap<_, _> = residuals.iter().map(|tb| ((tb.plane, tb.start_x, tb.start_y), tb)).collect();
+    let residual = |plane: u8, x: u32, y: u32| {
+        residuals.get(&(plane, x, y)).map(|tb| inverse_transform_block(tb.tx_size, tb.tx_type, bit_depth, &tb.dequant))
+    };
+    // 调色板预测不依赖相邻样本，先写入，后面的帧内预测可能用到它们
+    for block in palette {
+        let w = TX_WIDTH[block.tx_size as usize] as usize;
+        let r = residual(block.plane, block.start_x, block.start_y);
+        let plane = &mut frame[block.plane as usize];
+        write_block(plane, block.start_x as usize, block.start_y as usize, w, &block.pred, r.as_deref(), bit_depth);
+    }
+    // 帧内预测要按解码顺序，CFL 用的亮度已经重建
+    for block in intra {
+        let w = TX_WIDTH[block.tx_size as usize] as usize;
+        let mut pred = predict_intra(&frame[block.plane as usize], block, bit_depth, enable_intra_edge_filter);
+        if let Some(cfl) = &block.cfl {
+            predict_chroma_from_luma(&frame[0], block, cfl, &mut pred, bit_depth);
+        }
+        let r = residual(block.plane, block.start_x, block.start_y);
+        let plane = &mut frame[block.plane as usize];
+        write_block(plane, block.start_x as usize, block.start_y as usize, w, &pred, r.as_deref(), bit_depth);
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{
+    decoder::tile::{Block, TileDecoder},
+    entropy::CdfContext,
+    error::{Error, Result},
+    utils::{
+        consts::{BLOCK_HEIGHT, BLOCK_MAX_TX_DEPTH, BLOCK_WIDTH, MAX_TX_SIZE_RECT, MAX_VARTX_DEPTH, MI_HEIGHT_LOG2, MI_SIZE, MI_WIDTH_LOG2, NUM_4X4_BLOCKS_HIGH, NUM_4X4_BLOCKS_WIDE, PARTITION_SUBSIZE, SPLIT_TX_SIZE, TX_HEIGHT, TX_SIZES, TX_SIZE_SQR_UP, TX_WIDTH},
+        enums::{Partition, SubSize, TxMode, TxSize},
+    },
+};
+
+
+
+// 按块宽度选择 partition 的 CDF
+fn partition_cdf(cdf: &mut CdfContext, ctx: usize, b_size: SubSize) -> &mut [u32] {
+    match MI_WIDTH_LOG2[b_size as usize] {
+        1 => &mut cdf.partition_w8_cdf[ctx],
+        2 => &mut cdf.partition_w16_cdf[ctx],
+        3 => &mut cdf.partition_w32_cdf[ctx],
+        4 => &mut cdf.partition_w64_cdf[ctx],
+        _ => &mut cdf.partition_w128_cdf[ctx],
+    }
+}
+
+
+impl TileDecoder<'_> {
+    /// 5.11.4 Decode partition syntax
+    pub(super) fn decode_partition(&mut self, r: u32, c: u32, b_size: SubSize) -> Result<()> {
+        if r >= self.fh.mi_rows || c >= self.fh.mi_cols {
+            return Ok(());
+        }
+        let num4x4 = NUM_4X4_BLOCKS_WIDE[b_size as usize] as u32;
+        let half_block4x4 = num4x4 >> 1;
+        let quarter_block4x4 = half_block4x4 >> 1;
+        let has_rows = r + half_block4x4 < self.fh.mi_rows;
+        let has_cols = c + half_block4x4 < self.fh.mi_cols;
+        let partition = if b_size == SubSize::Block4X4 {
+            Partition::None
+        } else if has_rows && has_cols {
+            self.read_partition(r, c, b_size)?
+        } else if has_cols {
+            if self.read_split_or(r, c, b_size, true)? { Partition::Split } else { Partition::Horz }
+        } else if has_rows {
+            if self.read_split_or(r, c, b_size, false)? { Partition::Split } else { Partition::Vert }
+        } else {
+            Partition::Split
+        };
+        let sub_size: SubSize = PARTITION_SUBSIZE[partition as usize][b_size as usize].into();
+        let split_size: SubSize = PARTITION_SUBSIZE[Partition::Split as usize][b_size as usize].into();
+        let (half, quarter) = (half_block4x4, quarter_block4x4);
+        match partition {
+            Partition::None => self.decode_block(r, c, sub_size)?,
+            Partition::Horz => {
+                self.decode_block(r, c, sub_size)?;
+                if has_rows {
+                    self.decode_block(r + half, c, sub_size)?;
+                }
+            }
+            Partition::Vert => {
+                self.decode_block(r, c, sub_size)?;
+                if has_cols {
+                    self.decode_block(r, c + half, sub_size)?;
+                }
+            }
+            Partition::Split => {
+                self.decode_partition(r, c, sub_size)?;
+                self.decode_partition(r, c + half, sub_size)?;
+                self.decode_partition(r + half, c, sub_size)?;
+                self.decode_partition(r + half, c + half, sub_size)?;
+            }
+            Partition::HorzA => {
+                self.decode_block(r, c, split_size)?;
+                self.decode_block(r, c + half, split_size)?;
+                self.decode_block(r + half, c, sub_size)?;
+            }
+            Partition::HorzB => {
+                self.decode_block(r, c, sub_size)?;
+                self.decode_block(r + half, c, split_size)?;
+                self.decode_block(r + half, c + half, split_size)?;
+            }
+            Partition::VertA => {
+                self.decode_block(r, c, split_size)?;
+                self.decode_block(r + half, c, split_size)?;
+                self.decode_block(r, c + half, sub_size)?;
+            }
+            Partition::VertB => {
+                self.decode_block(r, c, sub_size)?;
+                self.decode_block(r, c + half, split_size)?;
+                self.decode_block(r + half, c + half, split_size)?;
+            }
+            Partition::Horz4 => {
+                for i in 0..4 {
+                    if i < 3 || r + quarter * 3 < self.fh.mi_rows {
+                        self.decode_block(r + quarter * i, c, sub_size)?;
+                    }
+                }
+            }
+            Partition::Vert4 => {
+                for i in 0..4 {
+                    if i < 3 || c + quarter * 3 < self.fh.mi_cols {
+                        self.decode_block(r, c + quarter * i, sub_size)?;
+                    }
+                }
+            }
+            Partition::Unknown => return Err(Error::InvalidValue { name: "partition", value: partition as u32 }),
+        }
+        Ok(())
+    }
+
+    // 上方块比当前块窄、左边块比当前块矮时 ctx 增加
+    fn partition_ctx(&self, r: u32, c: u32, b_size: SubSize) -> usize {
+        let bsl = MI_WIDTH_LOG2[b_size as usize];
+        let above = self.is_inside(r as i64 - 1, c as i64)
+            && MI_WIDTH_LOG2[self.mi.get(r - 1, c).mi_size as usize] < bsl;
+        let left = self.is_inside(r as i64, c as i64 - 1)
+            && MI_HEIGHT_LOG2[self.mi.get(r, c - 1).mi_size as usize] < bsl;
+        left as usize * 2 + above as usize
+    }
+
+    fn read_partition(&mut self, r: u32, c: u32, b_size: SubSize) -> Result<Partition> {
+        let ctx = self.partition_ctx(r, c, b_size);
+        let cdf = partition_cdf(&mut self.cdf, ctx, b_size);
+        Ok((self.symbol.read_symbol(cdf) as u8).into())
+    }
+
+    // split_or_horz / split_or_vert，由 partition 的 CDF 合并出一个不自适应的二元 CDF
+    fn read_split_or(&mut self, r: u32, c: u32, b_size: SubSize, horz: bool) -> Result<bool> {
+        let ctx = self.partition_ctx(r, c, b_size);
+        let cdf = partition_cdf(&mut self.cdf, ctx, b_size);
+        let prob = |p: Partition| {
+            let i = p as usize;
+            cdf[i] - if i > 0 { cdf[i - 1] } else { 0 }
+        };
+        let mut psum = prob(Partition::Split) + prob(Partition::HorzA);
+        if horz {
+            psum += prob(Partition::Horz) + prob(Partition::HorzB) + prob(Partition::VertA);
+            if b_size != SubSize::Block128X128 {
+                psum += prob(Partition::Horz4);
+            }
+        } else {
+            psum += prob(Partition::Vert) + prob(Partition::VertA) + prob(Partition::VertB);
+            if b_size != SubSize::Block128X128 {
+                psum += prob(Partition::Vert4);
+            }
+        }
+        let mut cdf = [(1 << 15) - psum, 1 << 15, 0];
+        Ok(self.symbol.read_symbol(&mut cdf) == 1)
+    }
+
+    /// 5.11.5 Decode block syntax
+    fn decode_block(&mut self, r: u32, c: u32, sub_size: SubSize) -> Result<()> {
+        if sub_size == SubSize::Unknown {
+            return Err(Error::InvalidValue { name: "sub_size", value: sub_size as u32 });
+        }
+        let bw4 = NUM_4X4_BLOCKS_WIDE[sub_size as usize] as u32;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[sub_size as usize] as u32;
+        let cc = &self.seq.color_config;
+        // 4xN 或 Nx4 的块只有奇数位置的块带色度
+        let has_chroma = cc.num_planes > 1
+            && !(bh4 == 1 && cc.subsampling_y && r & 1 == 0)
+            && !(bw4 == 1 && cc.subsampling_x && c & 1 == 0);
+        let (r64, c64) = (r as i64, c as i64);
+        let avail_u = self.is_inside(r64 - 1, c64);
+        let avail_l = self.is_inside(r64, c64 - 1);
+        // 4xN 的色度块覆盖左边一列，Nx4 的覆盖上面一行
+        let avail_u_chroma = has_chroma && if cc.subsampling_y && bh4 == 1 { self.is_inside(r64 - 2, c64) } else { avail_u };
+        let avail_l_chroma = has_chroma && if cc.subsampling_x && bw4 == 1 { self.is_inside(r64, c64 - 2) } else { avail_l };
+        self.b = Block { mi_row: r, mi_col: c, mi_size: sub_size, has_chroma, avail_u, avail_l, avail_u_chroma, avail_l_chroma, ..Default::default() };
+        self.b.info.mi_size = sub_size;
+
+        if self.fh.frame_is_intra {
+            self.intra_frame_mode_info()?;
+        } else {
+            self.inter_frame_mode_info()?;
+        }
+        self.palette_tokens();
+        self.read_block_tx_size()?;
+
+        self.b.info.qindex = self.current_q_index;
+        self.b.info.delta_lf = self.delta_lf;
+        self.b.info.inter_tx_size = self.b.info.tx_size;
+        self.mi.fill(r, c, bw4, bh4, &self.b.info);
+        if !self.b.inter_tx_sizes.is_empty() {
+            for row in r..(r + bh4).min(self.fh.mi_rows) {
+                for col in c..(c + bw4).min(self.fh.mi_cols) {
+                    self.mi.get_mut(row, col).inter_tx_size = self.b.inter_tx_sizes[((row - r) * bw4 + col - c) as usize];
+                }
+            }
+        }
+        if self.b.info.skip {
+            self.reset_block_context();
+        }
+        self.residual()
+    }
+
+    /// 5.11.16 Block TX size syntax
+    fn read_block_tx_size(&mut self) -> Result<()> {
+        let info = &self.b.info;
+        if self.fh.tx_mode == TxMode::Select && self.b.mi_size != SubSize::Block4X4 && info.is_inter && !info.skip && !self.b.lossless {
+            let mi_size = self.b.mi_size as usize;
+            let bw4 = NUM_4X4_BLOCKS_WIDE[mi_size] as u32;
+            let bh4 = NUM_4X4_BLOCKS_HIGH[mi_size] as u32;
+            let max_tx_size: TxSize = MAX_TX_SIZE_RECT[mi_size].into();
+            let tx_w4 = (TX_WIDTH[max_tx_size as usize] / MI_SIZE) as u32;
+            let tx_h4 = (TX_HEIGHT[max_tx_size as usize] / MI_SIZE) as u32;
+            // 帧外的部分不读，保持最大的变换尺寸
+            self.b.inter_tx_sizes = vec![max_tx_size; (bw4 * bh4) as usize];
+            for row in (self.b.mi_row..self.b.mi_row + bh4).step_by(tx_h4 as usize) {
+                for col in (self.b.mi_col..self.b.mi_col + bw4).step_by(tx_w4 as usize) {
+                    self.read_var_tx_size(row, col, max_tx_size, 0);
+                }
+            }
+            return Ok(());
+        }
+        let allow_select = !info.skip || !info.is_inter;
+        self.read_tx_size(allow_select)
+    }
+
+    /// 5.11.17 Var TX size syntax
+    fn read_var_tx_size(&mut self, row: u32, col: u32, tx_size: TxSize, depth: u8) {
+        if row >= self.fh.mi_rows || col >= self.fh.mi_cols {
+            return;
+        }
+        let txfm_split = if tx_size == TxSize::Tx4X4 || depth == MAX_VARTX_DEPTH {
+            false
+        } else {
+            let ctx = self.txfm_split_ctx(row, col, tx_size);
+            self.symbol.read_symbol(&mut self.cdf.txfm_split_cdf[ctx]) == 1
+        };
+        let w4 = (TX_WIDTH[tx_size as usize] / MI_SIZE) as u32;
+        let h4 = (TX_HEIGHT[tx_size as usize] / MI_SIZE) as u32;
+        if txfm_split {
+            let sub_tx_size: TxSize = SPLIT_TX_SIZE[tx_size as usize].into();
+            let step_w = (TX_WIDTH[sub_tx_size as usize] / MI_SIZE) as u32;
+            let step_h = (TX_HEIGHT[sub_tx_size as usize] / MI_SIZE) as u32;
+            for i in (0..h4).step_by(step_h as usize) {
+                for j in (0..w4).step_by(step_w as usize) {
+                    self.read_var_tx_size(row + i, col + j, sub_tx_size, depth + 1);
+                }
+            }
+        } else {
+            let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32;
+            let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as u32;
+            for i in row - self.b.mi_row..(row - self.b.mi_row + h4).min(bh4) {
+                for j in col - self.b.mi_col..(col - self.b.mi_col + w4).min(bw4) {
+                    self.b.inter_tx_sizes[(i * bw4 + j) as usize] = tx_size;
+                }
+            }
+            self.b.info.tx_size = tx_size;
+        }
+    }
+
+    // txfm_split 的 ctx：上方和左边的变换尺寸是否比当前的小，以及当前尺寸和块的最大尺寸的关系
+    fn txfm_split_ctx(&self, row: u32, col: u32, tx_size: TxSize) -> usize {
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32;
+        let (r, c) = (row - self.b.mi_row, col - self.b.mi_col);
+        let above_w = if r > 0 {
+            TX_WIDTH[self.b.inter_tx_sizes[((r - 1) * bw4 + c) as usize] as usize]
+        } else if !self.b.avail_u {
+            64
+        } else {
+            match self.mi.get(row - 1, col) {
+                above if above.skip && above.is_inter => BLOCK_WIDTH[above.mi_size as usize],
+                above => TX_WIDTH[above.inter_tx_size as usize],
+            }
+        };
+        let left_h = if c > 0 {
+            TX_HEIGHT[self.b.inter_tx_sizes[(r * bw4 + c - 1) as usize] as usize]
+        } else if !self.b.avail_l {
+            64
+        } else {
+            match self.mi.get(row, col - 1) {
+                left if left.skip && left.is_inter => BLOCK_HEIGHT[left.mi_size as usize],
+                left => TX_HEIGHT[left.inter_tx_size as usize],
+            }
+        };
+        let above = (above_w < TX_WIDTH[tx_size as usize]) as usize;
+        let left = (left_h < TX_HEIGHT[tx_size as usize]) as usize;
+        let mi_size = self.b.mi_size as usize;
+        // 方形的最大变换尺寸，最大 64
+        let size = BLOCK_WIDTH[mi_size].max(BLOCK_HEIGHT[mi_size]).min(64);
+        let max_tx_size = size.trailing_zeros() as usize - 2;
+        let category = (TX_SIZE_SQR_UP[tx_size as usize] as usize != max_tx_size && max_tx_size > TxSize::Tx8X8 as usize) as usize
+            + (TX_SIZES - 1 - max_tx_size) * 2;
+        category * 3 + above + left
+    }
+
+    /// 5.11.15 TX size syntax
+    fn read_tx_size(&mut self, allow_select: bool) -> Result<()> {
+        if self.b.lossless {
+            self.b.info.tx_size = TxSize::Tx4X4;
+            return Ok(());
+        }
+        let mi_size = self.b.mi_size as usize;
+        let max_rect_tx_size = MAX_TX_SIZE_RECT[mi_size];
+        let mut tx_size = max_rect_tx_size;
+        if self.b.mi_size != SubSize::Block4X4 && allow_select && self.fh.tx_mode == TxMode::Select {
+            let ctx = self.tx_depth_ctx(max_rect_tx_size);
+            let cdf = match BLOCK_MAX_TX_DEPTH[mi_size] {
+                4 => &mut self.cdf.tx_64x64_cdf[ctx][..],
+                3 => &mut self.cdf.tx_32x32_cdf[ctx][..],
+                2 => &mut self.cdf.tx_16x16_cdf[ctx][..],
+                _ => &mut self.cdf.tx_8x8_cdf[ctx][..],
+            };
+            let tx_depth = self.symbol.read_symbol(cdf);
+            for _ in 0..tx_depth {
+                tx_size = SPLIT_TX_SIZE[tx_size as usize];
+            }
+        }
+        self.b.info.tx_size = tx_size.into();
+        Ok(())
+    }
+
+    // tx_depth 的 ctx：比较上方和左边的变换尺寸与当前最大变换尺寸
+    fn tx_depth_ctx(&self, max_rect_tx_size: u8) -> usize {
+        let max_tx_width = TX_WIDTH[max_rect_tx_size as usize];
+        let max_tx_height = TX_HEIGHT[max_rect_tx_size as usize];
+        let above_w = match self.above() {
+            Some(above) if above.is_inter => BLOCK_WIDTH[above.mi_size as usize],
+            Some(above) => TX_WIDTH[above.inter_tx_size as usize],
+            None => 0,
+        };
+        let left_h = match self.left() {
+            Some(left) if left.is_inter => BLOCK_HEIGHT[left.mi_size as usize],
+            Some(left) => TX_HEIGHT[left.inter_tx_size as usize],
+            None => 0,
+        };
+        (above_w >= max_tx_width) as usize + (left_h >= max_tx_height) as usize
+    }
+}
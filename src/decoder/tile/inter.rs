@@ -0,0 +1,741 @@
+use crate::{
+    decoder::{mode_info::ModeInfo, mvpred::{MvBlock, MvPredictor}, tile::TileDecoder},
+    error::Result,
+    utils::{
+        consts::{BLOCK_HEIGHT, BLOCK_WIDTH, CLASS0_SIZE, COMP_NEWMV_CTXS, COMPOUND_MODE_CTX_MAP, LAST_FRAME, LEAST_SQUARES_SAMPLES_MAX, MAX_SEGMENTS, NONE_FRAME, NUM_4X4_BLOCKS_HIGH, NUM_4X4_BLOCKS_WIDE, REF_SCALE_SHIFT, SEG_LVL_GLOBALMV, SEG_LVL_REF_FRAME, SEG_LVL_SKIP, SIZE_GROUP, TRANSLATION, WEDGE_BITS},
+        enums::{CompMode, CompRefType, CompoundType, InterMode, Interpolationfilter, MotionMode, MVJoint, RefFrame, SubSize},
+        math::clip3,
+    },
+};
+
+
+
+const INTRA_FRAME: i8 = RefFrame::Intra as i8;
+
+
+// check_backward
+fn is_backward(ref_frame: i8) -> bool {
+    ref_frame >= RefFrame::Bwdref as i8 && ref_frame <= RefFrame::Altref as i8
+}
+
+// is_samedir_ref_pair
+fn is_samedir(ref0: i8, ref1: i8) -> bool {
+    (ref0 >= RefFrame::Bwdref as i8) == (ref1 >= RefFrame::Bwdref as i8)
+}
+
+// 8.3.2 中比较两组参考帧出现次数的 ctx
+fn ref_count_ctx(counts0: u8, counts1: u8) -> usize {
+    match counts0.cmp(&counts1) {
+        std::cmp::Ordering::Less => 0,
+        std::cmp::Ordering::Equal => 1,
+        std::cmp::Ordering::Greater => 2,
+    }
+}
+
+
+impl TileDecoder<'_> {
+    /// 5.11.18 Inter frame mode info syntax
+    pub(super) fn inter_frame_mode_info(&mut self) -> Result<()> {
+        self.b.info.use_intrabc = false;
+        self.b.info.skip = false;
+        self.inter_segment_id(true);
+        self.read_skip_mode();
+        if self.b.info.skip_mode {
+            self.b.info.skip = true;
+        } else {
+            self.read_skip();
+        }
+        if !self.fh.segmentation.seg_id_pre_skip {
+            self.inter_segment_id(false);
+        }
+        self.b.lossless = self.fh.lossless_array[self.b.info.segment_id as usize];
+        self.read_cdef();
+        self.read_delta_qindex();
+        self.read_delta_lf();
+        self.read_deltas = false;
+        self.read_is_inter();
+        if self.b.info.is_inter {
+            self.inter_block_mode_info()
+        } else {
+            self.intra_block_mode_info()
+        }
+    }
+
+    /// 5.11.19 Inter segment ID syntax
+    fn inter_segment_id(&mut self, pre_skip: bool) {
+        let seg = &self.fh.segmentation;
+        if !seg.segmentation_enabled {
+            self.b.info.segment_id = 0;
+            return;
+        }
+        let predicted_segment_id = self.get_segment_id();
+        if !seg.segmentation_update_map {
+            self.b.info.segment_id = predicted_segment_id;
+            return;
+        }
+        if pre_skip && !seg.seg_id_pre_skip {
+            self.b.info.segment_id = 0;
+            return;
+        }
+        if !pre_skip && self.b.info.skip {
+            self.b.info.seg_id_predicted = false;
+            self.b.info.segment_id = self.read_segment_id();
+            return;
+        }
+        if seg.segmentation_temporal_update {
+            let ctx = self.left().is_some_and(|left| left.seg_id_predicted) as usize
+                + self.above().is_some_and(|above| above.seg_id_predicted) as usize;
+            self.b.info.seg_id_predicted = self.symbol.read_symbol(&mut self.cdf.segment_id_predicted_cdf[ctx]) == 1;
+            self.b.info.segment_id = if self.b.info.seg_id_predicted { predicted_segment_id } else { self.read_segment_id() };
+        } else {
+            self.b.info.segment_id = self.read_segment_id();
+        }
+    }
+
+    // get_segment_id，块内 PrevSegmentIds 的最小值
+    fn get_segment_id(&self) -> u8 {
+        let fh = self.fh;
+        let Some(prev_segment_ids) = self.refs.and_then(|refs| refs.load_previous_segment_ids(fh)) else {
+            return 0;
+        };
+        let x_mis = (fh.mi_cols - self.b.mi_col).min(NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32);
+        let y_mis = (fh.mi_rows - self.b.mi_row).min(NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as u32);
+        let mut seg = MAX_SEGMENTS as u8 - 1;
+        for y in 0..y_mis {
+            for x in 0..x_mis {
+                let idx = ((self.b.mi_row + y) * fh.mi_cols + self.b.mi_col + x) as usize;
+                seg = seg.min(prev_segment_ids[idx]);
+            }
+        }
+        seg
+    }
+
+    fn seg_feature_active(&self, feature: usize) -> bool {
+        self.fh.segmentation.feature_active(self.b.info.segment_id as usize, feature)
+    }
+
+    /// 5.11.10 Skip mode syntax
+    fn read_skip_mode(&mut self) {
+        let mi_size = self.b.mi_size as usize;
+        self.b.info.skip_mode = if self.seg_feature_active(SEG_LVL_SKIP as usize) || self.seg_feature_active(SEG_LVL_REF_FRAME)
+            || self.seg_feature_active(SEG_LVL_GLOBALMV as usize) || !self.fh.skip_mode_present
+            || BLOCK_WIDTH[mi_size] < 8 || BLOCK_HEIGHT[mi_size] < 8 {
+            false
+        } else {
+            let ctx = self.above().is_some_and(|above| above.skip_mode) as usize + self.left().is_some_and(|left| left.skip_mode) as usize;
+            self.symbol.read_symbol(&mut self.cdf.skip_mode_cdf[ctx]) == 1
+        };
+    }
+
+    /// 5.11.20 Is inter syntax
+    fn read_is_inter(&mut self) {
+        self.b.info.is_inter = if self.b.info.skip_mode {
+            true
+        } else if self.seg_feature_active(SEG_LVL_REF_FRAME) {
+            self.fh.segmentation.feature_data[self.b.info.segment_id as usize][SEG_LVL_REF_FRAME] != INTRA_FRAME as i16
+        } else if self.seg_feature_active(SEG_LVL_GLOBALMV as usize) {
+            true
+        } else {
+            let above_intra = self.above().map(|above| above.ref_frame[0] <= INTRA_FRAME);
+            let left_intra = self.left().map(|left| left.ref_frame[0] <= INTRA_FRAME);
+            let ctx = match (above_intra, left_intra) {
+                (Some(above), Some(left)) => if above && left { 3 } else { (above || left) as usize },
+                (Some(intra), None) | (None, Some(intra)) => 2 * intra as usize,
+                (None, None) => 0,
+            };
+            self.symbol.read_symbol(&mut self.cdf.is_inter_cdf[ctx]) == 1
+        };
+    }
+
+    /// 5.11.23 Inter block mode info syntax
+    fn inter_block_mode_info(&mut self) -> Result<()> {
+        self.b.info.palette_size_y = 0;
+        self.b.info.palette_size_uv = 0;
+        self.read_ref_frames();
+        let is_compound = self.b.info.ref_frame[1] > INTRA_FRAME;
+        self.find_mv_stack();
+        let stack = &self.b.mv_stack;
+        let mode = if self.b.info.skip_mode {
+            InterMode::NearestNearestMv
+        } else if self.seg_feature_active(SEG_LVL_SKIP as usize) || self.seg_feature_active(SEG_LVL_GLOBALMV as usize) {
+            InterMode::GlobalMv
+        } else if is_compound {
+            let ctx = COMPOUND_MODE_CTX_MAP[(stack.ref_mv_context >> 1) as usize][stack.new_mv_context.min(COMP_NEWMV_CTXS - 1) as usize];
+            let compound_mode = self.symbol.read_symbol(&mut self.cdf.compound_mode_cdf[ctx as usize]) as u8;
+            (InterMode::NearestNearestMv as u8 + compound_mode).into()
+        } else if self.symbol.read_symbol(&mut self.cdf.new_mv_cdf[stack.new_mv_context as usize]) == 0 {
+            InterMode::NewMv
+        } else if self.symbol.read_symbol(&mut self.cdf.zero_mv_cdf[stack.zero_mv_context as usize]) == 0 {
+            InterMode::GlobalMv
+        } else if self.symbol.read_symbol(&mut self.cdf.ref_mv_cdf[stack.ref_mv_context as usize]) == 0 {
+            InterMode::NearestMv
+        } else {
+            InterMode::NearMv
+        };
+        self.b.info.inter_mode = mode;
+        self.b.info.ref_mv_idx = if mode == InterMode::NewMv || mode == InterMode::NewNewMv {
+            self.read_drl_mode(0)
+        } else if mode.has_nearmv() {
+            self.read_drl_mode(1)
+        } else {
+            0
+        };
+        self.assign_mv(is_compound)?;
+        self.read_interintra_mode(is_compound);
+        self.read_motion_mode(is_compound);
+        self.read_compound_type(is_compound);
+        if self.fh.interpolation_filter == Interpolationfilter::Switchable {
+            let dirs = if self.seq.enable_dual_filter { 2 } else { 1 };
+            for dir in 0..dirs {
+                self.b.info.interp_filter[dir] = if self.needs_interp_filter() {
+                    let ctx = self.interp_filter_ctx(dir);
+                    (self.symbol.read_symbol(&mut self.cdf.interp_filter_cdf[ctx]) as u8).into()
+                } else {
+                    Interpolationfilter::EightTap
+                };
+            }
+            if !self.seq.enable_dual_filter {
+                self.b.info.interp_filter[1] = self.b.info.interp_filter[0];
+            }
+        } else {
+            self.b.info.interp_filter = [self.fh.interpolation_filter; 2];
+        }
+        Ok(())
+    }
+
+    // drl_mode，从 start 开始最多读两次
+    fn read_drl_mode(&mut self, start: u8) -> u8 {
+        let mut ref_mv_idx = start;
+        for idx in start..start + 2 {
+            if self.b.mv_stack.num_mv_found > idx + 1 {
+                let ctx = self.b.mv_stack.drl_ctx_stack[idx as usize] as usize;
+                if self.symbol.read_symbol(&mut self.cdf.drl_mode_cdf[ctx]) == 0 {
+                    return idx;
+                }
+                ref_mv_idx = idx + 1;
+            }
+        }
+        ref_mv_idx
+    }
+
+    /// 5.11.25 Ref frames syntax
+    fn read_ref_frames(&mut self) {
+        let ref_frame = if self.b.info.skip_mode {
+            [self.fh.skip_mode_frame[0] as i8, self.fh.skip_mode_frame[1] as i8]
+        } else if self.seg_feature_active(SEG_LVL_REF_FRAME) {
+            [self.fh.segmentation.feature_data[self.b.info.segment_id as usize][SEG_LVL_REF_FRAME] as i8, NONE_FRAME]
+        } else if self.seg_feature_active(SEG_LVL_SKIP as usize) || self.seg_feature_active(SEG_LVL_GLOBALMV as usize) {
+            [RefFrame::Last as i8, NONE_FRAME]
+        } else {
+            let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize];
+            let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize];
+            let comp_mode = if self.fh.reference_select && bw4.min(bh4) >= 2 {
+                let ctx = self.comp_mode_ctx();
+                (self.symbol.read_symbol(&mut self.cdf.comp_mode_cdf[ctx]) as u8).into()
+            } else {
+                CompMode::Single
+            };
+            if comp_mode == CompMode::Compound {
+                self.read_compound_ref_frames()
+            } else {
+                [self.read_single_ref_frame() as i8, NONE_FRAME]
+            }
+        };
+        self.b.info.ref_frame = ref_frame;
+    }
+
+    fn read_compound_ref_frames(&mut self) -> [i8; 2] {
+        use RefFrame::*;
+        let ctx = self.comp_ref_type_ctx();
+        let comp_ref_type: CompRefType = (self.symbol.read_symbol(&mut self.cdf.comp_ref_type_cdf[ctx]) as u8).into();
+        if comp_ref_type == CompRefType::Unidir {
+            let ctx = self.ref_ctx(&[Last, Last2, Last3, Golden], &[Bwdref, Altref2, Altref]);
+            let pair = if self.symbol.read_symbol(&mut self.cdf.uni_comp_ref_cdf[ctx][0]) == 1 {
+                (Bwdref, Altref)
+            } else {
+                let ctx = self.ref_ctx(&[Last2], &[Last3, Golden]);
+                if self.symbol.read_symbol(&mut self.cdf.uni_comp_ref_cdf[ctx][1]) == 1 {
+                    let ctx = self.ref_ctx(&[Last3], &[Golden]);
+                    if self.symbol.read_symbol(&mut self.cdf.uni_comp_ref_cdf[ctx][2]) == 1 { (Last, Golden) } else { (Last, Last3) }
+                } else {
+                    (Last, Last2)
+                }
+            };
+            return [pair.0 as i8, pair.1 as i8];
+        }
+        let ctx = self.ref_ctx(&[Last, Last2], &[Last3, Golden]);
+        let ref0 = if self.symbol.read_symbol(&mut self.cdf.comp_ref_cdf[ctx][0]) == 0 {
+            let ctx = self.ref_ctx(&[Last], &[Last2]);
+            if self.symbol.read_symbol(&mut self.cdf.comp_ref_cdf[ctx][1]) == 1 { Last2 } else { Last }
+        } else {
+            let ctx = self.ref_ctx(&[Last3], &[Golden]);
+            if self.symbol.read_symbol(&mut self.cdf.comp_ref_cdf[ctx][2]) == 1 { Golden } else { Last3 }
+        };
+        let ctx = self.ref_ctx(&[Bwdref, Altref2], &[Altref]);
+        let ref1 = if self.symbol.read_symbol(&mut self.cdf.comp_bwd_ref_cdf[ctx][0]) == 0 {
+            let ctx = self.ref_ctx(&[Bwdref], &[Altref2]);
+            if self.symbol.read_symbol(&mut self.cdf.comp_bwd_ref_cdf[ctx][1]) == 1 { Altref2 } else { Bwdref }
+        } else {
+            Altref
+        };
+        [ref0 as i8, ref1 as i8]
+    }
+
+    fn read_single_ref_frame(&mut self) -> RefFrame {
+        use RefFrame::*;
+        // single_ref_p1 .. single_ref_p6 对应 single_ref_cdf[ctx][0..6]
+        let single_ref = |td: &mut Self, n: usize, a: &[RefFrame], b: &[RefFrame]| {
+            let ctx = td.ref_ctx(a, b);
+            td.symbol.read_symbol(&mut td.cdf.single_ref_cdf[ctx][n - 1]) == 1
+        };
+        if single_ref(self, 1, &[Last, Last2, Last3, Golden], &[Bwdref, Altref2, Altref]) {
+            if !single_ref(self, 2, &[Bwdref, Altref2], &[Altref]) {
+                if single_ref(self, 6, &[Bwdref], &[Altref2]) { Altref2 } else { Bwdref }
+            } else {
+                Altref
+            }
+        } else if single_ref(self, 3, &[Last, Last2], &[Last3, Golden]) {
+            if single_ref(self, 5, &[Last3], &[Golden]) { Golden } else { Last3 }
+        } else if single_ref(self, 4, &[Last], &[Last2]) {
+            Last2
+        } else {
+            Last
+        }
+    }
+
+    // count_refs，上方和左边块使用该参考帧的次数
+    fn count_refs(&self, frame_type: RefFrame) -> u8 {
+        [self.above(), self.left()].into_iter().flatten()
+            .map(|info| info.ref_frame.iter().filter(|&&r| r == frame_type as i8).count() as u8)
+            .sum()
+    }
+
+    fn ref_ctx(&self, a: &[RefFrame], b: &[RefFrame]) -> usize {
+        let count = |frames: &[RefFrame]| frames.iter().map(|&f| self.count_refs(f)).sum();
+        ref_count_ctx(count(a), count(b))
+    }
+
+    // comp_mode 的 ctx
+    fn comp_mode_ctx(&self) -> usize {
+        let above = self.above().map(|above| above.ref_frame);
+        let left = self.left().map(|left| left.ref_frame);
+        match (above, left) {
+            (Some(above), Some(left)) => {
+                let (above_single, left_single) = (above[1] <= INTRA_FRAME, left[1] <= INTRA_FRAME);
+                if above_single && left_single {
+                    (is_backward(above[0]) ^ is_backward(left[0])) as usize
+                } else if above_single {
+                    2 + (is_backward(above[0]) || above[0] <= INTRA_FRAME) as usize
+                } else if left_single {
+                    2 + (is_backward(left[0]) || left[0] <= INTRA_FRAME) as usize
+                } else {
+                    4
+                }
+            }
+            (Some(r), None) | (None, Some(r)) => if r[1] <= INTRA_FRAME { is_backward(r[0]) as usize } else { 3 },
+            (None, None) => 1,
+        }
+    }
+
+    // comp_ref_type 的 ctx
+    fn comp_ref_type_ctx(&self) -> usize {
+        let above = self.above().map(|above| above.ref_frame);
+        let left = self.left().map(|left| left.ref_frame);
+        let comp_inter = |r: Option<[i8; 2]>| r.is_some_and(|r| r[0] > INTRA_FRAME && r[1] > INTRA_FRAME);
+        let uni_comp = |r: Option<[i8; 2]>| comp_inter(r) && r.is_some_and(|r| is_samedir(r[0], r[1]));
+        let (above_comp_inter, left_comp_inter) = (comp_inter(above), comp_inter(left));
+        let (above_uni_comp, left_uni_comp) = (uni_comp(above), uni_comp(left));
+        let above_inter = above.is_some_and(|r| r[0] > INTRA_FRAME);
+        let left_inter = left.is_some_and(|r| r[0] > INTRA_FRAME);
+        if above_inter && left_inter {
+            let (above0, left0) = (above.unwrap()[0], left.unwrap()[0]);
+            let samedir = is_samedir(above0, left0) as usize;
+            if !above_comp_inter && !left_comp_inter {
+                1 + 2 * samedir
+            } else if !above_comp_inter {
+                if !left_uni_comp { 1 } else { 3 + samedir }
+            } else if !left_comp_inter {
+                if !above_uni_comp { 1 } else { 3 + samedir }
+            } else if !above_uni_comp && !left_uni_comp {
+                0
+            } else if !above_uni_comp || !left_uni_comp {
+                2
+            } else {
+                3 + ((above0 == RefFrame::Bwdref as i8) == (left0 == RefFrame::Bwdref as i8)) as usize
+            }
+        } else if above.is_some() && left.is_some() {
+            if above_comp_inter {
+                1 + 2 * above_uni_comp as usize
+            } else if left_comp_inter {
+                1 + 2 * left_uni_comp as usize
+            } else {
+                2
+            }
+        } else if above_comp_inter {
+            4 * above_uni_comp as usize
+        } else if left_comp_inter {
+            4 * left_uni_comp as usize
+        } else {
+            2
+        }
+    }
+
+    /// 5.11.26 Assign MV syntax
+    fn assign_mv(&mut self, is_compound: bool) -> Result<()> {
+        for i in 0..1 + is_compound as usize {
+            let comp_mode = self.b.info.inter_mode.get_mode(i);
+            let stack = &self.b.mv_stack;
+            let pred_mv = if comp_mode == InterMode::GlobalMv {
+                stack.global_mvs[i]
+            } else {
+                let mut pos = if comp_mode == InterMode::NearestMv { 0 } else { self.b.info.ref_mv_idx as usize };
+                if comp_mode == InterMode::NewMv && stack.num_mv_found <= 1 {
+                    pos = 0;
+                }
+                stack.ref_stack_mv[pos][i]
+            };
+            self.b.info.mv[i] = if comp_mode == InterMode::NewMv { self.read_mv(pred_mv)? } else { pred_mv };
+        }
+        Ok(())
+    }
+
+    /// 5.11.32 MV syntax
+    fn read_mv(&mut self, pred_mv: [i32; 2]) -> Result<[i32; 2]> {
+        let mv_ctx = 0;
+        let mv_joint: MVJoint = (self.symbol.read_symbol(&mut self.cdf.mv[mv_ctx].mv_joint_cdf) as u8).into();
+        let (changes_row, changes_col) = mv_joint.changes_row_and_col()?;
+        let mut diff_mv = [0; 2];
+        if changes_row {
+            diff_mv[0] = self.read_mv_component(mv_ctx, 0);
+        }
+        if changes_col {
+            diff_mv[1] = self.read_mv_component(mv_ctx, 1);
+        }
+        Ok([pred_mv[0] + diff_mv[0], pred_mv[1] + diff_mv[1]])
+    }
+
+    /// 5.11.33 MV component syntax
+    fn read_mv_component(&mut self, mv_ctx: usize, comp: usize) -> i32 {
+        let cdf = &mut self.cdf.mv[mv_ctx];
+        let symbol = &mut self.symbol;
+        let force_integer_mv = self.fh.force_integer_mv;
+        let allow_high_precision_mv = self.fh.allow_high_precision_mv;
+        let mv_sign = symbol.read_symbol(&mut cdf.mv_sign_cdf[comp]) == 1;
+        let mv_class = symbol.read_symbol(&mut cdf.mv_class_cdf[comp]);
+        let mag = if mv_class == 0 {
+            let mv_class0_bit = symbol.read_symbol(&mut cdf.mv_class0_bit_cdf[comp]);
+            let mv_class0_fr = if force_integer_mv { 3 } else { symbol.read_symbol(&mut cdf.mv_class0_fr_cdf[comp][mv_class0_bit]) };
+            let mv_class0_hp = if allow_high_precision_mv { symbol.read_symbol(&mut cdf.mv_class0_hp_cdf[comp]) } else { 1 };
+            ((mv_class0_bit << 3) | (mv_class0_fr << 1) | mv_class0_hp) + 1
+        } else {
+            let mut d = 0;
+            for i in 0..mv_class {
+                d |= symbol.read_symbol(&mut cdf.mv_bit_cdf[comp][i]) << i;
+            }
+            let mag = CLASS0_SIZE << (mv_class + 2);
+            let mv_fr = if force_integer_mv { 3 } else { symbol.read_symbol(&mut cdf.mv_fr_cdf[comp]) };
+            let mv_hp = if allow_high_precision_mv { symbol.read_symbol(&mut cdf.mv_hp_cdf[comp]) } else { 1 };
+            mag + ((d << 3) | (mv_fr << 1) | mv_hp) + 1
+        };
+        if mv_sign { -(mag as i32) } else { mag as i32 }
+    }
+
+    /// 5.11.28 Read inter intra syntax
+    fn read_interintra_mode(&mut self, is_compound: bool) {
+        let mi_size = self.b.mi_size as u8;
+        let info = &mut self.b.info;
+        info.interintra = false;
+        if !info.skip_mode && self.seq.enable_interintra_compound && !is_compound
+            && (SubSize::Block8X8 as u8..=SubSize::Block32X32 as u8).contains(&mi_size) {
+            let ctx = SIZE_GROUP[mi_size as usize] as usize - 1;
+            info.interintra = self.symbol.read_symbol(&mut self.cdf.inter_intra_cdf[ctx]) == 1;
+            if info.interintra {
+                info.interintra_mode = (self.symbol.read_symbol(&mut self.cdf.inter_intra_mode_cdf[ctx]) as u8).into();
+                info.ref_frame[1] = INTRA_FRAME;
+                info.angle_delta_y = 0;
+                info.angle_delta_uv = 0;
+                info.use_filter_intra = false;
+                info.wedge_interintra = self.symbol.read_symbol(&mut self.cdf.wedge_inter_intra_cdf[mi_size as usize]) == 1;
+                if info.wedge_interintra {
+                    info.wedge_index = self.symbol.read_symbol(&mut self.cdf.wedge_index_cdf[mi_size as usize]) as u8;
+                    info.wedge_sign = 0;
+                }
+            }
+        }
+    }
+
+    /// 5.11.27 Read motion mode syntax
+    fn read_motion_mode(&mut self, is_compound: bool) {
+        let mi_size = self.b.mi_size as usize;
+        let info = &self.b.info;
+        let gm_type = self.fh.global_motion.gm_type[info.ref_frame[0] as usize];
+        let global = info.inter_mode == InterMode::GlobalMv || info.inter_mode == InterMode::GlobalGlobalMv;
+        if info.skip_mode || !self.fh.is_motion_mode_switchable || BLOCK_WIDTH[mi_size].min(BLOCK_HEIGHT[mi_size]) < 8
+            || (!self.fh.force_integer_mv && global && gm_type > TRANSLATION)
+            || is_compound || info.ref_frame[1] == INTRA_FRAME || !self.has_overlappable_candidates() {
+            self.b.info.motion_mode = MotionMode::Simple;
+            return;
+        }
+        self.find_warp_samples();
+        self.b.info.motion_mode = if self.fh.force_integer_mv || self.b.num_samples == 0 || !self.fh.allow_warped_motion
+            || self.is_scaled(self.b.info.ref_frame[0]) {
+            if self.symbol.read_symbol(&mut self.cdf.use_obmc_cdf[mi_size]) == 1 { MotionMode::Obmc } else { MotionMode::Simple }
+        } else {
+            (self.symbol.read_symbol(&mut self.cdf.motion_mode_cdf[mi_size]) as u8).into()
+        };
+    }
+
+    // has_overlappable_candidates，上方或左边有 inter 块
+    fn has_overlappable_candidates(&self) -> bool {
+        let (mi_rows, mi_cols) = (self.fh.mi_rows, self.fh.mi_cols);
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        if self.b.avail_u {
+            let w4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32;
+            for x4 in (mi_col..mi_cols.min(mi_col + w4)).step_by(2) {
+                let x5 = (x4 | 1).min(mi_cols - 1);
+                if self.mi.get(mi_row - 1, x5).ref_frame[0] > INTRA_FRAME {
+                    return true;
+                }
+            }
+        }
+        if self.b.avail_l {
+            let h4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as u32;
+            for y4 in (mi_row..mi_rows.min(mi_row + h4)).step_by(2) {
+                let y5 = (y4 | 1).min(mi_rows - 1);
+                if self.mi.get(y5, mi_col - 1).ref_frame[0] > INTRA_FRAME {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// 7.10.4 Find warp samples process
+    fn find_warp_samples(&mut self) {
+        self.b.num_samples = 0;
+        self.b.num_samples_scanned = 0;
+        let mi_size = self.b.mi_size as usize;
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        let w4 = NUM_4X4_BLOCKS_WIDE[mi_size] as u32;
+        let h4 = NUM_4X4_BLOCKS_HIGH[mi_size] as u32;
+        let (mut do_top_left, mut do_top_right) = (true, true);
+        if self.b.avail_u {
+            let src_w = NUM_4X4_BLOCKS_WIDE[self.mi.get(mi_row - 1, mi_col).mi_size as usize] as u32;
+            if w4 <= src_w {
+                let col_offset = mi_col & (src_w - 1);
+                if col_offset > 0 {
+                    do_top_left = false;
+                }
+                if src_w - col_offset > w4 {
+                    do_top_right = false;
+                }
+                self.add_sample(-1, 0);
+            } else {
+                let mut i = 0;
+                while i < w4.min(self.fh.mi_cols - mi_col) {
+                    let src_w = NUM_4X4_BLOCKS_WIDE[self.mi.get(mi_row - 1, mi_col + i).mi_size as usize] as u32;
+                    let step = src_w.max(NUM_4X4_BLOCKS_WIDE[SubSize::Block8X8 as usize] as u32);
+                    self.add_sample(-1, i as i64);
+                    i += step;
+                }
+            }
+        }
+        if self.b.avail_l {
+            let src_h = NUM_4X4_BLOCKS_HIGH[self.mi.get(mi_row, mi_col - 1).mi_size as usize] as u32;
+            if h4 <= src_h {
+                if mi_row & (src_h - 1) > 0 {
+                    do_top_left = false;
+                }
+                self.add_sample(0, -1);
+            } else {
+                let mut i = 0;
+                while i < h4.min(self.fh.mi_rows - mi_row) {
+                    let src_h = NUM_4X4_BLOCKS_HIGH[self.mi.get(mi_row + i, mi_col - 1).mi_size as usize] as u32;
+                    let step = src_h.max(NUM_4X4_BLOCKS_HIGH[SubSize::Block8X8 as usize] as u32);
+                    self.add_sample(i as i64, -1);
+                    i += step;
+                }
+            }
+        }
+        if do_top_left {
+            self.add_sample(-1, -1);
+        }
+        if do_top_right && w4.max(h4) <= 16 {
+            self.add_sample(-1, w4 as i64);
+        }
+        if self.b.num_samples == 0 && self.b.num_samples_scanned > 0 {
+            self.b.num_samples = 1;
+        }
+    }
+
+    /// 7.10.4.2 Add sample process
+    fn add_sample(&mut self, delta_row: i64, delta_col: i64) {
+        if self.b.num_samples_scanned >= LEAST_SQUARES_SAMPLES_MAX {
+            return;
+        }
+        let mv_row = self.b.mi_row as i64 + delta_row;
+        let mv_col = self.b.mi_col as i64 + delta_col;
+        if !self.is_inside(mv_row, mv_col) || !self.mi.is_decoded(mv_row as u32, mv_col as u32) {
+            return;
+        }
+        let cand = self.mi.get(mv_row as u32, mv_col as u32);
+        if cand.ref_frame[0] != self.b.info.ref_frame[0] || cand.ref_frame[1] != NONE_FRAME {
+            return;
+        }
+        let cand_w4 = NUM_4X4_BLOCKS_WIDE[cand.mi_size as usize] as i64;
+        let cand_h4 = NUM_4X4_BLOCKS_HIGH[cand.mi_size as usize] as i64;
+        let cand_row = mv_row & !(cand_h4 - 1);
+        let cand_col = mv_col & !(cand_w4 - 1);
+        let mid_y = (cand_row * 4 + cand_h4 * 4 / 2 - 1) as i32;
+        let mid_x = (cand_col * 4 + cand_w4 * 4 / 2 - 1) as i32;
+        let mi_size = self.b.mi_size as usize;
+        let threshold = clip3(16, 112, BLOCK_WIDTH[mi_size].max(BLOCK_HEIGHT[mi_size]) as i32);
+        let cand_mv = self.mi.get(cand_row as u32, cand_col as u32).mv[0];
+        let mv = self.b.info.mv[0];
+        let valid = (cand_mv[0] - mv[0]).abs() + (cand_mv[1] - mv[1]).abs() <= threshold;
+        let cand = [mid_y * 8, mid_x * 8, mid_y * 8 + cand_mv[0], mid_x * 8 + cand_mv[1]];
+        self.b.num_samples_scanned += 1;
+        if !valid && self.b.num_samples_scanned > 1 {
+            return;
+        }
+        self.b.cand_list[self.b.num_samples as usize] = cand;
+        if valid {
+            self.b.num_samples += 1;
+        }
+    }
+
+    // is_scaled，参考帧和当前帧尺寸不同
+    fn is_scaled(&self, ref_frame: i8) -> bool {
+        let Some(refs) = self.refs else {
+            return false;
+        };
+        let slot = &refs.slots[self.fh.ref_frame_idx[ref_frame as usize - LAST_FRAME]];
+        let no_scale = 1 << REF_SCALE_SHIFT;
+        let x_scale = ((slot.upscaled_width << REF_SCALE_SHIFT) + self.fh.frame_width / 2) / self.fh.frame_width;
+        let y_scale = ((slot.frame_height << REF_SCALE_SHIFT) + self.fh.frame_height / 2) / self.fh.frame_height;
+        x_scale != no_scale || y_scale != no_scale
+    }
+
+    /// 5.11.29 Read compound type syntax
+    fn read_compound_type(&mut self, is_compound: bool) {
+        let mi_size = self.b.mi_size as usize;
+        self.b.info.comp_group_idx = 0;
+        self.b.info.compound_idx = 1;
+        if self.b.info.skip_mode {
+            self.b.info.compound_type = CompoundType::Average;
+            return;
+        }
+        if !is_compound {
+            let info = &mut self.b.info;
+            info.compound_type = match (info.interintra, info.wedge_interintra) {
+                (true, true) => CompoundType::Wedge,
+                (true, false) => CompoundType::Intra,
+                _ => CompoundType::Average,
+            };
+            return;
+        }
+        let n = WEDGE_BITS[mi_size];
+        if self.seq.enable_masked_compound {
+            let ctx = self.comp_group_idx_ctx();
+            self.b.info.comp_group_idx = self.symbol.read_symbol(&mut self.cdf.comp_group_idx_cdf[ctx]) as u8;
+        }
+        let compound_type = if self.b.info.comp_group_idx == 0 {
+            if self.seq.enable_jnt_comp {
+                let ctx = self.compound_idx_ctx();
+                self.b.info.compound_idx = self.symbol.read_symbol(&mut self.cdf.compound_idx_cdf[ctx]) as u8;
+                if self.b.info.compound_idx == 1 { CompoundType::Average } else { CompoundType::Distance }
+            } else {
+                CompoundType::Average
+            }
+        } else if n == 0 {
+            CompoundType::Diffwtd
+        } else {
+            (self.symbol.read_symbol(&mut self.cdf.compound_type_cdf[mi_size]) as u8).into()
+        };
+        if compound_type == CompoundType::Wedge {
+            self.b.info.wedge_index = self.symbol.read_symbol(&mut self.cdf.wedge_index_cdf[mi_size]) as u8;
+            self.b.info.wedge_sign = self.symbol.read_literal(1) as u8;
+        } else if compound_type == CompoundType::Diffwtd {
+            self.b.info.mask_type = (self.symbol.read_literal(1) as u8).into();
+        }
+        self.b.info.compound_type = compound_type;
+    }
+
+    // comp_group_idx 的 ctx
+    fn comp_group_idx_ctx(&self) -> usize {
+        let ctx: usize = [self.above(), self.left()].into_iter().flatten().map(|info| {
+            if info.ref_frame[1] > INTRA_FRAME {
+                info.comp_group_idx as usize
+            } else if info.ref_frame[0] == RefFrame::Altref as i8 {
+                3
+            } else {
+                0
+            }
+        }).sum();
+        ctx.min(5)
+    }
+
+    // compound_idx 的 ctx
+    fn compound_idx_ctx(&self) -> usize {
+        let ref_frame = self.b.info.ref_frame;
+        let dist = |r: i8| self.seq.get_relative_dist(self.fh.order_hints[r as usize], self.fh.order_hint).abs();
+        let ctx = if dist(ref_frame[0]) == dist(ref_frame[1]) { 3 } else { 0 };
+        ctx + [self.above(), self.left()].into_iter().flatten().map(|info| {
+            if info.ref_frame[1] > INTRA_FRAME {
+                info.compound_idx as usize
+            } else {
+                (info.ref_frame[0] == RefFrame::Altref as i8) as usize
+            }
+        }).sum::<usize>()
+    }
+
+    // needs_interp_filter
+    fn needs_interp_filter(&self) -> bool {
+        let mi_size = self.b.mi_size as usize;
+        let info = &self.b.info;
+        let large = BLOCK_WIDTH[mi_size].min(BLOCK_HEIGHT[mi_size]) >= 8;
+        let gm_type = &self.fh.global_motion.gm_type;
+        if info.skip_mode || info.motion_mode == MotionMode::LocalWarp {
+            false
+        } else if large && info.inter_mode == InterMode::GlobalMv {
+            gm_type[info.ref_frame[0] as usize] == TRANSLATION
+        } else if large && info.inter_mode == InterMode::GlobalGlobalMv {
+            gm_type[info.ref_frame[0] as usize] == TRANSLATION || gm_type[info.ref_frame[1] as usize] == TRANSLATION
+        } else {
+            true
+        }
+    }
+
+    // interp_filter 的 ctx，使用同一参考帧的相邻块的滤波器
+    fn interp_filter_ctx(&self, dir: usize) -> usize {
+        let ref_frame = self.b.info.ref_frame;
+        let ctx = ((dir & 1) * 2 + (ref_frame[1] > INTRA_FRAME) as usize) * 4;
+        let filter_type = |info: Option<&ModeInfo>| match info {
+            Some(info) if info.ref_frame[0] == ref_frame[0] || info.ref_frame[1] == ref_frame[0] => info.interp_filter[dir] as usize,
+            _ => 3,
+        };
+        let left_type = filter_type(self.left());
+        let above_type = filter_type(self.above());
+        ctx + if left_type == above_type {
+            left_type
+        } else if left_type == 3 {
+            above_type
+        } else if above_type == 3 {
+            left_type
+        } else {
+            3
+        }
+    }
+
+    /// 7.10.2 Find MV stack process over the already decoded neighbours
+    fn find_mv_stack(&mut self) {
+        let predictor = MvPredictor {
+            fh: self.fh,
+            mi: self.mi,
+            motion_field: self.motion_field,
+            tile: [self.mi_row_start, self.mi_row_end, self.mi_col_start, self.mi_col_end],
+        };
+        let block = MvBlock { mi_row: self.b.mi_row, mi_col: self.b.mi_col, mi_size: self.b.mi_size, ref_frame: self.b.info.ref_frame };
+        self.b.mv_stack = predictor.find_mv_stack(&block);
+    }
+}
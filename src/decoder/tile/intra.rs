@@ -0,0 +1,478 @@
+use crate::{
+    decoder::{mode_info::ModeInfo, palette::{get_palette_cache, wavefront, ColorMap}, tile::TileDecoder},
+    entropy::CdfContext,
+    error::{Error, Result},
+    utils::{
+        consts::{BLOCK_HEIGHT, BLOCK_WIDTH, DELTA_LF_SMALL, DELTA_Q_SMALL, FRAME_LF_COUNT, INTRA_MODE_CONTEXT, MAX_ANGLE_DELTA, MAX_LOOP_FILTER, MI_HEIGHT_LOG2, MI_SIZE, MI_WIDTH_LOG2, NONE_FRAME, NUM_4X4_BLOCKS_HIGH, NUM_4X4_BLOCKS_WIDE, SEG_LVL_SKIP, SIZE_GROUP, SUBSAMPLED_SIZE},
+        enums::{IntraFrameYMode, RefFrame, SignUV, SubSize, UVMode},
+        math::{ceil_log2, clip3},
+    },
+};
+
+
+
+// 7.11.2 中 V_PRED 到 D67_PRED 是方向预测
+fn is_directional_mode(mode: u8) -> bool {
+    (IntraFrameYMode::V as u8..=IntraFrameYMode::D67 as u8).contains(&mode)
+}
+
+// palette_color_idx_y / palette_color_idx_uv 按调色板的大小选择 CDF
+fn palette_color_cdf(cdf: &mut CdfContext, plane: usize, palette_size: u8, ctx: usize) -> &mut [u32] {
+    match (plane > 0, palette_size) {
+        (false, 2) => &mut cdf.palette_size_2_y_color_cdf[ctx],
+        (false, 3) => &mut cdf.palette_size_3_y_color_cdf[ctx],
+        (false, 4) => &mut cdf.palette_size_4_y_color_cdf[ctx],
+        (false, 5) => &mut cdf.palette_size_5_y_color_cdf[ctx],
+        (false, 6) => &mut cdf.palette_size_6_y_color_cdf[ctx],
+        (false, 7) => &mut cdf.palette_size_7_y_color_cdf[ctx],
+        (false, _) => &mut cdf.palette_size_8_y_color_cdf[ctx],
+        (true, 2) => &mut cdf.palette_size_2_uv_color_cdf[ctx],
+        (true, 3) => &mut cdf.palette_size_3_uv_color_cdf[ctx],
+        (true, 4) => &mut cdf.palette_size_4_uv_color_cdf[ctx],
+        (true, 5) => &mut cdf.palette_size_5_uv_color_cdf[ctx],
+        (true, 6) => &mut cdf.palette_size_6_uv_color_cdf[ctx],
+        (true, 7) => &mut cdf.palette_size_7_uv_color_cdf[ctx],
+        (true, _) => &mut cdf.palette_size_8_uv_color_cdf[ctx],
+    }
+}
+
+// get_palette_cache 用到的亮度或 U 的调色板
+fn palette_colors(info: &ModeInfo, plane: usize) -> &[u16] {
+    if plane == 0 {
+        &info.palette_colors_y[..info.palette_size_y as usize]
+    } else {
+        &info.palette_colors_u[..info.palette_size_uv as usize]
+    }
+}
+
+
+/// neg_deinterleave, inverse of the segment id prediction mapping
+pub fn neg_deinterleave(diff: i32, r: i32, max: i32) -> i32 {
+    if r == 0 {
+        return diff;
+    }
+    if r >= max - 1 {
+        return max - diff - 1;
+    }
+    if 2 * r < max {
+        if diff <= 2 * r {
+            return if diff & 1 == 1 { r + ((diff + 1) >> 1) } else { r - (diff >> 1) };
+        }
+        diff
+    } else {
+        if diff <= 2 * (max - r - 1) {
+            return if diff & 1 == 1 { r + ((diff + 1) >> 1) } else { r - (diff >> 1) };
+        }
+        max - (diff + 1)
+    }
+}
+
+
+impl TileDecoder<'_> {
+    /// 5.11.7 Intra frame mode info syntax
+    pub(super) fn intra_frame_mode_info(&mut self) -> Result<()> {
+        let seg_id_pre_skip = self.fh.segmentation.seg_id_pre_skip;
+        if seg_id_pre_skip {
+            self.intra_segment_id();
+        }
+        self.b.info.skip_mode = false;
+        self.read_skip();
+        if !seg_id_pre_skip {
+            self.intra_segment_id();
+        }
+        self.read_cdef();
+        self.read_delta_qindex();
+        self.read_delta_lf();
+        self.read_deltas = false;
+        self.b.info.use_intrabc = self.fh.allow_intrabc && self.symbol.read_symbol(&mut self.cdf.intrabc_cdf) == 1;
+        if self.b.info.use_intrabc {
+            return Err(Error::Unsupported("intrabc"));
+        }
+        self.b.info.is_inter = false;
+        self.b.info.ref_frame = [RefFrame::Intra as i8, NONE_FRAME];
+        self.intra_frame_y_mode();
+        self.intra_modes()
+    }
+
+    /// 5.11.22 Intra block mode info syntax
+    pub(super) fn intra_block_mode_info(&mut self) -> Result<()> {
+        self.b.info.ref_frame = [RefFrame::Intra as i8, NONE_FRAME];
+        let ctx = SIZE_GROUP[self.b.mi_size as usize] as usize;
+        self.b.info.y_mode = (self.symbol.read_symbol(&mut self.cdf.y_mode_cdf[ctx]) as u8).into();
+        self.intra_modes()
+    }
+
+    // y_mode 之后的部分，intra frame 和 inter frame 中的 intra 块相同
+    fn intra_modes(&mut self) -> Result<()> {
+        self.intra_angle_info_y();
+        if self.b.has_chroma {
+            self.uv_mode();
+            if self.b.info.uv_mode == UVMode::UVCfl {
+                self.read_cfl_alphas()?;
+            }
+            self.intra_angle_info_uv();
+        }
+        self.b.info.palette_size_y = 0;
+        self.b.info.palette_size_uv = 0;
+        let mi_size = self.b.mi_size as usize;
+        if mi_size >= SubSize::Block8X8 as usize && BLOCK_WIDTH[mi_size] <= 64 && BLOCK_HEIGHT[mi_size] <= 64 && self.fh.allow_screen_content_tools {
+            self.palette_mode_info();
+        }
+        self.filter_intra_mode_info();
+        Ok(())
+    }
+
+    /// 5.11.8 Intra segment ID syntax
+    fn intra_segment_id(&mut self) {
+        self.b.info.segment_id = if self.fh.segmentation.segmentation_enabled {
+            self.read_segment_id()
+        } else {
+            0
+        };
+        self.b.lossless = self.fh.lossless_array[self.b.info.segment_id as usize];
+    }
+
+    /// 5.11.9 Read segment ID syntax
+    pub(super) fn read_segment_id(&mut self) -> u8 {
+        let prev_ul = if self.b.avail_u && self.b.avail_l {
+            self.mi.get(self.b.mi_row - 1, self.b.mi_col - 1).segment_id as i32
+        } else {
+            -1
+        };
+        let prev_u = self.above().map_or(-1, |above| above.segment_id as i32);
+        let prev_l = self.left().map_or(-1, |left| left.segment_id as i32);
+        let pred = if prev_u == -1 {
+            prev_l.max(0)
+        } else if prev_l == -1 || prev_ul == prev_u {
+            prev_u
+        } else {
+            prev_l
+        };
+        if self.b.info.skip {
+            return pred as u8;
+        }
+        let ctx = if prev_ul < 0 {
+            0
+        } else if prev_ul == prev_u && prev_ul == prev_l {
+            2
+        } else if prev_ul == prev_u || prev_ul == prev_l || prev_u == prev_l {
+            1
+        } else {
+            0
+        };
+        let segment_id = self.symbol.read_symbol(&mut self.cdf.segment_id_cdf[ctx]) as i32;
+        let last_active_seg_id = self.fh.segmentation.last_active_seg_id as i32;
+        let segment_id = neg_deinterleave(segment_id, pred, last_active_seg_id + 1);
+        clip3(0, last_active_seg_id, segment_id) as u8
+    }
+
+    /// 5.11.11 Skip syntax
+    pub(super) fn read_skip(&mut self) {
+        let seg = &self.fh.segmentation;
+        self.b.info.skip = if seg.seg_id_pre_skip && seg.feature_active(self.b.info.segment_id as usize, SEG_LVL_SKIP as usize) {
+            true
+        } else {
+            let ctx = self.above().is_some_and(|above| above.skip) as usize + self.left().is_some_and(|left| left.skip) as usize;
+            self.symbol.read_symbol(&mut self.cdf.skip_cdf[ctx]) == 1
+        };
+    }
+
+    /// 5.11.56 Read CDEF syntax
+    pub(super) fn read_cdef(&mut self) {
+        if self.b.info.skip || self.fh.coded_lossless || !self.seq.enable_cdef || self.fh.allow_intrabc {
+            return;
+        }
+        // 每个 64x64 块第一次出现非 skip 的块时读取
+        let cdef_size4 = NUM_4X4_BLOCKS_WIDE[SubSize::Block64X64 as usize] as u32;
+        let cdef_mask4 = !(cdef_size4 - 1);
+        let r = self.b.mi_row & cdef_mask4;
+        let c = self.b.mi_col & cdef_mask4;
+        if self.mi.cdef_idx(r, c) == -1 {
+            let cdef_idx = self.symbol.read_literal(self.fh.cdef.cdef_bits) as i8;
+            let w4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32;
+            let h4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as u32;
+            for y in (r..self.b.mi_row + h4).step_by(cdef_size4 as usize) {
+                for x in (c..self.b.mi_col + w4).step_by(cdef_size4 as usize) {
+                    self.mi.set_cdef_idx(y, x, cdef_idx);
+                }
+            }
+        }
+    }
+
+    /// 5.11.12 Quantizer index delta syntax
+    pub(super) fn read_delta_qindex(&mut self) {
+        if self.b.mi_size == self.sb_size() && self.b.info.skip {
+            return;
+        }
+        if self.read_deltas {
+            let mut delta_q_abs = self.symbol.read_symbol(&mut self.cdf.delta_q_cdf) as i32;
+            if delta_q_abs == DELTA_Q_SMALL as i32 {
+                let delta_q_rem_bits = self.symbol.read_literal(3) as u8 + 1;
+                let delta_q_abs_bits = self.symbol.read_literal(delta_q_rem_bits) as i32;
+                delta_q_abs = delta_q_abs_bits + (1 << delta_q_rem_bits) + 1;
+            }
+            if delta_q_abs != 0 {
+                let delta_q_sign_bit = self.symbol.read_literal(1);
+                let reduced_delta_qindex = if delta_q_sign_bit == 1 { -delta_q_abs } else { delta_q_abs };
+                let qindex = self.current_q_index as i32 + (reduced_delta_qindex << self.fh.delta.delta_q_res);
+                self.current_q_index = clip3(1, 255, qindex) as u8;
+            }
+        }
+    }
+
+    /// 5.11.13 Loop filter delta syntax
+    pub(super) fn read_delta_lf(&mut self) {
+        if self.b.mi_size == self.sb_size() && self.b.info.skip {
+            return;
+        }
+        let delta = &self.fh.delta;
+        if self.read_deltas && delta.delta_lf_present {
+            let frame_lf_count = if !delta.delta_lf_multi {
+                1
+            } else if self.seq.color_config.mono_chrome {
+                FRAME_LF_COUNT - 2
+            } else {
+                FRAME_LF_COUNT
+            };
+            for i in 0..frame_lf_count {
+                let cdf = if delta.delta_lf_multi { &mut self.cdf.delta_lf_multi_cdf[i] } else { &mut self.cdf.delta_lf_cdf };
+                let mut delta_lf_abs = self.symbol.read_symbol(cdf) as i32;
+                if delta_lf_abs == DELTA_LF_SMALL as i32 {
+                    let delta_lf_rem_bits = self.symbol.read_literal(3) as u8 + 1;
+                    let delta_lf_abs_bits = self.symbol.read_literal(delta_lf_rem_bits) as i32;
+                    delta_lf_abs = delta_lf_abs_bits + (1 << delta_lf_rem_bits) + 1;
+                }
+                if delta_lf_abs != 0 {
+                    let delta_lf_sign_bit = self.symbol.read_literal(1);
+                    let reduced_delta_lf_level = if delta_lf_sign_bit == 1 { -delta_lf_abs } else { delta_lf_abs };
+                    let level = self.delta_lf[i] as i32 + (reduced_delta_lf_level << delta.delta_lf_res);
+                    self.delta_lf[i] = clip3(-(MAX_LOOP_FILTER as i32), MAX_LOOP_FILTER as i32, level) as i8;
+                }
+            }
+        }
+    }
+
+    // intra_frame_y_mode，ctx 来自上方和左边块的模式
+    fn intra_frame_y_mode(&mut self) {
+        let above_mode = self.above().map_or(IntraFrameYMode::Dc, |above| above.y_mode);
+        let left_mode = self.left().map_or(IntraFrameYMode::Dc, |left| left.y_mode);
+        let above_ctx = INTRA_MODE_CONTEXT[above_mode as usize] as usize;
+        let left_ctx = INTRA_MODE_CONTEXT[left_mode as usize] as usize;
+        let cdf = &mut self.cdf.intra_frame_y_mode_cdf[above_ctx][left_ctx];
+        self.b.info.y_mode = (self.symbol.read_symbol(cdf) as u8).into();
+    }
+
+    /// 5.11.42 Intra angle info luma syntax
+    fn intra_angle_info_y(&mut self) {
+        let mode = self.b.info.y_mode as u8;
+        self.b.info.angle_delta_y = if self.b.mi_size as u8 >= SubSize::Block8X8 as u8 && is_directional_mode(mode) {
+            let cdf = &mut self.cdf.angle_delta_cdf[(mode - IntraFrameYMode::V as u8) as usize];
+            self.symbol.read_symbol(cdf) as i8 - MAX_ANGLE_DELTA as i8
+        } else {
+            0
+        };
+    }
+
+    // uv_mode，块足够小时可以使用 CFL
+    fn uv_mode(&mut self) {
+        let mi_size = self.b.mi_size as usize;
+        let cc = &self.seq.color_config;
+        let residual_size = SUBSAMPLED_SIZE[mi_size][cc.subsampling_x as usize][cc.subsampling_y as usize];
+        let cfl_allowed = if self.b.lossless {
+            residual_size == SubSize::Block4X4 as u8
+        } else {
+            BLOCK_WIDTH[mi_size].max(BLOCK_HEIGHT[mi_size]) <= 32
+        };
+        let y_mode = self.b.info.y_mode as usize;
+        let cdf = if cfl_allowed {
+            &mut self.cdf.uv_mode_cfl_allowed_cdf[y_mode][..]
+        } else {
+            &mut self.cdf.uv_mode_cfl_not_allowed_cdf[y_mode][..]
+        };
+        self.b.info.uv_mode = (self.symbol.read_symbol(cdf) as u8).into();
+    }
+
+    /// 5.11.45 Read CFL alphas syntax
+    fn read_cfl_alphas(&mut self) -> Result<()> {
+        let cfl_alpha_signs = self.symbol.read_symbol(&mut self.cdf.cfl_sign_cdf) as u8;
+        let (sign_u, sign_v) = SignUV::from_cfl_alpha_signs(cfl_alpha_signs)?;
+        let mut read_alpha = |sign: SignUV, other: SignUV| {
+            if sign == SignUV::Zero {
+                return 0;
+            }
+            let ctx = (sign as usize - 1) * 3 + other as usize;
+            let alpha = 1 + self.symbol.read_symbol(&mut self.cdf.cfl_alpha_cdf[ctx]) as i8;
+            if sign == SignUV::Neg { -alpha } else { alpha }
+        };
+        self.b.info.cfl_alpha_u = read_alpha(sign_u, sign_v);
+        self.b.info.cfl_alpha_v = read_alpha(sign_v, sign_u);
+        Ok(())
+    }
+
+    /// 5.11.43 Intra angle info chroma syntax
+    fn intra_angle_info_uv(&mut self) {
+        let mode = self.b.info.uv_mode as u8;
+        self.b.info.angle_delta_uv = if self.b.mi_size as u8 >= SubSize::Block8X8 as u8 && is_directional_mode(mode) {
+            let cdf = &mut self.cdf.angle_delta_cdf[(mode - UVMode::V as u8) as usize];
+            self.symbol.read_symbol(cdf) as i8 - MAX_ANGLE_DELTA as i8
+        } else {
+            0
+        };
+    }
+
+    /// 5.11.46 Palette mode info syntax
+    fn palette_mode_info(&mut self) {
+        let mi_size = self.b.mi_size as usize;
+        let bsize_ctx = (MI_WIDTH_LOG2[mi_size] + MI_HEIGHT_LOG2[mi_size] - 2) as usize;
+        if self.b.info.y_mode == IntraFrameYMode::Dc {
+            let ctx = self.above().is_some_and(|above| above.palette_size_y > 0) as usize
+                + self.left().is_some_and(|left| left.palette_size_y > 0) as usize;
+            let has_palette_y = self.symbol.read_symbol(&mut self.cdf.palette_y_mode_cdf[bsize_ctx][ctx]) == 1;
+            if has_palette_y {
+                let palette_size = self.symbol.read_symbol(&mut self.cdf.palette_y_size_cdf[bsize_ctx]) as u8 + 2;
+                self.b.info.palette_size_y = palette_size;
+                let cache = self.palette_cache(0);
+                self.b.info.palette_colors_y = self.read_palette_colors(palette_size, &cache, 1);
+            }
+        }
+        if self.b.has_chroma && self.b.info.uv_mode == UVMode::Dc {
+            let ctx = (self.b.info.palette_size_y > 0) as usize;
+            let has_palette_uv = self.symbol.read_symbol(&mut self.cdf.palette_uv_mode_cdf[ctx]) == 1;
+            if has_palette_uv {
+                let palette_size = self.symbol.read_symbol(&mut self.cdf.palette_uv_size_cdf[bsize_ctx]) as u8 + 2;
+                self.b.info.palette_size_uv = palette_size;
+                let cache = self.palette_cache(1);
+                self.b.info.palette_colors_u = self.read_palette_colors(palette_size, &cache, 0);
+                self.b.info.palette_colors_v = self.read_palette_colors_v(palette_size);
+            }
+        }
+    }
+
+    // get_palette_cache，上方的块在上一个 64 行时不使用
+    fn palette_cache(&self, plane: usize) -> Vec<u16> {
+        let above = match self.above() {
+            Some(above) if !(self.b.mi_row * MI_SIZE as u32).is_multiple_of(64) => palette_colors(above, plane),
+            _ => &[],
+        };
+        let left = self.left().map_or(&[][..], |left| palette_colors(left, plane));
+        get_palette_cache(above, left)
+    }
+
+    // 亮度和 U 的颜色：先从 cache 中选，其余的按递增的差值编码，亮度的差值至少为 1
+    fn read_palette_colors(&mut self, palette_size: u8, cache: &[u16], min_delta: u32) -> [u16; 8] {
+        let bit_depth = self.seq.color_config.bit_depth;
+        let n = palette_size as usize;
+        let mut colors = [0; 8];
+        let mut idx = 0;
+        for &color in cache {
+            if idx == n {
+                break;
+            }
+            if self.symbol.read_literal(1) == 1 {
+                colors[idx] = color;
+                idx += 1;
+            }
+        }
+        if idx < n {
+            colors[idx] = self.symbol.read_literal(bit_depth) as u16;
+            idx += 1;
+        }
+        if idx < n {
+            let mut palette_bits = bit_depth - 3 + self.symbol.read_literal(2) as u8;
+            while idx < n {
+                let delta = self.symbol.read_literal(palette_bits) + min_delta;
+                colors[idx] = (colors[idx - 1] as u32 + delta).min((1 << bit_depth) - 1) as u16;
+                let range = (1 << bit_depth) - colors[idx] as u32 - min_delta;
+                palette_bits = palette_bits.min(ceil_log2(range));
+                idx += 1;
+            }
+        }
+        colors[..n].sort_unstable();
+        colors
+    }
+
+    // V 的颜色不排序，可以按带符号的差值编码，超出范围时回绕
+    fn read_palette_colors_v(&mut self, palette_size: u8) -> [u16; 8] {
+        let bit_depth = self.seq.color_config.bit_depth;
+        let mut colors = [0; 8];
+        let delta_encode_palette_colors_v = self.symbol.read_literal(1) == 1;
+        if delta_encode_palette_colors_v {
+            let max_val = 1 << bit_depth;
+            let palette_bits = bit_depth - 4 + self.symbol.read_literal(2) as u8;
+            colors[0] = self.symbol.read_literal(bit_depth) as u16;
+            for idx in 1..palette_size as usize {
+                let mut palette_delta_v = self.symbol.read_literal(palette_bits) as i32;
+                if palette_delta_v != 0 && self.symbol.read_literal(1) == 1 {
+                    palette_delta_v = -palette_delta_v;
+                }
+                let mut val = colors[idx - 1] as i32 + palette_delta_v;
+                if val < 0 {
+                    val += max_val;
+                }
+                if val >= max_val {
+                    val -= max_val;
+                }
+                colors[idx] = val.clamp(0, max_val - 1) as u16;
+            }
+        } else {
+            for color in colors.iter_mut().take(palette_size as usize) {
+                *color = self.symbol.read_literal(bit_depth) as u16;
+            }
+        }
+        colors
+    }
+
+    /// 5.11.49 Palette tokens syntax
+    pub(super) fn palette_tokens(&mut self) {
+        let mi_size = self.b.mi_size as usize;
+        let block_width = BLOCK_WIDTH[mi_size] as usize;
+        let block_height = BLOCK_HEIGHT[mi_size] as usize;
+        let onscreen_width = block_width.min(((self.fh.mi_cols - self.b.mi_col) * MI_SIZE as u32) as usize);
+        let onscreen_height = block_height.min(((self.fh.mi_rows - self.b.mi_row) * MI_SIZE as u32) as usize);
+        if self.b.info.palette_size_y > 0 {
+            self.b.color_map_y = self.read_color_map(0, block_width, block_height, onscreen_width, onscreen_height);
+        }
+        if self.b.info.palette_size_uv > 0 {
+            let (sub_x, sub_y) = self.plane_subsampling(1);
+            let (mut block_width, mut onscreen_width) = (block_width >> sub_x, onscreen_width >> sub_x);
+            let (mut block_height, mut onscreen_height) = (block_height >> sub_y, onscreen_height >> sub_y);
+            // 4x16 和 16x4 的色度只有 2 个样本宽或高，补到 4
+            if block_width < 4 {
+                block_width += 2;
+                onscreen_width += 2;
+            }
+            if block_height < 4 {
+                block_height += 2;
+                onscreen_height += 2;
+            }
+            self.b.color_map_uv = self.read_color_map(1, block_width, block_height, onscreen_width, onscreen_height);
+        }
+    }
+
+    // ColorMapY / ColorMapUV，按 wavefront 的顺序读取帧内的部分
+    fn read_color_map(&mut self, plane: usize, width: usize, height: usize, onscreen_width: usize, onscreen_height: usize) -> ColorMap {
+        let palette_size = if plane == 0 { self.b.info.palette_size_y } else { self.b.info.palette_size_uv };
+        let mut map = ColorMap::new(width, height);
+        map.set(0, 0, self.symbol.read_ns(palette_size as u32) as u8);
+        for (row, col) in wavefront(onscreen_width, onscreen_height).skip(1) {
+            let (ctx, color_order) = map.color_context(row, col, palette_size as usize);
+            let palette_color_idx = self.symbol.read_symbol(palette_color_cdf(&mut self.cdf, plane, palette_size, ctx));
+            map.set(row, col, color_order[palette_color_idx]);
+        }
+        map.extend(onscreen_width, onscreen_height);
+        map
+    }
+
+    /// 5.11.24 Filter intra mode info syntax
+    fn filter_intra_mode_info(&mut self) {
+        let mi_size = self.b.mi_size as usize;
+        self.b.info.use_filter_intra = false;
+        if self.seq.enable_filter_intra && self.b.info.y_mode == IntraFrameYMode::Dc && self.b.info.palette_size_y == 0
+            && BLOCK_WIDTH[mi_size].max(BLOCK_HEIGHT[mi_size]) <= 32 {
+            self.b.info.use_filter_intra = self.symbol.read_symbol(&mut self.cdf.filter_intra_cdf[mi_size]) == 1;
+            if self.b.info.use_filter_intra {
+                self.b.info.filter_intra_mode = (self.symbol.read_symbol(&mut self.cdf.filter_intra_mode_cdf) as u8).into();
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+// ! 5.11 Tile group OBU syntax, decode_tile and the block level syntax
+
+use crate::{
+    decoder::{intra_pred::IntraPrediction, mode_info::{ModeInfo, ModeInfoGrid}, motion_field::MotionFieldMvs, mvpred::MvStack, palette::{ColorMap, PalettePrediction}, tile::{residual::TransformBlock, restoration::RestorationUnit}},
+    entropy::{CdfContext, SymbolDecoder},
+    error::Result,
+    obu::{frame::{FrameHeader, RefFrameState}, sequence::SequenceHeader, tile_group::Tile},
+    utils::{consts::{FRAME_LF_COUNT, LEAST_SQUARES_SAMPLES_MAX, NUM_4X4_BLOCKS_WIDE, SGRPROJ_XQD_MID, WIENER_COEFFS, WIENER_TAPS_MID}, enums::{SubSize, TxSize, TxType}},
+};
+
+pub mod block;
+pub mod inter;
+pub mod intra;
+pub mod residual;
+pub mod restoration;
+
+
+
+// BlockDecoded 每行的长度，128x128 超级块的 32 个 4x4 加上两边各一个
+const BLOCK_DECODED_STRIDE: usize = 34;
+
+// 正在解码的块，对应规范里的 MiRow / MiCol / MiSize / HasChroma / AvailU 等全局变量
+#[derive(Debug, Default)]
+struct Block {
+    mi_row: u32,
+    mi_col: u32,
+    mi_size: SubSize,
+    has_chroma: bool,
+    avail_u: bool,
+    avail_l: bool,
+    avail_u_chroma: bool,
+    avail_l_chroma: bool,
+    lossless: bool,
+    info: ModeInfo,
+    // ColorMapY / ColorMapUV
+    color_map_y: ColorMap,
+    color_map_uv: ColorMap,
+    // read_var_tx_size 读到的 InterTxSizes，块内按光栅顺序，每个 4x4 一项
+    inter_tx_sizes: Vec<TxSize>,
+    mv_stack: MvStack,
+    // 7.10.4 find_warp_samples 的结果
+    num_samples: u8,
+    num_samples_scanned: u8,
+    cand_list: [[i32; 4]; LEAST_SQUARES_SAMPLES_MAX as usize],
+}
+
+
+/// 5.11.2 Decode tile syntax, writes the mode info of one tile into the frame grid
+pub struct TileDecoder<'a> {
+    seq: &'a SequenceHeader,
+    fh: &'a FrameHeader,
+    refs: Option<&'a RefFrameState>,
+    motion_field: Option<&'a MotionFieldMvs>,
+    symbol: SymbolDecoder<'a>,
+    cdf: CdfContext,
+    mi: &'a mut ModeInfoGrid,
+    mi_row_start: u32,
+    mi_row_end: u32,
+    mi_col_start: u32,
+    mi_col_end: u32,
+    current_q_index: u8,
+    delta_lf: [i8; FRAME_LF_COUNT],
+    read_deltas: bool,
+    b: Block,
+    // AboveLevelContext / AboveDcContext / LeftLevelContext / LeftDcContext，按 plane 中 4x4 的绝对位置索引
+    above_level_context: [Vec<u8>; 3],
+    above_dc_context: [Vec<u8>; 3],
+    left_level_context: [Vec<u8>; 3],
+    left_dc_context: [Vec<u8>; 3],
+    // TxTypes，每个亮度 4x4 一个
+    tx_types: Vec<TxType>,
+    // BlockDecoded，当前超级块内每个 4x4 是否已解码，下标从 -1 开始
+    block_decoded: [Vec<bool>; 3],
+    // MaxLumaW / MaxLumaH，最后一个亮度变换块的右边界和下边界，CFL 用来限制亮度的范围
+    max_luma_w: u32,
+    max_luma_h: u32,
+    // RefLrWiener / RefSgrXqd，每个 tile 开始时重置
+    ref_lr_wiener: [[[i32; WIENER_COEFFS as usize]; 2]; 3],
+    ref_sgr_xqd: [[i32; 2]; 3],
+    restoration_units: Vec<RestorationUnit>,
+    residuals: Vec<TransformBlock>,
+    intra_predictions: Vec<IntraPrediction>,
+    palette_predictions: Vec<PalettePrediction>,
+}
+
+impl<'a> TileDecoder<'a> {
+    /// Starts the symbol decoder on the tile data, cdf is the context the tile starts from
+    pub fn new(seq: &'a SequenceHeader, fh: &'a FrameHeader, tile: &Tile<'a>, cdf: CdfContext, mi: &'a mut ModeInfoGrid) -> Result<Self> {
+        let info = &fh.tile_info;
+        let (row, col) = (tile.tile_row as usize, tile.tile_col as usize);
+        // 块可以超出帧的右边和下边，最多一个 128x128 超级块
+        let above = vec![0; fh.mi_cols as usize + 32];
+        let left = vec![0; fh.mi_rows as usize + 32];
+        Ok(Self {
+            seq,
+            fh,
+            refs: None,
+            motion_field: None,
+            symbol: SymbolDecoder::init_symbol(tile.data, fh.disable_cdf_update)?,
+            cdf,
+            mi,
+            mi_row_start: info.mi_row_starts[row],
+            mi_row_end: info.mi_row_starts[row + 1],
+            mi_col_start: info.mi_col_starts[col],
+            mi_col_end: info.mi_col_starts[col + 1],
+            current_q_index: fh.quantization.base_q_idx,
+            delta_lf: [0; FRAME_LF_COUNT],
+            read_deltas: false,
+            b: Block::default(),
+            above_level_context: [above.clone(), above.clone(), above.clone()],
+            above_dc_context: [above.clone(), above.clone(), above],
+            left_level_context: [left.clone(), left.clone(), left.clone()],
+            left_dc_context: [left.clone(), left.clone(), left],
+            tx_types: vec![TxType::DctDct; (fh.mi_rows * fh.mi_cols) as usize],
+            block_decoded: [vec![false; BLOCK_DECODED_STRIDE * BLOCK_DECODED_STRIDE], vec![false; BLOCK_DECODED_STRIDE * BLOCK_DECODED_STRIDE], vec![false; BLOCK_DECODED_STRIDE * BLOCK_DECODED_STRIDE]],
+            max_luma_w: 0,
+            max_luma_h: 0,
+            ref_lr_wiener: [[WIENER_TAPS_MID; 2]; 3],
+            ref_sgr_xqd: [SGRPROJ_XQD_MID; 3],
+            restoration_units: Vec::new(),
+            residuals: Vec::new(),
+            intra_predictions: Vec::new(),
+            palette_predictions: Vec::new(),
+        })
+    }
+
+    /// Reference slots of an inter frame, without them every reference is taken to be unscaled and
+    /// there are no previous segment ids
+    pub fn set_ref_frames(&mut self, refs: &'a RefFrameState) {
+        self.refs = Some(refs);
+    }
+
+    /// Projected motion field for the temporal candidates, without it they are all unavailable
+    pub fn set_motion_field(&mut self, motion_field: &'a MotionFieldMvs) {
+        self.motion_field = Some(motion_field);
+    }
+
+    pub fn decode_tile(&mut self) -> Result<()> {
+        let sb_size = self.sb_size();
+        let sb_size4 = NUM_4X4_BLOCKS_WIDE[sb_size as usize] as u32;
+        for r in (self.mi_row_start..self.mi_row_end).step_by(sb_size4 as usize) {
+            self.clear_left_context();
+            for c in (self.mi_col_start..self.mi_col_end).step_by(sb_size4 as usize) {
+                self.read_deltas = self.fh.delta.delta_q_present;
+                self.clear_block_decoded_flags(r, c, sb_size4);
+                self.clear_cdef(r, c);
+                self.read_lr(r, c, sb_size);
+                self.decode_partition(r, c, sb_size)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loop restoration parameters of the units read so far, in decoding order
+    pub fn restoration_units(&self) -> &[RestorationUnit] {
+        &self.restoration_units
+    }
+
+    /// Coefficients of the transform blocks decoded so far, in decoding order
+    pub fn residuals(&self) -> &[TransformBlock] {
+        &self.residuals
+    }
+
+    /// Intra prediction inputs of the transform blocks of intra blocks without a palette, in decoding order
+    pub fn intra_predictions(&self) -> &[IntraPrediction] {
+        &self.intra_predictions
+    }
+
+    /// Predictions of the transform blocks of palette blocks, in decoding order
+    pub fn palette_predictions(&self) -> &[PalettePrediction] {
+        &self.palette_predictions
+    }
+
+    /// 8.2.4 exit_symbol, returns the CDFs as adapted by this tile
+    pub fn finish(self) -> Result<CdfContext> {
+        self.symbol.exit_symbol()?;
+        Ok(self.cdf)
+    }
+
+    fn sb_size(&self) -> SubSize {
+        if self.seq.use_128x128_superblock {
+            SubSize::Block128X128
+        } else {
+            SubSize::Block64X64
+        }
+    }
+
+    // is_inside，位置是否在当前 tile 内
+    fn is_inside(&self, cand_r: i64, cand_c: i64) -> bool {
+        cand_c >= self.mi_col_start as i64 && cand_c < self.mi_col_end as i64
+            && cand_r >= self.mi_row_start as i64 && cand_r < self.mi_row_end as i64
+    }
+
+    // clear_left_context，每个超级块行开始时清除
+    fn clear_left_context(&mut self) {
+        for context in self.left_level_context.iter_mut().chain(self.left_dc_context.iter_mut()) {
+            context.fill(0);
+        }
+    }
+
+    // clear_block_decoded_flags，每个超级块开始时设置，上方和左边在 tile 内的部分已解码
+    fn clear_block_decoded_flags(&mut self, r: u32, c: u32, sb_size4: u32) {
+        let num_planes = self.seq.color_config.num_planes as usize;
+        for plane in 0..num_planes {
+            let (sub_x, sub_y) = self.plane_subsampling(plane);
+            let sb_width4 = ((self.mi_col_end - c) >> sub_x) as i32;
+            let sb_height4 = ((self.mi_row_end - r) >> sub_y) as i32;
+            for y in -1..=(sb_size4 >> sub_y) as i32 {
+                for x in -1..=(sb_size4 >> sub_x) as i32 {
+                    let decoded = (y < 0 && x < sb_width4) || (x < 0 && y < sb_height4);
+                    self.set_block_decoded(plane, y, x, decoded);
+                }
+            }
+            self.set_block_decoded(plane, (sb_size4 >> sub_y) as i32, -1, false);
+        }
+    }
+
+    fn block_decoded(&self, plane: usize, y: i32, x: i32) -> bool {
+        self.block_decoded[plane][((y + 1) as usize) * BLOCK_DECODED_STRIDE + (x + 1) as usize]
+    }
+
+    fn set_block_decoded(&mut self, plane: usize, y: i32, x: i32, decoded: bool) {
+        self.block_decoded[plane][((y + 1) as usize) * BLOCK_DECODED_STRIDE + (x + 1) as usize] = decoded;
+    }
+
+    // clear_cdef，每个超级块开始时清除
+    fn clear_cdef(&mut self, r: u32, c: u32) {
+        self.mi.set_cdef_idx(r, c, -1);
+        if self.seq.use_128x128_superblock {
+            self.mi.set_cdef_idx(r, c + 16, -1);
+            self.mi.set_cdef_idx(r + 16, c, -1);
+            self.mi.set_cdef_idx(r + 16, c + 16, -1);
+        }
+    }
+
+    // 上方和左边的 mode info，不可用时为 None
+    fn above(&self) -> Option<&ModeInfo> {
+        self.b.avail_u.then(|| self.mi.get(self.b.mi_row - 1, self.b.mi_col))
+    }
+
+    fn left(&self) -> Option<&ModeInfo> {
+        self.b.avail_l.then(|| self.mi.get(self.b.mi_row, self.b.mi_col - 1))
+    }
+}
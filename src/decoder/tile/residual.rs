@@ -0,0 +1,659 @@
+// ! 5.11.34 - 5.11.39 Residual syntax, the coefficients of every transform block
+
+use crate::{
+    decoder::{intra_pred::{ChromaFromLuma, IntraPrediction}, mode_info::ModeInfo, palette::{predict_palette, PalettePrediction}, quant::Quantizer, tile::TileDecoder},
+    error::{Error, Result},
+    utils::{
+        consts::*,
+        enums::{IntraFrameYMode, RefFrame, TxSize, TxType, UVMode},
+        scan::*,
+    },
+};
+
+
+
+/// Coefficients of one transform block with eob > 0, quant is in the scan layout of the coded size
+/// (at most 32x32, the rest of a 64 point transform is zero), dequant is the same layout after 7.12.3
+#[derive(Debug, PartialEq, Clone)]
+pub struct TransformBlock {
+    pub plane: u8,
+    pub start_x: u32,
+    pub start_y: u32,
+    pub tx_size: TxSize,
+    pub tx_type: TxType,
+    pub eob: u16,
+    pub quant: Vec<i32>,
+    pub dequant: Vec<i32>,
+}
+
+
+fn get_default_scan(tx_size: TxSize) -> &'static [u16] {
+    match tx_size {
+        TxSize::Tx4X4 => &DEFAULT_SCAN_4X4,
+        TxSize::Tx8X8 => &DEFAULT_SCAN_8X8,
+        TxSize::Tx16X16 => &DEFAULT_SCAN_16X16,
+        TxSize::Tx4X8 => &DEFAULT_SCAN_4X8,
+        TxSize::Tx8X4 => &DEFAULT_SCAN_8X4,
+        TxSize::Tx8X16 => &DEFAULT_SCAN_8X16,
+        TxSize::Tx16X8 => &DEFAULT_SCAN_16X8,
+        TxSize::Tx16X32 => &DEFAULT_SCAN_16X32,
+        TxSize::Tx32X16 => &DEFAULT_SCAN_32X16,
+        TxSize::Tx4X16 => &DEFAULT_SCAN_4X16,
+        TxSize::Tx16X4 => &DEFAULT_SCAN_16X4,
+        TxSize::Tx8X32 => &DEFAULT_SCAN_8X32,
+        TxSize::Tx32X8 => &DEFAULT_SCAN_32X8,
+        _ => &DEFAULT_SCAN_32X32,
+    }
+}
+
+fn get_mrow_scan(tx_size: TxSize) -> &'static [u16] {
+    match tx_size {
+        TxSize::Tx4X4 => &MROW_SCAN_4X4,
+        TxSize::Tx4X8 => &MROW_SCAN_4X8,
+        TxSize::Tx8X4 => &MROW_SCAN_8X4,
+        TxSize::Tx8X8 => &MROW_SCAN_8X8,
+        TxSize::Tx8X16 => &MROW_SCAN_8X16,
+        TxSize::Tx16X8 => &MROW_SCAN_16X8,
+        TxSize::Tx4X16 => &MROW_SCAN_4X16,
+        TxSize::Tx16X4 => &MROW_SCAN_16X4,
+        _ => &MROW_SCAN_16X16,
+    }
+}
+
+fn get_mcol_scan(tx_size: TxSize) -> &'static [u16] {
+    match tx_size {
+        TxSize::Tx4X4 => &MCOL_SCAN_4X4,
+        TxSize::Tx4X8 => &MCOL_SCAN_4X8,
+        TxSize::Tx8X4 => &MCOL_SCAN_8X4,
+        TxSize::Tx8X8 => &MCOL_SCAN_8X8,
+        TxSize::Tx8X16 => &MCOL_SCAN_8X16,
+        TxSize::Tx16X8 => &MCOL_SCAN_16X8,
+        TxSize::Tx4X16 => &MCOL_SCAN_4X16,
+        TxSize::Tx16X4 => &MCOL_SCAN_16X4,
+        _ => &MCOL_SCAN_16X16,
+    }
+}
+
+/// get_scan, the scan order of a transform block given its type
+pub fn get_scan(tx_size: TxSize, tx_type: TxType) -> &'static [u16] {
+    match tx_size {
+        TxSize::Tx16X64 => return &DEFAULT_SCAN_16X32,
+        TxSize::Tx64X16 => return &DEFAULT_SCAN_32X16,
+        _ if TX_SIZE_SQR_UP[tx_size as usize] == TxSize::Tx64X64 as u8 => return &DEFAULT_SCAN_32X32,
+        _ => {}
+    }
+    match tx_type.tx_class() {
+        _ if tx_type == TxType::Idtx => get_default_scan(tx_size),
+        TX_CLASS_VERT => get_mrow_scan(tx_size),
+        TX_CLASS_HORIZ => get_mcol_scan(tx_size),
+        _ => get_default_scan(tx_size),
+    }
+}
+
+/// 5.11.40 Compute transform type process. luma_tx_type is the TxTypes entry of the transform block,
+/// for inter chroma the one of the co-located luma; intra chroma uses the type implied by uv_mode
+pub fn compute_tx_type(plane: usize, tx_size: TxSize, info: &ModeInfo, lossless: bool, reduced_tx_set: bool, luma_tx_type: TxType) -> TxType {
+    if lossless {
+        return TxType::WhtWht;
+    }
+    if TX_SIZE_SQR_UP[tx_size as usize] > TxSize::Tx32X32 as u8 {
+        return TxType::DctDct;
+    }
+    if plane == 0 {
+        return luma_tx_type;
+    }
+    let tx_set = tx_size.tx_set(info.is_inter, reduced_tx_set);
+    let tx_type = if info.is_inter { luma_tx_type } else { MODE_TO_TXFM[info.uv_mode as usize] };
+    if tx_type.is_in_set(tx_set, info.is_inter) { tx_type } else { TxType::DctDct }
+}
+
+// Tx_Width_Log2 / Tx_Height_Log2 等按 Adjusted_Tx_Size 取，系数区域最大 32x32
+fn adjusted_size(tx_size: TxSize) -> (usize, usize) {
+    let adj = ADJUSTED_TX_SIZE[tx_size as usize] as usize;
+    (TX_WIDTH_LOG2[adj] as usize, TX_HEIGHT_LOG2[adj] as usize)
+}
+
+// Coeff_Base_Ctx_Offset 的规律：方形按 row + col 分级，横长和竖长的块在靠近 DC 的两列 / 两行单独分类
+fn coeff_base_ctx_offset(bwl: usize, bhl: usize, row: usize, col: usize) -> u8 {
+    if bwl < bhl && row < 2 {
+        11
+    } else if bwl > bhl && col < 2 {
+        16
+    } else if row + col < 2 {
+        1
+    } else if row + col < 4 {
+        6
+    } else {
+        21
+    }
+}
+
+// get_coeff_base_ctx，isEob 为 0 的部分
+fn coeff_base_ctx(quant: &[i32], bwl: usize, bhl: usize, tx_class: u8, pos: usize) -> usize {
+    let row = pos >> bwl;
+    let col = pos - (row << bwl);
+    let mut mag = 0;
+    for offset in &SIG_REF_DIFF_OFFSET[tx_class as usize] {
+        let ref_row = row as isize + offset[0] as isize;
+        let ref_col = col as isize + offset[1] as isize;
+        if ref_row >= 0 && ref_col >= 0 && ref_row < 1 << bhl && ref_col < 1 << bwl {
+            mag += quant[((ref_row as usize) << bwl) + ref_col as usize].abs().min(3);
+        }
+    }
+    let ctx = ((mag + 1) >> 1).min(4) as u8;
+    if tx_class == TX_CLASS_2D {
+        if row == 0 && col == 0 {
+            return 0;
+        }
+        return (ctx + coeff_base_ctx_offset(bwl, bhl, row, col)) as usize;
+    }
+    let idx = if tx_class == TX_CLASS_VERT { row } else { col };
+    (ctx + COEFF_BASE_POS_CTX_OFFSET[idx.min(2)]) as usize
+}
+
+// coeff_br 的 ctx
+fn coeff_br_ctx(quant: &[i32], bwl: usize, bhl: usize, tx_class: u8, pos: usize) -> usize {
+    let row = pos >> bwl;
+    let col = pos - (row << bwl);
+    let mut mag = 0;
+    for offset in &MAG_REF_OFFSET_WITH_TX_CLASS[tx_class as usize] {
+        let ref_row = row as isize + offset[0] as isize;
+        let ref_col = col as isize + offset[1] as isize;
+        if ref_row >= 0 && ref_col >= 0 && ref_row < 1 << bhl && ref_col < 1 << bwl {
+            mag += quant[((ref_row as usize) << bwl) + ref_col as usize].min((COEFF_BASE_RANGE + NUM_BASE_LEVELS + 1) as i32);
+        }
+    }
+    let mag = ((mag + 1) >> 1).min(6) as usize;
+    let near = match tx_class {
+        _ if pos == 0 => return mag,
+        TX_CLASS_2D => row < 2 && col < 2,
+        TX_CLASS_HORIZ => col == 0,
+        _ => row == 0,
+    };
+    if near { mag + 7 } else { mag + 14 }
+}
+
+
+impl TileDecoder<'_> {
+    /// 5.11.34 Residual syntax
+    pub(super) fn residual(&mut self) -> Result<()> {
+        let mi_size = self.b.mi_size as usize;
+        let bw4 = NUM_4X4_BLOCKS_WIDE[mi_size] as u32;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[mi_size] as u32;
+        let width_chunks = (bw4 >> 4).max(1);
+        let height_chunks = (bh4 >> 4).max(1);
+        let num_planes = if self.b.has_chroma { 3 } else { 1 };
+        for chunk_y in 0..height_chunks {
+            for chunk_x in 0..width_chunks {
+                let mi_row_chunk = self.b.mi_row + (chunk_y << 4);
+                let mi_col_chunk = self.b.mi_col + (chunk_x << 4);
+                for plane in 0..num_planes {
+                    let (sub_x, sub_y) = self.plane_subsampling(plane);
+                    let tx_size = if self.b.lossless { TxSize::Tx4X4 } else { self.get_tx_size(plane, self.b.info.tx_size) };
+                    let step_x = (TX_WIDTH[tx_size as usize] >> 2) as u32;
+                    let step_y = (TX_HEIGHT[tx_size as usize] >> 2) as u32;
+                    let plane_size = self.plane_residual_size(plane);
+                    let num4x4_w = (NUM_4X4_BLOCKS_WIDE[plane_size] as u32).min(16 >> sub_x);
+                    let num4x4_h = (NUM_4X4_BLOCKS_HIGH[plane_size] as u32).min(16 >> sub_y);
+                    if self.b.info.is_inter && !self.b.lossless && plane == 0 {
+                        let base_x = (mi_col_chunk >> sub_x) * MI_SIZE as u32;
+                        let base_y = (mi_row_chunk >> sub_y) * MI_SIZE as u32;
+                        self.transform_tree(base_x, base_y, num4x4_w * 4, num4x4_h * 4)?;
+                    } else {
+                        let base_x_block = (self.b.mi_col >> sub_x) * MI_SIZE as u32;
+                        let base_y_block = (self.b.mi_row >> sub_y) * MI_SIZE as u32;
+                        for y in (0..num4x4_h).step_by(step_y as usize) {
+                            for x in (0..num4x4_w).step_by(step_x as usize) {
+                                let x = x + ((chunk_x << 4) >> sub_x);
+                                let y = y + ((chunk_y << 4) >> sub_y);
+                                self.transform_block(plane, base_x_block + 4 * x, base_y_block + 4 * y, tx_size)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 5.11.36 Transform tree syntax, only the luma of inter blocks
+    fn transform_tree(&mut self, start_x: u32, start_y: u32, w: u32, h: u32) -> Result<()> {
+        let max_x = self.fh.mi_cols * MI_SIZE as u32;
+        let max_y = self.fh.mi_rows * MI_SIZE as u32;
+        if start_x >= max_x || start_y >= max_y {
+            return Ok(());
+        }
+        let row = start_y >> MI_SIZE_LOG2;
+        let col = start_x >> MI_SIZE_LOG2;
+        let tx_size = self.mi.get(row, col).inter_tx_size;
+        if w <= TX_WIDTH[tx_size as usize] as u32 && h <= TX_HEIGHT[tx_size as usize] as u32 {
+            return self.transform_block(0, start_x, start_y, tx_size);
+        }
+        if w > h {
+            self.transform_tree(start_x, start_y, w / 2, h)?;
+            self.transform_tree(start_x + w / 2, start_y, w / 2, h)
+        } else if w < h {
+            self.transform_tree(start_x, start_y, w, h / 2)?;
+            self.transform_tree(start_x, start_y + h / 2, w, h / 2)
+        } else {
+            self.transform_tree(start_x, start_y, w / 2, h / 2)?;
+            self.transform_tree(start_x + w / 2, start_y, w / 2, h / 2)?;
+            self.transform_tree(start_x, start_y + h / 2, w / 2, h / 2)?;
+            self.transform_tree(start_x + w / 2, start_y + h / 2, w / 2, h / 2)
+        }
+    }
+
+    /// 5.11.35 Transform block syntax
+    fn transform_block(&mut self, plane: usize, start_x: u32, start_y: u32, tx_size: TxSize) -> Result<()> {
+        let (sub_x, sub_y) = self.plane_subsampling(plane);
+        let max_x = (self.fh.mi_cols * MI_SIZE as u32) >> sub_x;
+        let max_y = (self.fh.mi_rows * MI_SIZE as u32) >> sub_y;
+        if start_x >= max_x || start_y >= max_y {
+            return Ok(());
+        }
+        let sb_mask = if self.seq.use_128x128_superblock { 31 } else { 15 };
+        let sub_block_mi_row = ((((start_y << sub_y) >> MI_SIZE_LOG2) & sb_mask) >> sub_y) as i32;
+        let sub_block_mi_col = ((((start_x << sub_x) >> MI_SIZE_LOG2) & sb_mask) >> sub_x) as i32;
+        let step_x = (TX_WIDTH[tx_size as usize] >> MI_SIZE_LOG2) as i32;
+        let step_y = (TX_HEIGHT[tx_size as usize] >> MI_SIZE_LOG2) as i32;
+        let palette_size = if plane == 0 { self.b.info.palette_size_y } else { self.b.info.palette_size_uv };
+        if !self.b.info.is_inter && palette_size > 0 {
+            let info = &self.b.info;
+            let (palette, map) = match plane {
+                0 => (&info.palette_colors_y, &self.b.color_map_y),
+                1 => (&info.palette_colors_u, &self.b.color_map_uv),
+                _ => (&info.palette_colors_v, &self.b.color_map_uv),
+            };
+            let x = (start_x - (self.b.mi_col >> sub_x) * MI_SIZE as u32) as usize;
+            let y = (start_y - (self.b.mi_row >> sub_y) * MI_SIZE as u32) as usize;
+            let pred = predict_palette(&palette[..palette_size as usize], map, x, y, tx_size);
+            self.palette_predictions.push(PalettePrediction { plane: plane as u8, start_x, start_y, tx_size, pred });
+        } else if !self.b.info.is_inter {
+            let info = &self.b.info;
+            let mode = match plane {
+                0 => info.y_mode,
+                _ if info.uv_mode == UVMode::UVCfl => IntraFrameYMode::Dc,
+                _ => IntraFrameYMode::from(info.uv_mode as u8),
+            };
+            let (avail_l, avail_u) = if plane == 0 { (self.b.avail_l, self.b.avail_u) } else { (self.b.avail_l_chroma, self.b.avail_u_chroma) };
+            let prediction = IntraPrediction {
+                plane: plane as u8,
+                start_x,
+                start_y,
+                tx_size,
+                mode,
+                angle_delta: if plane == 0 { info.angle_delta_y } else { info.angle_delta_uv },
+                use_filter_intra: plane == 0 && info.use_filter_intra,
+                filter_intra_mode: info.filter_intra_mode,
+                have_left: avail_l || start_x > (self.b.mi_col >> sub_x) * MI_SIZE as u32,
+                have_above: avail_u || start_y > (self.b.mi_row >> sub_y) * MI_SIZE as u32,
+                have_above_right: self.block_decoded(plane, sub_block_mi_row - 1, sub_block_mi_col + step_x),
+                have_below_left: self.block_decoded(plane, sub_block_mi_row + step_y, sub_block_mi_col - 1),
+                smooth_neighbor: self.intra_filter_type(plane),
+                max_x: max_x - 1,
+                max_y: max_y - 1,
+                cfl: (plane > 0 && info.uv_mode == UVMode::UVCfl).then_some(ChromaFromLuma {
+                    alpha: if plane == 1 { info.cfl_alpha_u } else { info.cfl_alpha_v },
+                    subsampling_x: sub_x == 1,
+                    subsampling_y: sub_y == 1,
+                    max_luma_w: self.max_luma_w,
+                    max_luma_h: self.max_luma_h,
+                }),
+            };
+            self.intra_predictions.push(prediction);
+        }
+        if plane == 0 {
+            self.max_luma_w = start_x + TX_WIDTH[tx_size as usize] as u32;
+            self.max_luma_h = start_y + TX_HEIGHT[tx_size as usize] as u32;
+        }
+        if !self.b.info.skip {
+            self.coeffs(plane, start_x, start_y, tx_size)?;
+        }
+        for i in 0..step_y {
+            for j in 0..step_x {
+                self.set_block_decoded(plane, sub_block_mi_row + i, sub_block_mi_col + j, true);
+            }
+        }
+        Ok(())
+    }
+
+    // get_filter_type，上方或左边的块是否使用 SMOOTH 类预测
+    fn intra_filter_type(&self, plane: usize) -> bool {
+        let (sub_x, sub_y) = self.plane_subsampling(plane);
+        let (avail_l, avail_u) = if plane == 0 { (self.b.avail_l, self.b.avail_u) } else { (self.b.avail_l_chroma, self.b.avail_u_chroma) };
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        // 色度取 4x4 亮度块中带色度的那个
+        let above_smooth = avail_u && {
+            let r = if sub_y == 1 && mi_row & 1 == 1 { mi_row - 2 } else { mi_row - 1 };
+            let c = if sub_x == 1 && mi_col & 1 == 0 { mi_col + 1 } else { mi_col };
+            self.is_smooth(r, c, plane)
+        };
+        let left_smooth = avail_l && {
+            let r = if sub_y == 1 && mi_row & 1 == 0 { mi_row + 1 } else { mi_row };
+            let c = if sub_x == 1 && mi_col & 1 == 1 { mi_col - 2 } else { mi_col - 1 };
+            self.is_smooth(r, c, plane)
+        };
+        above_smooth || left_smooth
+    }
+
+    fn is_smooth(&self, row: u32, col: u32, plane: usize) -> bool {
+        let info = self.mi.get(row, col);
+        let mode = if plane == 0 {
+            info.y_mode as u8
+        } else if info.ref_frame[0] > RefFrame::Intra as i8 {
+            return false;
+        } else {
+            info.uv_mode as u8
+        };
+        matches!(IntraFrameYMode::from(mode), IntraFrameYMode::Smooth | IntraFrameYMode::SmoothV | IntraFrameYMode::SmoothH)
+    }
+
+    /// 5.11.37 Get TX size, the transform size of a chroma plane
+    fn get_tx_size(&self, plane: usize, tx_size: TxSize) -> TxSize {
+        if plane == 0 {
+            return tx_size;
+        }
+        let uv_tx = MAX_TX_SIZE_RECT[self.plane_residual_size(plane)] as usize;
+        if TX_WIDTH[uv_tx] == 64 || TX_HEIGHT[uv_tx] == 64 {
+            return match (TX_WIDTH[uv_tx], TX_HEIGHT[uv_tx]) {
+                (16, _) => TxSize::Tx16X32,
+                (_, 16) => TxSize::Tx32X16,
+                _ => TxSize::Tx32X32,
+            };
+        }
+        (uv_tx as u8).into()
+    }
+
+    // get_plane_residual_size
+    fn plane_residual_size(&self, plane: usize) -> usize {
+        let (sub_x, sub_y) = self.plane_subsampling(plane);
+        SUBSAMPLED_SIZE[self.b.mi_size as usize][sub_x as usize][sub_y as usize] as usize
+    }
+
+    pub(super) fn plane_subsampling(&self, plane: usize) -> (u32, u32) {
+        let cc = &self.seq.color_config;
+        if plane > 0 { (cc.subsampling_x as u32, cc.subsampling_y as u32) } else { (0, 0) }
+    }
+
+    /// 5.11.5 reset_block_context, a skipped block has no coefficients in any of its planes
+    pub(super) fn reset_block_context(&mut self) {
+        let mi_size = self.b.mi_size as usize;
+        let bw4 = NUM_4X4_BLOCKS_WIDE[mi_size] as u32;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[mi_size] as u32;
+        let num_planes = if self.b.has_chroma { 3 } else { 1 };
+        for plane in 0..num_planes {
+            let (sub_x, sub_y) = self.plane_subsampling(plane);
+            for i in (self.b.mi_col >> sub_x)..((self.b.mi_col + bw4) >> sub_x) {
+                self.above_level_context[plane][i as usize] = 0;
+                self.above_dc_context[plane][i as usize] = 0;
+            }
+            for i in (self.b.mi_row >> sub_y)..((self.b.mi_row + bh4) >> sub_y) {
+                self.left_level_context[plane][i as usize] = 0;
+                self.left_dc_context[plane][i as usize] = 0;
+            }
+        }
+    }
+
+    // compute_tx_type 的 TxTypes 查找：亮度取当前位置，inter 块的色度取对应的亮度位置
+    fn block_tx_type(&self, plane: usize, tx_size: TxSize, block_x: u32, block_y: u32) -> TxType {
+        let (x4, y4) = if plane == 0 {
+            (block_x, block_y)
+        } else {
+            let cc = &self.seq.color_config;
+            (self.b.mi_col.max(block_x << cc.subsampling_x as u32), self.b.mi_row.max(block_y << cc.subsampling_y as u32))
+        };
+        let luma_tx_type = self.tx_types[(y4 * self.fh.mi_cols + x4) as usize];
+        compute_tx_type(plane, tx_size, &self.b.info, self.b.lossless, self.fh.reduced_tx_set, luma_tx_type)
+    }
+
+    // TxTypes 只保存帧内的部分
+    fn set_tx_types(&mut self, x4: u32, y4: u32, tx_size: TxSize, tx_type: TxType) {
+        let w4 = (TX_WIDTH[tx_size as usize] >> 2) as u32;
+        let h4 = (TX_HEIGHT[tx_size as usize] >> 2) as u32;
+        for y in y4..(y4 + h4).min(self.fh.mi_rows) {
+            for x in x4..(x4 + w4).min(self.fh.mi_cols) {
+                self.tx_types[(y * self.fh.mi_cols + x) as usize] = tx_type;
+            }
+        }
+    }
+
+    /// 5.11.47 Transform type syntax
+    fn transform_type(&mut self, x4: u32, y4: u32, tx_size: TxSize) {
+        let set = tx_size.tx_set(self.b.info.is_inter, self.fh.reduced_tx_set);
+        let qindex = if self.fh.segmentation.segmentation_enabled {
+            self.fh.get_qindex(true, self.b.info.segment_id as usize, self.current_q_index as i32)
+        } else {
+            self.fh.quantization.base_q_idx as i32
+        };
+        let mut tx_type = TxType::DctDct;
+        if set > 0 && qindex > 0 {
+            let tx_sz_sqr = TX_SIZE_SQR[tx_size as usize] as usize;
+            if self.b.info.is_inter {
+                tx_type = match set {
+                    TX_SET_INTER_1 => TX_TYPE_INTER_INV_SET1[self.symbol.read_symbol(&mut self.cdf.inter_tx_type_set1_cdf[tx_sz_sqr])],
+                    TX_SET_INTER_2 => TX_TYPE_INTER_INV_SET2[self.symbol.read_symbol(&mut self.cdf.inter_tx_type_set2_cdf)],
+                    _ => TX_TYPE_INTER_INV_SET3[self.symbol.read_symbol(&mut self.cdf.inter_tx_type_set3_cdf[tx_sz_sqr])],
+                };
+            } else {
+                let intra_dir = if self.b.info.use_filter_intra {
+                    FILTER_INTRA_MODE_TO_INTRA_DIR[self.b.info.filter_intra_mode as usize]
+                } else {
+                    self.b.info.y_mode as u8
+                } as usize;
+                tx_type = if set == TX_SET_INTRA_1 {
+                    TX_TYPE_INTRA_INV_SET1[self.symbol.read_symbol(&mut self.cdf.intra_tx_type_set1_cdf[tx_sz_sqr][intra_dir])]
+                } else {
+                    TX_TYPE_INTRA_INV_SET2[self.symbol.read_symbol(&mut self.cdf.intra_tx_type_set2_cdf[tx_sz_sqr][intra_dir])]
+                };
+            }
+        }
+        self.set_tx_types(x4, y4, tx_size, tx_type);
+    }
+
+    // 当前 plane 中帧内 4x4 的行数和列数
+    fn plane_max4(&self, plane: usize) -> (u32, u32) {
+        let (sub_x, sub_y) = self.plane_subsampling(plane);
+        (self.fh.mi_cols >> sub_x, self.fh.mi_rows >> sub_y)
+    }
+
+    // all_zero 的 ctx
+    fn all_zero_ctx(&self, plane: usize, tx_size: TxSize, x4: u32, y4: u32) -> usize {
+        let (max_x4, max_y4) = self.plane_max4(plane);
+        let w = TX_WIDTH[tx_size as usize] as u32;
+        let h = TX_HEIGHT[tx_size as usize] as u32;
+        let above = (x4..(x4 + w / 4).min(max_x4)).map(|i| i as usize);
+        let left = (y4..(y4 + h / 4).min(max_y4)).map(|i| i as usize);
+        let plane_size = self.plane_residual_size(plane);
+        let bw = BLOCK_WIDTH[plane_size] as u32;
+        let bh = BLOCK_HEIGHT[plane_size] as u32;
+        if plane == 0 {
+            let top = above.map(|i| self.above_level_context[0][i]).max().unwrap_or(0);
+            let left = left.map(|i| self.left_level_context[0][i]).max().unwrap_or(0);
+            let (max, min) = (top.max(left), top.min(left));
+            if bw == w && bh == h {
+                0
+            } else if max == 0 {
+                1
+            } else if min == 0 {
+                2 + (max > 3) as usize
+            } else if max <= 3 {
+                4
+            } else if min <= 3 {
+                5
+            } else {
+                6
+            }
+        } else {
+            let above = above.fold(0, |acc, i| acc | self.above_level_context[plane][i] | self.above_dc_context[plane][i]);
+            let left = left.fold(0, |acc, i| acc | self.left_level_context[plane][i] | self.left_dc_context[plane][i]);
+            let ctx = 7 + (above != 0) as usize + (left != 0) as usize;
+            if bw * bh > w * h { ctx + 3 } else { ctx }
+        }
+    }
+
+    // dc_sign 的 ctx，比较上方和左边负的和正的 DC 的个数
+    fn dc_sign_ctx(&self, plane: usize, tx_size: TxSize, x4: u32, y4: u32) -> usize {
+        let (max_x4, max_y4) = self.plane_max4(plane);
+        let w4 = (TX_WIDTH[tx_size as usize] >> 2) as u32;
+        let h4 = (TX_HEIGHT[tx_size as usize] >> 2) as u32;
+        let above = (x4..(x4 + w4).min(max_x4)).map(|i| self.above_dc_context[plane][i as usize]);
+        let left = (y4..(y4 + h4).min(max_y4)).map(|i| self.left_dc_context[plane][i as usize]);
+        let dc_sign: i32 = above.chain(left).map(|sign| match sign {
+            1 => -1,
+            2 => 1,
+            _ => 0,
+        }).sum();
+        if dc_sign < 0 {
+            1
+        } else if dc_sign > 0 {
+            2
+        } else {
+            0
+        }
+    }
+
+    // eob_pt_16 ... eob_pt_1024，返回 eobPt
+    fn read_eob_pt(&mut self, tx_size: TxSize, ptype: usize, tx_class: u8) -> usize {
+        let eob_multisize = (TX_WIDTH_LOG2[tx_size as usize].min(5) + TX_HEIGHT_LOG2[tx_size as usize].min(5)) as usize - 4;
+        let ctx = (tx_class != TX_CLASS_2D) as usize;
+        let coeff = &mut self.cdf.coeff;
+        let cdf = match eob_multisize {
+            0 => &mut coeff.eob_pt_16_cdf[ptype][ctx][..],
+            1 => &mut coeff.eob_pt_32_cdf[ptype][ctx][..],
+            2 => &mut coeff.eob_pt_64_cdf[ptype][ctx][..],
+            3 => &mut coeff.eob_pt_128_cdf[ptype][ctx][..],
+            4 => &mut coeff.eob_pt_256_cdf[ptype][ctx][..],
+            5 => &mut coeff.eob_pt_512_cdf[ptype][..],
+            _ => &mut coeff.eob_pt_1024_cdf[ptype][..],
+        };
+        self.symbol.read_symbol(cdf) + 1
+    }
+
+    // Golomb 编码的剩余部分
+    fn read_golomb(&mut self) -> Result<i32> {
+        let mut length = 0;
+        loop {
+            length += 1;
+            if length > 20 {
+                return Err(Error::Conformance { section: "5.11.39", reason: "golomb_length_bit is 0 twenty times in a row" });
+            }
+            if self.symbol.read_literal(1) == 1 {
+                break;
+            }
+        }
+        let mut x = 1;
+        for _ in 0..length - 1 {
+            x = (x << 1) | self.symbol.read_literal(1) as i32;
+        }
+        Ok(x)
+    }
+
+    /// 5.11.39 Coefficients syntax, blocks with eob > 0 are kept in residuals
+    fn coeffs(&mut self, plane: usize, start_x: u32, start_y: u32, tx_size: TxSize) -> Result<()> {
+        let x4 = start_x >> 2;
+        let y4 = start_y >> 2;
+        let w4 = (TX_WIDTH[tx_size as usize] >> 2) as u32;
+        let h4 = (TX_HEIGHT[tx_size as usize] >> 2) as u32;
+        let tx_sz_ctx = ((TX_SIZE_SQR[tx_size as usize] + TX_SIZE_SQR_UP[tx_size as usize] + 1) >> 1) as usize;
+        let ptype = (plane > 0) as usize;
+        let (bwl, bhl) = adjusted_size(tx_size);
+        let mut cul_level = 0;
+        let mut dc_category = 0;
+
+        let ctx = self.all_zero_ctx(plane, tx_size, x4, y4);
+        let all_zero = self.symbol.read_symbol(&mut self.cdf.coeff.txb_skip_cdf[tx_sz_ctx][ctx]) == 1;
+        if all_zero {
+            if plane == 0 {
+                self.set_tx_types(x4, y4, tx_size, TxType::DctDct);
+            }
+        } else {
+            if plane == 0 {
+                self.transform_type(x4, y4, tx_size);
+            }
+            let tx_type = self.block_tx_type(plane, tx_size, x4, y4);
+            let tx_class = tx_type.tx_class();
+            let scan = get_scan(tx_size, tx_type);
+            let mut quant = vec![0; 1 << (bwl + bhl)];
+
+            let eob_pt = self.read_eob_pt(tx_size, ptype, tx_class);
+            let mut eob = if eob_pt < 2 { eob_pt } else { (1 << (eob_pt - 2)) + 1 };
+            if eob_pt >= 3 {
+                let eob_shift = eob_pt - 3;
+                if self.symbol.read_symbol(&mut self.cdf.coeff.eob_extra_cdf[tx_sz_ctx][ptype][eob_pt - 3]) == 1 {
+                    eob += 1 << eob_shift;
+                }
+                for i in 1..eob_pt - 2 {
+                    let eob_shift = eob_pt - 2 - 1 - i;
+                    if self.symbol.read_literal(1) == 1 {
+                        eob += 1 << eob_shift;
+                    }
+                }
+            }
+
+            let area = 1 << (bwl + bhl);
+            for c in (0..eob).rev() {
+                let pos = scan[c] as usize;
+                let mut level = if c == eob - 1 {
+                    let ctx = match c {
+                        0 => 0,
+                        _ if c <= area / 8 => 1,
+                        _ if c <= area / 4 => 2,
+                        _ => 3,
+                    };
+                    self.symbol.read_symbol(&mut self.cdf.coeff.coeff_base_eob_cdf[tx_sz_ctx][ptype][ctx]) as i32 + 1
+                } else {
+                    let ctx = coeff_base_ctx(&quant, bwl, bhl, tx_class, pos);
+                    self.symbol.read_symbol(&mut self.cdf.coeff.coeff_base_cdf[tx_sz_ctx][ptype][ctx]) as i32
+                };
+                if level > NUM_BASE_LEVELS as i32 {
+                    let ctx = coeff_br_ctx(&quant, bwl, bhl, tx_class, pos);
+                    for _ in 0..COEFF_BASE_RANGE as usize / (BR_CDF_SIZE - 1) {
+                        let coeff_br = self.symbol.read_symbol(&mut self.cdf.coeff.coeff_br_cdf[tx_sz_ctx.min(3)][ptype][ctx]);
+                        level += coeff_br as i32;
+                        if coeff_br < BR_CDF_SIZE - 1 {
+                            break;
+                        }
+                    }
+                }
+                quant[pos] = level;
+            }
+
+            for (c, &pos) in scan[..eob].iter().enumerate() {
+                let pos = pos as usize;
+                let sign = if quant[pos] == 0 {
+                    false
+                } else if c == 0 {
+                    let ctx = self.dc_sign_ctx(plane, tx_size, x4, y4);
+                    self.symbol.read_symbol(&mut self.cdf.coeff.dc_sign_cdf[ptype][ctx]) == 1
+                } else {
+                    self.symbol.read_literal(1) == 1
+                };
+                if quant[pos] > (NUM_BASE_LEVELS + COEFF_BASE_RANGE) as i32 {
+                    quant[pos] = self.read_golomb()? + (COEFF_BASE_RANGE + NUM_BASE_LEVELS) as i32;
+                }
+                if pos == 0 && quant[pos] > 0 {
+                    dc_category = if sign { 1 } else { 2 };
+                }
+                quant[pos] &= 0xFFFFF;
+                cul_level += quant[pos];
+                if sign {
+                    quant[pos] = -quant[pos];
+                }
+            }
+            cul_level = cul_level.min(63);
+            let quantizer = Quantizer::new(self.fh, plane as u8, self.b.info.segment_id, self.current_q_index, self.seq.color_config.bit_depth);
+            let dequant = quantizer.dequantize(&quant, tx_size, tx_type)?;
+            self.residuals.push(TransformBlock { plane: plane as u8, start_x, start_y, tx_size, tx_type, eob: eob as u16, quant, dequant });
+        }
+
+        for i in x4..x4 + w4 {
+            self.above_level_context[plane][i as usize] = cul_level as u8;
+            self.above_dc_context[plane][i as usize] = dc_category;
+        }
+        for i in y4..y4 + h4 {
+            self.left_level_context[plane][i as usize] = cul_level as u8;
+            self.left_dc_context[plane][i as usize] = dc_category;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+// ! 5.11.57 - 5.11.58 Loop restoration unit syntax
+
+use crate::{
+    decoder::tile::TileDecoder,
+    utils::{
+        consts::{MI_SIZE, NUM_4X4_BLOCKS_HIGH, NUM_4X4_BLOCKS_WIDE, SGRPROJ_PARAMS_BITS, SGRPROJ_PRJ_BITS, SGRPROJ_PRJ_SUBEXP_K, SGRPROJ_XQD_MAX, SGRPROJ_XQD_MIN, SGR_PARAMS, SUPERRES_NUM, WIENER_COEFFS, WIENER_TAPS_K, WIENER_TAPS_MAX, WIENER_TAPS_MIN},
+        enums::{FrameRestorationType, SubSize},
+        math::{clip3, inverse_recenter},
+    },
+};
+
+
+
+/// LrType, LrWiener, LrSgrSet and LrSgrXqd of one loop restoration unit
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RestorationUnit {
+    pub plane: u8,
+    pub unit_row: u32,
+    pub unit_col: u32,
+    // NONE / WIENER / SGRPROJ
+    pub lr_type: FrameRestorationType,
+    // [pass][coefficient]，色度的第一个系数总是 0
+    pub lr_wiener: [[i8; WIENER_COEFFS as usize]; 2],
+    pub lr_sgr_set: u8,
+    pub lr_sgr_xqd: [i8; 2],
+}
+
+
+// count_units_in_frame
+fn count_units_in_frame(unit_size: u32, frame_size: u32) -> u32 {
+    ((frame_size + (unit_size >> 1)) / unit_size).max(1)
+}
+
+
+impl TileDecoder<'_> {
+    /// 5.11.57 Read loop restoration syntax, the units whose top left corner is inside the superblock
+    pub(super) fn read_lr(&mut self, r: u32, c: u32, b_size: SubSize) {
+        if self.fh.allow_intrabc {
+            return;
+        }
+        let w = NUM_4X4_BLOCKS_WIDE[b_size as usize] as u32;
+        let h = NUM_4X4_BLOCKS_HIGH[b_size as usize] as u32;
+        for plane in 0..self.seq.color_config.num_planes as usize {
+            if self.fh.lr.frame_restoration_type[plane] == FrameRestorationType::None {
+                continue;
+            }
+            let (sub_x, sub_y) = self.plane_subsampling(plane);
+            let unit_size = self.fh.lr.loop_restoration_size[plane] as u32;
+            let unit_rows = count_units_in_frame(unit_size, (self.fh.frame_height + sub_y) >> sub_y);
+            let unit_cols = count_units_in_frame(unit_size, (self.fh.upscaled_width + sub_x) >> sub_x);
+            let unit_row_start = (r * (MI_SIZE as u32 >> sub_y)).div_ceil(unit_size);
+            let unit_row_end = unit_rows.min(((r + h) * (MI_SIZE as u32 >> sub_y)).div_ceil(unit_size));
+            // 超分辨率时按放大后的宽度计算
+            let (numerator, denominator) = if self.fh.use_superres {
+                ((MI_SIZE as u32 >> sub_x) * self.fh.superres_denom, unit_size * SUPERRES_NUM)
+            } else {
+                (MI_SIZE as u32 >> sub_x, unit_size)
+            };
+            let unit_col_start = (c * numerator).div_ceil(denominator);
+            let unit_col_end = unit_cols.min(((c + w) * numerator).div_ceil(denominator));
+            for unit_row in unit_row_start..unit_row_end {
+                for unit_col in unit_col_start..unit_col_end {
+                    self.read_lr_unit(plane, unit_row, unit_col);
+                }
+            }
+        }
+    }
+
+    /// 5.11.58 Read loop restoration unit syntax
+    fn read_lr_unit(&mut self, plane: usize, unit_row: u32, unit_col: u32) {
+        let lr_type = match self.fh.lr.frame_restoration_type[plane] {
+            FrameRestorationType::Wiener if self.symbol.read_symbol(&mut self.cdf.use_wiener_cdf) == 1 => FrameRestorationType::Wiener,
+            FrameRestorationType::Sgrproj if self.symbol.read_symbol(&mut self.cdf.use_sgrproj_cdf) == 1 => FrameRestorationType::Sgrproj,
+            FrameRestorationType::Switchable => (self.symbol.read_symbol(&mut self.cdf.restoration_type_cdf) as u8).into(),
+            _ => FrameRestorationType::None,
+        };
+        let mut unit = RestorationUnit { plane: plane as u8, unit_row, unit_col, lr_type, ..Default::default() };
+        match lr_type {
+            FrameRestorationType::Wiener => {
+                // 色度只有两个系数
+                let first_coeff = if plane > 0 { 1 } else { 0 };
+                for pass in 0..2 {
+                    for j in first_coeff..WIENER_COEFFS as usize {
+                        let reference = self.ref_lr_wiener[plane][pass][j];
+                        let v = self.decode_signed_subexp_with_ref_bool(WIENER_TAPS_MIN[j], WIENER_TAPS_MAX[j] + 1, WIENER_TAPS_K[j], reference);
+                        unit.lr_wiener[pass][j] = v as i8;
+                        self.ref_lr_wiener[plane][pass][j] = v;
+                    }
+                }
+            }
+            FrameRestorationType::Sgrproj => {
+                let set = self.symbol.read_literal(SGRPROJ_PARAMS_BITS) as usize;
+                unit.lr_sgr_set = set as u8;
+                for i in 0..2 {
+                    let (min, max) = (SGRPROJ_XQD_MIN[i], SGRPROJ_XQD_MAX[i]);
+                    let v = if SGR_PARAMS[set][i * 2] != 0 {
+                        self.decode_signed_subexp_with_ref_bool(min, max + 1, SGRPROJ_PRJ_SUBEXP_K, self.ref_sgr_xqd[plane][i])
+                    } else if i == 1 {
+                        // 只用第二个滤波器时由第一个系数推出
+                        clip3(min, max, (1 << SGRPROJ_PRJ_BITS) - self.ref_sgr_xqd[plane][0])
+                    } else {
+                        0
+                    };
+                    unit.lr_sgr_xqd[i] = v as i8;
+                    self.ref_sgr_xqd[plane][i] = v;
+                }
+            }
+            _ => {}
+        }
+        self.restoration_units.push(unit);
+    }
+
+    // decode_signed_subexp_with_ref_bool，范围是 [low, high)
+    fn decode_signed_subexp_with_ref_bool(&mut self, low: i32, high: i32, k: u8, r: i32) -> i32 {
+        let mx = (high - low) as u32;
+        let r = (r - low) as u32;
+        let v = self.decode_subexp_bool(mx, k);
+        let x = if (r << 1) <= mx { inverse_recenter(r, v) } else { mx - 1 - inverse_recenter(mx - 1 - r, v) };
+        x as i32 + low
+    }
+
+    // decode_subexp_bool
+    fn decode_subexp_bool(&mut self, num_syms: u32, k: u8) -> u32 {
+        let mut i = 0;
+        let mut mk = 0;
+        loop {
+            let b2 = if i > 0 { k + i - 1 } else { k };
+            let a = 1 << b2;
+            if num_syms <= mk + 3 * a {
+                return self.symbol.read_ns(num_syms - mk) + mk;
+            }
+            if self.symbol.read_literal(1) == 0 {
+                return self.symbol.read_literal(b2) + mk;
+            }
+            i += 1;
+            mk += a;
+        }
+    }
+}
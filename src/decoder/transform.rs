@@ -0,0 +1,446 @@
+// ! 7.13.2 / 7.13.3 一维反变换和二维反变换过程
+
+use crate::utils::{
+    consts::{COS128_LOOKUP, SINPI_1_9, SINPI_2_9, SINPI_3_9, SINPI_4_9, TRANSFORM_ROW_SHIFT, TX_HEIGHT_LOG2, TX_WIDTH_LOG2},
+    enums::{TxSize, TxType},
+};
+
+
+
+fn round2(x: i64, n: u8) -> i64 {
+    if n == 0 {
+        return x;
+    }
+    (x + (1 << (n - 1))) >> n
+}
+
+// brev，numBits 位的 x 按位反转
+fn brev(num_bits: u32, x: usize) -> usize {
+    x.reverse_bits() >> (usize::BITS - num_bits)
+}
+
+fn cos128(angle: i32) -> i64 {
+    let angle2 = angle & 255;
+    (match angle2 {
+        0..=64 => COS128_LOOKUP[angle2 as usize],
+        65..=128 => -COS128_LOOKUP[(128 - angle2) as usize],
+        129..=192 => -COS128_LOOKUP[(angle2 - 128) as usize],
+        _ => COS128_LOOKUP[(256 - angle2) as usize],
+    }) as i64
+}
+
+fn sin128(angle: i32) -> i64 {
+    cos128(angle - 64)
+}
+
+// B( a, b, angle, flip )，蝶形旋转，flip 时交换结果
+fn butterfly(t: &mut [i32], a: usize, b: usize, angle: i32, flip: bool) {
+    let x = t[a] as i64 * cos128(angle) - t[b] as i64 * sin128(angle);
+    let y = t[a] as i64 * sin128(angle) + t[b] as i64 * cos128(angle);
+    t[a] = round2(x, 12) as i32;
+    t[b] = round2(y, 12) as i32;
+    if flip {
+        t.swap(a, b);
+    }
+}
+
+// H( a, b, flip )，Hadamard 旋转
+fn hadamard(t: &mut [i32], a: usize, b: usize, flip: bool) {
+    let (a, b) = if flip { (b, a) } else { (a, b) };
+    let (x, y) = (t[a], t[b]);
+    t[a] = x + y;
+    t[b] = x - y;
+}
+
+/// 7.13.2.3 Inverse DCT process, n is log2 of the length (2 to 6)
+pub fn inverse_dct(t: &mut [i32], n: u32) {
+    let n0 = 1 << n;
+    // 7.13.2.2 Inverse DCT array permutation process
+    let copy: Vec<i32> = t[..n0].to_vec();
+    for i in 0..n0 {
+        t[i] = copy[brev(n, i)];
+    }
+    let b = |i: usize| i as i32;
+    if n == 6 {
+        for i in 0..16 {
+            butterfly(t, 32 + i, 63 - i, 63 - 4 * brev(4, i) as i32, false);
+        }
+    }
+    if n >= 5 {
+        for i in 0..8 {
+            butterfly(t, 16 + i, 31 - i, 6 + ((brev(3, 7 - i) as i32) << 3), false);
+        }
+    }
+    if n == 6 {
+        for i in 0..16 {
+            hadamard(t, 32 + i * 2, 33 + i * 2, i & 1 == 1);
+        }
+    }
+    if n >= 4 {
+        for i in 0..4 {
+            butterfly(t, 8 + i, 15 - i, 12 + ((brev(2, 3 - i) as i32) << 4), false);
+        }
+    }
+    if n >= 5 {
+        for i in 0..8 {
+            hadamard(t, 16 + 2 * i, 17 + 2 * i, i & 1 == 1);
+        }
+    }
+    if n == 6 {
+        for i in 0..4 {
+            for j in 0..2 {
+                butterfly(t, 62 - i * 4 - j, 33 + i * 4 + j, 60 - 16 * brev(2, i) as i32 + 64 * b(j), true);
+            }
+        }
+    }
+    if n >= 3 {
+        for i in 0..2 {
+            butterfly(t, 4 + i, 7 - i, 56 - 32 * b(i), false);
+        }
+    }
+    if n >= 4 {
+        for i in 0..4 {
+            hadamard(t, 8 + 2 * i, 9 + 2 * i, i & 1 == 1);
+        }
+    }
+    if n >= 5 {
+        for i in 0..2 {
+            for j in 0..2 {
+                butterfly(t, 30 - 4 * i - j, 17 + 4 * i + j, 24 + (b(j) << 6) + ((1 - b(i)) << 5), true);
+            }
+        }
+    }
+    if n == 6 {
+        for i in 0..8 {
+            for j in 0..2 {
+                hadamard(t, 32 + i * 4 + j, 35 + i * 4 - j, i & 1 == 1);
+            }
+        }
+    }
+    for i in 0..2 {
+        butterfly(t, 2 * i, 2 * i + 1, 32 + 16 * b(i), i == 0);
+    }
+    if n >= 3 {
+        for i in 0..2 {
+            hadamard(t, 4 + 2 * i, 5 + 2 * i, i == 1);
+        }
+    }
+    if n >= 4 {
+        for i in 0..2 {
+            butterfly(t, 14 - i, 9 + i, 48 + 64 * b(i), true);
+        }
+    }
+    if n >= 5 {
+        for i in 0..4 {
+            for j in 0..2 {
+                hadamard(t, 16 + 4 * i + j, 19 + 4 * i - j, i & 1 == 1);
+            }
+        }
+    }
+    if n == 6 {
+        for i in 0..2 {
+            for j in 0..4 {
+                butterfly(t, 61 - i * 8 - j, 34 + i * 8 + j, 56 - b(i) * 32 + (b(j) >> 1) * 64, true);
+            }
+        }
+    }
+    for i in 0..2 {
+        hadamard(t, i, 3 - i, false);
+    }
+    if n >= 3 {
+        butterfly(t, 6, 5, 32, true);
+    }
+    if n >= 4 {
+        for i in 0..2 {
+            for j in 0..2 {
+                hadamard(t, 8 + 4 * i + j, 11 + 4 * i - j, i == 1);
+            }
+        }
+    }
+    if n >= 5 {
+        for i in 0..4 {
+            butterfly(t, 29 - i, 18 + i, 48 + (b(i) >> 1) * 64, true);
+        }
+    }
+    if n == 6 {
+        for i in 0..4 {
+            for j in 0..4 {
+                hadamard(t, 32 + 8 * i + j, 39 + 8 * i - j, i & 1 == 1);
+            }
+        }
+    }
+    if n >= 3 {
+        for i in 0..4 {
+            hadamard(t, i, 7 - i, false);
+        }
+    }
+    if n >= 4 {
+        for i in 0..2 {
+            butterfly(t, 13 - i, 10 + i, 32, true);
+        }
+    }
+    if n >= 5 {
+        for i in 0..2 {
+            for j in 0..4 {
+                hadamard(t, 16 + i * 8 + j, 23 + i * 8 - j, i == 1);
+            }
+        }
+    }
+    if n == 6 {
+        for i in 0..8 {
+            butterfly(t, 59 - i, 36 + i, if i < 4 { 48 } else { 112 }, true);
+        }
+    }
+    if n >= 4 {
+        for i in 0..8 {
+            hadamard(t, i, 15 - i, false);
+        }
+    }
+    if n >= 5 {
+        for i in 0..4 {
+            butterfly(t, 27 - i, 20 + i, 32, true);
+        }
+    }
+    if n == 6 {
+        for i in 0..8 {
+            hadamard(t, 32 + i, 47 - i, false);
+            hadamard(t, 48 + i, 63 - i, true);
+        }
+    }
+    if n >= 5 {
+        for i in 0..16 {
+            hadamard(t, i, 31 - i, false);
+        }
+    }
+    if n == 6 {
+        for i in 0..8 {
+            butterfly(t, 55 - i, 40 + i, 32, true);
+        }
+    }
+    if n == 6 {
+        for i in 0..32 {
+            hadamard(t, i, 63 - i, false);
+        }
+    }
+}
+
+/// 7.13.2.6 Inverse ADST4 process
+pub fn inverse_adst4(t: &mut [i32]) {
+    let (t0, t1, t2, t3) = (t[0] as i64, t[1] as i64, t[2] as i64, t[3] as i64);
+    let mut s0 = SINPI_1_9 * t0;
+    let mut s1 = SINPI_2_9 * t0;
+    let mut s2 = SINPI_3_9 * t1;
+    let mut s3 = SINPI_4_9 * t2;
+    let s4 = SINPI_1_9 * t2;
+    let s5 = SINPI_2_9 * t3;
+    let s6 = SINPI_4_9 * t3;
+    let a7 = t0 - t2;
+    let b7 = a7 + t3;
+    s0 += s3;
+    s1 -= s4;
+    s3 = s2;
+    s2 = SINPI_3_9 * b7;
+    s0 += s5;
+    s1 -= s6;
+    let x0 = s0 + s3;
+    let x1 = s1 + s3;
+    let x2 = s2;
+    let x3 = s0 + s1 - s3;
+    t[0] = round2(x0, 12) as i32;
+    t[1] = round2(x1, 12) as i32;
+    t[2] = round2(x2, 12) as i32;
+    t[3] = round2(x3, 12) as i32;
+}
+
+// 7.13.2.4 Inverse ADST input array permutation process
+fn adst_input_permutation(t: &mut [i32], n: u32) {
+    let n0 = 1 << n;
+    let copy: Vec<i32> = t[..n0].to_vec();
+    for (i, x) in t[..n0].iter_mut().enumerate() {
+        let idx = if i & 1 == 1 { i - 1 } else { n0 - i - 1 };
+        *x = copy[idx];
+    }
+}
+
+// 7.13.2.5 Inverse ADST output array permutation process
+fn adst_output_permutation(t: &mut [i32], n: u32) {
+    let n0 = 1 << n;
+    let copy: Vec<i32> = t[..n0].to_vec();
+    for (i, x) in t[..n0].iter_mut().enumerate() {
+        let a = (i >> 3) & 1;
+        let b = ((i >> 2) & 1) ^ ((i >> 3) & 1);
+        let c = ((i >> 1) & 1) ^ ((i >> 2) & 1);
+        let d = (i & 1) ^ ((i >> 1) & 1);
+        let idx = ((d << 3) | (c << 2) | (b << 1) | a) >> (4 - n);
+        *x = if i & 1 == 1 { -copy[idx] } else { copy[idx] };
+    }
+}
+
+/// 7.13.2.7 Inverse ADST8 process
+pub fn inverse_adst8(t: &mut [i32]) {
+    adst_input_permutation(t, 3);
+    for i in 0..4 {
+        butterfly(t, 2 * i, 2 * i + 1, 60 - 16 * i as i32, true);
+    }
+    for i in 0..4 {
+        hadamard(t, i, 4 + i, false);
+    }
+    for i in 0..2 {
+        butterfly(t, 4 + 3 * i, 5 + i, 48 - 32 * i as i32, true);
+    }
+    for i in 0..2 {
+        for j in 0..2 {
+            hadamard(t, 4 * j + i, 2 + 4 * j + i, false);
+        }
+    }
+    for i in 0..2 {
+        butterfly(t, 2 + 4 * i, 3 + 4 * i, 32, true);
+    }
+    adst_output_permutation(t, 3);
+}
+
+/// 7.13.2.8 Inverse ADST16 process
+pub fn inverse_adst16(t: &mut [i32]) {
+    adst_input_permutation(t, 4);
+    for i in 0..8 {
+        butterfly(t, 2 * i, 2 * i + 1, 62 - 8 * i as i32, true);
+    }
+    for i in 0..8 {
+        hadamard(t, i, 8 + i, false);
+    }
+    for i in 0..2 {
+        butterfly(t, 8 + 2 * i, 9 + 2 * i, 56 - 32 * i as i32, true);
+        butterfly(t, 13 + 2 * i, 12 + 2 * i, 8 + 32 * i as i32, true);
+    }
+    for i in 0..4 {
+        for j in 0..2 {
+            hadamard(t, 8 * j + i, 4 + 8 * j + i, false);
+        }
+    }
+    for i in 0..2 {
+        for j in 0..2 {
+            butterfly(t, 4 + 8 * j + 3 * i, 5 + 8 * j + i, 48 - 32 * i as i32, true);
+        }
+    }
+    for i in 0..2 {
+        for j in 0..4 {
+            hadamard(t, 4 * j + i, 2 + 4 * j + i, false);
+        }
+    }
+    for i in 0..4 {
+        butterfly(t, 2 + 4 * i, 3 + 4 * i, 32, true);
+    }
+    adst_output_permutation(t, 4);
+}
+
+/// 7.13.2.15 Inverse identity transform process
+pub fn inverse_identity(t: &mut [i32], n: u32) {
+    for x in &mut t[..1 << n] {
+        *x = match n {
+            2 => round2(*x as i64 * 5793, 12) as i32,
+            3 => *x * 2,
+            4 => round2(*x as i64 * 11586, 12) as i32,
+            _ => *x * 4,
+        };
+    }
+}
+
+/// 7.13.2.10 Inverse WHT process
+pub fn inverse_wht(t: &mut [i32], shift: u8) {
+    let mut a = t[0] >> shift;
+    let mut c = t[1] >> shift;
+    let mut d = t[2] >> shift;
+    let mut b = t[3] >> shift;
+    a += c;
+    d -= b;
+    let e = (a - d) >> 1;
+    b = e - b;
+    c = e - c;
+    a -= b;
+    d += c;
+    t[0] = a;
+    t[1] = b;
+    t[2] = c;
+    t[3] = d;
+}
+
+fn clamp_bits(x: i32, bits: u8) -> i32 {
+    x.clamp(-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+}
+
+// 行（或列）的一维反变换，kind 为 0 DCT、1 ADST、2 identity
+fn inverse_transform_1d(t: &mut [i32], n: u32, kind: u8) {
+    match (kind, n) {
+        (0, _) => inverse_dct(t, n),
+        (1, 2) => inverse_adst4(t),
+        (1, 3) => inverse_adst8(t),
+        (1, _) => inverse_adst16(t),
+        _ => inverse_identity(t, n),
+    }
+}
+
+/// 7.13.3 2D inverse transform process
+///
+/// coeffs is the dequantized block in the layout of `TransformBlock::quant`, row-major with a
+/// width of min(w, 32) and a height of min(h, 32), the output is the w*h residual in raster order
+pub fn inverse_transform_block(tx_size: TxSize, tx_type: TxType, bit_depth: u8, coeffs: &[i32]) -> Vec<i32> {
+    let log2w = TX_WIDTH_LOG2[tx_size as usize] as u32;
+    let log2h = TX_HEIGHT_LOG2[tx_size as usize] as u32;
+    let (w, h) = (1usize << log2w, 1usize << log2h);
+    let tw = w.min(32);
+    let lossless = tx_type == TxType::WhtWht;
+    let row_shift = if lossless { 0 } else { TRANSFORM_ROW_SHIFT[tx_size as usize] };
+    let col_shift = if lossless { 0 } else { 4 };
+    let col_clamp = (bit_depth + 6).max(16);
+    let row_kind = match tx_type {
+        TxType::DctDct | TxType::AdstDct | TxType::FlipadstDct | TxType::HDct => 0,
+        TxType::DctAdst | TxType::AdstAdst | TxType::DctFlipadst | TxType::FlipadstFlipadst | TxType::AdstFlipadst | TxType::FlipadstAdst | TxType::HAdst | TxType::HFlipadst => 1,
+        _ => 2,
+    };
+    let col_kind = match tx_type {
+        TxType::DctDct | TxType::DctAdst | TxType::DctFlipadst | TxType::VDct => 0,
+        TxType::AdstDct | TxType::AdstAdst | TxType::FlipadstDct | TxType::FlipadstFlipadst | TxType::AdstFlipadst | TxType::FlipadstAdst | TxType::VAdst | TxType::VFlipadst => 1,
+        _ => 2,
+    };
+    let flip_lr = matches!(tx_type, TxType::DctFlipadst | TxType::FlipadstFlipadst | TxType::AdstFlipadst | TxType::HFlipadst);
+    let flip_ud = matches!(tx_type, TxType::FlipadstDct | TxType::FlipadstFlipadst | TxType::FlipadstAdst | TxType::VFlipadst);
+
+    let mut residual = vec![0; w * h];
+    let mut t = vec![0; w.max(h)];
+    // 64 点变换只有左上 32x32 非零，之后的行全为 0，变换结果也为 0
+    for i in 0..h.min(32) {
+        t[..w].fill(0);
+        t[..tw].copy_from_slice(&coeffs[i * tw..(i + 1) * tw]);
+        if lossless {
+            inverse_wht(&mut t, 2);
+        } else {
+            for x in &mut t[..w] {
+                if log2w.abs_diff(log2h) == 1 {
+                    *x = round2(*x as i64 * 2896, 12) as i32;
+                }
+                *x = clamp_bits(*x, bit_depth + 8);
+            }
+            inverse_transform_1d(&mut t, log2w, row_kind);
+        }
+        for j in 0..w {
+            let x = if flip_lr { t[w - 1 - j] } else { t[j] };
+            let x = round2(x as i64, row_shift) as i32;
+            residual[i * w + j] = if lossless { x } else { clamp_bits(x, col_clamp) };
+        }
+    }
+    for j in 0..w {
+        for i in 0..h {
+            t[i] = residual[i * w + j];
+        }
+        if lossless {
+            inverse_wht(&mut t, 0);
+        } else {
+            inverse_transform_1d(&mut t, log2h, col_kind);
+        }
+        for i in 0..h {
+            let x = if flip_ud { t[h - 1 - i] } else { t[i] };
+            residual[i * w + j] = round2(x as i64, col_shift) as i32;
+        }
+    }
+    residual
+}
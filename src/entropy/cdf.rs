@@ -0,0 +1,191 @@
+// ! 7.20 / 8.2 CDF 的初始化、保存和加载
+
+use crate::utils::consts::*;
+
+
+
+// 数组最后一个元素是符号计数器，嵌套数组逐层清零
+trait CdfArray {
+    fn reset_counters(&mut self);
+}
+
+impl<const N: usize> CdfArray for [u32; N] {
+    fn reset_counters(&mut self) {
+        self[N - 1] = 0;
+    }
+}
+
+impl<T: CdfArray, const N: usize> CdfArray for [T; N] {
+    fn reset_counters(&mut self) {
+        for cdf in self.iter_mut() {
+            cdf.reset_counters();
+        }
+    }
+}
+
+
+macro_rules! cdf_struct {
+    ($(#[$meta:meta])* $name:ident($($arg:ident: $arg_ty:ty),*) { $($field:ident: $ty:ty = $default:expr,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, PartialEq, Clone)]
+        pub struct $name {
+            $(pub $field: $ty,)*
+        }
+
+        #[allow(clippy::new_without_default)]
+        impl $name {
+            pub fn new($($arg: $arg_ty),*) -> Self {
+                Self { $($field: $default,)* }
+            }
+        }
+
+        impl CdfArray for $name {
+            fn reset_counters(&mut self) {
+                $(self.$field.reset_counters();)*
+            }
+        }
+    };
+}
+
+
+/// 7.12.2 get_coeff_cdf_q_ctx
+pub fn coeff_cdf_q_ctx(base_q_idx: u8) -> usize {
+    match base_q_idx {
+        0..=20 => 0,
+        21..=60 => 1,
+        61..=120 => 2,
+        _ => 3,
+    }
+}
+
+
+cdf_struct! {
+    /// Motion vector CDFs of one MvCtx, indexed by comp
+    MvCdf() {
+        mv_joint_cdf: [u32; MV_JOINTS + 1] = DEFAULT_MV_JOINT_CDF,
+        mv_class_cdf: [[u32; MV_CLASSES + 1]; 2] = DEFAULT_MV_CLASS_CDF,
+        mv_class0_bit_cdf: [[u32; 3]; 2] = [DEFAULT_MV_CLASS0_BIT_CDF; 2],
+        mv_class0_fr_cdf: [[[u32; MV_FR_SIZE + 1]; CLASS0_SIZE]; 2] = DEFAULT_MV_CLASS0_FR_CDF,
+        mv_class0_hp_cdf: [[u32; 3]; 2] = [DEFAULT_MV_CLASS0_HP_CDF; 2],
+        mv_sign_cdf: [[u32; 3]; 2] = [DEFAULT_MV_SIGN_CDF; 2],
+        mv_bit_cdf: [[[u32; 3]; MV_OFFSET_BITS]; 2] = [DEFAULT_MV_BIT_CDF; 2],
+        mv_fr_cdf: [[u32; MV_FR_SIZE + 1]; 2] = DEFAULT_MV_FR_CDF,
+        mv_hp_cdf: [[u32; 3]; 2] = [DEFAULT_MV_HP_CDF; 2],
+    }
+}
+
+
+cdf_struct! {
+    /// init_coeff_cdfs, the default set is chosen by base_q_idx
+    CoeffCdf(idx: usize) {
+        txb_skip_cdf: [[[u32; 3]; TXB_SKIP_CONTEXTS]; TX_SIZES] = DEFAULT_TXB_SKIP_CDF[idx],
+        eob_pt_16_cdf: [[[u32; 6]; 2]; PLANE_TYPES] = DEFAULT_EOB_PT_16_CDF[idx],
+        eob_pt_32_cdf: [[[u32; 7]; 2]; PLANE_TYPES] = DEFAULT_EOB_PT_32_CDF[idx],
+        eob_pt_64_cdf: [[[u32; 8]; 2]; PLANE_TYPES] = DEFAULT_EOB_PT_64_CDF[idx],
+        eob_pt_128_cdf: [[[u32; 9]; 2]; PLANE_TYPES] = DEFAULT_EOB_PT_128_CDF[idx],
+        eob_pt_256_cdf: [[[u32; 10]; 2]; PLANE_TYPES] = DEFAULT_EOB_PT_256_CDF[idx],
+        eob_pt_512_cdf: [[u32; 11]; PLANE_TYPES] = DEFAULT_EOB_PT_512_CDF[idx],
+        eob_pt_1024_cdf: [[u32; 12]; PLANE_TYPES] = DEFAULT_EOB_PT_1024_CDF[idx],
+        eob_extra_cdf: [[[[u32; 3]; EOB_COEF_CONTEXTS]; PLANE_TYPES]; TX_SIZES] = DEFAULT_EOB_EXTRA_CDF[idx],
+        dc_sign_cdf: [[[u32; 3]; DC_SIGN_CONTEXTS]; PLANE_TYPES] = DEFAULT_DC_SIGN_CDF[idx],
+        coeff_base_eob_cdf: [[[[u32; 4]; SIG_COEF_CONTEXTS_EOB]; PLANE_TYPES]; TX_SIZES] = DEFAULT_COEFF_BASE_EOB_CDF[idx],
+        coeff_base_cdf: [[[[u32; 5]; SIG_COEF_CONTEXTS]; PLANE_TYPES]; TX_SIZES] = DEFAULT_COEFF_BASE_CDF[idx],
+        coeff_br_cdf: [[[[u32; BR_CDF_SIZE + 1]; LEVEL_CONTEXTS]; PLANE_TYPES]; TX_SIZES] = DEFAULT_COEFF_BR_CDF[idx],
+    }
+}
+
+
+cdf_struct! {
+    /// All CDF arrays of a frame, init_non_coeff_cdfs plus init_coeff_cdfs
+    CdfContext(base_q_idx: u8) {
+        y_mode_cdf: [[u32; INTRA_MODES + 1]; BLOCK_SIZE_GROUPS] = DEFAULT_Y_MODE_CDF,
+        intra_frame_y_mode_cdf: [[[u32; INTRA_MODES + 1]; INTRA_MODE_CONTEXTS]; INTRA_MODE_CONTEXTS] = DEFAULT_INTRA_FRAME_Y_MODE_CDF,
+        uv_mode_cfl_not_allowed_cdf: [[u32; UV_INTRA_MODES_CFL_NOT_ALLOWED + 1]; INTRA_MODES] = DEFAULT_UV_MODE_CFL_NOT_ALLOWED_CDF,
+        uv_mode_cfl_allowed_cdf: [[u32; UV_INTRA_MODES_CFL_ALLOWED + 1]; INTRA_MODES] = DEFAULT_UV_MODE_CFL_ALLOWED_CDF,
+        angle_delta_cdf: [[u32; (2 * MAX_ANGLE_DELTA + 1) + 1]; DIRECTIONAL_MODES] = DEFAULT_ANGLE_DELTA_CDF,
+        intrabc_cdf: [u32; 2 + 1] = DEFAULT_INTRABC_CDF,
+        partition_w8_cdf: [[u32; 5]; PARTITION_CONTEXTS] = DEFAULT_PARTITION_W8_CDF,
+        partition_w16_cdf: [[u32; 11]; PARTITION_CONTEXTS] = DEFAULT_PARTITION_W16_CDF,
+        partition_w32_cdf: [[u32; 11]; PARTITION_CONTEXTS] = DEFAULT_PARTITION_W32_CDF,
+        partition_w64_cdf: [[u32; 11]; PARTITION_CONTEXTS] = DEFAULT_PARTITION_W64_CDF,
+        partition_w128_cdf: [[u32; 9]; PARTITION_CONTEXTS] = DEFAULT_PARTITION_W128_CDF,
+        tx_8x8_cdf: [[u32; MAX_TX_DEPTH + 1]; TX_SIZE_CONTEXTS] = DEFAULT_TX_8X8_CDF,
+        tx_16x16_cdf: [[u32; MAX_TX_DEPTH + 2]; TX_SIZE_CONTEXTS] = DEFAULT_TX_16X16_CDF,
+        tx_32x32_cdf: [[u32; MAX_TX_DEPTH + 2]; TX_SIZE_CONTEXTS] = DEFAULT_TX_32X32_CDF,
+        tx_64x64_cdf: [[u32; MAX_TX_DEPTH + 2]; TX_SIZE_CONTEXTS] = DEFAULT_TX_64X64_CDF,
+        txfm_split_cdf: [[u32; 3]; TXFM_PARTITION_CONTEXTS] = DEFAULT_TXFM_SPLIT_CDF,
+        filter_intra_mode_cdf: [u32; 6] = DEFAULT_FILTER_INTRA_MODE_CDF,
+        filter_intra_cdf: [[u32; 3]; BLOCK_SIZES] = DEFAULT_FILTER_INTRA_CDF,
+        segment_id_cdf: [[u32; MAX_SEGMENTS + 1]; SEGMENT_ID_CONTEXTS] = DEFAULT_SEGMENT_ID_CDF,
+        segment_id_predicted_cdf: [[u32; 3]; SEGMENT_ID_PREDICTED_CONTEXTS] = DEFAULT_SEGMENT_ID_PREDICTED_CDF,
+        mv: [MvCdf; MV_CONTEXTS] = [MvCdf::new(), MvCdf::new()],
+        new_mv_cdf: [[u32; 3]; NEW_MV_CONTEXTS] = DEFAULT_NEW_MV_CDF,
+        zero_mv_cdf: [[u32; 3]; ZERO_MV_CONTEXTS] = DEFAULT_ZERO_MV_CDF,
+        ref_mv_cdf: [[u32; 3]; REF_MV_CONTEXTS] = DEFAULT_REF_MV_CDF,
+        drl_mode_cdf: [[u32; 3]; DRL_MODE_CONTEXTS] = DEFAULT_DRL_MODE_CDF,
+        is_inter_cdf: [[u32; 3]; IS_INTER_CONTEXTS] = DEFAULT_IS_INTER_CDF,
+        comp_mode_cdf: [[u32; 3]; COMP_INTER_CONTEXTS] = DEFAULT_COMP_MODE_CDF,
+        skip_mode_cdf: [[u32; 3]; SKIP_MODE_CONTEXTS] = DEFAULT_SKIP_MODE_CDF,
+        skip_cdf: [[u32; 3]; SKIP_CONTEXTS] = DEFAULT_SKIP_CDF,
+        comp_ref_cdf: [[[u32; 3]; FWD_REFS - 1]; REF_CONTEXTS] = DEFAULT_COMP_REF_CDF,
+        comp_bwd_ref_cdf: [[[u32; 3]; BWD_REFS - 1]; REF_CONTEXTS] = DEFAULT_COMP_BWD_REF_CDF,
+        single_ref_cdf: [[[u32; 3]; SINGLE_REFS - 1]; REF_CONTEXTS] = DEFAULT_SINGLE_REF_CDF,
+        compound_mode_cdf: [[u32; COMPOUND_MODES + 1]; COMPOUND_MODE_CONTEXTS] = DEFAULT_COMPOUND_MODE_CDF,
+        interp_filter_cdf: [[u32; INTERP_FILTERS + 1]; INTERP_FILTER_CONTEXTS] = DEFAULT_INTERP_FILTER_CDF,
+        motion_mode_cdf: [[u32; MOTION_MODES + 1]; BLOCK_SIZES] = DEFAULT_MOTION_MODE_CDF,
+        palette_y_size_cdf: [[u32; PALETTE_SIZES + 1]; PALETTE_BLOCK_SIZE_CONTEXTS] = DEFAULT_PALETTE_Y_SIZE_CDF,
+        palette_uv_size_cdf: [[u32; PALETTE_SIZES + 1]; PALETTE_BLOCK_SIZE_CONTEXTS] = DEFAULT_PALETTE_UV_SIZE_CDF,
+        palette_size_2_y_color_cdf: [[u32; 3]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_2_Y_COLOR_CDF,
+        palette_size_3_y_color_cdf: [[u32; 4]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_3_Y_COLOR_CDF,
+        palette_size_4_y_color_cdf: [[u32; 5]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_4_Y_COLOR_CDF,
+        palette_size_5_y_color_cdf: [[u32; 6]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_5_Y_COLOR_CDF,
+        palette_size_6_y_color_cdf: [[u32; 7]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_6_Y_COLOR_CDF,
+        palette_size_7_y_color_cdf: [[u32; 8]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_7_Y_COLOR_CDF,
+        palette_size_8_y_color_cdf: [[u32; 9]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_8_Y_COLOR_CDF,
+        palette_size_2_uv_color_cdf: [[u32; 3]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_2_UV_COLOR_CDF,
+        palette_size_3_uv_color_cdf: [[u32; 4]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_3_UV_COLOR_CDF,
+        palette_size_4_uv_color_cdf: [[u32; 5]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_4_UV_COLOR_CDF,
+        palette_size_5_uv_color_cdf: [[u32; 6]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_5_UV_COLOR_CDF,
+        palette_size_6_uv_color_cdf: [[u32; 7]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_6_UV_COLOR_CDF,
+        palette_size_7_uv_color_cdf: [[u32; 8]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_7_UV_COLOR_CDF,
+        palette_size_8_uv_color_cdf: [[u32; 9]; PALETTE_COLOR_CONTEXTS] = DEFAULT_PALETTE_SIZE_8_UV_COLOR_CDF,
+        palette_y_mode_cdf: [[[u32; 3]; PALETTE_Y_MODE_CONTEXTS]; PALETTE_BLOCK_SIZE_CONTEXTS] = DEFAULT_PALETTE_Y_MODE_CDF,
+        palette_uv_mode_cdf: [[u32; 3]; PALETTE_UV_MODE_CONTEXTS] = DEFAULT_PALETTE_UV_MODE_CDF,
+        delta_q_cdf: [u32; DELTA_Q_SMALL + 2] = DEFAULT_DELTA_Q_CDF,
+        delta_lf_cdf: [u32; DELTA_LF_SMALL + 2] = DEFAULT_DELTA_LF_CDF,
+        delta_lf_multi_cdf: [[u32; DELTA_LF_SMALL + 2]; FRAME_LF_COUNT] = [DEFAULT_DELTA_LF_CDF; FRAME_LF_COUNT],
+        intra_tx_type_set1_cdf: [[[u32; 8]; INTRA_MODES]; 2] = DEFAULT_INTRA_TX_TYPE_SET1_CDF,
+        intra_tx_type_set2_cdf: [[[u32; 6]; INTRA_MODES]; 3] = DEFAULT_INTRA_TX_TYPE_SET2_CDF,
+        inter_tx_type_set1_cdf: [[u32; 17]; 2] = DEFAULT_INTER_TX_TYPE_SET1_CDF,
+        inter_tx_type_set2_cdf: [u32; 13] = DEFAULT_INTER_TX_TYPE_SET2_CDF,
+        inter_tx_type_set3_cdf: [[u32; 3]; 4] = DEFAULT_INTER_TX_TYPE_SET3_CDF,
+        compound_idx_cdf: [[u32; 3]; COMPOUND_IDX_CONTEXTS] = DEFAULT_COMPOUND_IDX_CDF,
+        comp_group_idx_cdf: [[u32; 3]; COMP_GROUP_IDX_CONTEXTS] = DEFAULT_COMP_GROUP_IDX_CDF,
+        compound_type_cdf: [[u32; COMPOUND_TYPES + 1]; BLOCK_SIZES] = DEFAULT_COMPOUND_TYPE_CDF,
+        inter_intra_cdf: [[u32; 3]; BLOCK_SIZE_GROUPS - 1] = DEFAULT_INTER_INTRA_CDF,
+        inter_intra_mode_cdf: [[u32; INTERINTRA_MODES + 1]; BLOCK_SIZE_GROUPS - 1] = DEFAULT_INTER_INTRA_MODE_CDF,
+        wedge_index_cdf: [[u32; 16 + 1]; BLOCK_SIZES] = DEFAULT_WEDGE_INDEX_CDF,
+        wedge_inter_intra_cdf: [[u32; 3]; BLOCK_SIZES] = DEFAULT_WEDGE_INTER_INTRA_CDF,
+        use_obmc_cdf: [[u32; 3]; BLOCK_SIZES] = DEFAULT_USE_OBMC_CDF,
+        comp_ref_type_cdf: [[u32; 3]; COMP_REF_TYPE_CONTEXTS] = DEFAULT_COMP_REF_TYPE_CDF,
+        uni_comp_ref_cdf: [[[u32; 3]; UNIDIR_COMP_REFS - 1]; REF_CONTEXTS] = DEFAULT_UNI_COMP_REF_CDF,
+        cfl_sign_cdf: [u32; CFL_JOINT_SIGNS + 1] = DEFAULT_CFL_SIGN_CDF,
+        cfl_alpha_cdf: [[u32; CFL_ALPHABET_SIZE + 1]; CFL_ALPHA_CONTEXTS] = DEFAULT_CFL_ALPHA_CDF,
+        use_wiener_cdf: [u32; 2 + 1] = DEFAULT_USE_WIENER_CDF,
+        use_sgrproj_cdf: [u32; 2 + 1] = DEFAULT_USE_SGRPROJ_CDF,
+        restoration_type_cdf: [u32; RESTORE_SWITCHABLE + 1] = DEFAULT_RESTORATION_TYPE_CDF,
+        coeff: CoeffCdf = CoeffCdf::new(coeff_cdf_q_ctx(base_q_idx)),
+    }
+}
+
+impl CdfContext {
+    /// init_coeff_cdfs
+    pub fn init_coeff_cdfs(&mut self, base_q_idx: u8) {
+        self.coeff = CoeffCdf::new(coeff_cdf_q_ctx(base_q_idx));
+    }
+
+    /// Clears the symbol counters, done whenever CDFs are carried to another frame or tile
+    pub fn reset_counters(&mut self) {
+        CdfArray::reset_counters(self);
+    }
+}
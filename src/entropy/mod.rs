@@ -0,0 +1,5 @@
+pub mod symbol;
+pub mod cdf;
+
+pub use symbol::SymbolDecoder;
+pub use cdf::CdfContext;
@@ -0,0 +1,136 @@
+// ! 8.2 Symbol decoding process
+
+use crate::{error::{Error, Result}, utils::{consts::{EC_MIN_PROB, EC_PROB_SHIFT}, math::floor_log2}};
+
+
+
+/// 8.2 Symbol decoder over the bytes of one tile
+pub struct SymbolDecoder<'a> {
+    data: &'a [u8],
+    // 已读取的比特数
+    position: usize,
+    symbol_value: u32,
+    symbol_range: u32,
+    symbol_max_bits: i32,
+    disable_cdf_update: bool,
+}
+
+impl<'a> SymbolDecoder<'a> {
+    /// 8.2.2 Initialization process for symbol decoder
+    pub fn init_symbol(data: &'a [u8], disable_cdf_update: bool) -> Result<Self> {
+        if data.is_empty() {
+            return Err(Error::Truncated { needed: 8, available: 0 });
+        }
+        let sz = data.len();
+        let mut decoder = Self { data, position: 0, symbol_value: 0, symbol_range: 1 << 15, symbol_max_bits: 0, disable_cdf_update };
+        let num_bits = (sz * 8).min(15) as u32;
+        let buf = decoder.read_bits(num_bits);
+        let padded_buf = buf << (15 - num_bits);
+        decoder.symbol_value = ((1 << 15) - 1) ^ padded_buf;
+        decoder.symbol_max_bits = 8 * sz as i32 - 15;
+        Ok(decoder)
+    }
+
+    // f(n)，n 最多 15，不会读到 tile 之外
+    fn read_bits(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        let start = self.position >> 3;
+        let mut window = 0u32;
+        for i in 0..4 {
+            window = window << 8 | self.data.get(start + i).copied().unwrap_or(0) as u32;
+        }
+        let offset = self.position & 0b111;
+        self.position += n as usize;
+        (window << offset) >> (32 - n)
+    }
+
+    /// 8.2.6 Symbol decoding process
+    pub fn read_symbol(&mut self, cdf: &mut [u32]) -> usize {
+        let n = cdf.len() - 1;
+        let symbol = self.decode(cdf);
+        if !self.disable_cdf_update {
+            // 最后一个元素是自适应计数器
+            let rate = 3 + (cdf[n] > 15) as u32 + (cdf[n] > 31) as u32 + (n.ilog2()).min(2);
+            let mut tmp = 0;
+            for (i, value) in cdf.iter_mut().enumerate().take(n - 1) {
+                if i == symbol {
+                    tmp = 1 << 15;
+                }
+                if tmp < *value {
+                    *value -= (*value - tmp) >> rate;
+                } else {
+                    *value += (tmp - *value) >> rate;
+                }
+            }
+            cdf[n] += (cdf[n] < 32) as u32;
+        }
+        symbol
+    }
+
+    fn decode(&mut self, cdf: &[u32]) -> usize {
+        let n = cdf.len() - 1;
+        let mut cur = self.symbol_range;
+        let mut prev;
+        let mut symbol = 0;
+        loop {
+            prev = cur;
+            let f = (1 << 15) - cdf[symbol];
+            cur = ((self.symbol_range >> 8) * (f >> EC_PROB_SHIFT)) >> (7 - EC_PROB_SHIFT);
+            cur += EC_MIN_PROB as u32 * (n - symbol - 1) as u32;
+            if self.symbol_value >= cur {
+                break;
+            }
+            symbol += 1;
+        }
+        self.symbol_range = prev - cur;
+        self.symbol_value -= cur;
+        self.renormalize();
+        symbol
+    }
+
+    fn renormalize(&mut self) {
+        let bits = 15 - self.symbol_range.ilog2();
+        self.symbol_range <<= bits;
+        let num_bits = bits.min(self.symbol_max_bits.max(0) as u32);
+        let new_data = self.read_bits(num_bits);
+        let padded_data = new_data << (bits - num_bits);
+        self.symbol_value = padded_data ^ (((self.symbol_value + 1) << bits) - 1);
+        self.symbol_max_bits -= bits as i32;
+    }
+
+    /// 8.2.3 Boolean decoding process
+    pub fn read_bool(&mut self) -> bool {
+        self.decode(&[1 << 14, 1 << 15, 0]) == 1
+    }
+
+    /// 4.10.2 L(n)
+    pub fn read_literal(&mut self, n: u8) -> u32 {
+        let mut x = 0;
+        for _ in 0..n {
+            x = 2 * x + self.read_bool() as u32;
+        }
+        x
+    }
+
+    /// 4.10.9 NS(n), n must be greater than 0
+    pub fn read_ns(&mut self, n: u32) -> u32 {
+        let w = floor_log2(n) + 1;
+        let m = (1 << w) - n;
+        let v = self.read_literal(w - 1);
+        if v < m {
+            return v;
+        }
+        let extra_bit = self.read_literal(1);
+        (v << 1) - m + extra_bit
+    }
+
+    /// 8.2.4 Exit process for symbol decoder
+    pub fn exit_symbol(self) -> Result<()> {
+        if self.symbol_max_bits < -14 {
+            return Err(Error::Conformance { section: "8.2.4", reason: "symbol decoder read past the end of the tile" });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt;
+
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    // Not enough bits left in the buffer, values are counted in bits
+    Truncated { needed: usize, available: usize },
+
+    // obu_forbidden_bit is set in the OBU header
+    ForbiddenBit,
+
+    // A syntax element or enum holds a value outside of its allowed range
+    InvalidValue { name: &'static str, value: u32 },
+
+    // The stream uses a feature this decoder does not handle
+    Unsupported(&'static str),
+
+    // The stream violates a requirement of bitstream conformance
+    Conformance { section: &'static str, reason: &'static str },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { needed, available } => write!(f, "data truncated: need {needed} bits, {available} available"),
+            Self::ForbiddenBit => write!(f, "obu_forbidden_bit is set"),
+            Self::InvalidValue { name, value } => write!(f, "invalid value {value} for {name}"),
+            Self::Unsupported(feature) => write!(f, "unsupported feature: {feature}"),
+            Self::Conformance { section, reason } => write!(f, "conformance violation (spec {section}): {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
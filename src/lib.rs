@@ -1,4 +1,10 @@
-
-
+pub mod error;
 pub mod utils;
-pub mod obu;
\ No newline at end of file
+pub mod obu;
+pub mod container;
+pub mod decoder;
+pub mod entropy;
+pub mod output;
+
+pub use error::{Error, Result};
+pub use decoder::{Decoder, Picture};
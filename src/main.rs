@@ -0,0 +1,314 @@
+use std::{env, fs::File, io::{self, BufWriter, Read, Write}, path::Path, process::ExitCode};
+
+use wav1d::{container::{annexb::AnnexBReader, ivf::{IvfHeader, IvfReader}, BitstreamFormat}, output::{hash::hex, HashWriter, PictureWriter, Y4mWriter, YuvWriter}, Decoder, Picture};
+
+
+
+const USAGE: &str = "\
+Usage: wav1d -i <input> [-o <output>] [options]
+
+Options:
+  -i, --input <file>          input file, - for stdin
+  -o, --output <file>         output file, - for stdout
+      --demuxer <name>        ivf, obu or annexb, detected from the input by default
+      --muxer <name>          y4m, yuv, md5 or framemd5, taken from the output extension by default
+      --crc32                 add CRC32 checksums to the md5 and framemd5 output
+      --limit <n>             stop after writing n frames
+      --skip <n>              drop the first n frames
+      --oppoint <n>           operating point to decode, 0 by default
+      --alllayers             output all spatial layers instead of only the highest
+      --output-bitdepth <n>   convert samples to 8, 10 or 12 bits
+      --verify <md5>          compare the md5 of the decoded frames with <md5>
+
+md5, framemd5 and --verify fail on frames that need inter prediction or loop filtering, which are
+not implemented yet; y4m and yuv output warn and write them unfiltered.
+  -h, --help                  show this help";
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Demuxer {
+    Ivf,
+    Obu,
+    AnnexB,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Muxer {
+    Y4m,
+    Yuv,
+    // 整个码流的 md5
+    Md5,
+    // 每帧一行 md5
+    FrameMd5,
+}
+
+
+#[derive(Debug, Default)]
+struct Options {
+    input: String,
+    output: Option<String>,
+    demuxer: Option<Demuxer>,
+    muxer: Option<Muxer>,
+    limit: Option<usize>,
+    skip: usize,
+    operating_point: usize,
+    all_layers: bool,
+    output_bit_depth: Option<u8>,
+    verify: Option<String>,
+    crc32: bool,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                return Ok(None);
+            }
+            let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+            match arg.as_str() {
+                "-i" | "--input" => options.input = value()?,
+                "-o" | "--output" => options.output = Some(value()?),
+                "--demuxer" => options.demuxer = Some(match value()?.as_str() {
+                    "ivf" => Demuxer::Ivf,
+                    "obu" | "section5" => Demuxer::Obu,
+                    "annexb" => Demuxer::AnnexB,
+                    v => return Err(format!("unknown demuxer {v}")),
+                }),
+                "--muxer" => options.muxer = Some(match value()?.as_str() {
+                    "y4m" => Muxer::Y4m,
+                    "yuv" => Muxer::Yuv,
+                    "md5" => Muxer::Md5,
+                    "framemd5" => Muxer::FrameMd5,
+                    v => return Err(format!("unknown muxer {v}")),
+                }),
+                "--limit" => options.limit = Some(number(&arg, &value()?)?),
+                "--skip" => options.skip = number(&arg, &value()?)?,
+                "--oppoint" => options.operating_point = number(&arg, &value()?)?,
+                "--alllayers" => options.all_layers = true,
+                "--output-bitdepth" => match number(&arg, &value()?)? {
+                    depth @ (8 | 10 | 12) => options.output_bit_depth = Some(depth as u8),
+                    depth => return Err(format!("unsupported output bit depth {depth}")),
+                },
+                "--verify" => options.verify = Some(value()?.to_ascii_lowercase()),
+                "--crc32" => options.crc32 = true,
+                _ => return Err(format!("unknown option {arg}")),
+            }
+        }
+        if options.input.is_empty() {
+            return Err("no input file given".into());
+        }
+        if options.output.is_none() && options.verify.is_none() {
+            return Err("no output file given".into());
+        }
+        Ok(Some(options))
+    }
+}
+
+fn number(name: &str, value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("invalid number {value} for {name}"))
+}
+
+
+// 按 --skip / --limit 过滤后写入输出和校验
+struct Output {
+    writer: Option<Box<dyn PictureWriter>>,
+    hash: Option<HashWriter>,
+    output_bit_depth: Option<u8>,
+    skip: usize,
+    limit: Option<usize>,
+    count: usize,
+    // 已经提示过输出了没有完成重建的图像
+    warned: bool,
+}
+
+impl Output {
+    fn done(&self) -> bool {
+        self.limit.is_some_and(|limit| self.count >= limit)
+    }
+
+    fn write(&mut self, picture: Picture) -> io::Result<()> {
+        if self.done() {
+            return Ok(());
+        }
+        if self.skip > 0 {
+            self.skip -= 1;
+            return Ok(());
+        }
+        let picture = match self.output_bit_depth {
+            Some(bit_depth) if bit_depth != picture.bit_depth() => picture.with_bit_depth(bit_depth),
+            _ => picture,
+        };
+        if let Some(writer) = &mut self.writer {
+            if !picture.reconstructed && !self.warned {
+                eprintln!("wav1d: warning: writing pictures that are not fully reconstructed, inter blocks and loop filters are missing");
+                self.warned = true;
+            }
+            writer.write_picture(&picture)?;
+        }
+        if let Some(hash) = &mut self.hash {
+            hash.write_picture(&picture)?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn drain(&mut self, decoder: &mut Decoder) -> io::Result<()> {
+        for e in decoder.take_metadata_errors() {
+            eprintln!("wav1d: skipping invalid metadata OBU: {e}");
+        }
+        while let Some(picture) = decoder.get_picture() {
+            self.write(picture)?;
+        }
+        Ok(())
+    }
+}
+
+
+fn read_input(input: &str) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    if input == "-" {
+        io::stdin().lock().read_to_end(&mut data)?;
+    } else {
+        File::open(input)?.read_to_end(&mut data)?;
+    }
+    Ok(data)
+}
+
+fn run(options: Options) -> Result<(), Box<dyn std::error::Error>> {
+    let data = read_input(&options.input)?;
+    let demuxer = options.demuxer.unwrap_or(if data.starts_with(b"DKIF") {
+        Demuxer::Ivf
+    } else if BitstreamFormat::detect(&data) == BitstreamFormat::AnnexB {
+        Demuxer::AnnexB
+    } else {
+        Demuxer::Obu
+    });
+    let framerate = match demuxer {
+        Demuxer::Ivf => {
+            let header = IvfHeader::parse(&data)?;
+            (header.timebase_den, header.timebase_num)
+        }
+        _ => (25, 1),
+    };
+
+    let muxer = options.muxer.unwrap_or(match &options.output {
+        Some(output) if output.ends_with(".y4m") => Muxer::Y4m,
+        Some(output) if output.ends_with(".md5") => Muxer::Md5,
+        _ => Muxer::Yuv,
+    });
+    let file = match &options.output {
+        Some(output) => {
+            let file: Box<dyn Write> = if output == "-" {
+                Box::new(io::stdout().lock())
+            } else {
+                Box::new(File::create(output)?)
+            };
+            Some(BufWriter::new(file))
+        }
+        None => None,
+    };
+    // md5 和 framemd5 在解码结束后才写出结果，HashWriter 拒绝没有完成重建的图像
+    let hashing = matches!(muxer, Muxer::Md5 | Muxer::FrameMd5);
+    let (writer, mut report) = match file {
+        Some(file) if !hashing => {
+            let writer: Box<dyn PictureWriter> = match muxer {
+                Muxer::Y4m => Box::new(Y4mWriter::new(file, framerate)),
+                _ => Box::new(YuvWriter::new(file)),
+            };
+            (Some(writer), None)
+        }
+        file => (None, file),
+    };
+    let mut output = Output {
+        writer,
+        hash: (hashing || options.verify.is_some()).then(|| HashWriter::new(options.crc32)),
+        output_bit_depth: options.output_bit_depth,
+        skip: options.skip,
+        limit: options.limit,
+        count: 0,
+        warned: false,
+    };
+
+    let mut decoder = Decoder::new();
+    decoder.set_operating_point(options.operating_point);
+    decoder.set_all_layers(options.all_layers);
+    match demuxer {
+        Demuxer::Ivf => {
+            for frame in IvfReader::new(&data)? {
+                decoder.send_data(frame?.data)?;
+                output.drain(&mut decoder)?;
+                if output.done() {
+                    break;
+                }
+            }
+        }
+        Demuxer::AnnexB => {
+            for temporal_unit in AnnexBReader::from(data.as_slice()) {
+                for (header, payload) in temporal_unit?.obus() {
+                    decoder.send_obu(header.clone(), payload)?;
+                }
+                output.drain(&mut decoder)?;
+                if output.done() {
+                    break;
+                }
+            }
+        }
+        Demuxer::Obu => {
+            decoder.send_data(&data)?;
+            output.drain(&mut decoder)?;
+        }
+    }
+    if !output.done() {
+        decoder.flush()?;
+        output.drain(&mut decoder)?;
+    }
+    if let Some(writer) = &mut output.writer {
+        writer.flush()?;
+    }
+
+    let Some(hash) = output.hash else {
+        return Ok(());
+    };
+    if let Some(file) = &mut report {
+        let text = match muxer {
+            Muxer::FrameMd5 => {
+                let name = Path::new(&options.input).file_stem().and_then(|s| s.to_str()).unwrap_or("stdin");
+                hash.frame_report(name)
+            }
+            _ => hash.stream_report(),
+        };
+        file.write_all(text.as_bytes())?;
+        file.flush()?;
+    }
+    if let Some(expected) = options.verify {
+        let md5 = hex(&hash.md5());
+        if md5 != expected {
+            return Err(format!("md5 mismatch: expected {expected}, decoded {md5}").into());
+        }
+        eprintln!("md5 verified: {md5}");
+    }
+    Ok(())
+}
+
+
+fn main() -> ExitCode {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("wav1d: {e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("wav1d: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,1412 @@
+use crate::{entropy::CdfContext, error::{Error, Result}, obu::{header::ObuHeader, sequence::SequenceHeader}, utils::{bits::{BitsReader, FromBitsReader}, consts::{AFFINE, GM_ABS_ALPHA_BITS, GM_ABS_TRANS_BITS, GM_ABS_TRANS_ONLY_BITS, GM_ALPHA_PREC_BITS, GM_TRANS_ONLY_PREC_BITS, GM_TRANS_PREC_BITS, IDENTITY, LAST_FRAME, MAX_LOOP_FILTER, MAX_SEGMENTS, MAX_TILE_AREA, MAX_TILE_COLS, MAX_TILE_ROWS, MAX_TILE_WIDTH, NUM_REF_FRAMES, PRIMARY_REF_NONE, REFS_PER_FRAME, RESTORATION_TILESIZE_MAX, ROTZOOM, SEG_LVL_ALT_Q, SEG_LVL_MAX, SEG_LVL_REF_FRAME, SEGMENTATION_FEATURE_BITS, SEGMENTATION_FEATURE_MAX, SEGMENTATION_FEATURE_SIGNED, SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS, SUPERRES_DENOM_BITS, SUPERRES_DENOM_MIN, SUPERRES_NUM, TOTAL_REFS_PER_FRAME, TRANSLATION, WARPEDMODEL_PREC_BITS}, enums::{FrameRestorationType, FrameType, Interpolationfilter, RefFrame, TxMode}, math::{clip3, inverse_recenter}}};
+
+
+
+const ALL_FRAMES: u8 = 0xff;
+
+// 7.8 Ref_Frame_List
+const REF_FRAME_LIST: [RefFrame; REFS_PER_FRAME - 2] = [RefFrame::Last2, RefFrame::Last3, RefFrame::Bwdref, RefFrame::Altref2, RefFrame::Altref];
+
+// 5.9.2 loop_filter_ref_deltas default values
+const DEFAULT_LOOP_FILTER_REF_DELTAS: [i8; TOTAL_REFS_PER_FRAME as usize] = [1, 0, 0, 0, -1, 0, -1, -1];
+
+const DEFAULT_GM_PARAMS: [i32; 6] = [0, 0, 1 << WARPEDMODEL_PREC_BITS, 0, 0, 1 << WARPEDMODEL_PREC_BITS];
+
+
+
+/// 5.9.15 Tile info syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TileInfo {
+    pub uniform_tile_spacing_flag: bool,
+    pub tile_cols_log2: u8,
+    pub tile_rows_log2: u8,
+    pub tile_cols: u32,
+    pub tile_rows: u32,
+    // TileCols + 1 entries, the last one is MiCols
+    pub mi_col_starts: Vec<u32>,
+    // TileRows + 1 entries, the last one is MiRows
+    pub mi_row_starts: Vec<u32>,
+    pub context_update_tile_id: u32,
+    pub tile_size_bytes: u8,
+}
+
+
+/// 5.9.12 Quantization params syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct QuantizationParams {
+    pub base_q_idx: u8,
+    pub delta_q_y_dc: i32,
+    pub diff_uv_delta: bool,
+    pub delta_q_u_dc: i32,
+    pub delta_q_u_ac: i32,
+    pub delta_q_v_dc: i32,
+    pub delta_q_v_ac: i32,
+    pub using_qmatrix: bool,
+    pub qm_y: u8,
+    pub qm_u: u8,
+    pub qm_v: u8,
+}
+
+
+/// 5.9.14 Segmentation params syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SegmentationParams {
+    pub segmentation_enabled: bool,
+    pub segmentation_update_map: bool,
+    pub segmentation_temporal_update: bool,
+    pub segmentation_update_data: bool,
+    pub feature_enabled: [[bool; SEG_LVL_MAX]; MAX_SEGMENTS],
+    pub feature_data: [[i16; SEG_LVL_MAX]; MAX_SEGMENTS],
+    pub seg_id_pre_skip: bool,
+    pub last_active_seg_id: u8,
+}
+
+impl SegmentationParams {
+    /// 7.12.2 seg_feature_active_idx
+    pub fn feature_active(&self, segment_id: usize, feature: usize) -> bool {
+        self.segmentation_enabled && self.feature_enabled[segment_id][feature]
+    }
+}
+
+
+/// 5.9.17 / 5.9.18 Delta quantizer and delta loop filter params syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DeltaParams {
+    pub delta_q_present: bool,
+    pub delta_q_res: u8,
+    pub delta_lf_present: bool,
+    pub delta_lf_res: u8,
+    pub delta_lf_multi: bool,
+}
+
+
+/// 5.9.11 Loop filter params syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LoopFilterParams {
+    pub loop_filter_level: [u8; 4],
+    pub loop_filter_sharpness: u8,
+    pub loop_filter_delta_enabled: bool,
+    pub loop_filter_delta_update: bool,
+    pub loop_filter_ref_deltas: [i8; TOTAL_REFS_PER_FRAME as usize],
+    pub loop_filter_mode_deltas: [i8; 2],
+}
+
+
+/// 5.9.19 CDEF params syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CdefParams {
+    pub cdef_damping: u8,
+    pub cdef_bits: u8,
+    pub cdef_y_pri_strength: [u8; 8],
+    pub cdef_y_sec_strength: [u8; 8],
+    pub cdef_uv_pri_strength: [u8; 8],
+    pub cdef_uv_sec_strength: [u8; 8],
+}
+
+
+/// 5.9.20 Loop restoration params syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LrParams {
+    pub uses_lr: bool,
+    pub frame_restoration_type: [FrameRestorationType; 3],
+    pub loop_restoration_size: [u16; 3],
+}
+
+
+/// 5.9.24 Global motion params syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct GlobalMotionParams {
+    // IDENTITY / TRANSLATION / ROTZOOM / AFFINE, indexed by reference frame
+    pub gm_type: [u8; TOTAL_REFS_PER_FRAME as usize],
+    pub gm_params: [[i32; 6]; TOTAL_REFS_PER_FRAME as usize],
+}
+
+impl Default for GlobalMotionParams {
+    fn default() -> Self {
+        Self { gm_type: [IDENTITY; TOTAL_REFS_PER_FRAME as usize], gm_params: [DEFAULT_GM_PARAMS; TOTAL_REFS_PER_FRAME as usize] }
+    }
+}
+
+
+/// 5.9.30 Film grain params syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FilmGrainParams {
+    pub apply_grain: bool,
+    pub grain_seed: u16,
+    pub update_grain: bool,
+    pub point_y_value: Vec<u8>,
+    pub point_y_scaling: Vec<u8>,
+    pub chroma_scaling_from_luma: bool,
+    pub point_cb_value: Vec<u8>,
+    pub point_cb_scaling: Vec<u8>,
+    pub point_cr_value: Vec<u8>,
+    pub point_cr_scaling: Vec<u8>,
+    pub grain_scaling_minus_8: u8,
+    pub ar_coeff_lag: u8,
+    pub ar_coeffs_y_plus_128: Vec<u8>,
+    pub ar_coeffs_cb_plus_128: Vec<u8>,
+    pub ar_coeffs_cr_plus_128: Vec<u8>,
+    pub ar_coeff_shift_minus_6: u8,
+    pub grain_scale_shift: u8,
+    pub cb_mult: u8,
+    pub cb_luma_mult: u8,
+    pub cb_offset: u16,
+    pub cr_mult: u8,
+    pub cr_luma_mult: u8,
+    pub cr_offset: u16,
+    pub overlap_flag: bool,
+    pub clip_to_restricted_range: bool,
+}
+
+
+/// State saved into one reference slot by the reference frame update process (7.20)
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RefFrameSlot {
+    pub valid: bool,
+    pub frame_id: u32,
+    pub upscaled_width: u32,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub render_width: u32,
+    pub render_height: u32,
+    pub mi_cols: u32,
+    pub mi_rows: u32,
+    pub frame_type: FrameType,
+    pub showable_frame: bool,
+    pub order_hint: u32,
+    pub saved_order_hints: [u32; TOTAL_REFS_PER_FRAME as usize],
+    pub global_motion: GlobalMotionParams,
+    pub loop_filter_ref_deltas: [i8; TOTAL_REFS_PER_FRAME as usize],
+    pub loop_filter_mode_deltas: [i8; 2],
+    pub feature_enabled: [[bool; SEG_LVL_MAX]; MAX_SEGMENTS],
+    pub feature_data: [[i16; SEG_LVL_MAX]; MAX_SEGMENTS],
+    pub film_grain: FilmGrainParams,
+    // save_cdfs 保存的 CDF，由解码器在帧解码完成后写入
+    pub cdfs: Option<Box<CdfContext>>,
+    // SavedSegmentIds，每个 4x4 一个，为空时按全 0 处理
+    pub saved_segment_ids: Vec<u8>,
+    // SavedRefFrames / SavedMvs，每个 8x8 一个 (7.19)，为空时没有可投影的运动场
+    pub saved_ref_frames: Vec<i8>,
+    pub saved_mvs: Vec<[i32; 2]>,
+}
+
+
+/// Reference slots kept between frame headers
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RefFrameState {
+    pub slots: [RefFrameSlot; NUM_REF_FRAMES],
+}
+
+impl RefFrameState {
+    /// 7.20 Reference frame update process, the saved CDFs, segment ids and motion field are
+    /// copied from the shown slot for show_existing_frame, otherwise they are saved afterwards
+    pub fn update(&mut self, fh: &FrameHeader) {
+        // 7.21 load_cdfs / load_segment_ids / load_motion_field 读到的值
+        let shown = if fh.show_existing_frame { self.slots[fh.frame_to_show_map_idx as usize].clone() } else { RefFrameSlot::default() };
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if (fh.refresh_frame_flags >> i) & 1 == 0 {
+                continue;
+            }
+            *slot = RefFrameSlot {
+                valid: true,
+                frame_id: fh.current_frame_id,
+                upscaled_width: fh.upscaled_width,
+                frame_width: fh.frame_width,
+                frame_height: fh.frame_height,
+                render_width: fh.render_width,
+                render_height: fh.render_height,
+                mi_cols: fh.mi_cols,
+                mi_rows: fh.mi_rows,
+                frame_type: fh.frame_type,
+                showable_frame: fh.showable_frame,
+                order_hint: fh.order_hint,
+                saved_order_hints: fh.order_hints,
+                global_motion: fh.global_motion.clone(),
+                loop_filter_ref_deltas: fh.loop_filter.loop_filter_ref_deltas,
+                loop_filter_mode_deltas: fh.loop_filter.loop_filter_mode_deltas,
+                feature_enabled: fh.segmentation.feature_enabled,
+                feature_data: fh.segmentation.feature_data,
+                film_grain: fh.film_grain.clone(),
+                cdfs: shown.cdfs.clone(),
+                saved_segment_ids: shown.saved_segment_ids.clone(),
+                saved_ref_frames: shown.saved_ref_frames.clone(),
+                saved_mvs: shown.saved_mvs.clone(),
+            };
+        }
+    }
+
+    /// Saves the motion field of the frame into every slot it refreshes, called after update
+    pub fn save_motion_field(&mut self, fh: &FrameHeader, ref_frames: &[i8], mvs: &[[i32; 2]]) {
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if (fh.refresh_frame_flags >> i) & 1 == 1 {
+                slot.saved_ref_frames = ref_frames.to_vec();
+                slot.saved_mvs = mvs.to_vec();
+            }
+        }
+    }
+
+    /// Saves SegmentIds into every slot the frame refreshes, called after update
+    pub fn save_segment_ids(&mut self, fh: &FrameHeader, segment_ids: &[u8]) {
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if (fh.refresh_frame_flags >> i) & 1 == 1 {
+                slot.saved_segment_ids = segment_ids.to_vec();
+            }
+        }
+    }
+
+    /// load_previous_segment_ids, PrevSegmentIds of the frame or None when they are all 0
+    pub fn load_previous_segment_ids(&self, fh: &FrameHeader) -> Option<&[u8]> {
+        if fh.primary_ref_frame == PRIMARY_REF_NONE {
+            return None;
+        }
+        let slot = &self.slots[fh.ref_frame_idx[fh.primary_ref_frame]];
+        let same_size = slot.mi_rows == fh.mi_rows && slot.mi_cols == fh.mi_cols;
+        (same_size && !slot.saved_segment_ids.is_empty()).then_some(slot.saved_segment_ids.as_slice())
+    }
+
+    /// save_cdfs( ctx )
+    pub fn save_cdfs(&mut self, ctx: usize, cdfs: &CdfContext) {
+        self.slots[ctx].cdfs = Some(Box::new(cdfs.clone()));
+    }
+
+    /// load_cdfs( ctx ), the symbol counters start again from 0
+    pub fn load_cdfs(&self, ctx: usize) -> Result<CdfContext> {
+        let Some(cdfs) = &self.valid_slot(ctx)?.cdfs else {
+            return Err(Error::Conformance { section: "7.20", reason: "reference slot holds no saved CDFs" });
+        };
+        let mut cdfs = cdfs.as_ref().clone();
+        cdfs.reset_counters();
+        Ok(cdfs)
+    }
+
+    fn valid_slot(&self, idx: usize) -> Result<&RefFrameSlot> {
+        let slot = &self.slots[idx];
+        if !slot.valid {
+            return Err(Error::Conformance { section: "6.8.2", reason: "reference to an invalid frame slot" });
+        }
+        Ok(slot)
+    }
+}
+
+
+// 帧头解析过程中对 RefValid / RefOrderHint 的修改，整个帧头解析成功后才写回 RefFrameState
+struct PendingRefs {
+    valid: [bool; NUM_REF_FRAMES],
+    order_hint: [u32; NUM_REF_FRAMES],
+    // error_resilient_mode 下 order hint 不一致的槽，写回时整个槽被重置
+    reset: [bool; NUM_REF_FRAMES],
+}
+
+impl PendingRefs {
+    fn new(state: &RefFrameState) -> Self {
+        Self {
+            valid: state.slots.each_ref().map(|slot| slot.valid),
+            order_hint: state.slots.each_ref().map(|slot| slot.order_hint),
+            reset: [false; NUM_REF_FRAMES],
+        }
+    }
+
+    // 和 RefFrameState::valid_slot 一样的检查，但看的是解析中的 RefValid
+    fn check_valid(&self, idx: usize) -> Result<()> {
+        if !self.valid[idx] {
+            return Err(Error::Conformance { section: "6.8.2", reason: "reference to an invalid frame slot" });
+        }
+        Ok(())
+    }
+
+    fn apply(&self, state: &mut RefFrameState) {
+        for (i, slot) in state.slots.iter_mut().enumerate() {
+            if self.reset[i] {
+                // 7.20: the slot is invalidated but keeps the signalled order hint
+                *slot = RefFrameSlot {
+                    order_hint: self.order_hint[i],
+                    ..Default::default()
+                };
+            } else {
+                slot.valid = self.valid[i];
+                slot.order_hint = self.order_hint[i];
+            }
+        }
+    }
+}
+
+
+/// 5.9.2 Uncompressed header syntax
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FrameHeader {
+    pub show_existing_frame: bool,
+    pub frame_to_show_map_idx: u8,
+    pub frame_presentation_time: u32,
+    pub display_frame_id: u32,
+    pub frame_type: FrameType,
+    pub frame_is_intra: bool,
+    pub show_frame: bool,
+    pub showable_frame: bool,
+    pub error_resilient_mode: bool,
+    pub disable_cdf_update: bool,
+    pub allow_screen_content_tools: bool,
+    pub force_integer_mv: bool,
+    pub current_frame_id: u32,
+    pub frame_size_override_flag: bool,
+    pub order_hint: u32,
+    pub primary_ref_frame: usize,
+    pub buffer_removal_time: Vec<u32>,
+    pub refresh_frame_flags: u8,
+    pub ref_order_hint: [u32; NUM_REF_FRAMES],
+    pub allow_intrabc: bool,
+    pub frame_refs_short_signaling: bool,
+    pub ref_frame_idx: [usize; REFS_PER_FRAME],
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub upscaled_width: u32,
+    pub render_width: u32,
+    pub render_height: u32,
+    pub use_superres: bool,
+    pub superres_denom: u32,
+    pub mi_cols: u32,
+    pub mi_rows: u32,
+    pub allow_high_precision_mv: bool,
+    pub interpolation_filter: Interpolationfilter,
+    pub is_motion_mode_switchable: bool,
+    pub use_ref_frame_mvs: bool,
+    // Indexed by reference frame, LAST_FRAME..=ALTREF_FRAME are meaningful
+    pub order_hints: [u32; TOTAL_REFS_PER_FRAME as usize],
+    pub ref_frame_sign_bias: [bool; TOTAL_REFS_PER_FRAME as usize],
+    pub disable_frame_end_update_cdf: bool,
+    pub tile_info: TileInfo,
+    pub quantization: QuantizationParams,
+    pub segmentation: SegmentationParams,
+    pub delta: DeltaParams,
+    pub lossless_array: [bool; MAX_SEGMENTS],
+    pub coded_lossless: bool,
+    pub all_lossless: bool,
+    // SegQMLevel, indexed by plane then segment
+    pub seg_qm_level: [[u8; MAX_SEGMENTS]; 3],
+    pub loop_filter: LoopFilterParams,
+    pub cdef: CdefParams,
+    pub lr: LrParams,
+    pub tx_mode: TxMode,
+    pub reference_select: bool,
+    pub skip_mode_present: bool,
+    pub skip_mode_frame: [usize; 2],
+    pub allow_warped_motion: bool,
+    pub reduced_tx_set: bool,
+    pub global_motion: GlobalMotionParams,
+    pub film_grain: FilmGrainParams,
+}
+
+impl FrameHeader {
+    /// Parses an uncompressed header, RefValid and RefOrderHint in state are only updated once the
+    /// whole header has been parsed
+    pub fn from_bits_reader(reader: &mut BitsReader, seq: &SequenceHeader, obu: &ObuHeader, state: &mut RefFrameState) -> Result<Self> {
+        let mut fh = Self::default();
+        let id_len = seq.additional_frame_id_length_minus_1 + seq.delta_frame_id_length_minus_2 + 3;
+        let equal_picture_interval = seq.timing_info.as_ref().is_some_and(|t| t.equal_picture_interval);
+        let mut refs = PendingRefs::new(state);
+
+        if seq.reduced_still_picture_header {
+            fh.frame_type = FrameType::Key;
+            fh.frame_is_intra = true;
+            fh.show_frame = true;
+        } else {
+            fh.show_existing_frame = reader.read_bit()?;
+            if fh.show_existing_frame {
+                fh.frame_to_show_map_idx = reader.read_u8(3)?;
+                if let Some(info) = &seq.decoder_model_info && !equal_picture_interval {
+                    fh.frame_presentation_time = reader.read_u32(info.frame_presentation_time_length_minus_1 + 1)?;
+                }
+                if seq.frame_id_numbers_present_flag {
+                    fh.display_frame_id = reader.read_u32(id_len)?;
+                }
+                fh.load_existing_frame(state)?;
+                return Ok(fh);
+            }
+
+            fh.frame_type = reader.read_u8(2)?.into();
+            fh.frame_is_intra = matches!(fh.frame_type, FrameType::Intra | FrameType::Key);
+            fh.show_frame = reader.read_bit()?;
+            if fh.show_frame && let Some(info) = &seq.decoder_model_info && !equal_picture_interval {
+                fh.frame_presentation_time = reader.read_u32(info.frame_presentation_time_length_minus_1 + 1)?;
+            }
+            fh.showable_frame = if fh.show_frame {
+                fh.frame_type != FrameType::Key
+            } else {
+                reader.read_bit()?
+            };
+            fh.error_resilient_mode = if fh.frame_type == FrameType::Switch || (fh.frame_type == FrameType::Key && fh.show_frame) {
+                true
+            } else {
+                reader.read_bit()?
+            };
+        }
+
+        if fh.frame_type == FrameType::Key && fh.show_frame {
+            refs.valid = [false; NUM_REF_FRAMES];
+            refs.order_hint = [0; NUM_REF_FRAMES];
+        }
+
+        fh.disable_cdf_update = reader.read_bit()?;
+        fh.allow_screen_content_tools = if seq.seq_force_screen_content_tools == SELECT_SCREEN_CONTENT_TOOLS {
+            reader.read_bit()?
+        } else {
+            seq.seq_force_screen_content_tools != 0
+        };
+        if fh.allow_screen_content_tools {
+            fh.force_integer_mv = if seq.seq_force_integer_mv == SELECT_INTEGER_MV {
+                reader.read_bit()?
+            } else {
+                seq.seq_force_integer_mv != 0
+            };
+        }
+        if fh.frame_is_intra {
+            fh.force_integer_mv = true;
+        }
+
+        if seq.frame_id_numbers_present_flag {
+            fh.current_frame_id = reader.read_u32(id_len)?;
+            fh.mark_ref_frames(seq, id_len, state, &mut refs);
+        }
+
+        fh.frame_size_override_flag = if fh.frame_type == FrameType::Switch {
+            true
+        } else if seq.reduced_still_picture_header {
+            false
+        } else {
+            reader.read_bit()?
+        };
+        fh.order_hint = reader.read_u32(seq.order_hint_bits)?;
+        fh.primary_ref_frame = if fh.frame_is_intra || fh.error_resilient_mode {
+            PRIMARY_REF_NONE
+        } else {
+            reader.read_u8(3)? as usize
+        };
+
+        if let Some(info) = &seq.decoder_model_info {
+            let buffer_removal_time_present_flag = reader.read_bit()?;
+            if buffer_removal_time_present_flag {
+                for op in seq.operating_points.iter() {
+                    if !op.decoder_model_present_for_this_op {
+                        continue;
+                    }
+                    let in_temporal_layer = (op.idc >> obu.temporal_id) & 1 == 1;
+                    let in_spatial_layer = (op.idc >> (obu.spatial_id + 8)) & 1 == 1;
+                    if op.idc == 0 || (in_temporal_layer && in_spatial_layer) {
+                        fh.buffer_removal_time.push(reader.read_u32(info.buffer_removal_time_length_minus_1 + 1)?);
+                    }
+                }
+            }
+        }
+
+        fh.refresh_frame_flags = if fh.frame_type == FrameType::Switch || (fh.frame_type == FrameType::Key && fh.show_frame) {
+            ALL_FRAMES
+        } else {
+            reader.read_u8(8)?
+        };
+        if fh.frame_type == FrameType::Intra && fh.refresh_frame_flags == ALL_FRAMES {
+            return Err(Error::Conformance { section: "6.8.2", reason: "intra only frame must not refresh all reference frames" });
+        }
+        if (!fh.frame_is_intra || fh.refresh_frame_flags != ALL_FRAMES) && fh.error_resilient_mode && seq.enable_order_hint {
+            for i in 0..NUM_REF_FRAMES {
+                fh.ref_order_hint[i] = reader.read_u32(seq.order_hint_bits)?;
+                if fh.ref_order_hint[i] != refs.order_hint[i] || !refs.valid[i] {
+                    refs.valid[i] = false;
+                    refs.order_hint[i] = fh.ref_order_hint[i];
+                    refs.reset[i] = true;
+                }
+            }
+        }
+
+        if fh.frame_is_intra {
+            fh.frame_size(reader, seq)?;
+            fh.render_size(reader)?;
+            if fh.allow_screen_content_tools && fh.upscaled_width == fh.frame_width {
+                fh.allow_intrabc = reader.read_bit()?;
+            }
+        } else {
+            if seq.enable_order_hint {
+                fh.frame_refs_short_signaling = reader.read_bit()?;
+                if fh.frame_refs_short_signaling {
+                    let last_frame_idx = reader.read_u8(3)? as usize;
+                    let gold_frame_idx = reader.read_u8(3)? as usize;
+                    fh.set_frame_refs(seq, &refs.order_hint, last_frame_idx, gold_frame_idx)?;
+                }
+            }
+            for i in 0..REFS_PER_FRAME {
+                if !fh.frame_refs_short_signaling {
+                    fh.ref_frame_idx[i] = reader.read_u8(3)? as usize;
+                }
+                if seq.frame_id_numbers_present_flag {
+                    let delta_frame_id = reader.read_u32(seq.delta_frame_id_length_minus_2 + 2)? + 1;
+                    let expected_frame_id = (fh.current_frame_id + (1 << id_len) - delta_frame_id) % (1 << id_len);
+                    if state.slots[fh.ref_frame_idx[i]].frame_id != expected_frame_id {
+                        return Err(Error::Conformance { section: "6.8.2", reason: "delta_frame_id does not match the reference frame id" });
+                    }
+                }
+                refs.check_valid(fh.ref_frame_idx[i])?;
+            }
+            if fh.frame_size_override_flag && !fh.error_resilient_mode {
+                fh.frame_size_with_refs(reader, seq, state)?;
+            } else {
+                fh.frame_size(reader, seq)?;
+                fh.render_size(reader)?;
+            }
+            fh.allow_high_precision_mv = if fh.force_integer_mv {
+                false
+            } else {
+                reader.read_bit()?
+            };
+            fh.interpolation_filter = Interpolationfilter::from_bits_reader(reader)?;
+            fh.is_motion_mode_switchable = reader.read_bit()?;
+            fh.use_ref_frame_mvs = if fh.error_resilient_mode || !seq.enable_ref_frame_mvs {
+                false
+            } else {
+                reader.read_bit()?
+            };
+            for i in 0..REFS_PER_FRAME {
+                let ref_frame = LAST_FRAME + i;
+                let hint = state.slots[fh.ref_frame_idx[i]].order_hint;
+                fh.order_hints[ref_frame] = hint;
+                fh.ref_frame_sign_bias[ref_frame] = seq.enable_order_hint && seq.get_relative_dist(hint, fh.order_hint) > 0;
+            }
+        }
+
+        fh.disable_frame_end_update_cdf = if seq.reduced_still_picture_header || fh.disable_cdf_update {
+            true
+        } else {
+            reader.read_bit()?
+        };
+
+        if fh.primary_ref_frame == PRIMARY_REF_NONE {
+            fh.setup_past_independence();
+        } else {
+            fh.load_previous(state)?;
+        }
+
+        fh.tile_info(reader, seq)?;
+        fh.quantization_params(reader, seq)?;
+        fh.segmentation_params(reader)?;
+        fh.delta_params(reader)?;
+
+        fh.coded_lossless = true;
+        for segment_id in 0..MAX_SEGMENTS {
+            let qindex = fh.get_qindex(true, segment_id, 0);
+            let q = &fh.quantization;
+            let lossless = qindex == 0 && q.delta_q_y_dc == 0 && q.delta_q_u_ac == 0 && q.delta_q_u_dc == 0 && q.delta_q_v_ac == 0 && q.delta_q_v_dc == 0;
+            fh.lossless_array[segment_id] = lossless;
+            if !lossless {
+                fh.coded_lossless = false;
+            }
+            if q.using_qmatrix {
+                let levels = if lossless { [15; 3] } else { [q.qm_y, q.qm_u, q.qm_v] };
+                for (plane, level) in levels.into_iter().enumerate() {
+                    fh.seg_qm_level[plane][segment_id] = level;
+                }
+            }
+        }
+        fh.all_lossless = fh.coded_lossless && fh.frame_width == fh.upscaled_width;
+
+        fh.loop_filter_params(reader, seq)?;
+        fh.cdef_params(reader, seq)?;
+        fh.lr_params(reader, seq)?;
+        fh.tx_mode = if fh.coded_lossless {
+            TxMode::Only4X4
+        } else if reader.read_bit()? {
+            TxMode::Select
+        } else {
+            TxMode::Largest
+        };
+        fh.reference_select = if fh.frame_is_intra {
+            false
+        } else {
+            reader.read_bit()?
+        };
+        fh.skip_mode_params(reader, seq, state)?;
+        fh.allow_warped_motion = if fh.frame_is_intra || fh.error_resilient_mode || !seq.enable_warped_motion {
+            false
+        } else {
+            reader.read_bit()?
+        };
+        fh.reduced_tx_set = reader.read_bit()?;
+        fh.global_motion_params(reader)?;
+        fh.film_grain_params(reader, seq, state)?;
+
+        refs.apply(state);
+        Ok(fh)
+    }
+
+    /// 7.12.2 get_qindex
+    pub fn get_qindex(&self, ignore_delta_q: bool, segment_id: usize, current_q_index: i32) -> i32 {
+        let base_q_idx = self.quantization.base_q_idx as i32;
+        if self.segmentation.feature_active(segment_id, SEG_LVL_ALT_Q) {
+            let data = self.segmentation.feature_data[segment_id][SEG_LVL_ALT_Q] as i32;
+            let qindex = if !ignore_delta_q && self.delta.delta_q_present {
+                current_q_index + data
+            } else {
+                base_q_idx + data
+            };
+            clip3(0, 255, qindex)
+        } else if !ignore_delta_q && self.delta.delta_q_present {
+            current_q_index
+        } else {
+            base_q_idx
+        }
+    }
+
+    // 7.21 Reference frame loading process
+    fn load_existing_frame(&mut self, state: &RefFrameState) -> Result<()> {
+        let slot = state.valid_slot(self.frame_to_show_map_idx as usize)?;
+        if !slot.showable_frame {
+            return Err(Error::Conformance { section: "6.8.2", reason: "frame_to_show_map_idx refers to a frame that is not showable" });
+        }
+        self.frame_type = slot.frame_type;
+        self.frame_is_intra = matches!(slot.frame_type, FrameType::Intra | FrameType::Key);
+        self.show_frame = true;
+        // a shown key frame can not be shown again
+        self.showable_frame = slot.showable_frame && slot.frame_type != FrameType::Key;
+        self.current_frame_id = slot.frame_id;
+        self.upscaled_width = slot.upscaled_width;
+        self.frame_width = slot.frame_width;
+        self.frame_height = slot.frame_height;
+        self.render_width = slot.render_width;
+        self.render_height = slot.render_height;
+        self.mi_cols = slot.mi_cols;
+        self.mi_rows = slot.mi_rows;
+        self.order_hint = slot.order_hint;
+        self.order_hints = slot.saved_order_hints;
+        self.global_motion = slot.global_motion.clone();
+        self.loop_filter.loop_filter_ref_deltas = slot.loop_filter_ref_deltas;
+        self.loop_filter.loop_filter_mode_deltas = slot.loop_filter_mode_deltas;
+        self.segmentation.feature_enabled = slot.feature_enabled;
+        self.segmentation.feature_data = slot.feature_data;
+        self.film_grain = slot.film_grain.clone();
+        if self.frame_type == FrameType::Key {
+            self.refresh_frame_flags = ALL_FRAMES;
+        }
+        Ok(())
+    }
+
+    // 7.6.1 Frame id numbers: mark_ref_frames
+    fn mark_ref_frames(&self, seq: &SequenceHeader, id_len: u8, state: &RefFrameState, refs: &mut PendingRefs) {
+        let diff_len = seq.delta_frame_id_length_minus_2 + 2;
+        let current = self.current_frame_id;
+        for (slot, valid) in state.slots.iter().zip(refs.valid.iter_mut()) {
+            let stale = if current > (1 << diff_len) {
+                slot.frame_id > current || slot.frame_id < current - (1 << diff_len)
+            } else {
+                slot.frame_id > current && slot.frame_id < (1 << id_len) + current - (1 << diff_len)
+            };
+            if stale {
+                *valid = false;
+            }
+        }
+    }
+
+    /// 5.9.5 Frame size syntax
+    fn frame_size(&mut self, reader: &mut BitsReader, seq: &SequenceHeader) -> Result<()> {
+        if self.frame_size_override_flag {
+            self.frame_width = reader.read_u32(seq.frame_width_bits_minus_1 + 1)? + 1;
+            self.frame_height = reader.read_u32(seq.frame_height_bits_minus_1 + 1)? + 1;
+            if self.frame_width > seq.max_frame_width_minus_1 + 1 || self.frame_height > seq.max_frame_height_minus_1 + 1 {
+                return Err(Error::Conformance { section: "6.8.5", reason: "frame size exceeds the maximum frame size" });
+            }
+        } else {
+            self.frame_width = seq.max_frame_width_minus_1 + 1;
+            self.frame_height = seq.max_frame_height_minus_1 + 1;
+        }
+        self.superres_params(reader, seq)?;
+        self.compute_image_size();
+        Ok(())
+    }
+
+    /// 5.9.6 Render size syntax
+    fn render_size(&mut self, reader: &mut BitsReader) -> Result<()> {
+        let render_and_frame_size_different = reader.read_bit()?;
+        if render_and_frame_size_different {
+            self.render_width = reader.read_u32(16)? + 1;
+            self.render_height = reader.read_u32(16)? + 1;
+        } else {
+            self.render_width = self.upscaled_width;
+            self.render_height = self.frame_height;
+        }
+        Ok(())
+    }
+
+    /// 5.9.7 Frame size with refs syntax
+    fn frame_size_with_refs(&mut self, reader: &mut BitsReader, seq: &SequenceHeader, state: &RefFrameState) -> Result<()> {
+        for i in 0..REFS_PER_FRAME {
+            let found_ref = reader.read_bit()?;
+            if found_ref {
+                let slot = &state.slots[self.ref_frame_idx[i]];
+                self.upscaled_width = slot.upscaled_width;
+                self.frame_width = self.upscaled_width;
+                self.frame_height = slot.frame_height;
+                self.render_width = slot.render_width;
+                self.render_height = slot.render_height;
+                self.superres_params(reader, seq)?;
+                self.compute_image_size();
+                return Ok(());
+            }
+        }
+        self.frame_size(reader, seq)?;
+        self.render_size(reader)
+    }
+
+    /// 5.9.8 Superres params syntax
+    fn superres_params(&mut self, reader: &mut BitsReader, seq: &SequenceHeader) -> Result<()> {
+        self.use_superres = if seq.enable_superres {
+            reader.read_bit()?
+        } else {
+            false
+        };
+        self.superres_denom = if self.use_superres {
+            reader.read_u32(SUPERRES_DENOM_BITS)? + SUPERRES_DENOM_MIN
+        } else {
+            SUPERRES_NUM
+        };
+        self.upscaled_width = self.frame_width;
+        self.frame_width = (self.upscaled_width * SUPERRES_NUM + (self.superres_denom / 2)) / self.superres_denom;
+        Ok(())
+    }
+
+    /// 5.9.9 Compute image size semantics
+    fn compute_image_size(&mut self) {
+        self.mi_cols = 2 * ((self.frame_width + 7) >> 3);
+        self.mi_rows = 2 * ((self.frame_height + 7) >> 3);
+    }
+
+    /// 7.8 Set frame refs process
+    fn set_frame_refs(&mut self, seq: &SequenceHeader, ref_order_hint: &[u32; NUM_REF_FRAMES], last_frame_idx: usize, gold_frame_idx: usize) -> Result<()> {
+        let mut ref_frame_idx = [-1i32; REFS_PER_FRAME];
+        ref_frame_idx[RefFrame::Last as usize - LAST_FRAME] = last_frame_idx as i32;
+        ref_frame_idx[RefFrame::Golden as usize - LAST_FRAME] = gold_frame_idx as i32;
+        let mut used_frame = [false; NUM_REF_FRAMES];
+        used_frame[last_frame_idx] = true;
+        used_frame[gold_frame_idx] = true;
+
+        let cur_frame_hint = 1i32 << (seq.order_hint_bits - 1);
+        let mut shifted_order_hints = [0i32; NUM_REF_FRAMES];
+        for (i, hint) in shifted_order_hints.iter_mut().enumerate() {
+            *hint = cur_frame_hint + seq.get_relative_dist(ref_order_hint[i], self.order_hint);
+        }
+        if shifted_order_hints[last_frame_idx] >= cur_frame_hint || shifted_order_hints[gold_frame_idx] >= cur_frame_hint {
+            return Err(Error::Conformance { section: "7.8", reason: "last and golden frames must be forward references" });
+        }
+
+        // backward: hint >= curFrameHint, forward: hint < curFrameHint
+        let find = |used_frame: &[bool; NUM_REF_FRAMES], backward: bool, latest: bool| -> Option<usize> {
+            let mut res: Option<(usize, i32)> = None;
+            for (i, &hint) in shifted_order_hints.iter().enumerate() {
+                if used_frame[i] || (hint >= cur_frame_hint) != backward {
+                    continue;
+                }
+                let better = match res {
+                    None => true,
+                    Some((_, best)) => if latest { hint >= best } else { hint < best },
+                };
+                if better {
+                    res = Some((i, hint));
+                }
+            }
+            res.map(|(i, _)| i)
+        };
+
+        for (ref_frame, latest) in [(RefFrame::Altref, true), (RefFrame::Bwdref, false), (RefFrame::Altref2, false)] {
+            if let Some(r) = find(&used_frame, true, latest) {
+                ref_frame_idx[ref_frame as usize - LAST_FRAME] = r as i32;
+                used_frame[r] = true;
+            }
+        }
+        for ref_frame in REF_FRAME_LIST {
+            let i = ref_frame as usize - LAST_FRAME;
+            if ref_frame_idx[i] < 0 && let Some(r) = find(&used_frame, false, true) {
+                ref_frame_idx[i] = r as i32;
+                used_frame[r] = true;
+            }
+        }
+
+        let mut earliest: Option<(usize, i32)> = None;
+        for (i, &hint) in shifted_order_hints.iter().enumerate() {
+            if earliest.is_none_or(|(_, best)| hint < best) {
+                earliest = Some((i, hint));
+            }
+        }
+        let earliest = earliest.map(|(i, _)| i).unwrap_or_default();
+        for (dst, src) in self.ref_frame_idx.iter_mut().zip(ref_frame_idx) {
+            *dst = if src < 0 { earliest } else { src as usize };
+        }
+        Ok(())
+    }
+
+    // 7.20 setup_past_independence
+    fn setup_past_independence(&mut self) {
+        self.segmentation.feature_enabled = Default::default();
+        self.segmentation.feature_data = Default::default();
+        self.loop_filter.loop_filter_delta_enabled = true;
+        self.loop_filter.loop_filter_ref_deltas = DEFAULT_LOOP_FILTER_REF_DELTAS;
+        self.loop_filter.loop_filter_mode_deltas = [0; 2];
+        self.global_motion = GlobalMotionParams::default();
+    }
+
+    // 7.20 load_previous
+    fn load_previous(&mut self, state: &RefFrameState) -> Result<()> {
+        let slot = state.valid_slot(self.ref_frame_idx[self.primary_ref_frame])?;
+        self.global_motion = slot.global_motion.clone();
+        self.loop_filter.loop_filter_ref_deltas = slot.loop_filter_ref_deltas;
+        self.loop_filter.loop_filter_mode_deltas = slot.loop_filter_mode_deltas;
+        self.segmentation.feature_enabled = slot.feature_enabled;
+        self.segmentation.feature_data = slot.feature_data;
+        Ok(())
+    }
+
+    /// 5.9.15 Tile info syntax
+    fn tile_info(&mut self, reader: &mut BitsReader, seq: &SequenceHeader) -> Result<()> {
+        fn tile_log2(blk_size: u32, target: u32) -> u8 {
+            let mut k = 0;
+            while (blk_size << k) < target {
+                k += 1;
+            }
+            k
+        }
+
+        let (sb_cols, sb_rows, sb_shift) = if seq.use_128x128_superblock {
+            ((self.mi_cols + 31) >> 5, (self.mi_rows + 31) >> 5, 5)
+        } else {
+            ((self.mi_cols + 15) >> 4, (self.mi_rows + 15) >> 4, 4)
+        };
+        let sb_size = sb_shift + 2;
+        let max_tile_width_sb = MAX_TILE_WIDTH >> sb_size;
+        let mut max_tile_area_sb = MAX_TILE_AREA >> (2 * sb_size);
+        let min_log2_tile_cols = tile_log2(max_tile_width_sb, sb_cols);
+        let max_log2_tile_cols = tile_log2(1, sb_cols.min(MAX_TILE_COLS));
+        let max_log2_tile_rows = tile_log2(1, sb_rows.min(MAX_TILE_ROWS));
+        let min_log2_tiles = min_log2_tile_cols.max(tile_log2(max_tile_area_sb, sb_rows * sb_cols));
+
+        let ti = &mut self.tile_info;
+        ti.uniform_tile_spacing_flag = reader.read_bit()?;
+        ti.mi_col_starts.clear();
+        ti.mi_row_starts.clear();
+        if ti.uniform_tile_spacing_flag {
+            ti.tile_cols_log2 = min_log2_tile_cols;
+            while ti.tile_cols_log2 < max_log2_tile_cols {
+                if !reader.read_bit()? {
+                    break;
+                }
+                ti.tile_cols_log2 += 1;
+            }
+            let tile_width_sb = (sb_cols + (1 << ti.tile_cols_log2) - 1) >> ti.tile_cols_log2;
+            ti.mi_col_starts.extend((0..sb_cols).step_by(tile_width_sb as usize).map(|start_sb| start_sb << sb_shift));
+
+            let min_log2_tile_rows = min_log2_tiles.saturating_sub(ti.tile_cols_log2);
+            ti.tile_rows_log2 = min_log2_tile_rows;
+            while ti.tile_rows_log2 < max_log2_tile_rows {
+                if !reader.read_bit()? {
+                    break;
+                }
+                ti.tile_rows_log2 += 1;
+            }
+            let tile_height_sb = (sb_rows + (1 << ti.tile_rows_log2) - 1) >> ti.tile_rows_log2;
+            ti.mi_row_starts.extend((0..sb_rows).step_by(tile_height_sb as usize).map(|start_sb| start_sb << sb_shift));
+        } else {
+            let mut widest_tile_sb = 0;
+            let mut start_sb = 0;
+            while start_sb < sb_cols {
+                ti.mi_col_starts.push(start_sb << sb_shift);
+                let max_width = (sb_cols - start_sb).min(max_tile_width_sb);
+                let size_sb = reader.read_ns(max_width)? + 1;
+                widest_tile_sb = widest_tile_sb.max(size_sb);
+                start_sb += size_sb;
+            }
+
+            if min_log2_tiles > 0 {
+                max_tile_area_sb = (sb_rows * sb_cols) >> (min_log2_tiles + 1);
+            } else {
+                max_tile_area_sb = sb_rows * sb_cols;
+            }
+            let max_tile_height_sb = (max_tile_area_sb / widest_tile_sb).max(1);
+            let mut start_sb = 0;
+            while start_sb < sb_rows {
+                ti.mi_row_starts.push(start_sb << sb_shift);
+                let max_height = (sb_rows - start_sb).min(max_tile_height_sb);
+                let size_sb = reader.read_ns(max_height)? + 1;
+                start_sb += size_sb;
+            }
+        }
+        ti.tile_cols = ti.mi_col_starts.len() as u32;
+        ti.tile_rows = ti.mi_row_starts.len() as u32;
+        if !ti.uniform_tile_spacing_flag {
+            ti.tile_cols_log2 = tile_log2(1, ti.tile_cols);
+            ti.tile_rows_log2 = tile_log2(1, ti.tile_rows);
+        }
+        ti.mi_col_starts.push(self.mi_cols);
+        ti.mi_row_starts.push(self.mi_rows);
+        if ti.tile_cols > MAX_TILE_COLS || ti.tile_rows > MAX_TILE_ROWS {
+            return Err(Error::Conformance { section: "6.8.14", reason: "too many tiles" });
+        }
+
+        if ti.tile_cols_log2 > 0 || ti.tile_rows_log2 > 0 {
+            ti.context_update_tile_id = reader.read_u32(ti.tile_rows_log2 + ti.tile_cols_log2)?;
+            if ti.context_update_tile_id >= ti.tile_cols * ti.tile_rows {
+                return Err(Error::Conformance { section: "6.8.14", reason: "context_update_tile_id out of range" });
+            }
+            ti.tile_size_bytes = reader.read_u8(2)? + 1;
+        } else {
+            ti.context_update_tile_id = 0;
+        }
+        Ok(())
+    }
+
+    /// 5.9.12 Quantization params syntax
+    fn quantization_params(&mut self, reader: &mut BitsReader, seq: &SequenceHeader) -> Result<()> {
+        fn read_delta_q(reader: &mut BitsReader) -> Result<i32> {
+            if reader.read_bit()? {
+                reader.read_su(1 + 6)
+            } else {
+                Ok(0)
+            }
+        }
+
+        let q = &mut self.quantization;
+        q.base_q_idx = reader.read_u8(8)?;
+        q.delta_q_y_dc = read_delta_q(reader)?;
+        if seq.color_config.num_planes > 1 {
+            q.diff_uv_delta = if seq.color_config.separate_uv_delta_q {
+                reader.read_bit()?
+            } else {
+                false
+            };
+            q.delta_q_u_dc = read_delta_q(reader)?;
+            q.delta_q_u_ac = read_delta_q(reader)?;
+            if q.diff_uv_delta {
+                q.delta_q_v_dc = read_delta_q(reader)?;
+                q.delta_q_v_ac = read_delta_q(reader)?;
+            } else {
+                q.delta_q_v_dc = q.delta_q_u_dc;
+                q.delta_q_v_ac = q.delta_q_u_ac;
+            }
+        }
+        q.using_qmatrix = reader.read_bit()?;
+        if q.using_qmatrix {
+            q.qm_y = reader.read_u8(4)?;
+            q.qm_u = reader.read_u8(4)?;
+            q.qm_v = if seq.color_config.separate_uv_delta_q {
+                reader.read_u8(4)?
+            } else {
+                q.qm_u
+            };
+        }
+        Ok(())
+    }
+
+    /// 5.9.14 Segmentation params syntax
+    fn segmentation_params(&mut self, reader: &mut BitsReader) -> Result<()> {
+        let primary_ref_none = self.primary_ref_frame == PRIMARY_REF_NONE;
+        let seg = &mut self.segmentation;
+        seg.segmentation_enabled = reader.read_bit()?;
+        if seg.segmentation_enabled {
+            if primary_ref_none {
+                seg.segmentation_update_map = true;
+                seg.segmentation_temporal_update = false;
+                seg.segmentation_update_data = true;
+            } else {
+                seg.segmentation_update_map = reader.read_bit()?;
+                if seg.segmentation_update_map {
+                    seg.segmentation_temporal_update = reader.read_bit()?;
+                }
+                seg.segmentation_update_data = reader.read_bit()?;
+            }
+            if seg.segmentation_update_data {
+                for i in 0..MAX_SEGMENTS {
+                    for j in 0..SEG_LVL_MAX {
+                        let feature_enabled = reader.read_bit()?;
+                        let mut clipped_value = 0;
+                        if feature_enabled {
+                            let bits_to_read = SEGMENTATION_FEATURE_BITS[j];
+                            let limit = SEGMENTATION_FEATURE_MAX[j] as i32;
+                            clipped_value = if SEGMENTATION_FEATURE_SIGNED[j] == 1 {
+                                clip3(-limit, limit, reader.read_su(1 + bits_to_read)?)
+                            } else {
+                                clip3(0, limit, reader.read_u32(bits_to_read)? as i32)
+                            };
+                        }
+                        seg.feature_enabled[i][j] = feature_enabled;
+                        seg.feature_data[i][j] = clipped_value as i16;
+                    }
+                }
+            }
+        } else {
+            seg.feature_enabled = Default::default();
+            seg.feature_data = Default::default();
+        }
+        seg.seg_id_pre_skip = false;
+        seg.last_active_seg_id = 0;
+        for i in 0..MAX_SEGMENTS {
+            for j in 0..SEG_LVL_MAX {
+                if seg.feature_enabled[i][j] {
+                    seg.last_active_seg_id = i as u8;
+                    if j >= SEG_LVL_REF_FRAME {
+                        seg.seg_id_pre_skip = true;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 5.9.17 Quantizer index delta parameters syntax, 5.9.18 Loop filter delta parameters syntax
+    fn delta_params(&mut self, reader: &mut BitsReader) -> Result<()> {
+        let d = &mut self.delta;
+        if self.quantization.base_q_idx > 0 {
+            d.delta_q_present = reader.read_bit()?;
+        }
+        if d.delta_q_present {
+            d.delta_q_res = reader.read_u8(2)?;
+            if !self.allow_intrabc {
+                d.delta_lf_present = reader.read_bit()?;
+            }
+            if d.delta_lf_present {
+                d.delta_lf_res = reader.read_u8(2)?;
+                d.delta_lf_multi = reader.read_bit()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 5.9.11 Loop filter params syntax
+    fn loop_filter_params(&mut self, reader: &mut BitsReader, seq: &SequenceHeader) -> Result<()> {
+        let lf = &mut self.loop_filter;
+        if self.coded_lossless || self.allow_intrabc {
+            lf.loop_filter_level[0] = 0;
+            lf.loop_filter_level[1] = 0;
+            lf.loop_filter_ref_deltas = DEFAULT_LOOP_FILTER_REF_DELTAS;
+            lf.loop_filter_mode_deltas = [0; 2];
+            return Ok(());
+        }
+        lf.loop_filter_level[0] = reader.read_u8(6)?;
+        lf.loop_filter_level[1] = reader.read_u8(6)?;
+        if seq.color_config.num_planes > 1 && (lf.loop_filter_level[0] > 0 || lf.loop_filter_level[1] > 0) {
+            lf.loop_filter_level[2] = reader.read_u8(6)?;
+            lf.loop_filter_level[3] = reader.read_u8(6)?;
+        }
+        lf.loop_filter_sharpness = reader.read_u8(3)?;
+        lf.loop_filter_delta_enabled = reader.read_bit()?;
+        if lf.loop_filter_delta_enabled {
+            lf.loop_filter_delta_update = reader.read_bit()?;
+            if lf.loop_filter_delta_update {
+                for delta in lf.loop_filter_ref_deltas.iter_mut() {
+                    if reader.read_bit()? {
+                        *delta = reader.read_su(1 + 6)? as i8;
+                    }
+                }
+                for delta in lf.loop_filter_mode_deltas.iter_mut() {
+                    if reader.read_bit()? {
+                        *delta = reader.read_su(1 + 6)? as i8;
+                    }
+                }
+            }
+        }
+        debug_assert!(lf.loop_filter_level.iter().all(|&l| l <= MAX_LOOP_FILTER));
+        Ok(())
+    }
+
+    /// 5.9.19 CDEF params syntax
+    fn cdef_params(&mut self, reader: &mut BitsReader, seq: &SequenceHeader) -> Result<()> {
+        let cdef = &mut self.cdef;
+        if self.coded_lossless || self.allow_intrabc || !seq.enable_cdef {
+            *cdef = CdefParams { cdef_damping: 3, ..Default::default() };
+            return Ok(());
+        }
+        cdef.cdef_damping = reader.read_u8(2)? + 3;
+        cdef.cdef_bits = reader.read_u8(2)?;
+        for i in 0..(1 << cdef.cdef_bits) {
+            cdef.cdef_y_pri_strength[i] = reader.read_u8(4)?;
+            cdef.cdef_y_sec_strength[i] = reader.read_u8(2)?;
+            if cdef.cdef_y_sec_strength[i] == 3 {
+                cdef.cdef_y_sec_strength[i] += 1;
+            }
+            if seq.color_config.num_planes > 1 {
+                cdef.cdef_uv_pri_strength[i] = reader.read_u8(4)?;
+                cdef.cdef_uv_sec_strength[i] = reader.read_u8(2)?;
+                if cdef.cdef_uv_sec_strength[i] == 3 {
+                    cdef.cdef_uv_sec_strength[i] += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 5.9.20 Loop restoration params syntax
+    fn lr_params(&mut self, reader: &mut BitsReader, seq: &SequenceHeader) -> Result<()> {
+        let lr = &mut self.lr;
+        *lr = LrParams::default();
+        if self.all_lossless || self.allow_intrabc || !seq.enable_restoration {
+            return Ok(());
+        }
+        let mut uses_chroma_lr = false;
+        for i in 0..seq.color_config.num_planes as usize {
+            lr.frame_restoration_type[i] = FrameRestorationType::from_lr_type(reader.read_u8(2)?);
+            if lr.frame_restoration_type[i] != FrameRestorationType::None {
+                lr.uses_lr = true;
+                if i > 0 {
+                    uses_chroma_lr = true;
+                }
+            }
+        }
+        if lr.uses_lr {
+            let mut lr_unit_shift = reader.read_u8(1)?;
+            if seq.use_128x128_superblock {
+                lr_unit_shift += 1;
+            } else if lr_unit_shift > 0 {
+                lr_unit_shift += reader.read_u8(1)?;
+            }
+            lr.loop_restoration_size[0] = RESTORATION_TILESIZE_MAX >> (2 - lr_unit_shift);
+            let lr_uv_shift = if seq.color_config.subsampling_x && seq.color_config.subsampling_y && uses_chroma_lr {
+                reader.read_u8(1)?
+            } else {
+                0
+            };
+            lr.loop_restoration_size[1] = lr.loop_restoration_size[0] >> lr_uv_shift;
+            lr.loop_restoration_size[2] = lr.loop_restoration_size[0] >> lr_uv_shift;
+        }
+        Ok(())
+    }
+
+    /// 5.9.22 Skip mode params syntax
+    fn skip_mode_params(&mut self, reader: &mut BitsReader, seq: &SequenceHeader, state: &RefFrameState) -> Result<()> {
+        let mut skip_mode_allowed = false;
+        if !self.frame_is_intra && self.reference_select && seq.enable_order_hint {
+            let mut forward: Option<(usize, u32)> = None;
+            let mut backward: Option<(usize, u32)> = None;
+            for i in 0..REFS_PER_FRAME {
+                let ref_hint = state.slots[self.ref_frame_idx[i]].order_hint;
+                if seq.get_relative_dist(ref_hint, self.order_hint) < 0 {
+                    if forward.is_none_or(|(_, hint)| seq.get_relative_dist(ref_hint, hint) > 0) {
+                        forward = Some((i, ref_hint));
+                    }
+                } else if seq.get_relative_dist(ref_hint, self.order_hint) > 0
+                    && backward.is_none_or(|(_, hint)| seq.get_relative_dist(ref_hint, hint) < 0) {
+                    backward = Some((i, ref_hint));
+                }
+            }
+            if let Some((forward_idx, forward_hint)) = forward {
+                let second = if let Some((backward_idx, _)) = backward {
+                    Some(backward_idx)
+                } else {
+                    let mut second_forward: Option<(usize, u32)> = None;
+                    for i in 0..REFS_PER_FRAME {
+                        let ref_hint = state.slots[self.ref_frame_idx[i]].order_hint;
+                        if seq.get_relative_dist(ref_hint, forward_hint) < 0
+                            && second_forward.is_none_or(|(_, hint)| seq.get_relative_dist(ref_hint, hint) > 0) {
+                            second_forward = Some((i, ref_hint));
+                        }
+                    }
+                    second_forward.map(|(i, _)| i)
+                };
+                if let Some(second_idx) = second {
+                    skip_mode_allowed = true;
+                    self.skip_mode_frame = [LAST_FRAME + forward_idx.min(second_idx), LAST_FRAME + forward_idx.max(second_idx)];
+                }
+            }
+        }
+        self.skip_mode_present = if skip_mode_allowed {
+            reader.read_bit()?
+        } else {
+            false
+        };
+        Ok(())
+    }
+
+    /// 5.9.24 Global motion params syntax
+    fn global_motion_params(&mut self, reader: &mut BitsReader) -> Result<()> {
+        let prev_gm_params = self.global_motion.gm_params;
+        self.global_motion = GlobalMotionParams::default();
+        if self.frame_is_intra {
+            return Ok(());
+        }
+        for (ref_frame, prev_params) in prev_gm_params.iter().enumerate().skip(LAST_FRAME) {
+            let is_global = reader.read_bit()?;
+            let gm_type = if is_global {
+                let is_rot_zoom = reader.read_bit()?;
+                if is_rot_zoom {
+                    ROTZOOM
+                } else if reader.read_bit()? {
+                    TRANSLATION
+                } else {
+                    AFFINE
+                }
+            } else {
+                IDENTITY
+            };
+            self.global_motion.gm_type[ref_frame] = gm_type;
+
+            let mut read_global_param = |idx: usize| -> Result<()> {
+                let mut abs_bits = GM_ABS_ALPHA_BITS;
+                let mut prec_bits = GM_ALPHA_PREC_BITS;
+                if idx < 2 {
+                    if gm_type == TRANSLATION {
+                        let hp = !self.allow_high_precision_mv as u8;
+                        abs_bits = GM_ABS_TRANS_ONLY_BITS - hp;
+                        prec_bits = GM_TRANS_ONLY_PREC_BITS - hp;
+                    } else {
+                        abs_bits = GM_ABS_TRANS_BITS;
+                        prec_bits = GM_TRANS_PREC_BITS;
+                    }
+                }
+                let prec_diff = WARPEDMODEL_PREC_BITS - prec_bits;
+                let round = if idx % 3 == 2 { 1 << WARPEDMODEL_PREC_BITS } else { 0 };
+                let sub = if idx % 3 == 2 { 1 << prec_bits } else { 0 };
+                let mx = 1i32 << abs_bits;
+                let r = (prev_params[idx] >> prec_diff) - sub;
+                let v = decode_signed_subexp_with_ref(reader, -mx, mx + 1, r)?;
+                self.global_motion.gm_params[ref_frame][idx] = (v << prec_diff) + round;
+                Ok(())
+            };
+
+            if gm_type >= ROTZOOM {
+                read_global_param(2)?;
+                read_global_param(3)?;
+                if gm_type == AFFINE {
+                    read_global_param(4)?;
+                    read_global_param(5)?;
+                }
+            }
+            if gm_type >= TRANSLATION {
+                read_global_param(0)?;
+                read_global_param(1)?;
+            }
+            if gm_type == ROTZOOM {
+                let params = &mut self.global_motion.gm_params[ref_frame];
+                params[4] = -params[3];
+                params[5] = params[2];
+            }
+        }
+        Ok(())
+    }
+
+    /// 5.9.30 Film grain params syntax
+    fn film_grain_params(&mut self, reader: &mut BitsReader, seq: &SequenceHeader, state: &RefFrameState) -> Result<()> {
+        if !seq.film_grain_params_present || (!self.show_frame && !self.showable_frame) {
+            self.film_grain = FilmGrainParams::default();
+            return Ok(());
+        }
+        let mut fg = FilmGrainParams { apply_grain: reader.read_bit()?, ..Default::default() };
+        if !fg.apply_grain {
+            self.film_grain = fg;
+            return Ok(());
+        }
+        fg.grain_seed = reader.read_u16(16)?;
+        fg.update_grain = if self.frame_type == FrameType::Inter {
+            reader.read_bit()?
+        } else {
+            true
+        };
+        if !fg.update_grain {
+            let film_grain_params_ref_idx = reader.read_u8(3)? as usize;
+            if !self.ref_frame_idx.contains(&film_grain_params_ref_idx) {
+                return Err(Error::Conformance { section: "6.8.20", reason: "film_grain_params_ref_idx must be one of ref_frame_idx" });
+            }
+            let grain_seed = fg.grain_seed;
+            fg = state.slots[film_grain_params_ref_idx].film_grain.clone();
+            fg.grain_seed = grain_seed;
+            self.film_grain = fg;
+            return Ok(());
+        }
+
+        let cc = &seq.color_config;
+        let num_y_points = reader.read_u8(4)?;
+        for _ in 0..num_y_points {
+            fg.point_y_value.push(reader.read_u8(8)?);
+            fg.point_y_scaling.push(reader.read_u8(8)?);
+        }
+        fg.chroma_scaling_from_luma = if cc.mono_chrome {
+            false
+        } else {
+            reader.read_bit()?
+        };
+        if !(cc.mono_chrome || fg.chroma_scaling_from_luma || (cc.subsampling_x && cc.subsampling_y && num_y_points == 0)) {
+            let num_cb_points = reader.read_u8(4)?;
+            for _ in 0..num_cb_points {
+                fg.point_cb_value.push(reader.read_u8(8)?);
+                fg.point_cb_scaling.push(reader.read_u8(8)?);
+            }
+            let num_cr_points = reader.read_u8(4)?;
+            for _ in 0..num_cr_points {
+                fg.point_cr_value.push(reader.read_u8(8)?);
+                fg.point_cr_scaling.push(reader.read_u8(8)?);
+            }
+        }
+        fg.grain_scaling_minus_8 = reader.read_u8(2)?;
+        fg.ar_coeff_lag = reader.read_u8(2)?;
+        let num_pos_luma = 2 * fg.ar_coeff_lag as usize * (fg.ar_coeff_lag as usize + 1);
+        let num_pos_chroma = if num_y_points > 0 {
+            for _ in 0..num_pos_luma {
+                fg.ar_coeffs_y_plus_128.push(reader.read_u8(8)?);
+            }
+            num_pos_luma + 1
+        } else {
+            num_pos_luma
+        };
+        if fg.chroma_scaling_from_luma || !fg.point_cb_value.is_empty() {
+            for _ in 0..num_pos_chroma {
+                fg.ar_coeffs_cb_plus_128.push(reader.read_u8(8)?);
+            }
+        }
+        if fg.chroma_scaling_from_luma || !fg.point_cr_value.is_empty() {
+            for _ in 0..num_pos_chroma {
+                fg.ar_coeffs_cr_plus_128.push(reader.read_u8(8)?);
+            }
+        }
+        fg.ar_coeff_shift_minus_6 = reader.read_u8(2)?;
+        fg.grain_scale_shift = reader.read_u8(2)?;
+        if !fg.point_cb_value.is_empty() {
+            fg.cb_mult = reader.read_u8(8)?;
+            fg.cb_luma_mult = reader.read_u8(8)?;
+            fg.cb_offset = reader.read_u16(9)?;
+        }
+        if !fg.point_cr_value.is_empty() {
+            fg.cr_mult = reader.read_u8(8)?;
+            fg.cr_luma_mult = reader.read_u8(8)?;
+            fg.cr_offset = reader.read_u16(9)?;
+        }
+        fg.overlap_flag = reader.read_bit()?;
+        fg.clip_to_restricted_range = reader.read_bit()?;
+        self.film_grain = fg;
+        Ok(())
+    }
+}
+
+
+/// 5.9.26 Decode signed subexp with ref syntax
+fn decode_signed_subexp_with_ref(reader: &mut BitsReader, low: i32, high: i32, r: i32) -> Result<i32> {
+    let x = decode_unsigned_subexp_with_ref(reader, (high - low) as u32, (r - low) as u32)?;
+    Ok(x as i32 + low)
+}
+
+/// 5.9.27 Decode unsigned subexp with ref syntax
+fn decode_unsigned_subexp_with_ref(reader: &mut BitsReader, mx: u32, r: u32) -> Result<u32> {
+    let v = decode_subexp(reader, mx)?;
+    if (r << 1) <= mx {
+        Ok(inverse_recenter(r, v))
+    } else {
+        Ok(mx - 1 - inverse_recenter(mx - 1 - r, v))
+    }
+}
+
+/// 5.9.28 Decode subexp syntax
+fn decode_subexp(reader: &mut BitsReader, num_syms: u32) -> Result<u32> {
+    let mut i = 0;
+    let mut mk = 0;
+    let k = 3;
+    loop {
+        let b2 = if i > 0 { k + i - 1 } else { k };
+        let a = 1 << b2;
+        if num_syms <= mk + 3 * a {
+            return Ok(reader.read_ns(num_syms - mk)? + mk);
+        }
+        let subexp_more_bits = reader.read_bit()?;
+        if !subexp_more_bits {
+            return Ok(reader.read_u32(b2)? + mk);
+        }
+        i += 1;
+        mk += a;
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{error::{Error, Result}, obu::header::ObuHeader, utils::bits::{BitsReader, FromBitsReader}};
+
+
+
+/// 5.2 Low overhead bitstream format, yields every OBU header with its payload
+pub struct ObuIter<'a> {
+    data: &'a [u8],
+    position: usize,
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> From<&'a [u8]> for ObuIter<'a> {
+    fn from(value: &'a [u8]) -> Self {
+        Self { data: value, position: 0, offset: 0, failed: false }
+    }
+}
+
+impl<'a> ObuIter<'a> {
+    /// Byte offset of the OBU most recently returned, or of the one that failed
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn read_obu(&mut self) -> Result<(ObuHeader, &'a [u8])> {
+        let data = &self.data[self.position..];
+        let mut reader = BitsReader::from(data);
+        let mut header = ObuHeader::from_bits_reader(&mut reader)?;
+        let header_len = reader.read_position() >> 3;
+        let available = data.len() - header_len;
+        // without obu_size the OBU runs to the end of the buffer
+        if !header.obu_has_size_field {
+            header.obu_size = available;
+        }
+        if header.obu_size > available {
+            return Err(Error::Truncated { needed: header.obu_size * 8, available: available * 8 });
+        }
+        let payload = &data[header_len..header_len + header.obu_size];
+        self.position += header_len + header.obu_size;
+        Ok((header, payload))
+    }
+}
+
+impl<'a> Iterator for ObuIter<'a> {
+    type Item = Result<(ObuHeader, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.position >= self.data.len() {
+            return None;
+        }
+        // the position can not be recovered after a bad header, so stop at the first error
+        self.offset = self.position;
+        let res = self.read_obu();
+        self.failed = res.is_err();
+        Some(res)
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{error::{Error, Result}, utils::{bits::{BitsReader, FromBitsReader}, enums::{MetadataType, ScalabilityModeIdc}}};
+
+
+
+/// 5.8.3 Metadata high dynamic range content light level syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct HdrCll {
+    pub max_cll: u16,
+    pub max_fall: u16,
+}
+
+impl FromBitsReader for HdrCll {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        Ok(Self { max_cll: reader.read_u16(16)?, max_fall: reader.read_u16(16)? })
+    }
+}
+
+
+/// 5.8.4 Metadata high dynamic range mastering display color volume syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct HdrMdcv {
+    // 0.16 定点数，依次为 R G B
+    pub primary_chromaticity_x: [u16; 3],
+    pub primary_chromaticity_y: [u16; 3],
+    pub white_point_chromaticity_x: u16,
+    pub white_point_chromaticity_y: u16,
+    // 24.8 定点数
+    pub luminance_max: u32,
+    // 18.14 定点数
+    pub luminance_min: u32,
+}
+
+impl FromBitsReader for HdrMdcv {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        let mut primary_chromaticity_x = [0; 3];
+        let mut primary_chromaticity_y = [0; 3];
+        for i in 0..3 {
+            primary_chromaticity_x[i] = reader.read_u16(16)?;
+            primary_chromaticity_y[i] = reader.read_u16(16)?;
+        }
+        Ok(Self {
+            primary_chromaticity_x,
+            primary_chromaticity_y,
+            white_point_chromaticity_x: reader.read_u16(16)?,
+            white_point_chromaticity_y: reader.read_u16(16)?,
+            luminance_max: reader.read_u32(32)?,
+            luminance_min: reader.read_u32(32)?,
+        })
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TemporalGroupEntry {
+    pub temporal_id: u8,
+    pub temporal_switching_up_point_flag: bool,
+    pub spatial_switching_up_point_flag: bool,
+    pub ref_pic_diff: Vec<u8>,
+}
+
+
+/// 5.8.6 Scalability structure syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScalabilityStructure {
+    pub spatial_layers_cnt_minus_1: u8,
+    pub spatial_layer_dimensions_present_flag: bool,
+    pub spatial_layer_description_present_flag: bool,
+    pub temporal_group_description_present_flag: bool,
+    // (spatial_layer_max_width, spatial_layer_max_height)，未出现时为空
+    pub spatial_layer_max_dimensions: Vec<(u16, u16)>,
+    pub spatial_layer_ref_id: Vec<u8>,
+    pub temporal_group: Vec<TemporalGroupEntry>,
+}
+
+impl FromBitsReader for ScalabilityStructure {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        let spatial_layers_cnt_minus_1 = reader.read_u8(2)?;
+        let spatial_layer_dimensions_present_flag = reader.read_bit()?;
+        let spatial_layer_description_present_flag = reader.read_bit()?;
+        let temporal_group_description_present_flag = reader.read_bit()?;
+        // scalability_structure_reserved_3bits
+        reader.read_skip(3)?;
+        let layers = spatial_layers_cnt_minus_1 as usize + 1;
+        let mut spatial_layer_max_dimensions = Vec::new();
+        if spatial_layer_dimensions_present_flag {
+            for _ in 0..layers {
+                spatial_layer_max_dimensions.push((reader.read_u16(16)?, reader.read_u16(16)?));
+            }
+        }
+        let mut spatial_layer_ref_id = Vec::new();
+        if spatial_layer_description_present_flag {
+            for _ in 0..layers {
+                spatial_layer_ref_id.push(reader.read_u8(8)?);
+            }
+        }
+        let mut temporal_group = Vec::new();
+        if temporal_group_description_present_flag {
+            let temporal_group_size = reader.read_u8(8)?;
+            for _ in 0..temporal_group_size {
+                let temporal_id = reader.read_u8(3)?;
+                let temporal_switching_up_point_flag = reader.read_bit()?;
+                let spatial_switching_up_point_flag = reader.read_bit()?;
+                let temporal_group_ref_cnt = reader.read_u8(3)?;
+                let mut ref_pic_diff = Vec::with_capacity(temporal_group_ref_cnt as usize);
+                for _ in 0..temporal_group_ref_cnt {
+                    ref_pic_diff.push(reader.read_u8(8)?);
+                }
+                temporal_group.push(TemporalGroupEntry {
+                    temporal_id,
+                    temporal_switching_up_point_flag,
+                    spatial_switching_up_point_flag,
+                    ref_pic_diff,
+                });
+            }
+        }
+        Ok(Self {
+            spatial_layers_cnt_minus_1,
+            spatial_layer_dimensions_present_flag,
+            spatial_layer_description_present_flag,
+            temporal_group_description_present_flag,
+            spatial_layer_max_dimensions,
+            spatial_layer_ref_id,
+            temporal_group,
+        })
+    }
+}
+
+
+/// 5.8.5 Metadata scalability syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct Scalability {
+    pub scalability_mode_idc: ScalabilityModeIdc,
+    // 只有 SCALABILITY_SS 才带 scalability_structure
+    pub scalability_structure: Option<ScalabilityStructure>,
+}
+
+impl FromBitsReader for Scalability {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        let scalability_mode_idc = reader.read_u8(8)?.into();
+        let scalability_structure = if scalability_mode_idc == ScalabilityModeIdc::SS {
+            Some(ScalabilityStructure::from_bits_reader(reader)?)
+        } else {
+            None
+        };
+        Ok(Self { scalability_mode_idc, scalability_structure })
+    }
+}
+
+
+/// 5.8.2 Metadata ITUT T35 syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct ItutT35 {
+    pub itu_t_t35_country_code: u8,
+    // 仅当 country code 为 0xFF 时出现
+    pub itu_t_t35_country_code_extension_byte: Option<u8>,
+    // 去掉 trailing bits 后的原始负载
+    pub itu_t_t35_payload_bytes: Vec<u8>,
+}
+
+impl ItutT35 {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = BitsReader::from(data);
+        let itu_t_t35_country_code = reader.read_u8(8)?;
+        let itu_t_t35_country_code_extension_byte = if itu_t_t35_country_code == 0xFF {
+            Some(reader.read_u8(8)?)
+        } else {
+            None
+        };
+        let payload = &data[reader.read_position() >> 3..];
+        // 负载以 trailing_bits 结束：一个 0x80 字节之后全是 0
+        let end = payload.iter().rposition(|&b| b != 0);
+        let itu_t_t35_payload_bytes = match end {
+            Some(end) if payload[end] == 0x80 => payload[..end].to_vec(),
+            _ => return Err(Error::Conformance { section: "5.8.2", reason: "itu_t_t35 payload without trailing bits" }),
+        };
+        Ok(Self { itu_t_t35_country_code, itu_t_t35_country_code_extension_byte, itu_t_t35_payload_bytes })
+    }
+}
+
+
+/// 5.8.7 Metadata timecode syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct Timecode {
+    pub counting_type: u8,
+    pub full_timestamp_flag: bool,
+    pub discontinuity_flag: bool,
+    pub cnt_dropped_flag: bool,
+    pub n_frames: u16,
+    // full_timestamp_flag 为 0 时各字段可以省略
+    pub seconds_value: Option<u8>,
+    pub minutes_value: Option<u8>,
+    pub hours_value: Option<u8>,
+    pub time_offset_length: u8,
+    pub time_offset_value: u32,
+}
+
+impl FromBitsReader for Timecode {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        let counting_type = reader.read_u8(5)?;
+        let full_timestamp_flag = reader.read_bit()?;
+        let discontinuity_flag = reader.read_bit()?;
+        let cnt_dropped_flag = reader.read_bit()?;
+        let n_frames = reader.read_u16(9)?;
+        let (mut seconds_value, mut minutes_value, mut hours_value) = (None, None, None);
+        if full_timestamp_flag {
+            seconds_value = Some(reader.read_u8(6)?);
+            minutes_value = Some(reader.read_u8(6)?);
+            hours_value = Some(reader.read_u8(5)?);
+        } else if reader.read_bit()? {
+            seconds_value = Some(reader.read_u8(6)?);
+            if reader.read_bit()? {
+                minutes_value = Some(reader.read_u8(6)?);
+                if reader.read_bit()? {
+                    hours_value = Some(reader.read_u8(5)?);
+                }
+            }
+        }
+        if seconds_value.is_some_and(|v| v > 59) || minutes_value.is_some_and(|v| v > 59) || hours_value.is_some_and(|v| v > 23) {
+            return Err(Error::Conformance { section: "6.7.7", reason: "timecode value out of range" });
+        }
+        let time_offset_length = reader.read_u8(5)?;
+        let time_offset_value = if time_offset_length > 0 {
+            reader.read_u32(time_offset_length)?
+        } else {
+            0
+        };
+        Ok(Self {
+            counting_type,
+            full_timestamp_flag,
+            discontinuity_flag,
+            cnt_dropped_flag,
+            n_frames,
+            seconds_value,
+            minutes_value,
+            hours_value,
+            time_offset_length,
+            time_offset_value,
+        })
+    }
+}
+
+
+/// 5.8.1 General metadata OBU syntax
+#[derive(Debug, PartialEq, Clone)]
+pub enum Metadata {
+    HdrCll(HdrCll),
+    HdrMdcv(HdrMdcv),
+    Scalability(Scalability),
+    ItutT35(ItutT35),
+    Timecode(Timecode),
+    // 保留或未注册的类型，按 6.7.1 忽略内容，只保留原始负载
+    Unknown { metadata_type: u32, payload: Vec<u8> },
+}
+
+impl Metadata {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = BitsReader::from(data);
+        let value = reader.read_leb128()? as u32;
+        let metadata_type = u8::try_from(value).map_or(MetadataType::Unknown, MetadataType::from);
+        let metadata = match metadata_type {
+            MetadataType::HdrCll => Self::HdrCll(HdrCll::from_bits_reader(&mut reader)?),
+            MetadataType::HdrMdcv => Self::HdrMdcv(HdrMdcv::from_bits_reader(&mut reader)?),
+            MetadataType::Scalability => Self::Scalability(Scalability::from_bits_reader(&mut reader)?),
+            MetadataType::ItutT35 => Self::ItutT35(ItutT35::parse(&data[reader.read_position() >> 3..])?),
+            MetadataType::Timecode => Self::Timecode(Timecode::from_bits_reader(&mut reader)?),
+            MetadataType::Unknown => Self::Unknown { metadata_type: value, payload: data[reader.read_position() >> 3..].to_vec() },
+        };
+        Ok(metadata)
+    }
+}
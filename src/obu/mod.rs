@@ -1,2 +1,8 @@
+pub mod header;
+pub mod sequence;
+pub mod frame;
+pub mod tile_group;
+pub mod metadata;
+pub mod iter;
 
-pub mod header;
\ No newline at end of file
+pub use iter::ObuIter;
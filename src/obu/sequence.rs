@@ -0,0 +1,404 @@
+use crate::{error::{Error, Result}, utils::{bits::{BitsReader, FromBitsReader}, consts::{SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS}, enums::{ChromaSamplePosition, ColorPrimaries, MatrixCoefficients, TransferCharacteristics}}};
+
+
+
+/// 5.5.3 Timing info syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct TimingInfo {
+    pub num_units_in_display_tick: u32,
+    pub time_scale: u32,
+    pub equal_picture_interval: bool,
+    pub num_ticks_per_picture_minus_1: u32,
+}
+
+impl FromBitsReader for TimingInfo {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        let num_units_in_display_tick = reader.read_u32(32)?;
+        let time_scale = reader.read_u32(32)?;
+        if num_units_in_display_tick == 0 || time_scale == 0 {
+            return Err(Error::Conformance { section: "6.4.3", reason: "num_units_in_display_tick and time_scale must be greater than 0" });
+        }
+        let equal_picture_interval = reader.read_bit()?;
+        let num_ticks_per_picture_minus_1 = if equal_picture_interval {
+            let v = reader.read_uvlc()?;
+            if v == u32::MAX {
+                return Err(Error::Conformance { section: "6.4.3", reason: "num_ticks_per_picture_minus_1 must not be 2^32 - 1" });
+            }
+            v
+        } else {
+            0
+        };
+        Ok(Self { num_units_in_display_tick, time_scale, equal_picture_interval, num_ticks_per_picture_minus_1 })
+    }
+}
+
+
+/// 5.5.4 Decoder model info syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct DecoderModelInfo {
+    pub buffer_delay_length_minus_1: u8,
+    pub num_units_in_decoding_tick: u32,
+    pub buffer_removal_time_length_minus_1: u8,
+    pub frame_presentation_time_length_minus_1: u8,
+}
+
+impl FromBitsReader for DecoderModelInfo {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        let buffer_delay_length_minus_1 = reader.read_u8(5)?;
+        let num_units_in_decoding_tick = reader.read_u32(32)?;
+        let buffer_removal_time_length_minus_1 = reader.read_u8(5)?;
+        let frame_presentation_time_length_minus_1 = reader.read_u8(5)?;
+        Ok(Self { buffer_delay_length_minus_1, num_units_in_decoding_tick, buffer_removal_time_length_minus_1, frame_presentation_time_length_minus_1 })
+    }
+}
+
+
+/// 5.5.5 Operating parameters info syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct OperatingParametersInfo {
+    pub decoder_buffer_delay: u32,
+    pub encoder_buffer_delay: u32,
+    pub low_delay_mode_flag: bool,
+}
+
+
+/// One entry of the operating point loop in 5.5.1
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct OperatingPoint {
+    pub idc: u16,
+    pub seq_level_idx: u8,
+    pub seq_tier: u8,
+    pub decoder_model_present_for_this_op: bool,
+    pub operating_parameters_info: Option<OperatingParametersInfo>,
+    pub initial_display_delay_present_for_this_op: bool,
+    pub initial_display_delay_minus_1: u8,
+}
+
+
+/// 5.5.2 Color config syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColorConfig {
+    pub high_bitdepth: bool,
+    pub twelve_bit: bool,
+    pub bit_depth: u8,
+    pub mono_chrome: bool,
+    pub num_planes: u8,
+    pub color_description_present_flag: bool,
+    pub color_primaries: ColorPrimaries,
+    pub transfer_characteristics: TransferCharacteristics,
+    pub matrix_coefficients: MatrixCoefficients,
+    pub color_range: bool,
+    pub subsampling_x: bool,
+    pub subsampling_y: bool,
+    pub chroma_sample_position: ChromaSamplePosition,
+    pub separate_uv_delta_q: bool,
+}
+
+impl ColorConfig {
+    pub fn from_bits_reader(reader: &mut BitsReader, seq_profile: u8) -> Result<Self> {
+        let high_bitdepth = reader.read_bit()?;
+        let mut twelve_bit = false;
+        let bit_depth = if seq_profile == 2 && high_bitdepth {
+            twelve_bit = reader.read_bit()?;
+            if twelve_bit { 12 } else { 10 }
+        } else if high_bitdepth {
+            10
+        } else {
+            8
+        };
+        let mono_chrome = if seq_profile == 1 {
+            false
+        } else {
+            reader.read_bit()?
+        };
+        let num_planes = if mono_chrome { 1 } else { 3 };
+        let color_description_present_flag = reader.read_bit()?;
+        let (color_primaries, transfer_characteristics, matrix_coefficients) = if color_description_present_flag {
+            (reader.read_u8(8)?.into(), reader.read_u8(8)?.into(), reader.read_u8(8)?.into())
+        } else {
+            (ColorPrimaries::Unspecified, TransferCharacteristics::Unspecified, MatrixCoefficients::Unspecified)
+        };
+
+        let mut res = Self {
+            high_bitdepth,
+            twelve_bit,
+            bit_depth,
+            mono_chrome,
+            num_planes,
+            color_description_present_flag,
+            color_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+            color_range: false,
+            subsampling_x: true,
+            subsampling_y: true,
+            chroma_sample_position: ChromaSamplePosition::Unknown,
+            separate_uv_delta_q: false,
+        };
+
+        if mono_chrome {
+            res.color_range = reader.read_bit()?;
+            return Ok(res);
+        }
+
+        if res.color_primaries == ColorPrimaries::Bt709
+            && res.transfer_characteristics == TransferCharacteristics::Srgb
+            && res.matrix_coefficients == MatrixCoefficients::Identity {
+            res.color_range = true;
+            res.subsampling_x = false;
+            res.subsampling_y = false;
+            if !(seq_profile == 1 || (seq_profile == 2 && bit_depth == 12)) {
+                return Err(Error::Conformance { section: "6.4.2", reason: "sRGB 4:4:4 requires seq_profile 1, or seq_profile 2 with 12-bit" });
+            }
+        } else {
+            res.color_range = reader.read_bit()?;
+            match seq_profile {
+                0 => {
+                    res.subsampling_x = true;
+                    res.subsampling_y = true;
+                },
+                1 => {
+                    res.subsampling_x = false;
+                    res.subsampling_y = false;
+                },
+                _ => {
+                    if bit_depth == 12 {
+                        res.subsampling_x = reader.read_bit()?;
+                        res.subsampling_y = if res.subsampling_x {
+                            reader.read_bit()?
+                        } else {
+                            false
+                        };
+                    } else {
+                        res.subsampling_x = true;
+                        res.subsampling_y = false;
+                    }
+                },
+            }
+            if res.subsampling_x && res.subsampling_y {
+                res.chroma_sample_position = reader.read_u8(2)?.into();
+            }
+        }
+        res.separate_uv_delta_q = reader.read_bit()?;
+        Ok(res)
+    }
+}
+
+
+/// 5.5.1 General sequence header OBU syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct SequenceHeader {
+    pub seq_profile: u8,
+    pub still_picture: bool,
+    pub reduced_still_picture_header: bool,
+    pub timing_info: Option<TimingInfo>,
+    pub decoder_model_info: Option<DecoderModelInfo>,
+    pub initial_display_delay_present_flag: bool,
+    pub operating_points: Vec<OperatingPoint>,
+    pub frame_width_bits_minus_1: u8,
+    pub frame_height_bits_minus_1: u8,
+    pub max_frame_width_minus_1: u32,
+    pub max_frame_height_minus_1: u32,
+    pub frame_id_numbers_present_flag: bool,
+    pub delta_frame_id_length_minus_2: u8,
+    pub additional_frame_id_length_minus_1: u8,
+    pub use_128x128_superblock: bool,
+    pub enable_filter_intra: bool,
+    pub enable_intra_edge_filter: bool,
+    pub enable_interintra_compound: bool,
+    pub enable_masked_compound: bool,
+    pub enable_warped_motion: bool,
+    pub enable_dual_filter: bool,
+    pub enable_order_hint: bool,
+    pub enable_jnt_comp: bool,
+    pub enable_ref_frame_mvs: bool,
+    pub seq_choose_screen_content_tools: bool,
+    pub seq_force_screen_content_tools: u8,
+    pub seq_choose_integer_mv: bool,
+    pub seq_force_integer_mv: u8,
+    pub order_hint_bits: u8,
+    pub enable_superres: bool,
+    pub enable_cdef: bool,
+    pub enable_restoration: bool,
+    pub color_config: ColorConfig,
+    pub film_grain_params_present: bool,
+}
+
+impl SequenceHeader {
+    pub fn operating_point_idc(&self, operating_point: usize) -> u16 {
+        self.operating_points.get(operating_point).map(|op| op.idc).unwrap_or_default()
+    }
+
+    /// get_relative_dist, the signed distance between two order hints
+    pub fn get_relative_dist(&self, a: u32, b: u32) -> i32 {
+        if !self.enable_order_hint {
+            return 0;
+        }
+        let diff = a as i32 - b as i32;
+        let m = 1 << (self.order_hint_bits - 1);
+        (diff & (m - 1)) - (diff & m)
+    }
+}
+
+impl FromBitsReader for SequenceHeader {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        let seq_profile = reader.read_u8(3)?;
+        if seq_profile > 2 {
+            return Err(Error::InvalidValue { name: "seq_profile", value: seq_profile as u32 });
+        }
+        let still_picture = reader.read_bit()?;
+        let reduced_still_picture_header = reader.read_bit()?;
+        if reduced_still_picture_header && !still_picture {
+            return Err(Error::Conformance { section: "6.4.1", reason: "reduced_still_picture_header requires still_picture" });
+        }
+
+        let mut timing_info = None;
+        let mut decoder_model_info = None;
+        let mut initial_display_delay_present_flag = false;
+        let mut operating_points = Vec::new();
+        if reduced_still_picture_header {
+            operating_points.push(OperatingPoint {
+                seq_level_idx: reader.read_u8(5)?,
+                ..Default::default()
+            });
+        } else {
+            let timing_info_present_flag = reader.read_bit()?;
+            if timing_info_present_flag {
+                timing_info = Some(TimingInfo::from_bits_reader(reader)?);
+                let decoder_model_info_present_flag = reader.read_bit()?;
+                if decoder_model_info_present_flag {
+                    decoder_model_info = Some(DecoderModelInfo::from_bits_reader(reader)?);
+                }
+            }
+            initial_display_delay_present_flag = reader.read_bit()?;
+            let operating_points_cnt_minus_1 = reader.read_u8(5)?;
+            for _ in 0..=operating_points_cnt_minus_1 {
+                let mut op = OperatingPoint {
+                    idc: reader.read_u16(12)?,
+                    seq_level_idx: reader.read_u8(5)?,
+                    ..Default::default()
+                };
+                if op.seq_level_idx > 7 {
+                    op.seq_tier = reader.read_u8(1)?;
+                }
+                if let Some(info) = &decoder_model_info {
+                    op.decoder_model_present_for_this_op = reader.read_bit()?;
+                    if op.decoder_model_present_for_this_op {
+                        // 5.5.5 Operating parameters info syntax
+                        let n = info.buffer_delay_length_minus_1 + 1;
+                        op.operating_parameters_info = Some(OperatingParametersInfo {
+                            decoder_buffer_delay: reader.read_u32(n)?,
+                            encoder_buffer_delay: reader.read_u32(n)?,
+                            low_delay_mode_flag: reader.read_bit()?,
+                        });
+                    }
+                }
+                if initial_display_delay_present_flag {
+                    op.initial_display_delay_present_for_this_op = reader.read_bit()?;
+                    if op.initial_display_delay_present_for_this_op {
+                        op.initial_display_delay_minus_1 = reader.read_u8(4)?;
+                    }
+                }
+                operating_points.push(op);
+            }
+        }
+
+        let frame_width_bits_minus_1 = reader.read_u8(4)?;
+        let frame_height_bits_minus_1 = reader.read_u8(4)?;
+        let max_frame_width_minus_1 = reader.read_u32(frame_width_bits_minus_1 + 1)?;
+        let max_frame_height_minus_1 = reader.read_u32(frame_height_bits_minus_1 + 1)?;
+        let frame_id_numbers_present_flag = if reduced_still_picture_header {
+            false
+        } else {
+            reader.read_bit()?
+        };
+        let (delta_frame_id_length_minus_2, additional_frame_id_length_minus_1) = if frame_id_numbers_present_flag {
+            (reader.read_u8(4)?, reader.read_u8(3)?)
+        } else {
+            (0, 0)
+        };
+        let use_128x128_superblock = reader.read_bit()?;
+        let enable_filter_intra = reader.read_bit()?;
+        let enable_intra_edge_filter = reader.read_bit()?;
+
+        let mut enable_interintra_compound = false;
+        let mut enable_masked_compound = false;
+        let mut enable_warped_motion = false;
+        let mut enable_dual_filter = false;
+        let mut enable_order_hint = false;
+        let mut enable_jnt_comp = false;
+        let mut enable_ref_frame_mvs = false;
+        let mut seq_choose_screen_content_tools = false;
+        let mut seq_force_screen_content_tools = SELECT_SCREEN_CONTENT_TOOLS;
+        let mut seq_choose_integer_mv = false;
+        let mut seq_force_integer_mv = SELECT_INTEGER_MV;
+        let mut order_hint_bits = 0;
+        if !reduced_still_picture_header {
+            enable_interintra_compound = reader.read_bit()?;
+            enable_masked_compound = reader.read_bit()?;
+            enable_warped_motion = reader.read_bit()?;
+            enable_dual_filter = reader.read_bit()?;
+            enable_order_hint = reader.read_bit()?;
+            if enable_order_hint {
+                enable_jnt_comp = reader.read_bit()?;
+                enable_ref_frame_mvs = reader.read_bit()?;
+            }
+            seq_choose_screen_content_tools = reader.read_bit()?;
+            if !seq_choose_screen_content_tools {
+                seq_force_screen_content_tools = reader.read_u8(1)?;
+            }
+            if seq_force_screen_content_tools > 0 {
+                seq_choose_integer_mv = reader.read_bit()?;
+                if !seq_choose_integer_mv {
+                    seq_force_integer_mv = reader.read_u8(1)?;
+                }
+            }
+            if enable_order_hint {
+                order_hint_bits = reader.read_u8(3)? + 1;
+            }
+        }
+
+        let enable_superres = reader.read_bit()?;
+        let enable_cdef = reader.read_bit()?;
+        let enable_restoration = reader.read_bit()?;
+        let color_config = ColorConfig::from_bits_reader(reader, seq_profile)?;
+        let film_grain_params_present = reader.read_bit()?;
+
+        Ok(Self {
+            seq_profile,
+            still_picture,
+            reduced_still_picture_header,
+            timing_info,
+            decoder_model_info,
+            initial_display_delay_present_flag,
+            operating_points,
+            frame_width_bits_minus_1,
+            frame_height_bits_minus_1,
+            max_frame_width_minus_1,
+            max_frame_height_minus_1,
+            frame_id_numbers_present_flag,
+            delta_frame_id_length_minus_2,
+            additional_frame_id_length_minus_1,
+            use_128x128_superblock,
+            enable_filter_intra,
+            enable_intra_edge_filter,
+            enable_interintra_compound,
+            enable_masked_compound,
+            enable_warped_motion,
+            enable_dual_filter,
+            enable_order_hint,
+            enable_jnt_comp,
+            enable_ref_frame_mvs,
+            seq_choose_screen_content_tools,
+            seq_force_screen_content_tools,
+            seq_choose_integer_mv,
+            seq_force_integer_mv,
+            order_hint_bits,
+            enable_superres,
+            enable_cdef,
+            enable_restoration,
+            color_config,
+            film_grain_params_present,
+        })
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{error::{Error, Result}, obu::frame::FrameHeader, utils::bits::BitsReader};
+
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tile<'a> {
+    pub tile_num: u32,
+    pub tile_row: u32,
+    pub tile_col: u32,
+    pub data: &'a [u8],
+}
+
+
+/// 5.11.1 General tile group OBU syntax
+#[derive(Debug, PartialEq, Clone)]
+pub struct TileGroup<'a> {
+    pub tile_start_and_end_present_flag: bool,
+    pub tg_start: u32,
+    pub tg_end: u32,
+    pub tiles: Vec<Tile<'a>>,
+}
+
+impl<'a> TileGroup<'a> {
+    pub fn parse(data: &'a [u8], fh: &FrameHeader) -> Result<Self> {
+        let info = &fh.tile_info;
+        let num_tiles = info.tile_cols * info.tile_rows;
+        let mut reader = BitsReader::from(data);
+        let mut tile_start_and_end_present_flag = false;
+        if num_tiles > 1 {
+            tile_start_and_end_present_flag = reader.read_bit()?;
+        }
+        let (tg_start, tg_end) = if num_tiles == 1 || !tile_start_and_end_present_flag {
+            (0, num_tiles - 1)
+        } else {
+            let tile_bits = info.tile_cols_log2 + info.tile_rows_log2;
+            (reader.read_u32(tile_bits)?, reader.read_u32(tile_bits)?)
+        };
+        if tg_end < tg_start || tg_end >= num_tiles {
+            return Err(Error::Conformance { section: "6.10.1", reason: "tg_end out of range" });
+        }
+        reader.read_alignment()?;
+
+        let mut position = reader.read_position() >> 3;
+        let mut tiles = Vec::with_capacity((tg_end - tg_start + 1) as usize);
+        for tile_num in tg_start..=tg_end {
+            let tile_size = if tile_num == tg_end {
+                data.len() - position
+            } else {
+                let size_bytes = info.tile_size_bytes as usize;
+                let mut reader = BitsReader::from(&data[position..]);
+                // le(TileSizeBytes)
+                let mut tile_size_minus_1 = 0;
+                for i in 0..size_bytes {
+                    tile_size_minus_1 |= (reader.read_u8(8)? as usize) << (i * 8);
+                }
+                position += size_bytes;
+                tile_size_minus_1 + 1
+            };
+            let available = data.len() - position;
+            if tile_size > available {
+                return Err(Error::Truncated { needed: tile_size * 8, available: available * 8 });
+            }
+            tiles.push(Tile {
+                tile_num,
+                tile_row: tile_num / info.tile_cols,
+                tile_col: tile_num % info.tile_cols,
+                data: &data[position..position + tile_size],
+            });
+            position += tile_size;
+        }
+        Ok(Self { tile_start_and_end_present_flag, tg_start, tg_end, tiles })
+    }
+
+    // 最后一个 tile group 结束整帧的解码
+    pub fn is_last(&self, fh: &FrameHeader) -> bool {
+        self.tg_end == fh.tile_info.tile_cols * fh.tile_info.tile_rows - 1
+    }
+}
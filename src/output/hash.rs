@@ -0,0 +1,95 @@
+use std::{fmt::Write as _, io};
+
+use crate::{decoder::Picture, output::{crc32::Crc32, md5::Md5, write_plane, PictureWriter}};
+
+
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FrameHash {
+    pub width: u32,
+    pub height: u32,
+    pub md5: [u8; 16],
+    pub crc32: Option<u32>,
+}
+
+
+/// Per-frame and whole-stream hashes of the plane data, in the byte layout YuvWriter writes
+///
+/// Only reconstructed pictures are accepted, placeholder pictures are rejected.
+pub struct HashWriter {
+    md5: Md5,
+    crc32: Option<Crc32>,
+    frames: Vec<FrameHash>,
+    buffer: Vec<u8>,
+}
+
+impl HashWriter {
+    pub fn new(crc32: bool) -> Self {
+        Self { md5: Md5::new(), crc32: crc32.then(Crc32::new), frames: Vec::new(), buffer: Vec::new() }
+    }
+
+    pub fn frames(&self) -> &[FrameHash] {
+        &self.frames
+    }
+
+    pub fn md5(&self) -> [u8; 16] {
+        self.md5.clone().finalize()
+    }
+
+    pub fn crc32(&self) -> Option<u32> {
+        self.crc32.as_ref().map(|c| c.finalize())
+    }
+
+    // 开启 CRC32 时在行尾追加一列
+    fn crc32_column(crc32: Option<u32>) -> String {
+        crc32.map(|c| format!("  crc32:{c:08x}")).unwrap_or_default()
+    }
+
+    /// Stream hash as printed by dav1d's md5 muxer
+    pub fn stream_report(&self) -> String {
+        format!("{}{}\n", hex(&self.md5()), Self::crc32_column(self.crc32()))
+    }
+
+    /// One line per frame as in the libaom test vector .md5 files
+    pub fn frame_report(&self, name: &str) -> String {
+        let mut res = String::new();
+        for (i, frame) in self.frames.iter().enumerate() {
+            let crc32 = Self::crc32_column(frame.crc32);
+            writeln!(res, "{}  {name}-{}x{}-{:04}.i420{crc32}", hex(&frame.md5), frame.width, frame.height, i + 1).unwrap();
+        }
+        res
+    }
+}
+
+impl PictureWriter for HashWriter {
+    fn write_picture(&mut self, picture: &Picture) -> io::Result<()> {
+        // 中间值的占位图像算出的 hash 和参考值没法比较
+        if !picture.reconstructed {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "can not hash a picture that has not been reconstructed"));
+        }
+        self.buffer.clear();
+        for plane in picture.planes.iter() {
+            write_plane(&mut self.buffer, plane, picture.bit_depth())?;
+        }
+        let mut md5 = Md5::new();
+        md5.update(&self.buffer);
+        self.md5.update(&self.buffer);
+        let crc32 = self.crc32.as_mut().map(|stream| {
+            stream.update(&self.buffer);
+            let mut crc32 = Crc32::new();
+            crc32.update(&self.buffer);
+            crc32.finalize()
+        });
+        self.frames.push(FrameHash { width: picture.width, height: picture.height, md5: md5.finalize(), crc32 });
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use std::io::{self, Write};
+
+use crate::decoder::{Picture, Plane};
+
+pub mod y4m;
+pub mod yuv;
+pub mod md5;
+pub mod crc32;
+pub mod hash;
+
+pub use y4m::Y4mWriter;
+pub use yuv::YuvWriter;
+pub use md5::Md5;
+pub use crc32::Crc32;
+pub use hash::HashWriter;
+
+
+
+pub trait PictureWriter {
+    fn write_picture(&mut self, picture: &Picture) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+
+// 8 位样本写一个字节，高位深写两个字节小端序
+pub fn write_plane<W: Write>(writer: &mut W, plane: &Plane, bit_depth: u8) -> io::Result<()> {
+    let bytes = if bit_depth > 8 { 2 } else { 1 };
+    let mut row = Vec::with_capacity(plane.width * bytes);
+    for y in 0..plane.height {
+        row.clear();
+        for &sample in plane.row(y) {
+            if bytes == 2 {
+                row.extend_from_slice(&sample.to_le_bytes());
+            } else {
+                row.push(sample as u8);
+            }
+        }
+        writer.write_all(&row)?;
+    }
+    Ok(())
+}
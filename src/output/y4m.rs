@@ -0,0 +1,74 @@
+use std::io::{self, Write};
+
+use crate::{decoder::Picture, output::{write_plane, PictureWriter}, utils::enums::ChromaSamplePosition};
+
+
+
+/// Y4M colorspace tag for the picture format
+pub fn colorspace(picture: &Picture) -> String {
+    let cc = &picture.color_config;
+    let depth = match cc.bit_depth {
+        8 => "",
+        10 => "p10",
+        _ => "p12",
+    };
+    if cc.mono_chrome {
+        let depth = depth.trim_start_matches('p');
+        return format!("mono{depth}");
+    }
+    match (cc.subsampling_x, cc.subsampling_y) {
+        (false, false) => format!("444{depth}"),
+        (true, false) => format!("422{depth}"),
+        _ if cc.bit_depth > 8 => format!("420{depth}"),
+        // 4:2:0 8 位时按色度采样位置区分
+        _ => match cc.chroma_sample_position {
+            ChromaSamplePosition::Vertical => "420mpeg2".into(),
+            ChromaSamplePosition::CoLocated => "420paldv".into(),
+            _ => "420jpeg".into(),
+        },
+    }
+}
+
+
+/// YUV4MPEG2 output, the stream header is taken from the first picture
+pub struct Y4mWriter<W: Write> {
+    writer: W,
+    framerate: (u32, u32),
+    // 第一帧的宽、高和色彩格式，后续帧必须一致
+    format: Option<(u32, u32, String)>,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    pub fn new(writer: W, framerate: (u32, u32)) -> Self {
+        Self { writer, framerate, format: None }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> PictureWriter for Y4mWriter<W> {
+    fn write_picture(&mut self, picture: &Picture) -> io::Result<()> {
+        let format = (picture.width, picture.height, colorspace(picture));
+        match &self.format {
+            None => {
+                let (num, den) = self.framerate;
+                writeln!(self.writer, "YUV4MPEG2 W{} H{} F{num}:{den} Ip A0:0 C{}", format.0, format.1, format.2)?;
+                self.format = Some(format);
+            }
+            Some(f) if *f != format => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "y4m can not change the picture format mid stream"));
+            }
+            _ => {}
+        }
+        self.writer.write_all(b"FRAME\n")?;
+        for plane in picture.planes.iter() {
+            write_plane(&mut self.writer, plane, picture.bit_depth())?;
+        }
+        Ok(())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,32 @@
+use std::io::{self, Write};
+
+use crate::{decoder::Picture, output::{write_plane, PictureWriter}};
+
+
+
+/// Planar raw YUV, monochrome pictures only write the Y plane
+pub struct YuvWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> YuvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> PictureWriter for YuvWriter<W> {
+    fn write_picture(&mut self, picture: &Picture) -> io::Result<()> {
+        for plane in picture.planes.iter() {
+            write_plane(&mut self.writer, plane, picture.bit_depth())?;
+        }
+        Ok(())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
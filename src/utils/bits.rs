@@ -1,5 +1,5 @@
 
-use crate::utils::math::floor_log2;
+use crate::{error::{Error, Result}, utils::math::floor_log2};
 
 pub struct BitsReader<'a> {
     data: &'a [u8],
@@ -18,13 +18,14 @@ impl<'a> BitsReader<'a> {
         (data & (1 << (7 - index))) != 0
     }
 
-    fn read_check(&mut self, count: usize) -> usize {
+    fn read_check(&mut self, count: usize) -> Result<usize> {
         let index = self.index;
-        self.index += count;
-        if self.index > self.index_max {
-            panic!("data len not enought: {} {}", self.index, self.index_max);
+        let available = self.index_max - index;
+        if count > available {
+            return Err(Error::Truncated { needed: count, available });
         }
-        index
+        self.index += count;
+        Ok(index)
     }
 
     pub fn get_bit(&self, index: usize) -> bool {
@@ -33,8 +34,23 @@ impl<'a> BitsReader<'a> {
         Self::get_bit_bool(self.data[n], index)
     }
 
-    pub fn get_1byte(&self, start: usize, count: usize) -> u8 {
-        assert!(count <= 8, "count too long");
+    // 检查 [start, start + count) 是否在数据范围内
+    fn get_check(&self, start: usize, count: usize) -> Result<()> {
+        let available = self.index_max.saturating_sub(start);
+        if count > available {
+            return Err(Error::Truncated { needed: count, available });
+        }
+        Ok(())
+    }
+
+    pub fn get_1byte(&self, start: usize, count: usize) -> Result<u8> {
+        if count > 8 {
+            return Err(Error::InvalidValue { name: "get_1byte bit count", value: count as u32 });
+        }
+        self.get_check(start, count)?;
+        if count == 0 {
+            return Ok(0);
+        }
         // 计算起始和结束的字节索引和比特偏移
         let start_byte = start >> 3;
         let start_bit = start & 0b111; // 在字节内的比特偏移（0 是最高位？还是最低位？）
@@ -47,8 +63,8 @@ impl<'a> BitsReader<'a> {
             let byte = self.data[start_byte];
             // 构造掩码：从 start_bit 到 end_bit（共 count 位）
             // 掩码：从 bit0 开始数，要取 [start_bit, end_bit] 这些位
-            let mask = ((1u8 << count) - 1) << (7 - end_bit); // 对齐到高位
-            (byte & mask) >> (7 - end_bit)
+            let mask = (((1u16 << count) - 1) << (7 - end_bit)) as u8; // 对齐到高位，count 为 8 时 u8 会溢出
+            Ok((byte & mask) >> (7 - end_bit))
         } else {
             // 跨字节：最多跨两个字节（因为 e-s+1 < 8）
             // 第一个字节：从 start_bit 到字节末尾（bit7）
@@ -62,12 +78,15 @@ impl<'a> BitsReader<'a> {
             let bits_from_second = count - bits_from_first;
             let mask_second = (1u8 << bits_from_second) - 1; // 低 bits_from_second 位置 1
             result |= (second_byte >> (8 - bits_from_second)) & mask_second;
-            result
+            Ok(result)
         }
     }
 
-    pub fn get_nbyte(&self, start: usize, count: usize, res: &mut [u8]) {
-        assert!(res.len() * 8 >= count, "get bits res length not enouth");
+    pub fn get_nbyte(&self, start: usize, count: usize, res: &mut [u8]) -> Result<()> {
+        if res.len() * 8 < count {
+            return Err(Error::InvalidValue { name: "get_nbyte result length", value: res.len() as u32 });
+        }
+        self.get_check(start, count)?;
         let mut src_byte_idx = start >> 3;           // 当前读取的源字节索引
         let mut src_bit_offset = 7 - (start & 0b111);   // 源字节内偏移（0 = MSB）
         let mut bits_left = count;         // 还需要复制的比特数
@@ -117,20 +136,22 @@ impl<'a> BitsReader<'a> {
         if pending_bits > 0 {
             res[dst_byte_idx] |= (pending >> (16 - pending_bits)) as u8;
         }
+        Ok(())
     }
 
-    pub fn read_bit(&mut self) -> bool {
-        let index = self.read_check(1);
-        self.get_bit(index)
+    pub fn read_bit(&mut self) -> Result<bool> {
+        let index = self.read_check(1)?;
+        Ok(self.get_bit(index))
     }
 
-    pub fn read_nbyte(&mut self, count: usize, res: &mut [u8]) {
-        let index = self.read_check(1);
-        Self::get_nbyte(self, index, count, res);
+    pub fn read_nbyte(&mut self, count: usize, res: &mut [u8]) -> Result<()> {
+        let index = self.read_check(count)?;
+        Self::get_nbyte(self, index, count, res)
     }
 
-    pub fn read_skip(&mut self, count: u8) {
-        self.index += count as usize;
+    pub fn read_skip(&mut self, count: u8) -> Result<()> {
+        self.read_check(count as usize)?;
+        Ok(())
     }
 
     // pub fn read_u128(&mut self, count: usize) -> u128 {
@@ -145,84 +166,108 @@ impl<'a> BitsReader<'a> {
     //     u64::from_le_bytes(res)
     // }
 
-    pub fn read_u32(&mut self, count: u8) -> u32 {
-        let mut res = [0u8; 4];
-        self.read_nbyte(count as usize, &mut res);
-        u32::from_le_bytes(res)
+    pub fn read_u32(&mut self, count: u8) -> Result<u32> {
+        if count > 32 {
+            return Err(Error::InvalidValue { name: "f(n) bit count", value: count as u32 });
+        }
+        let mut index = self.read_check(count as usize)?;
+        // 按 8 位一组从高位到低位拼接，f(n) 是大端序
+        let mut res = 0u32;
+        let mut left = count as usize;
+        while left > 0 {
+            let n = left.min(8);
+            res = (res << n) | Self::get_1byte(self, index, n)? as u32;
+            index += n;
+            left -= n;
+        }
+        Ok(res)
     }
 
-    pub fn read_u16(&mut self, count: u8) -> u16 {
-        let mut res = [0u8; 2];
-        self.read_nbyte(count as usize, &mut res);
-        u16::from_le_bytes(res)
+    pub fn read_u16(&mut self, count: u8) -> Result<u16> {
+        if count > 16 {
+            return Err(Error::InvalidValue { name: "f(n) bit count", value: count as u32 });
+        }
+        Ok(self.read_u32(count)? as u16)
     }
 
-    pub fn read_u8(&mut self, count: u8) -> u8 {
+    pub fn read_u8(&mut self, count: u8) -> Result<u8> {
+        if count > 8 {
+            return Err(Error::InvalidValue { name: "f(n) bit count", value: count as u32 });
+        }
+        if count == 0 {
+            return Ok(0);
+        }
         let count = count as usize;
-        let index = self.read_check(count);
+        let index = self.read_check(count)?;
         Self::get_1byte(self, index, count)
     }
 
-    pub fn read_uvlc(&mut self) -> u32 {
+    pub fn read_uvlc(&mut self) -> Result<u32> {
         let mut leading_zeros = 0;
         for _ in 0..32 {
-            if self.read_bit() {
+            if self.read_bit()? {
                 break;
             }
             leading_zeros += 1;
         }
         if leading_zeros >= 32 {
-            return u32::MAX
+            return Ok(u32::MAX)
         }
-        let mut res = [0u8; 4];
-        self.read_nbyte(leading_zeros, &mut res);
-        u32::from_le_bytes(res) + ( 1 << leading_zeros ) - 1
+        let value = self.read_u32(leading_zeros)?;
+        Ok(value + ( 1 << leading_zeros ) - 1)
     }
 
-    pub fn read_leb128(&mut self) -> usize {
+    pub fn read_leb128(&mut self) -> Result<usize> {
         let mut res = 0;
-        let mut shift = 0;
         const CONTINUATION_BIT: u8 = 1 << 7;
 
-        loop {
-            let v = self.read_u8(8);
+        for i in 0..8 {
+            let v = self.read_u8(8)?;
             let low_bits = (v & (!CONTINUATION_BIT)) as u64;
-            res |= low_bits << shift;
+            res |= low_bits << (i * 7);
 
-            if !Self::get_bit_bool(v, 7) {
-                break;
+            if !Self::get_bit_bool(v, 0) {
+                if res > u32::MAX as u64 {
+                    return Err(Error::InvalidValue { name: "leb128", value: u32::MAX });
+                }
+                return Ok(res as usize);
             }
-
-            shift += 7;
         }
-        res as usize
+        Err(Error::Conformance { section: "4.10.5", reason: "leb128 must not use more than 8 bytes" })
     }
 
-    pub fn read_su(&mut self, count: u8) -> i32 {
-        let mut value = self.read_u32(count) as i32;
+    pub fn read_su(&mut self, count: u8) -> Result<i32> {
+        if count == 0 {
+            return Err(Error::InvalidValue { name: "su(n) bit count", value: 0 });
+        }
+        let mut value = self.read_u32(count)? as i32;
         let sign_mask = 1 << (count - 1);
         if value & sign_mask > 0 {
             value -= 2 * sign_mask
         }
-        value
+        Ok(value)
     }
 
-    pub fn read_ns(&mut self, n: u32) -> u32 {
-        let w = floor_log2(n);
+    pub fn read_ns(&mut self, n: u32) -> Result<u32> {
+        if n == 0 {
+            return Err(Error::InvalidValue { name: "ns(n) range", value: n });
+        }
+        let w = floor_log2(n) + 1;
         let m = (1 << w) - n;
-        let v = self.read_u32(m as u8);
+        let v = self.read_u32(w - 1)?;
         if v < m {
-            return v;
+            return Ok(v);
         }
-        let extra_bit = self.read_u32(1);
-        (v << 1) - m + extra_bit
+        let extra_bit = self.read_u32(1)?;
+        Ok((v << 1) - m + extra_bit)
     }
 
-    pub fn read_alignment(&mut self) {
+    pub fn read_alignment(&mut self) -> Result<()> {
         let n = self.index & 0b111;
         if n > 0 {
-            self.read_skip(8 - n as u8);
+            self.read_skip(8 - n as u8)?;
         }
+        Ok(())
     }
 
     pub fn read_position(&self) -> usize {
@@ -231,6 +276,6 @@ impl<'a> BitsReader<'a> {
 }
 
 
-pub trait FromBitsReader {
-    fn from_bits_reader(reader: &mut BitsReader) -> Self;
+pub trait FromBitsReader: Sized {
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self>;
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::utils::enums::{FrameRestorationType, RefFrame};
+use crate::utils::enums::{FrameRestorationType, RefFrame, TxType};
 
 
 
@@ -208,6 +208,9 @@ pub const MV_CLASSES: usize = 11;
 // Number of values for mv_class0_bit
 pub const CLASS0_SIZE: usize = 2;
 
+// Number of values for mv_fr and mv_class0_fr
+pub const MV_FR_SIZE: usize = 4;
+
 // Maximum number of bits for decoding motion vectors
 pub const MV_OFFSET_BITS: usize = 10;
 
@@ -262,6 +265,9 @@ pub const DELTA_LF_SMALL: usize = 3;
 // Number of values in the quantizer matrix
 pub const QM_TOTAL_SIZE: u16 = 3344;
 
+// Number of fractional bits of the quantizer matrix weights
+pub const AOM_QM_BITS: u8 = 5;
+
 // Maximum magnitude of AngleDeltaY and AngleDeltaUV
 pub const MAX_ANGLE_DELTA: usize = 3;
 
@@ -277,6 +283,14 @@ pub const TX_SET_TYPES_INTRA: u8 = 3;
 // Number of inter transform set types
 pub const TX_SET_TYPES_INTER: u8 = 4;
 
+// Transform sets returned by get_tx_set, the intra and inter sets share the values
+pub const TX_SET_DCTONLY: u8 = 0;
+pub const TX_SET_INTRA_1: u8 = 1;
+pub const TX_SET_INTRA_2: u8 = 2;
+pub const TX_SET_INTER_1: u8 = 1;
+pub const TX_SET_INTER_2: u8 = 2;
+pub const TX_SET_INTER_3: u8 = 3;
+
 // Internal precision of warped motion models
 pub const WARPEDMODEL_PREC_BITS: u8 = 16;
 
@@ -575,6 +589,9 @@ pub const BUFFER_POOL_MAX_SIZE: usize = 10;
 
 pub const LAST_FRAME: usize = RefFrame::Last as usize;
 
+// RefFrame[1] of blocks using a single reference, NONE in the spec
+pub const NONE_FRAME: i8 = -1;
+
 
 
 pub const SEGMENTATION_FEATURE_BITS: [u8; SEG_LVL_MAX] = [8, 6, 6, 6, 6, 3, 0, 0];
@@ -585,6 +602,379 @@ pub const SEGMENTATION_FEATURE_MAX: [u8; SEG_LVL_MAX] = [255, MAX_LOOP_FILTER, M
 pub const RESTORE_SWITCHABLE: usize = FrameRestorationType::Switchable as usize;
 
 
+// 9.3 Conversion tables, indexed by block size
+pub const NUM_4X4_BLOCKS_WIDE: [u8; BLOCK_SIZES] = [1, 1, 2, 2, 2, 4, 4, 4, 8, 8, 8, 16, 16, 16, 32, 32, 1, 4, 2, 8, 4, 16];
+pub const NUM_4X4_BLOCKS_HIGH: [u8; BLOCK_SIZES] = [1, 2, 1, 2, 4, 2, 4, 8, 4, 8, 16, 8, 16, 32, 16, 32, 4, 1, 8, 2, 16, 4];
+pub const MI_WIDTH_LOG2: [u8; BLOCK_SIZES] = [0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4, 5, 5, 0, 2, 1, 3, 2, 4];
+pub const MI_HEIGHT_LOG2: [u8; BLOCK_SIZES] = [0, 1, 0, 1, 2, 1, 2, 3, 2, 3, 4, 3, 4, 5, 4, 5, 2, 0, 3, 1, 4, 2];
+pub const BLOCK_WIDTH: [u8; BLOCK_SIZES] = [4, 4, 8, 8, 8, 16, 16, 16, 32, 32, 32, 64, 64, 64, 128, 128, 4, 16, 8, 32, 16, 64];
+pub const BLOCK_HEIGHT: [u8; BLOCK_SIZES] = [4, 8, 4, 8, 16, 8, 16, 32, 16, 32, 64, 32, 64, 128, 64, 128, 16, 4, 32, 8, 64, 16];
+pub const SIZE_GROUP: [u8; BLOCK_SIZES] = [0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 0, 0, 1, 1, 2, 2];
+pub const MAX_TX_SIZE_RECT: [u8; BLOCK_SIZES] = [0, 5, 6, 1, 7, 8, 2, 9, 10, 3, 11, 12, 4, 4, 4, 4, 13, 14, 15, 16, 17, 18];
+pub const BLOCK_MAX_TX_DEPTH: [u8; BLOCK_SIZES] = [0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4, 4, 4, 4, 2, 2, 3, 3, 4, 4];
+
+// [partition][block size]
+pub const PARTITION_SUBSIZE: [[u8; BLOCK_SIZES]; 10] = [
+    [0, BLOCK_INVALID, BLOCK_INVALID, 3, BLOCK_INVALID, BLOCK_INVALID, 6, BLOCK_INVALID, BLOCK_INVALID, 9, BLOCK_INVALID, BLOCK_INVALID, 12, BLOCK_INVALID, BLOCK_INVALID, 15, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 2, BLOCK_INVALID, BLOCK_INVALID, 5, BLOCK_INVALID, BLOCK_INVALID, 8, BLOCK_INVALID, BLOCK_INVALID, 11, BLOCK_INVALID, BLOCK_INVALID, 14, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 1, BLOCK_INVALID, BLOCK_INVALID, 4, BLOCK_INVALID, BLOCK_INVALID, 7, BLOCK_INVALID, BLOCK_INVALID, 10, BLOCK_INVALID, BLOCK_INVALID, 13, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 0, BLOCK_INVALID, BLOCK_INVALID, 3, BLOCK_INVALID, BLOCK_INVALID, 6, BLOCK_INVALID, BLOCK_INVALID, 9, BLOCK_INVALID, BLOCK_INVALID, 12, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 2, BLOCK_INVALID, BLOCK_INVALID, 5, BLOCK_INVALID, BLOCK_INVALID, 8, BLOCK_INVALID, BLOCK_INVALID, 11, BLOCK_INVALID, BLOCK_INVALID, 14, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 2, BLOCK_INVALID, BLOCK_INVALID, 5, BLOCK_INVALID, BLOCK_INVALID, 8, BLOCK_INVALID, BLOCK_INVALID, 11, BLOCK_INVALID, BLOCK_INVALID, 14, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 1, BLOCK_INVALID, BLOCK_INVALID, 4, BLOCK_INVALID, BLOCK_INVALID, 7, BLOCK_INVALID, BLOCK_INVALID, 10, BLOCK_INVALID, BLOCK_INVALID, 13, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 1, BLOCK_INVALID, BLOCK_INVALID, 4, BLOCK_INVALID, BLOCK_INVALID, 7, BLOCK_INVALID, BLOCK_INVALID, 10, BLOCK_INVALID, BLOCK_INVALID, 13, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 17, BLOCK_INVALID, BLOCK_INVALID, 19, BLOCK_INVALID, BLOCK_INVALID, 21, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+    [BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, 16, BLOCK_INVALID, BLOCK_INVALID, 18, BLOCK_INVALID, BLOCK_INVALID, 20, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID, BLOCK_INVALID],
+];
+
+// [block size][subsampling_x][subsampling_y]
+pub const SUBSAMPLED_SIZE: [[[u8; 2]; 2]; BLOCK_SIZES] = [
+    [[0, 0], [0, 0]],
+    [[1, 0], [BLOCK_INVALID, 0]],
+    [[2, BLOCK_INVALID], [0, 0]],
+    [[3, 2], [1, 0]],
+    [[4, 3], [BLOCK_INVALID, 1]],
+    [[5, BLOCK_INVALID], [3, 2]],
+    [[6, 5], [4, 3]],
+    [[7, 6], [BLOCK_INVALID, 4]],
+    [[8, BLOCK_INVALID], [6, 5]],
+    [[9, 8], [7, 6]],
+    [[10, 9], [BLOCK_INVALID, 7]],
+    [[11, BLOCK_INVALID], [9, 8]],
+    [[12, 11], [10, 9]],
+    [[13, 12], [BLOCK_INVALID, 10]],
+    [[14, BLOCK_INVALID], [12, 11]],
+    [[15, 14], [13, 12]],
+    [[16, 1], [BLOCK_INVALID, 1]],
+    [[17, BLOCK_INVALID], [2, 2]],
+    [[18, 4], [BLOCK_INVALID, 16]],
+    [[19, BLOCK_INVALID], [5, 17]],
+    [[20, 7], [BLOCK_INVALID, 18]],
+    [[21, BLOCK_INVALID], [8, 19]],
+];
+
+// 9.3 Conversion tables, indexed by transform size
+pub const SPLIT_TX_SIZE: [u8; TX_SIZES_ALL] = [0, 0, 1, 2, 3, 0, 0, 1, 1, 2, 2, 3, 3, 5, 6, 7, 8, 9, 10];
+pub const TX_WIDTH: [u8; TX_SIZES_ALL] = [4, 8, 16, 32, 64, 4, 8, 8, 16, 16, 32, 32, 64, 4, 16, 8, 32, 16, 64];
+pub const TX_HEIGHT: [u8; TX_SIZES_ALL] = [4, 8, 16, 32, 64, 8, 4, 16, 8, 32, 16, 64, 32, 16, 4, 32, 8, 64, 16];
+pub const TX_WIDTH_LOG2: [u8; TX_SIZES_ALL] = [2, 3, 4, 5, 6, 2, 3, 3, 4, 4, 5, 5, 6, 2, 4, 3, 5, 4, 6];
+pub const TX_HEIGHT_LOG2: [u8; TX_SIZES_ALL] = [2, 3, 4, 5, 6, 3, 2, 4, 3, 5, 4, 6, 5, 4, 2, 5, 3, 6, 4];
+pub const TX_SIZE_SQR: [u8; TX_SIZES_ALL] = [0, 1, 2, 3, 4, 0, 0, 1, 1, 2, 2, 3, 3, 0, 0, 1, 1, 2, 2];
+pub const TX_SIZE_SQR_UP: [u8; TX_SIZES_ALL] = [0, 1, 2, 3, 4, 1, 1, 2, 2, 3, 3, 4, 4, 2, 2, 3, 3, 4, 4];
+// Qm_Offset, start of the weights of each transform size in Quantizer_Matrix, 64 point sizes use the 32 point weights
+pub const QM_OFFSET: [u16; TX_SIZES_ALL] = [0, 16, 80, 336, 336, 1360, 1392, 1424, 1552, 1680, 2192, 336, 336, 2704, 2768, 2832, 3088, 1680, 2192];
+// Quantizer_Matrix[qmLevel][plane > 0], levels 0 to 14; the weights of the spec are not filled in yet
+pub const QUANTIZER_MATRIX: [[[u8; QM_TOTAL_SIZE as usize]; 2]; 0] = [];
+// 64 点变换只编码左上 32x32 的系数
+pub const ADJUSTED_TX_SIZE: [u8; TX_SIZES_ALL] = [0, 1, 2, 3, 3, 5, 6, 7, 8, 9, 10, 3, 3, 13, 14, 15, 16, 9, 10];
+
+// 9.3 Conversion tables used by the coefficient contexts, [tx class][idx][row / col]
+pub const SIG_REF_DIFF_OFFSET: [[[i8; 2]; SIG_REF_DIFF_OFFSET_NUM as usize]; 3] = [
+    [[0, 1], [1, 0], [1, 1], [0, 2], [2, 0]],
+    [[0, 1], [1, 0], [0, 2], [0, 3], [0, 4]],
+    [[0, 1], [1, 0], [2, 0], [3, 0], [4, 0]],
+];
+
+pub const MAG_REF_OFFSET_WITH_TX_CLASS: [[[i8; 2]; 3]; 3] = [
+    [[0, 1], [1, 0], [1, 1]],
+    [[0, 1], [1, 0], [0, 2]],
+    [[0, 1], [1, 0], [2, 0]],
+];
+
+pub const COEFF_BASE_POS_CTX_OFFSET: [u8; 3] = [SIG_COEF_CONTEXTS_2D, SIG_COEF_CONTEXTS_2D + 5, SIG_COEF_CONTEXTS_2D + 10];
+
+// 9.3 Conversion tables for the transform type, the inverse maps are indexed by the coded symbol
+pub const TX_TYPE_INTRA_INV_SET1: [TxType; 7] = [TxType::Idtx, TxType::DctDct, TxType::VDct, TxType::HDct, TxType::AdstAdst, TxType::AdstDct, TxType::DctAdst];
+pub const TX_TYPE_INTRA_INV_SET2: [TxType; 5] = [TxType::Idtx, TxType::DctDct, TxType::AdstAdst, TxType::AdstDct, TxType::DctAdst];
+pub const TX_TYPE_INTER_INV_SET1: [TxType; 16] = [
+    TxType::Idtx, TxType::VDct, TxType::HDct, TxType::VAdst, TxType::HAdst, TxType::VFlipadst, TxType::HFlipadst, TxType::DctDct,
+    TxType::AdstDct, TxType::DctAdst, TxType::FlipadstDct, TxType::DctFlipadst, TxType::AdstAdst, TxType::FlipadstFlipadst, TxType::AdstFlipadst, TxType::FlipadstAdst,
+];
+pub const TX_TYPE_INTER_INV_SET2: [TxType; 12] = [
+    TxType::Idtx, TxType::VDct, TxType::HDct, TxType::DctDct, TxType::AdstDct, TxType::DctAdst, TxType::FlipadstDct, TxType::DctFlipadst,
+    TxType::AdstAdst, TxType::FlipadstFlipadst, TxType::AdstFlipadst, TxType::FlipadstAdst,
+];
+pub const TX_TYPE_INTER_INV_SET3: [TxType; 2] = [TxType::Idtx, TxType::DctDct];
+
+// [tx set][tx type]
+pub const TX_TYPE_IN_SET_INTRA: [[bool; TX_TYPES as usize]; TX_SET_TYPES_INTRA as usize] = [
+    [true, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false],
+    [true, true, true, true, false, false, false, false, false, true, true, true, false, false, false, false],
+    [true, true, true, true, false, false, false, false, false, true, false, false, false, false, false, false],
+];
+
+pub const TX_TYPE_IN_SET_INTER: [[bool; TX_TYPES as usize]; TX_SET_TYPES_INTER as usize] = [
+    [true, false, false, false, false, false, false, false, false, false, false, false, false, false, false, false],
+    [true; TX_TYPES as usize],
+    [true, true, true, true, true, true, true, true, true, true, true, true, false, false, false, false],
+    [true, false, false, false, false, false, false, false, false, true, false, false, false, false, false, false],
+];
+
+// [uv mode]，色度的隐式变换类型
+pub const MODE_TO_TXFM: [TxType; UV_INTRA_MODES_CFL_ALLOWED] = [
+    TxType::DctDct, TxType::AdstDct, TxType::DctAdst, TxType::DctDct, TxType::AdstAdst, TxType::AdstDct, TxType::DctAdst,
+    TxType::DctAdst, TxType::AdstDct, TxType::AdstAdst, TxType::AdstDct, TxType::DctAdst, TxType::AdstAdst, TxType::DctDct,
+];
+
+// [filter intra mode]
+pub const FILTER_INTRA_MODE_TO_INTRA_DIR: [u8; 5] = [0, 1, 2, 6, 0];
+
+// 9.3 Conversion tables, indexed by intra mode
+pub const INTRA_MODE_CONTEXT: [u8; INTRA_MODES] = [0, 1, 2, 3, 4, 4, 4, 4, 3, 0, 1, 2, 0];
+
+pub const COMPOUND_MODE_CTX_MAP: [[u8; COMP_NEWMV_CTXS as usize]; 3] = [
+    [0, 1, 1, 1, 1],
+    [1, 2, 3, 4, 4],
+    [4, 4, 5, 6, 7],
+];
+
+pub const WEDGE_BITS: [u8; BLOCK_SIZES] = [0, 0, 0, 4, 4, 4, 4, 4, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0, 4, 4, 0, 0];
+
+pub const DIV_MULT: [i32; 32] = [
+    0, 16384, 8192, 5461, 4096, 3276, 2730, 2340, 2048, 1820, 1638, 1489, 1365, 1260, 1170, 1092,
+    1024, 963, 910, 862, 819, 780, 744, 712, 682, 655, 630, 606, 585, 564, 546, 528,
+];
+
+// 7.13.2 Inverse transform process, round(4096 * cos(i * pi / 128))
+pub const COS128_LOOKUP: [i32; 65] = [
+    4096, 4095, 4091, 4085, 4076, 4065, 4052, 4036, 4017, 3996, 3973, 3948, 3920, 3889, 3857, 3822,
+    3784, 3745, 3703, 3659, 3612, 3564, 3513, 3461, 3406, 3349, 3290, 3229, 3166, 3102, 3035, 2967,
+    2896, 2824, 2751, 2675, 2598, 2520, 2440, 2359, 2276, 2191, 2106, 2019, 1931, 1842, 1751, 1660,
+    1567, 1474, 1380, 1285, 1189, 1092, 995, 897, 799, 700, 601, 501, 401, 301, 201, 101, 0,
+];
+
+pub const SINPI_1_9: i64 = 1321;
+pub const SINPI_2_9: i64 = 2482;
+pub const SINPI_3_9: i64 = 3344;
+pub const SINPI_4_9: i64 = 3803;
+
+pub const TRANSFORM_ROW_SHIFT: [u8; TX_SIZES_ALL] = [0, 1, 2, 2, 2, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2];
+
+// 7.12.2 Dequantization functions, indexed by (BitDepth - 8) >> 1 then qindex
+pub const DC_QLOOKUP: [[i32; 256]; 3] = [
+    [
+        4, 8, 8, 9, 10, 11, 12, 12, 13, 14, 15, 16, 17, 18, 19, 19,
+        20, 21, 22, 23, 24, 25, 26, 26, 27, 28, 29, 30, 31, 32, 32, 33,
+        34, 35, 36, 37, 38, 38, 39, 40, 41, 42, 43, 43, 44, 45, 46, 47,
+        48, 48, 49, 50, 51, 52, 53, 53, 54, 55, 56, 57, 57, 58, 59, 60,
+        61, 62, 62, 63, 64, 65, 66, 66, 67, 68, 69, 70, 70, 71, 72, 73,
+        74, 74, 75, 76, 77, 78, 78, 79, 80, 81, 81, 82, 83, 84, 85, 85,
+        87, 88, 90, 92, 93, 95, 96, 98, 99, 101, 102, 104, 105, 107, 108, 110,
+        111, 113, 114, 116, 117, 118, 120, 121, 123, 125, 127, 129, 131, 134, 136, 138,
+        140, 142, 144, 146, 148, 150, 152, 154, 156, 158, 161, 164, 166, 169, 172, 174,
+        177, 180, 182, 185, 187, 190, 192, 195, 199, 202, 205, 208, 211, 214, 217, 220,
+        223, 226, 230, 233, 237, 240, 243, 247, 250, 253, 257, 261, 265, 269, 272, 276,
+        280, 284, 288, 292, 296, 300, 304, 309, 313, 317, 322, 326, 330, 335, 340, 344,
+        349, 354, 359, 364, 369, 374, 379, 384, 389, 395, 400, 406, 411, 417, 423, 429,
+        435, 441, 447, 454, 461, 467, 475, 482, 489, 497, 505, 513, 522, 530, 539, 549,
+        559, 569, 579, 590, 602, 614, 626, 640, 654, 668, 684, 700, 717, 736, 755, 775,
+        796, 819, 843, 869, 896, 925, 955, 988, 1022, 1058, 1098, 1139, 1184, 1232, 1282, 1336,
+    ],
+    [
+        4, 9, 10, 13, 15, 17, 20, 22, 25, 28, 31, 34, 37, 40, 43, 47,
+        50, 53, 57, 60, 64, 68, 71, 75, 78, 82, 86, 90, 93, 97, 101, 105,
+        109, 113, 116, 120, 124, 128, 132, 136, 140, 143, 147, 151, 155, 159, 163, 166,
+        170, 174, 178, 182, 185, 189, 193, 197, 200, 204, 208, 212, 215, 219, 223, 226,
+        230, 233, 237, 241, 244, 248, 251, 255, 259, 262, 266, 269, 273, 276, 280, 283,
+        287, 290, 293, 297, 300, 304, 307, 310, 314, 317, 321, 324, 327, 331, 334, 337,
+        343, 350, 356, 362, 369, 375, 381, 387, 394, 400, 406, 412, 418, 424, 430, 436,
+        442, 448, 454, 460, 466, 472, 478, 484, 490, 499, 507, 516, 525, 533, 542, 550,
+        559, 567, 576, 584, 592, 601, 609, 617, 625, 634, 644, 655, 666, 676, 687, 698,
+        708, 718, 729, 739, 749, 759, 770, 782, 795, 807, 819, 831, 844, 856, 868, 880,
+        891, 906, 920, 933, 947, 961, 975, 988, 1001, 1015, 1030, 1045, 1061, 1076, 1090, 1105,
+        1120, 1137, 1153, 1170, 1186, 1202, 1218, 1236, 1253, 1271, 1288, 1306, 1323, 1342, 1361, 1379,
+        1398, 1416, 1436, 1456, 1476, 1496, 1516, 1537, 1559, 1580, 1601, 1624, 1647, 1670, 1692, 1717,
+        1741, 1766, 1791, 1817, 1844, 1871, 1900, 1929, 1958, 1990, 2021, 2054, 2088, 2123, 2159, 2197,
+        2236, 2276, 2319, 2363, 2410, 2458, 2508, 2561, 2616, 2675, 2737, 2802, 2871, 2944, 3020, 3102,
+        3188, 3280, 3375, 3478, 3586, 3702, 3823, 3953, 4089, 4236, 4394, 4559, 4737, 4929, 5130, 5347,
+    ],
+    [
+        4, 12, 18, 25, 33, 41, 50, 60, 70, 80, 91, 103, 115, 127, 140, 153,
+        166, 180, 194, 208, 222, 237, 251, 266, 281, 296, 312, 327, 343, 358, 374, 390,
+        405, 421, 437, 453, 469, 484, 500, 516, 532, 548, 564, 580, 596, 611, 627, 643,
+        659, 674, 690, 706, 721, 737, 752, 768, 783, 798, 814, 829, 844, 859, 874, 889,
+        904, 919, 934, 949, 964, 978, 993, 1008, 1022, 1037, 1051, 1065, 1080, 1094, 1108, 1122,
+        1136, 1151, 1165, 1179, 1192, 1206, 1220, 1234, 1248, 1261, 1275, 1288, 1302, 1315, 1329, 1342,
+        1368, 1393, 1419, 1444, 1469, 1494, 1519, 1544, 1569, 1594, 1618, 1643, 1668, 1692, 1717, 1741,
+        1765, 1789, 1814, 1838, 1862, 1885, 1909, 1933, 1957, 1992, 2027, 2061, 2096, 2130, 2165, 2199,
+        2233, 2267, 2300, 2334, 2367, 2400, 2434, 2467, 2499, 2532, 2575, 2618, 2661, 2704, 2746, 2788,
+        2830, 2872, 2913, 2954, 2995, 3036, 3076, 3127, 3177, 3226, 3275, 3324, 3373, 3421, 3469, 3517,
+        3565, 3621, 3677, 3733, 3788, 3843, 3897, 3951, 4005, 4058, 4119, 4181, 4241, 4301, 4361, 4420,
+        4479, 4546, 4612, 4677, 4742, 4807, 4871, 4942, 5013, 5083, 5153, 5222, 5291, 5367, 5442, 5517,
+        5591, 5665, 5745, 5825, 5905, 5984, 6063, 6149, 6234, 6319, 6404, 6495, 6587, 6678, 6769, 6867,
+        6966, 7064, 7163, 7269, 7376, 7483, 7599, 7715, 7832, 7958, 8085, 8214, 8352, 8492, 8635, 8788,
+        8945, 9104, 9275, 9450, 9639, 9832, 10031, 10245, 10465, 10702, 10946, 11210, 11482, 11776, 12081, 12409,
+        12750, 13118, 13501, 13913, 14343, 14807, 15290, 15812, 16356, 16943, 17575, 18237, 18949, 19718, 20521, 21387,
+    ],
+];
+
+pub const AC_QLOOKUP: [[i32; 256]; 3] = [
+    [
+        4, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22,
+        23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38,
+        39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54,
+        55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70,
+        71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86,
+        87, 88, 89, 90, 91, 92, 93, 94, 95, 96, 97, 98, 99, 100, 101, 102,
+        104, 106, 108, 110, 112, 114, 116, 118, 120, 122, 124, 126, 128, 130, 132, 134,
+        136, 138, 140, 142, 144, 146, 148, 150, 152, 155, 158, 161, 164, 167, 170, 173,
+        176, 179, 182, 185, 188, 191, 194, 197, 200, 203, 207, 211, 215, 219, 223, 227,
+        231, 235, 239, 243, 247, 251, 255, 260, 265, 270, 275, 280, 285, 290, 295, 300,
+        305, 311, 317, 323, 329, 335, 341, 347, 353, 359, 366, 373, 380, 387, 394, 401,
+        408, 416, 424, 432, 440, 448, 456, 465, 474, 483, 492, 501, 510, 520, 530, 540,
+        550, 560, 571, 582, 593, 604, 615, 627, 639, 651, 663, 676, 689, 702, 715, 729,
+        743, 757, 771, 786, 801, 816, 832, 848, 864, 881, 898, 915, 933, 951, 969, 988,
+        1007, 1026, 1046, 1066, 1087, 1108, 1129, 1151, 1173, 1196, 1219, 1243, 1267, 1292, 1317, 1343,
+        1369, 1396, 1423, 1451, 1479, 1508, 1537, 1567, 1597, 1628, 1660, 1692, 1725, 1759, 1793, 1828,
+    ],
+    [
+        4, 9, 11, 13, 16, 18, 21, 24, 27, 30, 33, 37, 40, 44, 48, 51,
+        55, 59, 63, 67, 71, 75, 79, 83, 88, 92, 96, 100, 105, 109, 114, 118,
+        122, 127, 131, 136, 140, 145, 149, 154, 158, 163, 168, 172, 177, 181, 186, 190,
+        195, 199, 204, 208, 213, 217, 222, 226, 231, 235, 240, 244, 249, 253, 258, 262,
+        267, 271, 275, 280, 284, 289, 293, 297, 302, 306, 311, 315, 319, 324, 328, 332,
+        337, 341, 345, 349, 354, 358, 362, 367, 371, 375, 379, 384, 388, 392, 396, 401,
+        409, 417, 425, 433, 441, 449, 458, 466, 474, 482, 490, 498, 506, 514, 523, 531,
+        539, 547, 555, 563, 571, 579, 588, 596, 604, 616, 628, 640, 652, 664, 676, 688,
+        700, 713, 725, 737, 749, 761, 773, 785, 797, 809, 825, 841, 857, 873, 889, 905,
+        922, 938, 954, 970, 986, 1002, 1018, 1038, 1058, 1078, 1098, 1118, 1138, 1158, 1178, 1198,
+        1218, 1242, 1266, 1290, 1314, 1338, 1362, 1386, 1411, 1435, 1463, 1491, 1519, 1547, 1575, 1603,
+        1631, 1663, 1695, 1727, 1759, 1791, 1823, 1859, 1895, 1931, 1967, 2003, 2039, 2079, 2119, 2159,
+        2199, 2239, 2283, 2327, 2371, 2415, 2459, 2507, 2555, 2603, 2651, 2703, 2755, 2807, 2859, 2915,
+        2971, 3027, 3083, 3143, 3203, 3263, 3327, 3391, 3455, 3523, 3591, 3659, 3731, 3803, 3876, 3952,
+        4028, 4104, 4184, 4264, 4348, 4432, 4516, 4604, 4692, 4784, 4876, 4972, 5068, 5168, 5268, 5372,
+        5476, 5584, 5692, 5804, 5916, 6032, 6148, 6268, 6388, 6512, 6640, 6768, 6900, 7036, 7172, 7312,
+    ],
+    [
+        4, 13, 19, 27, 35, 44, 54, 64, 75, 87, 99, 112, 126, 139, 154, 168,
+        183, 199, 214, 230, 247, 263, 280, 297, 314, 331, 349, 366, 384, 402, 420, 438,
+        456, 475, 493, 511, 530, 548, 567, 586, 604, 623, 642, 660, 679, 698, 716, 735,
+        753, 772, 791, 809, 828, 846, 865, 884, 902, 920, 939, 957, 976, 994, 1012, 1030,
+        1049, 1067, 1085, 1103, 1121, 1139, 1157, 1175, 1193, 1211, 1229, 1246, 1264, 1282, 1299, 1317,
+        1335, 1352, 1370, 1387, 1405, 1422, 1440, 1457, 1474, 1491, 1509, 1526, 1543, 1560, 1577, 1595,
+        1627, 1660, 1693, 1725, 1758, 1791, 1824, 1856, 1889, 1922, 1954, 1987, 2020, 2052, 2085, 2118,
+        2150, 2183, 2216, 2248, 2281, 2313, 2346, 2378, 2411, 2459, 2508, 2556, 2605, 2653, 2701, 2750,
+        2798, 2847, 2895, 2943, 2992, 3040, 3088, 3137, 3185, 3234, 3298, 3362, 3426, 3491, 3555, 3619,
+        3684, 3748, 3812, 3876, 3941, 4005, 4069, 4149, 4230, 4310, 4390, 4470, 4550, 4631, 4711, 4791,
+        4871, 4967, 5064, 5160, 5256, 5352, 5448, 5544, 5641, 5737, 5849, 5961, 6073, 6185, 6297, 6410,
+        6522, 6650, 6778, 6906, 7034, 7162, 7290, 7435, 7579, 7723, 7867, 8011, 8155, 8315, 8475, 8635,
+        8795, 8956, 9132, 9308, 9484, 9660, 9836, 10028, 10220, 10412, 10604, 10812, 11020, 11228, 11437, 11661,
+        11885, 12109, 12333, 12573, 12813, 13053, 13309, 13565, 13821, 14093, 14365, 14637, 14925, 15213, 15502, 15806,
+        16110, 16414, 16734, 17054, 17390, 17726, 18062, 18414, 18766, 19134, 19502, 19886, 20270, 20670, 21070, 21486,
+        21902, 22334, 22766, 23214, 23662, 24126, 24590, 25070, 25551, 26047, 26559, 27071, 27599, 28143, 28687, 29247,
+    ],
+];
+
+
+// 7.11.2.4 Directional intra prediction process, indexed by the angle below 90 degrees
+pub const DR_INTRA_DERIVATIVE: [i32; 90] = [
+    0, 0, 0, 1023, 0, 0, 547, 0, 0, 372, 0, 0, 0, 0, 273,
+    0, 0, 215, 0, 0, 178, 0, 0, 151, 0, 0, 132, 0, 0, 116,
+    0, 0, 102, 0, 0, 0, 90, 0, 0, 80, 0, 0, 71, 0, 0,
+    64, 0, 0, 57, 0, 0, 51, 0, 0, 45, 0, 0, 0, 40, 0,
+    0, 35, 0, 0, 31, 0, 0, 27, 0, 0, 23, 0, 0, 19, 0,
+    0, 15, 0, 0, 0, 0, 11, 0, 0, 7, 0, 0, 3, 0, 0,
+];
+
+pub const MODE_TO_ANGLE: [i32; INTRA_MODES] = [0, 90, 180, 45, 135, 113, 157, 203, 67, 0, 0, 0, 0];
+
+// 7.11.2.12 Intra edge filter process
+pub const INTRA_EDGE_KERNEL: [[i32; INTRA_EDGE_TAPS as usize]; INTRA_EDGE_KERNELS as usize] = [
+    [0, 4, 8, 4, 0],
+    [0, 5, 6, 5, 0],
+    [2, 4, 4, 4, 2],
+];
+
+// 7.11.2.6 Smooth intra prediction process
+pub const SM_WEIGHTS_TX_4X4: [u32; 4] = [255, 149, 85, 64];
+pub const SM_WEIGHTS_TX_8X8: [u32; 8] = [255, 197, 146, 105, 73, 50, 37, 32];
+pub const SM_WEIGHTS_TX_16X16: [u32; 16] = [255, 225, 196, 170, 145, 123, 102, 84, 68, 54, 43, 33, 26, 20, 17, 16];
+pub const SM_WEIGHTS_TX_32X32: [u32; 32] = [
+    255, 240, 225, 210, 196, 182, 169, 157, 145, 133, 122, 111, 101, 92, 83, 74,
+    66, 59, 52, 45, 39, 34, 29, 25, 21, 17, 14, 12, 10, 9, 8, 8,
+];
+pub const SM_WEIGHTS_TX_64X64: [u32; 64] = [
+    255, 248, 240, 233, 225, 218, 210, 203, 196, 189, 182, 176, 169, 163, 156, 150,
+    144, 138, 133, 127, 121, 116, 111, 106, 101, 96, 91, 86, 82, 77, 73, 69,
+    65, 61, 57, 54, 50, 47, 44, 41, 38, 35, 32, 29, 27, 25, 22, 20,
+    18, 16, 15, 13, 12, 10, 9, 8, 7, 6, 6, 5, 5, 4, 4, 4,
+];
+
+// 7.11.2.3 Recursive intra prediction process
+pub const INTRA_FILTER_TAPS: [[[i32; 7]; 8]; INTRA_FILTER_MODES] = [
+    [
+        [-6, 10, 0, 0, 0, 12, 0],
+        [-5, 2, 10, 0, 0, 9, 0],
+        [-3, 1, 1, 10, 0, 7, 0],
+        [-3, 1, 1, 2, 10, 5, 0],
+        [-4, 6, 0, 0, 0, 2, 12],
+        [-3, 2, 6, 0, 0, 2, 9],
+        [-3, 2, 2, 6, 0, 2, 7],
+        [-3, 1, 2, 2, 6, 3, 5],
+    ],
+    [
+        [-10, 16, 0, 0, 0, 10, 0],
+        [-6, 0, 16, 0, 0, 6, 0],
+        [-4, 0, 0, 16, 0, 4, 0],
+        [-2, 0, 0, 0, 16, 2, 0],
+        [-10, 16, 0, 0, 0, 0, 10],
+        [-6, 0, 16, 0, 0, 0, 6],
+        [-4, 0, 0, 16, 0, 0, 4],
+        [-2, 0, 0, 0, 16, 0, 2],
+    ],
+    [
+        [-8, 8, 0, 0, 0, 16, 0],
+        [-8, 0, 8, 0, 0, 16, 0],
+        [-8, 0, 0, 8, 0, 16, 0],
+        [-8, 0, 0, 0, 8, 16, 0],
+        [-4, 4, 0, 0, 0, 0, 16],
+        [-4, 0, 4, 0, 0, 0, 16],
+        [-4, 0, 0, 4, 0, 0, 16],
+        [-4, 0, 0, 0, 4, 0, 16],
+    ],
+    [
+        [-2, 8, 0, 0, 0, 10, 0],
+        [-1, 3, 8, 0, 0, 6, 0],
+        [-1, 2, 3, 8, 0, 4, 0],
+        [0, 1, 2, 3, 8, 2, 0],
+        [-1, 4, 0, 0, 0, 3, 10],
+        [-1, 3, 4, 0, 0, 4, 6],
+        [-1, 2, 3, 4, 0, 4, 4],
+        [-1, 2, 2, 3, 4, 3, 3],
+    ],
+    [
+        [-12, 14, 0, 0, 0, 14, 0],
+        [-10, 0, 14, 0, 0, 12, 0],
+        [-9, 0, 0, 14, 0, 11, 0],
+        [-8, 0, 0, 0, 14, 10, 0],
+        [-10, 12, 0, 0, 0, 0, 14],
+        [-9, 1, 12, 0, 0, 0, 12],
+        [-8, 0, 0, 12, 0, 1, 11],
+        [-7, 0, 0, 1, 12, 1, 9],
+    ],
+];
+
+// 7.11.4 get_palette_color_context
+pub const PALETTE_COLOR_HASH_MULTIPLIERS: [u8; PALETTE_NUM_NEIGHBORS as usize] = [1, 2, 2];
+
+// -1 的 hash 不会出现
+pub const PALETTE_COLOR_CONTEXT: [i8; PALETTE_MAX_COLOR_CONTEXT_HASH as usize + 1] = [-1, -1, 0, -1, -1, 4, 3, 2, 1];
+
+// 5.11.58 Read loop restoration unit syntax
+pub const SGR_PARAMS: [[i32; 4]; 1 << SGRPROJ_PARAMS_BITS] = [
+    [2, 140, 1, 3236], [2, 112, 1, 2158], [2, 93, 1, 1618], [2, 80, 1, 1438],
+    [2, 70, 1, 1295], [2, 58, 1, 1177], [2, 47, 1, 1079], [2, 37, 1, 996],
+    [2, 30, 1, 925], [2, 25, 1, 863], [0, -1, 2, 2589], [0, -1, 2, 1618],
+    [0, -1, 2, 1177], [0, -1, 2, 925], [2, 56, 0, -1], [2, 22, 0, -1],
+];
+pub const WIENER_TAPS_MIN: [i32; WIENER_COEFFS as usize] = [-5, -23, -17];
+pub const WIENER_TAPS_MID: [i32; WIENER_COEFFS as usize] = [3, -7, 15];
+pub const WIENER_TAPS_MAX: [i32; WIENER_COEFFS as usize] = [10, 8, 46];
+pub const WIENER_TAPS_K: [u8; WIENER_COEFFS as usize] = [1, 2, 3];
+pub const SGRPROJ_XQD_MIN: [i32; 2] = [-96, -32];
+pub const SGRPROJ_XQD_MID: [i32; 2] = [-32, 31];
+pub const SGRPROJ_XQD_MAX: [i32; 2] = [31, 95];
+
 // 9.4. Default CDF tables
 pub const DEFAULT_INTRA_FRAME_Y_MODE_CDF: [[[u32; INTRA_MODES + 1]; INTRA_MODE_CONTEXTS]; INTRA_MODE_CONTEXTS] = [
     [
@@ -1013,14 +1403,14 @@ pub const DEFAULT_MV_CLASS_CDF: [[u32; MV_CLASSES + 1]; 2] = [
     32762, 32767, 32768, 0 ]
 ];
 
-pub const DEFAULT_MV_CLASS0_FR_CDF: [[[u32; MV_JOINTS + 1]; CLASS0_SIZE]; 2] = [
+pub const DEFAULT_MV_CLASS0_FR_CDF: [[[u32; MV_FR_SIZE + 1]; CLASS0_SIZE]; 2] = [
   [ [ 16384, 24576, 26624, 32768, 0 ],
     [ 12288, 21248, 24128, 32768, 0 ] ],
   [ [ 16384, 24576, 26624, 32768, 0 ],
     [ 12288, 21248, 24128, 32768, 0 ] ],
 ];
 
-pub const DEFAULT_MV_FR_CDF: [[u32; MV_JOINTS + 1]; 2] = [
+pub const DEFAULT_MV_FR_CDF: [[u32; MV_FR_SIZE + 1]; 2] = [
   [ 8192, 17408, 21248, 32768, 0 ],
   [ 8192, 17408, 21248, 32768, 0 ],
 ];
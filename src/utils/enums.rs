@@ -1,10 +1,19 @@
 
-use crate::utils::bits::{BitsReader, FromBitsReader};
+use crate::{
+    error::{Error, Result},
+    utils::{
+        bits::{BitsReader, FromBitsReader},
+        consts::{
+            TX_CLASS_2D, TX_CLASS_HORIZ, TX_CLASS_VERT, TX_SET_DCTONLY, TX_SET_INTER_1, TX_SET_INTER_2, TX_SET_INTER_3, TX_SET_INTRA_1, TX_SET_INTRA_2,
+            TX_SIZE_SQR, TX_SIZE_SQR_UP, TX_TYPES, TX_TYPE_IN_SET_INTER, TX_TYPE_IN_SET_INTRA,
+        },
+    },
+};
 
 
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ObuType {
     SequenceHeader = 1,
     TemporalDelimiter = 2,
@@ -56,8 +65,9 @@ impl From<u8> for ObuType {
 
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum FrameType {
+    #[default]
     Key = 0,
     Inter = 1,
     Intra = 2,
@@ -81,7 +91,7 @@ impl From<u8> for FrameType {
 
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ScalabilityModeIdc {
     L1T2 = 0,
     L1T3 = 1,
@@ -155,7 +165,33 @@ impl From<u8> for ScalabilityModeIdc {
 
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MetadataType {
+    HdrCll = 1,
+    HdrMdcv = 2,
+    Scalability = 3,
+    ItutT35 = 4,
+    Timecode = 5,
+    Unknown,
+}
+
+impl From<u8> for MetadataType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::HdrCll,
+            2 => Self::HdrMdcv,
+            3 => Self::Scalability,
+            4 => Self::ItutT35,
+            5 => Self::Timecode,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+
+
+#[repr(u8)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ColorPrimaries {
     // BT.709
     Bt709 = 1,
@@ -217,7 +253,7 @@ impl From<u8> for ColorPrimaries {
 }
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TransferCharacteristics {
     // For future use
     Reserved0 = 0,
@@ -308,7 +344,7 @@ impl From<u8> for TransferCharacteristics {
 
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum MatrixCoefficients {
     // Identity matrix
     Identity = 0,
@@ -383,7 +419,7 @@ impl From<u8> for MatrixCoefficients {
 
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ChromaSamplePosition {
     // Unknown (in this case the source video transfer function must be signaled outside the AV1 bitstream)
     Unknown = 0,
@@ -413,8 +449,9 @@ impl From<u8> for ChromaSamplePosition {
 
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum FrameRestorationType {
+    #[default]
     None = 0,
     Wiener = 1,
     Sgrproj = 2,
@@ -454,8 +491,9 @@ impl FrameRestorationType {
 
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum Interpolationfilter {
+    #[default]
     EightTap = 0,
     EightTapSmooth = 1,
     EIGHTTAPSharp = 2,
@@ -479,12 +517,38 @@ impl From<u8> for Interpolationfilter {
 }
 
 impl FromBitsReader for Interpolationfilter {
-    fn from_bits_reader(reader: &mut BitsReader) -> Self {
-        let is_filter_switchable = reader.read_bit();
+    fn from_bits_reader(reader: &mut BitsReader) -> Result<Self> {
+        let is_filter_switchable = reader.read_bit()?;
         if is_filter_switchable {
-            Self::Switchable
+            Ok(Self::Switchable)
         } else {
-            reader.read_u8(2).into()
+            Ok(reader.read_u8(2)?.into())
+        }
+    }
+}
+
+
+/// 6.8.21 TX mode semantics
+#[repr(u8)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum TxMode {
+    // Inverse transform will use only 4x4 transforms
+    #[default]
+    Only4X4 = 0,
+    // Inverse transform will use the largest transform size that fits inside the block
+    Largest = 1,
+    // Choice of transform size is specified explicitly for each block
+    Select = 2,
+    Unknown,
+}
+
+impl From<u8> for TxMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Only4X4,
+            1 => Self::Largest,
+            2 => Self::Select,
+            _ => Self::Unknown,
         }
     }
 }
@@ -492,7 +556,7 @@ impl FromBitsReader for Interpolationfilter {
 
 /// 6.10.4 Decode partition semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Partition {
     None = 0,
     Horz = 1,
@@ -528,8 +592,9 @@ impl From<u8> for Partition {
 
 /// 6.10.4 Decode partition semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum SubSize {
+    #[default]
     Block4X4 = 0,
     Block4X8 = 1,
     Block8X4 = 2,
@@ -589,8 +654,9 @@ impl From<u8> for SubSize {
 
 /// 6.10.6 Intra frame mode info semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum IntraFrameYMode {
+    #[default]
     Dc = 0,
     V = 1,
     H = 2,
@@ -633,8 +699,9 @@ impl From<u8> for IntraFrameYMode {
 
 /// 6.10.6 Intra frame mode info semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum UVMode {
+    #[default]
     Dc = 0,
     V = 1,
     H = 2,
@@ -678,8 +745,9 @@ impl From<u8> for UVMode {
 
 /// 6.10.16 TX size semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum TxSize {
+    #[default]
     Tx4X4 = 0,
     Tx8X8 = 1,
     Tx16X16 = 2,
@@ -729,13 +797,116 @@ impl From<u8> for TxSize {
     }
 }
 
+impl TxSize {
+    /// get_tx_set, the set of transform types that can be signalled for this size
+    pub fn tx_set(self, is_inter: bool, reduced_tx_set: bool) -> u8 {
+        let tx_sz_sqr = TX_SIZE_SQR[self as usize];
+        let tx_sz_sqr_up = TX_SIZE_SQR_UP[self as usize];
+        if tx_sz_sqr_up > Self::Tx32X32 as u8 {
+            return TX_SET_DCTONLY;
+        }
+        if is_inter {
+            if reduced_tx_set || tx_sz_sqr_up == Self::Tx32X32 as u8 {
+                TX_SET_INTER_3
+            } else if tx_sz_sqr == Self::Tx16X16 as u8 {
+                TX_SET_INTER_2
+            } else {
+                TX_SET_INTER_1
+            }
+        } else if tx_sz_sqr_up == Self::Tx32X32 as u8 {
+            TX_SET_DCTONLY
+        } else if reduced_tx_set || tx_sz_sqr == Self::Tx16X16 as u8 {
+            TX_SET_INTRA_2
+        } else {
+            TX_SET_INTRA_1
+        }
+    }
+}
+
+
+
+/// 6.10.19 Transform type semantics, the first name is the column (vertical) transform.
+/// WhtWht is not coded, it is the transform of every block in a lossless segment
+#[repr(u8)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum TxType {
+    #[default]
+    DctDct = 0,
+    AdstDct = 1,
+    DctAdst = 2,
+    AdstAdst = 3,
+    FlipadstDct = 4,
+    DctFlipadst = 5,
+    FlipadstFlipadst = 6,
+    AdstFlipadst = 7,
+    FlipadstAdst = 8,
+    Idtx = 9,
+    VDct = 10,
+    HDct = 11,
+    VAdst = 12,
+    HAdst = 13,
+    VFlipadst = 14,
+    HFlipadst = 15,
+    WhtWht = 16,
+    Unknown,
+}
+
+impl From<u8> for TxType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::DctDct,
+            1 => Self::AdstDct,
+            2 => Self::DctAdst,
+            3 => Self::AdstAdst,
+            4 => Self::FlipadstDct,
+            5 => Self::DctFlipadst,
+            6 => Self::FlipadstFlipadst,
+            7 => Self::AdstFlipadst,
+            8 => Self::FlipadstAdst,
+            9 => Self::Idtx,
+            10 => Self::VDct,
+            11 => Self::HDct,
+            12 => Self::VAdst,
+            13 => Self::HAdst,
+            14 => Self::VFlipadst,
+            15 => Self::HFlipadst,
+            16 => Self::WhtWht,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl TxType {
+    /// get_tx_class
+    pub fn tx_class(self) -> u8 {
+        match self {
+            Self::VDct | Self::VAdst | Self::VFlipadst => TX_CLASS_VERT,
+            Self::HDct | Self::HAdst | Self::HFlipadst => TX_CLASS_HORIZ,
+            _ => TX_CLASS_2D,
+        }
+    }
+
+    /// is_tx_type_in_set
+    pub fn is_in_set(self, tx_set: u8, is_inter: bool) -> bool {
+        if self as u8 >= TX_TYPES {
+            return false;
+        }
+        if is_inter {
+            TX_TYPE_IN_SET_INTER[tx_set as usize][self as usize]
+        } else {
+            TX_TYPE_IN_SET_INTRA[tx_set as usize][self as usize]
+        }
+    }
+}
+
 
 
 
 /// 6.10.23 Filter intra mode info semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum FilterIntraMode {
+    #[default]
     Dc = 0,
     V = 1,
     H = 2,
@@ -758,9 +929,81 @@ impl From<u8> for FilterIntraMode {
 }
 
 
+/// 6.10.22 Inter block mode info semantics, YMode of inter blocks
+#[repr(u8)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum InterMode {
+    #[default]
+    NearestMv = 13,
+    NearMv = 14,
+    GlobalMv = 15,
+    NewMv = 16,
+    NearestNearestMv = 17,
+    NearNearMv = 18,
+    NearestNewMv = 19,
+    NewNearestMv = 20,
+    NearNewMv = 21,
+    NewNearMv = 22,
+    GlobalGlobalMv = 23,
+    NewNewMv = 24,
+    Unknown,
+}
+
+impl From<u8> for InterMode {
+    fn from(value: u8) -> Self {
+        match value {
+            13 => Self::NearestMv,
+            14 => Self::NearMv,
+            15 => Self::GlobalMv,
+            16 => Self::NewMv,
+            17 => Self::NearestNearestMv,
+            18 => Self::NearNearMv,
+            19 => Self::NearestNewMv,
+            20 => Self::NewNearestMv,
+            21 => Self::NearNewMv,
+            22 => Self::NewNearMv,
+            23 => Self::GlobalGlobalMv,
+            24 => Self::NewNewMv,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl InterMode {
+    /// get_mode, the single reference mode used for the given reference list
+    pub fn get_mode(&self, ref_list: usize) -> Self {
+        if (*self as u8) < Self::NearestNearestMv as u8 {
+            return *self;
+        }
+        let (first, second) = match self {
+            Self::NearestNearestMv => (Self::NearestMv, Self::NearestMv),
+            Self::NearNearMv => (Self::NearMv, Self::NearMv),
+            Self::NearestNewMv => (Self::NearestMv, Self::NewMv),
+            Self::NewNearestMv => (Self::NewMv, Self::NearestMv),
+            Self::NearNewMv => (Self::NearMv, Self::NewMv),
+            Self::NewNearMv => (Self::NewMv, Self::NearMv),
+            Self::NewNewMv => (Self::NewMv, Self::NewMv),
+            _ => (Self::GlobalMv, Self::GlobalMv),
+        };
+        if ref_list == 0 { first } else { second }
+    }
+
+    /// has_nearmv
+    pub fn has_nearmv(&self) -> bool {
+        matches!(self, Self::NearMv | Self::NearNearMv | Self::NearNewMv | Self::NewNearMv)
+    }
+
+    /// has_newmv
+    pub fn has_newmv(&self) -> bool {
+        matches!(self, Self::NewMv | Self::NewNewMv | Self::NearestNewMv | Self::NewNearestMv | Self::NearNewMv | Self::NewNearMv)
+    }
+}
+
+
+
 /// 6.10.24 Ref frames semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum CompMode {
     Single = 0,
     Compound = 1,
@@ -781,7 +1024,7 @@ impl From<u8> for CompMode {
 
 /// 6.10.24 Ref frames semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum CompRefType {
     // Both reference frames from the same group
     Unidir = 0,
@@ -804,7 +1047,7 @@ impl From<u8> for CompRefType {
 
 /// 6.10.24 Ref frames semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum RefFrame {
     Intra = 0,
     Last = 1,
@@ -837,8 +1080,9 @@ impl From<u8> for RefFrame {
 
 /// 6.10.26 Read motion mode semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum MotionMode {
+    #[default]
     Simple = 0,
     Obmc = 1,
     LocalWarp = 2,
@@ -860,8 +1104,9 @@ impl From<u8> for MotionMode {
 
 /// 6.10.27 Read inter intra semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum InterintraMode {
+    #[default]
     Dc = 0,
     V = 1,
     H = 2,
@@ -885,10 +1130,11 @@ impl From<u8> for InterintraMode {
 
 /// 6.10.28 Read compound type semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum CompoundType {
     Wedge = 0,
     Diffwtd = 1,
+    #[default]
     Average = 2,
     Intra = 3,
     Distance = 4,
@@ -912,8 +1158,9 @@ impl From<u8> for CompoundType {
 
 /// 6.10.28 Read compound type semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum MaskType {
+    #[default]
     Uniform45 = 0,
     Uniform45Inv = 1,
     Unknown,
@@ -934,13 +1181,14 @@ impl From<u8> for MaskType {
 
 /// 6.10.29 MV semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum MVJoint {
     Zero = 0,
     Hnzvz = 1,
     Hzvnz = 2,
     Hnzvnz = 3,
-    Unknown,
+    // 解码出的无效值
+    Unknown(u8),
 }
 
 impl From<u8> for MVJoint {
@@ -950,20 +1198,20 @@ impl From<u8> for MVJoint {
             1 => Self::Hnzvz,
             2 => Self::Hzvnz,
             3 => Self::Hnzvnz,
-            _ => Self::Unknown,
+            _ => Self::Unknown(value),
         }
     }
 }
 
 
 impl MVJoint {
-    pub fn changes_row_and_col(&self) -> (bool, bool) {
+    pub fn changes_row_and_col(&self) -> Result<(bool, bool)> {
         match self {
-            Self::Zero => (false, false),
-            Self::Hnzvz => (false, true),
-            Self::Hzvnz => (true, false),
-            Self::Hnzvnz => (true, true),
-            Self::Unknown => panic!("Unknown mv joint"),
+            Self::Zero => Ok((false, false)),
+            Self::Hnzvz => Ok((false, true)),
+            Self::Hzvnz => Ok((true, false)),
+            Self::Hnzvnz => Ok((true, true)),
+            Self::Unknown(value) => Err(Error::InvalidValue { name: "mv_joint", value: *value as u32 }),
         }
     }
 }
@@ -971,7 +1219,7 @@ impl MVJoint {
 
 /// 6.10.20 MV component semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum MVClass {
     Class0 = 0,
     Class1 = 1,
@@ -1012,7 +1260,7 @@ impl From<u8> for MVClass {
 
 /// 6.10.36 Read CFL alphas semantics
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum SignUV {
     Zero = 0,
     Neg= 1,
@@ -1033,17 +1281,17 @@ impl From<u8> for SignUV {
 
 
 impl SignUV {
-    pub fn from_cfl_alpha_signs(value: u8) -> (Self, Self) {
+    pub fn from_cfl_alpha_signs(value: u8) -> Result<(Self, Self)> {
         match value {
-            0 => (SignUV::Zero, SignUV::Neg),
-            1 => (SignUV::Zero, SignUV::Pos),
-            2 => (SignUV::Neg, SignUV::Zero),
-            3 => (SignUV::Neg, SignUV::Neg),
-            4 => (SignUV::Neg, SignUV::Pos),
-            5 => (SignUV::Pos, SignUV::Zero),
-            6 => (SignUV::Pos, SignUV::Neg),
-            7 => (SignUV::Pos, SignUV::Pos),
-            _ => panic!("Unknown SignUV"),
+            0 => Ok((SignUV::Zero, SignUV::Neg)),
+            1 => Ok((SignUV::Zero, SignUV::Pos)),
+            2 => Ok((SignUV::Neg, SignUV::Zero)),
+            3 => Ok((SignUV::Neg, SignUV::Neg)),
+            4 => Ok((SignUV::Neg, SignUV::Pos)),
+            5 => Ok((SignUV::Pos, SignUV::Zero)),
+            6 => Ok((SignUV::Pos, SignUV::Neg)),
+            7 => Ok((SignUV::Pos, SignUV::Pos)),
+            _ => Err(Error::InvalidValue { name: "cfl_alpha_signs", value: value as u32 }),
         }
     }
 }
@@ -40,3 +40,25 @@ pub fn clip3<T: PartialOrd>(x: T, y: T, z: T) -> T {
         z
     }
 }
+
+pub fn round2(x: i32, n: u8) -> i32 {
+    if n == 0 {
+        return x;
+    }
+    (x + (1 << (n - 1))) >> n
+}
+
+pub fn round2signed(x: i32, n: u8) -> i32 {
+    if x >= 0 { round2(x, n) } else { -round2(-x, n) }
+}
+
+/// inverse_recenter used by the subexp decoding of 5.9.27 and 5.11.58
+pub fn inverse_recenter(r: u32, v: u32) -> u32 {
+    if v > 2 * r {
+        v
+    } else if v & 1 == 1 {
+        r - ((v + 1) >> 1)
+    } else {
+        r + (v >> 1)
+    }
+}
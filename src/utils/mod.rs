@@ -3,4 +3,5 @@ pub mod bits;
 pub mod consts;
 pub mod enums;
 pub mod math;
+pub mod scan;
 pub mod funcs;
\ No newline at end of file
@@ -0,0 +1,83 @@
+// ! Scan orders of the coefficient syntax, Default_Scan_* / Mrow_Scan_* / Mcol_Scan_* in the spec
+//
+// 表中的值是 pos = row * w + col，这里按规律生成而不是逐个列出：
+// 方形按对角线来回扫描（4x4 的第一条对角线从上往下，更大的方形相反），
+// 竖长的块每条对角线从上往下，横长的块从下往上。
+
+
+
+const fn default_scan<const N: usize>(w: usize, h: usize) -> [u16; N] {
+    let mut scan = [0; N];
+    let mut i = 0;
+    let mut d = 0;
+    while d < w + h - 1 {
+        let r_min = if d >= w { d - w + 1 } else { 0 };
+        let r_max = if d < h { d } else { h - 1 };
+        let downwards = if w == h { d % 2 == (w == 4) as usize } else { w < h };
+        let mut k = 0;
+        while k <= r_max - r_min {
+            let r = if downwards { r_min + k } else { r_max - k };
+            scan[i] = (r * w + d - r) as u16;
+            i += 1;
+            k += 1;
+        }
+        d += 1;
+    }
+    scan
+}
+
+const fn mrow_scan<const N: usize>() -> [u16; N] {
+    let mut scan = [0; N];
+    let mut i = 0;
+    while i < N {
+        scan[i] = i as u16;
+        i += 1;
+    }
+    scan
+}
+
+const fn mcol_scan<const N: usize>(w: usize, h: usize) -> [u16; N] {
+    let mut scan = [0; N];
+    let mut i = 0;
+    while i < N {
+        scan[i] = ((i % h) * w + i / h) as u16;
+        i += 1;
+    }
+    scan
+}
+
+
+pub const DEFAULT_SCAN_4X4: [u16; 16] = default_scan(4, 4);
+pub const DEFAULT_SCAN_8X8: [u16; 64] = default_scan(8, 8);
+pub const DEFAULT_SCAN_16X16: [u16; 256] = default_scan(16, 16);
+pub const DEFAULT_SCAN_32X32: [u16; 1024] = default_scan(32, 32);
+pub const DEFAULT_SCAN_4X8: [u16; 32] = default_scan(4, 8);
+pub const DEFAULT_SCAN_8X4: [u16; 32] = default_scan(8, 4);
+pub const DEFAULT_SCAN_8X16: [u16; 128] = default_scan(8, 16);
+pub const DEFAULT_SCAN_16X8: [u16; 128] = default_scan(16, 8);
+pub const DEFAULT_SCAN_16X32: [u16; 512] = default_scan(16, 32);
+pub const DEFAULT_SCAN_32X16: [u16; 512] = default_scan(32, 16);
+pub const DEFAULT_SCAN_4X16: [u16; 64] = default_scan(4, 16);
+pub const DEFAULT_SCAN_16X4: [u16; 64] = default_scan(16, 4);
+pub const DEFAULT_SCAN_8X32: [u16; 256] = default_scan(8, 32);
+pub const DEFAULT_SCAN_32X8: [u16; 256] = default_scan(32, 8);
+
+pub const MROW_SCAN_4X4: [u16; 16] = mrow_scan();
+pub const MROW_SCAN_8X8: [u16; 64] = mrow_scan();
+pub const MROW_SCAN_16X16: [u16; 256] = mrow_scan();
+pub const MROW_SCAN_4X8: [u16; 32] = mrow_scan();
+pub const MROW_SCAN_8X4: [u16; 32] = mrow_scan();
+pub const MROW_SCAN_8X16: [u16; 128] = mrow_scan();
+pub const MROW_SCAN_16X8: [u16; 128] = mrow_scan();
+pub const MROW_SCAN_4X16: [u16; 64] = mrow_scan();
+pub const MROW_SCAN_16X4: [u16; 64] = mrow_scan();
+
+pub const MCOL_SCAN_4X4: [u16; 16] = mcol_scan(4, 4);
+pub const MCOL_SCAN_8X8: [u16; 64] = mcol_scan(8, 8);
+pub const MCOL_SCAN_16X16: [u16; 256] = mcol_scan(16, 16);
+pub const MCOL_SCAN_4X8: [u16; 32] = mcol_scan(4, 8);
+pub const MCOL_SCAN_8X4: [u16; 32] = mcol_scan(8, 4);
+pub const MCOL_SCAN_8X16: [u16; 128] = mcol_scan(8, 16);
+pub const MCOL_SCAN_16X8: [u16; 128] = mcol_scan(16, 8);
+pub const MCOL_SCAN_4X16: [u16; 64] = mcol_scan(4, 16);
+pub const MCOL_SCAN_16X4: [u16; 64] = mcol_scan(16, 4);
@@ -0,0 +1,78 @@
+mod common;
+
+use common::length_delimited;
+use wav1d::{container::{annexb::{read_obu, AnnexBReader}, BitstreamFormat}, utils::enums::ObuType, Error};
+
+
+
+// temporal delimiter, sequence header 和 frame OBU，都不带 obu_size
+fn temporal_unit(frame: &[u8]) -> Vec<u8> {
+    let td = length_delimited(&[vec![0x10]]);
+    let mut seq = vec![0x08];
+    seq.extend_from_slice(&[7, 7]);
+    let mut obu = vec![0x30];
+    obu.extend_from_slice(frame);
+    let fu = length_delimited(&[seq, obu]);
+    length_delimited(&[td, fu])
+}
+
+
+#[test]
+fn obu_size_field() {
+    let (h, p) = read_obu(&[0x32, 0x02, 1, 2]).unwrap();
+    assert_eq!((h.obu_type, h.obu_size, p), (ObuType::Frame, 2, [1u8, 2].as_slice()));
+    let (h, p) = read_obu(&[0x30, 1, 2, 9]).unwrap();
+    assert_eq!((h.obu_has_size_field, h.obu_size, p), (false, 3, [1u8, 2, 9].as_slice()));
+    assert!(matches!(read_obu(&[0x32, 0x04, 1, 2]), Err(Error::Conformance { section: "B.3", .. })));
+    // obu_size 必须正好等于 obu_length 减去头部
+    assert!(matches!(read_obu(&[0x32, 0x02, 1, 2, 9]), Err(Error::Conformance { section: "B.3", .. })));
+}
+
+
+#[test]
+fn temporal_units() {
+    let mut a = length_delimited(&[temporal_unit(&[1, 2, 3])]);
+    a.extend(length_delimited(&[temporal_unit(&[4; 200])]));
+    let mut reader = AnnexBReader::from(a.as_slice());
+
+    let tu = reader.next().unwrap().unwrap();
+    assert_eq!(reader.offset(), 0);
+    assert_eq!(tu.frame_units.len(), 2);
+    assert_eq!(tu.frame_units[0].obus.len(), 1);
+    assert_eq!(tu.frame_units[1].obus.len(), 2);
+    let types: Vec<_> = tu.obus().map(|(h, _)| h.obu_type).collect();
+    assert_eq!(types, [ObuType::TemporalDelimiter, ObuType::SequenceHeader, ObuType::Frame]);
+    assert_eq!(tu.frame_units[1].obus[1].1, [1u8, 2, 3].as_slice());
+
+    let tu = reader.next().unwrap().unwrap();
+    assert_eq!(reader.offset(), 14);
+    assert_eq!(tu.frame_units[1].obus[1].1.len(), 200);
+    assert!(reader.next().is_none());
+}
+
+
+#[test]
+fn invalid_sizes() {
+    let a = length_delimited(&[temporal_unit(&[1, 2, 3])]);
+    let mut reader = AnnexBReader::from(&a[..a.len() - 1]);
+    assert_eq!(reader.next().unwrap().err(), Some(Error::Truncated { needed: 104, available: 96 }));
+    assert!(reader.next().is_none());
+
+    // frame_unit_size 超出 temporal unit
+    let a = [3, 5, 1, 0x10];
+    let res = AnnexBReader::from(a.as_slice()).next().unwrap();
+    assert!(matches!(res, Err(Error::Conformance { reason: "frame_unit_size exceeds temporal_unit_size", .. })));
+}
+
+
+#[test]
+fn detect() {
+    let a = length_delimited(&[temporal_unit(&[1, 2, 3])]);
+    assert_eq!(BitstreamFormat::detect(&a), BitstreamFormat::AnnexB);
+    assert_eq!(BitstreamFormat::detect(&[0x12, 0x00, 0x32, 0x01, 0x00]), BitstreamFormat::Section5);
+    // temporal_unit_size 为 0x12 时第一个字节也像带 obu_size 的时间分隔符
+    let a = length_delimited(&[temporal_unit(&[0; 8])]);
+    assert_eq!(a[..2], [0x12, 0x02]);
+    assert_eq!(BitstreamFormat::detect(&a), BitstreamFormat::AnnexB);
+    assert_eq!(BitstreamFormat::detect(&[]), BitstreamFormat::Section5);
+}
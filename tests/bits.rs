@@ -1,4 +1,4 @@
-use wav1d::utils::bits::BitsReader;
+use wav1d::{error::Error, utils::bits::BitsReader};
 
 
 
@@ -8,7 +8,7 @@ fn onebyte1() {
     let r = BitsReader::from(a.as_slice());
     let br = 0b00111110u8;
 
-    let b = r.get_1byte(3, 8);
+    let b = r.get_1byte(3, 8).unwrap();
     assert_eq!(b, br, "{b:08b} {br:08b}");
 }
 
@@ -19,7 +19,7 @@ fn onebyte2() {
     let r = BitsReader::from(a.as_slice());
     let br = 0b00001111u8;
 
-    let b = r.get_1byte(3, 6);
+    let b = r.get_1byte(3, 6).unwrap();
     assert_eq!(b, br, "{b:08b} {br:08b}");
 }
 
@@ -30,7 +30,7 @@ fn onebyte3() {
     let r = BitsReader::from(a.as_slice());
     let br = 0b00000001u8;
 
-    let b = r.get_1byte(10, 3);
+    let b = r.get_1byte(10, 3).unwrap();
     assert_eq!(b, br, "{b:08b} {br:08b}");
 }
 
@@ -42,7 +42,7 @@ fn nbyte1() {
     let mut b = [0u8; 2];
     let br = [0b00111110u8, 0b00000000];
 
-    r.get_nbyte(3, 8, &mut b);
+    r.get_nbyte(3, 8, &mut b).unwrap();
     assert_eq!(b, br, "{:08b} {:08b}", b[0], br[0]);
 
     let res = u16::from_le_bytes(b);
@@ -50,6 +50,29 @@ fn nbyte1() {
 }
 
 
+#[test]
+fn get_out_of_range() {
+    let a = [0b10100111, 0b11001100];
+    let r = BitsReader::from(a.as_slice());
+    assert_eq!(r.get_1byte(10, 6).unwrap(), 0b001100);
+    assert!(matches!(r.get_1byte(10, 7), Err(Error::Truncated { needed: 7, available: 6 })));
+    assert!(matches!(r.get_1byte(20, 1), Err(Error::Truncated { needed: 1, available: 0 })));
+    assert!(r.get_1byte(0, 9).is_err());
+    let mut b = [0u8; 2];
+    assert!(matches!(r.get_nbyte(4, 13, &mut b), Err(Error::Truncated { needed: 13, available: 12 })));
+    assert!(r.get_nbyte(0, 16, &mut [0u8; 1]).is_err());
+}
+
+#[test]
+fn signed_values() {
+    let a = [0b11100011, 0b00000000];
+    let mut r = BitsReader::from(a.as_slice());
+    assert_eq!(r.read_su(3).unwrap(), -1);
+    assert_eq!(r.read_su(5).unwrap(), 3);
+    assert_eq!(r.read_su(0), Err(Error::InvalidValue { name: "su(n) bit count", value: 0 }));
+}
+
+
 
 
 #[test]
@@ -58,6 +81,6 @@ fn bit1() {
     let mut r = BitsReader::from(a.as_slice());
     let t = [true, false, true, false, false, true, true, true, true, true, false, false, true, true, false, false];
     for (ii, i) in t.into_iter().enumerate() {
-        assert_eq!(i, r.read_bit(), "{ii}");
+        assert_eq!(i, r.read_bit().unwrap(), "{ii}");
     }
 }
\ No newline at end of file
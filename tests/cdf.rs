@@ -0,0 +1,52 @@
+use wav1d::{entropy::{cdf::coeff_cdf_q_ctx, CdfContext}, obu::frame::RefFrameState, utils::consts::{DEFAULT_COEFF_BR_CDF, DEFAULT_SKIP_CDF, DEFAULT_TXB_SKIP_CDF, DEFAULT_MV_CLASS_CDF}, Error};
+
+
+
+#[test]
+fn coeff_cdf_selection() {
+    let q: Vec<_> = [0, 20, 21, 60, 61, 120, 121, 255].into_iter().map(coeff_cdf_q_ctx).collect();
+    assert_eq!(q, [0, 0, 1, 1, 2, 2, 3, 3]);
+
+    let mut cdf = CdfContext::new(100);
+    assert_eq!(cdf.coeff.txb_skip_cdf, DEFAULT_TXB_SKIP_CDF[2]);
+    assert_eq!(cdf.coeff.coeff_br_cdf, DEFAULT_COEFF_BR_CDF[2]);
+    assert_eq!(cdf.skip_cdf, DEFAULT_SKIP_CDF);
+    assert_eq!(cdf.mv[1].mv_class_cdf, DEFAULT_MV_CLASS_CDF);
+    cdf.init_coeff_cdfs(10);
+    assert_eq!(cdf.coeff.txb_skip_cdf, DEFAULT_TXB_SKIP_CDF[0]);
+    assert_eq!(cdf, CdfContext::new(0));
+}
+
+
+#[test]
+fn reset_counters() {
+    let mut cdf = CdfContext::new(0);
+    cdf.skip_cdf[1] = [20000, 32768, 17];
+    cdf.mv[0].mv_bit_cdf[1][9][2] = 32;
+    cdf.coeff.coeff_base_cdf[4][1][41][4] = 5;
+    cdf.delta_lf_multi_cdf[3][4] = 2;
+    cdf.reset_counters();
+    assert_eq!(cdf.skip_cdf[1], [20000, 32768, 0]);
+    assert_eq!(cdf.mv[0].mv_bit_cdf[1][9][2], 0);
+    assert_eq!(cdf.coeff.coeff_base_cdf[4][1][41][4], 0);
+    assert_eq!(cdf.delta_lf_multi_cdf[3][4], 0);
+}
+
+
+#[test]
+fn save_and_load() {
+    let mut state = RefFrameState::default();
+    let mut cdf = CdfContext::new(200);
+    cdf.intrabc_cdf = [12345, 32768, 9];
+    state.slots[3].valid = true;
+    state.save_cdfs(3, &cdf);
+    assert_eq!(state.slots[3].cdfs.as_deref(), Some(&cdf));
+
+    let loaded = state.load_cdfs(3).unwrap();
+    assert_eq!(loaded.intrabc_cdf, [12345, 32768, 0]);
+    assert_eq!(loaded.coeff, cdf.coeff);
+
+    assert!(matches!(state.load_cdfs(2), Err(Error::Conformance { section: "6.8.2", .. })));
+    state.slots[2].valid = true;
+    assert!(matches!(state.load_cdfs(2), Err(Error::Conformance { section: "7.20", .. })));
+}
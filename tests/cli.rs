@@ -0,0 +1,128 @@
+mod common;
+
+use std::{env, fs, path::PathBuf, process::{Command, Output}};
+
+use common::{length_delimited, lossless_temporal_unit, temporal_units, write_ivf};
+use wav1d::{obu::ObuIter, output::Md5};
+
+
+
+// 64x48 8 位 4:2:0 一帧的字节数
+const FRAME_SIZE: usize = 64 * 48 * 3 / 2;
+
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wav1d")).args(args).output().unwrap()
+}
+
+fn temp(name: &str, data: &[u8]) -> PathBuf {
+    let path = env::temp_dir().join(format!("wav1d-{}-{name}", std::process::id()));
+    fs::write(&path, data).unwrap();
+    path
+}
+
+fn ivf() -> Vec<u8> {
+    let tus = temporal_units();
+    let frames: Vec<_> = tus.iter().enumerate().map(|(i, tu)| (i as u64, tu.as_slice())).collect();
+    write_ivf(64, 48, &frames)
+}
+
+// 每个 OBU 保留 obu_size，按 Annex B 加上长度
+fn annexb() -> Vec<u8> {
+    let tus: Vec<_> = temporal_units().iter().map(|tu| {
+        let mut iter = ObuIter::from(tu.as_slice());
+        let mut starts = Vec::new();
+        while iter.next().is_some() {
+            starts.push(iter.offset());
+        }
+        starts.push(tu.len());
+        let obus: Vec<_> = starts.windows(2).map(|w| tu[w[0]..w[1]].to_vec()).collect();
+        length_delimited(&[length_delimited(&obus)])
+    }).collect();
+    length_delimited(&tus)
+}
+
+
+#[test]
+fn ivf_to_y4m() {
+    let input = temp("in.ivf", &ivf());
+    let output = temp("out.y4m", &[]);
+    let res = run(&["-i", input.to_str().unwrap(), "-o", output.to_str().unwrap()]);
+    assert!(res.status.success(), "{}", String::from_utf8_lossy(&res.stderr));
+    assert!(String::from_utf8_lossy(&res.stderr).contains("not fully reconstructed"));
+    let a = fs::read(&output).unwrap();
+    let header = b"YUV4MPEG2 W64 H48 F30:1 Ip A0:0 C420jpeg\n";
+    assert!(a.starts_with(header));
+    assert_eq!(a.len(), header.len() + 3 * (6 + FRAME_SIZE));
+}
+
+
+#[test]
+fn skip_limit_and_bit_depth() {
+    let input = temp("in.obu", &temporal_units().concat());
+    let output = temp("out.yuv", &[]);
+    let res = run(&["-i", input.to_str().unwrap(), "-o", output.to_str().unwrap(), "--skip", "1", "--limit", "1"]);
+    assert!(res.status.success(), "{}", String::from_utf8_lossy(&res.stderr));
+    assert_eq!(fs::read(&output).unwrap(), vec![128; FRAME_SIZE]);
+
+    let input = temp("in.annexb", &annexb());
+    let res = run(&["-i", input.to_str().unwrap(), "-o", output.to_str().unwrap(), "--output-bitdepth", "10"]);
+    assert!(res.status.success(), "{}", String::from_utf8_lossy(&res.stderr));
+    let a = fs::read(&output).unwrap();
+    assert_eq!(a.len(), 3 * 2 * FRAME_SIZE);
+    assert_eq!(a[..2], [0x00, 0x02]);
+}
+
+
+#[test]
+fn verify() {
+    let mut md5 = Md5::new();
+    md5.update(&vec![128; FRAME_SIZE]);
+    let md5 = md5.hex();
+    let input = temp("verify.obu", &lossless_temporal_unit());
+    let res = run(&["-i", input.to_str().unwrap(), "--verify", &md5]);
+    assert!(res.status.success(), "{}", String::from_utf8_lossy(&res.stderr));
+    assert!(String::from_utf8_lossy(&res.stderr).contains("md5 verified"));
+    let res = run(&["-i", input.to_str().unwrap(), "--verify", "00112233445566778899aabbccddeeff"]);
+    assert!(String::from_utf8_lossy(&res.stderr).contains("md5 mismatch"));
+
+    // 没有完成重建的帧不能校验
+    let input = temp("verify.ivf", &ivf());
+    let res = run(&["-i", input.to_str().unwrap(), "--verify", &md5]);
+    assert!(!res.status.success());
+    assert!(String::from_utf8_lossy(&res.stderr).contains("not been reconstructed"));
+}
+
+
+#[test]
+fn invalid_arguments() {
+    assert!(!run(&["-i"]).status.success());
+    assert!(!run(&["-i", "in.ivf"]).status.success());
+    assert!(!run(&["-i", "in.ivf", "-o", "out.yuv", "--output-bitdepth", "9"]).status.success());
+    assert!(run(&["--help"]).status.success());
+}
+
+
+#[test]
+fn md5_muxers() {
+    let mut md5 = Md5::new();
+    md5.update(&vec![128; FRAME_SIZE]);
+    let md5 = md5.hex();
+    let input = temp("clip.obu", &lossless_temporal_unit());
+    let output = temp("clip.md5", &[]);
+    let res = run(&["-i", input.to_str().unwrap(), "-o", output.to_str().unwrap()]);
+    assert!(res.status.success(), "{}", String::from_utf8_lossy(&res.stderr));
+    assert_eq!(fs::read_to_string(&output).unwrap(), format!("{md5}\n"));
+    let res = run(&["-i", input.to_str().unwrap(), "-o", output.to_str().unwrap(), "--muxer", "framemd5", "--crc32"]);
+    assert!(res.status.success(), "{}", String::from_utf8_lossy(&res.stderr));
+    let report = fs::read_to_string(&output).unwrap();
+    assert!(report.starts_with(&format!("{md5}  wav1d-")), "{report}");
+    assert!(report.contains("clip-64x48-0001.i420  crc32:"), "{report}");
+
+    let input = temp("clip.ivf", &ivf());
+    for args in [&["--muxer", "framemd5"][..], &["--crc32"][..]] {
+        let res = run(&[&["-i", input.to_str().unwrap(), "-o", output.to_str().unwrap()][..], args].concat());
+        assert!(!res.status.success());
+        assert!(String::from_utf8_lossy(&res.stderr).contains("not been reconstructed"));
+    }
+}
@@ -0,0 +1,473 @@
+#![allow(dead_code)]
+
+use wav1d::{
+    entropy::CdfContext,
+    obu::{frame::FrameHeader, sequence::ColorConfig},
+    utils::enums::{ChromaSamplePosition, IntraFrameYMode, Partition, UVMode},
+    Picture,
+};
+
+
+
+// 测试用的写比特工具，按 f(n) 的大端序写入
+#[derive(Default)]
+pub struct BitWriter {
+    data: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn put(&mut self, value: u64, count: usize) -> &mut Self {
+        for i in (0..count).rev() {
+            if self.bits & 0b111 == 0 {
+                self.data.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                let last = self.data.len() - 1;
+                self.data[last] |= 1 << (7 - (self.bits & 0b111));
+            }
+            self.bits += 1;
+        }
+        self
+    }
+
+    pub fn bit(&mut self, value: bool) -> &mut Self {
+        self.put(value as u64, 1)
+    }
+
+    pub fn uvlc(&mut self, value: u32) -> &mut Self {
+        let value = value as u64 + 1;
+        let leading_zeros = 63 - value.leading_zeros() as usize;
+        self.put(0, leading_zeros);
+        self.put(value, leading_zeros + 1)
+    }
+
+    pub fn leb128(&mut self, mut value: u64) -> &mut Self {
+        loop {
+            let byte = value & 0x7f;
+            value >>= 7;
+            if value == 0 {
+                self.put(byte, 8);
+                return self;
+            }
+            self.put(byte | 0x80, 8);
+        }
+    }
+
+    pub fn su(&mut self, value: i64, count: usize) -> &mut Self {
+        self.put(value as u64 & ((1 << count) - 1), count)
+    }
+
+    pub fn trailing_bits(&mut self) -> &mut Self {
+        self.bit(true);
+        while self.bits & 0b111 != 0 {
+            self.bit(false);
+        }
+        self
+    }
+
+    pub fn position(&self) -> usize {
+        self.bits
+    }
+
+    pub fn byte_alignment(&mut self) -> &mut Self {
+        self.put(0, (8 - (self.bits & 0b111)) & 0b111)
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+
+// profile 0, 8-bit 4:2:0, 一个 operating point, order hint 7 位, 不带 trailing bits
+pub fn write_sequence_header(w: &mut BitWriter, width: u32, height: u32) {
+    write_scalable_sequence_header(w, width, height, &[0]);
+}
+
+
+// 带多个 operating point 的序列头，idcs 为各 operating_point_idc
+pub fn write_scalable_sequence_header(w: &mut BitWriter, width: u32, height: u32, idcs: &[u64]) {
+    w.put(0, 3)         // seq_profile
+        .bit(false)     // still_picture
+        .bit(false)     // reduced_still_picture_header
+        .bit(false)     // timing_info_present_flag
+        .bit(false)     // initial_display_delay_present_flag
+        .put(idcs.len() as u64 - 1, 5);
+    for &idc in idcs {
+        w.put(idc, 12)  // operating_point_idc
+            .put(8, 5)  // seq_level_idx
+            .bit(false);// seq_tier
+    }
+    w.put(15, 4)        // frame_width_bits_minus_1
+        .put(15, 4)     // frame_height_bits_minus_1
+        .put(width as u64 - 1, 16)
+        .put(height as u64 - 1, 16)
+        .bit(false)     // frame_id_numbers_present_flag
+        .bit(false)     // use_128x128_superblock
+        .bit(true)      // enable_filter_intra
+        .bit(true)      // enable_intra_edge_filter
+        .bit(true)      // enable_interintra_compound
+        .bit(true)      // enable_masked_compound
+        .bit(true)      // enable_warped_motion
+        .bit(true)      // enable_dual_filter
+        .bit(true)      // enable_order_hint
+        .bit(true)      // enable_jnt_comp
+        .bit(true)      // enable_ref_frame_mvs
+        .bit(true)      // seq_choose_screen_content_tools
+        .bit(true)      // seq_choose_integer_mv
+        .put(6, 3)      // order_hint_bits_minus_1
+        .bit(false)     // enable_superres
+        .bit(true)      // enable_cdef
+        .bit(true)      // enable_restoration
+        .bit(false)     // high_bitdepth
+        .bit(false)     // mono_chrome
+        .bit(false)     // color_description_present_flag
+        .bit(false)     // color_range
+        .put(0, 2)      // chroma_sample_position
+        .bit(false)     // separate_uv_delta_q
+        .bit(false);    // film_grain_params_present
+}
+
+
+// 接在 write_sequence_header 之后的 key frame 头，单个 superblock 宽高时 tile_bits 为 [true]
+pub fn write_key_frame_header(w: &mut BitWriter, order_hint: u64, base_q_idx: u64, tile_bits: &[bool]) {
+    w.bit(false)        // show_existing_frame
+        .put(0, 2)      // frame_type
+        .bit(true)      // show_frame
+        .bit(false)     // disable_cdf_update
+        .bit(false)     // allow_screen_content_tools
+        .bit(false)     // frame_size_override_flag
+        .put(order_hint, 7)
+        .bit(false)     // render_and_frame_size_different
+        .bit(false);    // disable_frame_end_update_cdf
+    for &b in tile_bits {
+        w.bit(b);
+    }
+    w.put(base_q_idx, 8)
+        .bit(false).bit(false).bit(false)   // delta_q y_dc / u_dc / u_ac
+        .bit(false)                         // using_qmatrix
+        .bit(false);                        // segmentation_enabled
+    if base_q_idx > 0 {
+        w.bit(false);                       // delta_q_present
+    }
+    if base_q_idx > 0 {
+        w.put(10, 6).put(12, 6)             // loop_filter_level[0..2]
+            .put(3, 6).put(4, 6)            // loop_filter_level[2..4]
+            .put(2, 3)                      // loop_filter_sharpness
+            .bit(true)                      // loop_filter_delta_enabled
+            .bit(false)                     // loop_filter_delta_update
+            .put(2, 2)                      // cdef_damping_minus_3
+            .put(0, 2)                      // cdef_bits
+            .put(5, 4).put(3, 2).put(1, 4).put(1, 2)
+            .put(0, 2).put(0, 2).put(0, 2)  // lr_type
+            .bit(true);                     // tx_mode_select
+    }
+    w.bit(false);                           // reduced_tx_set
+}
+
+
+// 接在 write_key_frame_header 之后的 inter frame 头，全部参考 slot 0，不含 is_global
+pub fn write_inter_frame(w: &mut BitWriter, order_hint: u64, refresh: u64) {
+    w.bit(false).put(1, 2).bit(true)
+        .bit(false)                 // error_resilient_mode
+        .bit(false)                 // disable_cdf_update
+        .bit(false)                 // allow_screen_content_tools
+        .bit(false)                 // frame_size_override_flag
+        .put(order_hint, 7)
+        .put(0, 3)                  // primary_ref_frame
+        .put(refresh, 8)
+        .bit(false);                // frame_refs_short_signaling
+    for _ in 0..7 {
+        w.put(0, 3);                // ref_frame_idx
+    }
+    w.bit(false)                    // render_and_frame_size_different
+        .bit(true)                  // allow_high_precision_mv
+        .bit(true)                  // is_filter_switchable
+        .bit(true)                  // is_motion_mode_switchable
+        .bit(true)                  // use_ref_frame_mvs
+        .bit(false)                 // disable_frame_end_update_cdf
+        .bit(true)                  // uniform_tile_spacing_flag
+        .put(80, 8).bit(false).bit(false).bit(false).bit(false)
+        .bit(false)                 // segmentation_enabled
+        .bit(true).put(1, 2)        // delta_q_present, delta_q_res
+        .bit(true).put(2, 2).bit(true)
+        .put(0, 6).put(0, 6).put(0, 3).bit(false)
+        .put(0, 2).put(0, 2).put(0, 4).put(0, 2).put(0, 4).put(0, 2)
+        .put(0, 2).put(0, 2).put(0, 2)
+        .bit(true)                  // tx_mode_select
+        .bit(true)                  // reference_select
+        .bit(false)                 // allow_warped_motion
+        .bit(false);                // reduced_tx_set
+}
+
+
+// 按 IVF 格式封装帧数据
+pub fn write_ivf(width: u16, height: u16, frames: &[(u64, &[u8])]) -> Vec<u8> {
+    let mut a = Vec::new();
+    a.extend_from_slice(b"DKIF");
+    a.extend_from_slice(&0u16.to_le_bytes());
+    a.extend_from_slice(&32u16.to_le_bytes());
+    a.extend_from_slice(b"AV01");
+    a.extend_from_slice(&width.to_le_bytes());
+    a.extend_from_slice(&height.to_le_bytes());
+    a.extend_from_slice(&30u32.to_le_bytes());
+    a.extend_from_slice(&1u32.to_le_bytes());
+    a.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+    a.extend_from_slice(&0u32.to_le_bytes());
+    for (timestamp, data) in frames {
+        a.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        a.extend_from_slice(&timestamp.to_le_bytes());
+        a.extend_from_slice(data);
+    }
+    a
+}
+
+
+// 按 Annex B 给数据加上 leb128 长度前缀
+pub fn length_delimited(units: &[Vec<u8>]) -> Vec<u8> {
+    let mut a = Vec::new();
+    for unit in units {
+        a.extend(BitWriter::new().leb128(unit.len() as u64).bytes());
+        a.extend_from_slice(unit);
+    }
+    a
+}
+
+
+// 带 obu_size 的 OBU，无扩展头
+pub fn write_obu(obu_type: u64, payload: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    w.put(obu_type << 3 | 0b010, 8).leb128(payload.len() as u64);
+    let mut a = w.bytes();
+    a.extend_from_slice(payload);
+    a
+}
+
+
+// 带 obu_size 和扩展头的 OBU
+pub fn write_layer_obu(obu_type: u64, temporal_id: u64, spatial_id: u64, payload: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    w.put(obu_type << 3 | 0b110, 8).put(temporal_id, 3).put(spatial_id, 2).put(0, 3).leb128(payload.len() as u64);
+    let mut a = w.bytes();
+    a.extend_from_slice(payload);
+    a
+}
+
+
+// 测试用的符号编码器，和 8.2 的解码过程对应（libaom 的 od_ec 编码器）
+pub struct SymbolWriter {
+    low: u64,
+    rng: u32,
+    cnt: i32,
+    precarry: Vec<u16>,
+    disable_cdf_update: bool,
+}
+
+impl SymbolWriter {
+    pub fn new(disable_cdf_update: bool) -> Self {
+        Self { low: 0, rng: 0x8000, cnt: -9, precarry: Vec::new(), disable_cdf_update }
+    }
+
+    fn encode(&mut self, cdf: &[u32], s: usize) {
+        let n = (cdf.len() - 2) as u32;
+        let fl = if s > 0 { 32768 - cdf[s - 1] } else { 32768 };
+        let fh = 32768 - cdf[s];
+        let (mut l, mut r) = (self.low, self.rng);
+        let s = s as u32;
+        if fl < 32768 {
+            let u = (((r >> 8) * (fl >> 6)) >> 1) + 4 * (n - (s - 1));
+            let v = (((r >> 8) * (fh >> 6)) >> 1) + 4 * (n - s);
+            l += (r - u) as u64;
+            r = u - v;
+        } else {
+            r -= (((r >> 8) * (fh >> 6)) >> 1) + 4 * (n - s);
+        }
+        self.normalize(l, r);
+    }
+
+    fn normalize(&mut self, mut low: u64, rng: u32) {
+        let mut c = self.cnt;
+        let d = 15 - rng.ilog2() as i32;
+        let mut s = c + d;
+        if s >= 0 {
+            c += 16;
+            let mut m = (1u64 << c) - 1;
+            if s >= 8 {
+                self.precarry.push((low >> c) as u16);
+                low &= m;
+                c -= 8;
+                m >>= 8;
+            }
+            self.precarry.push((low >> c) as u16);
+            s = c + d - 24;
+            low &= m;
+        }
+        self.low = low << d;
+        self.rng = rng << d;
+        self.cnt = s;
+    }
+
+    pub fn symbol(&mut self, cdf: &mut [u32], s: usize) -> &mut Self {
+        self.encode(cdf, s);
+        if !self.disable_cdf_update {
+            let n = cdf.len() - 1;
+            let rate = 3 + (cdf[n] > 15) as u32 + (cdf[n] > 31) as u32 + n.ilog2().min(2);
+            let mut tmp = 0;
+            for (i, value) in cdf.iter_mut().enumerate().take(n - 1) {
+                if i == s {
+                    tmp = 1 << 15;
+                }
+                if tmp < *value {
+                    *value -= (*value - tmp) >> rate;
+                } else {
+                    *value += (tmp - *value) >> rate;
+                }
+            }
+            cdf[n] += (cdf[n] < 32) as u32;
+        }
+        self
+    }
+
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.encode(&[1 << 14, 1 << 15, 0], value as usize);
+        self
+    }
+
+    pub fn literal(&mut self, value: u32, n: u8) -> &mut Self {
+        for i in (0..n).rev() {
+            self.bool((value >> i) & 1 == 1);
+        }
+        self
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut c = self.cnt;
+        let m = 0x3fff;
+        let mut e = ((self.low + m) & !m) | (m + 1);
+        let mut s = c + 10;
+        if s > 0 {
+            let mut n = (1u64 << (c + 16)) - 1;
+            while s > 0 {
+                self.precarry.push((e >> (c + 16)) as u16);
+                e &= n;
+                s -= 8;
+                c -= 8;
+                n >>= 8;
+            }
+        }
+        let mut out = vec![0; self.precarry.len()];
+        let mut carry = 0u32;
+        for i in (0..out.len()).rev() {
+            carry += self.precarry[i] as u32;
+            out[i] = carry as u8;
+            carry >>= 8;
+        }
+        out
+    }
+}
+
+
+// 指定格式的空白图像，subsampling 为 (ss_x, ss_y)
+pub fn picture(width: u32, height: u32, bit_depth: u8, mono_chrome: bool, subsampling: (bool, bool), csp: ChromaSamplePosition) -> Picture {
+    let cc = ColorConfig {
+        high_bitdepth: bit_depth > 8,
+        twelve_bit: bit_depth == 12,
+        bit_depth,
+        mono_chrome,
+        num_planes: if mono_chrome { 1 } else { 3 },
+        color_description_present_flag: false,
+        color_primaries: 2.into(),
+        transfer_characteristics: 2.into(),
+        matrix_coefficients: 2.into(),
+        color_range: false,
+        subsampling_x: subsampling.0,
+        subsampling_y: subsampling.1,
+        chroma_sample_position: csp,
+        separate_uv_delta_q: false,
+    };
+    let fh = FrameHeader { upscaled_width: width, frame_width: width, frame_height: height, render_width: width, render_height: height, ..Default::default() };
+    Picture::new(&fh, &cc, 0, 0)
+}
+
+
+// 64x48 key frame 的 tile：一个 skip 的 64x64 DC_PRED 块，没有相邻样本，重建后是中间值
+pub fn key_frame_tile(cdf: &mut CdfContext) -> Vec<u8> {
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w64_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_not_allowed_cdf[0], UVMode::Dc as usize)
+        .symbol(&mut cdf.tx_64x64_cdf[0], 0);
+    w.finish()
+}
+
+// 接在 write_inter_frame 之后的 64x48 tile：同样的块，在 inter frame 里是帧内块
+pub fn inter_frame_tile(cdf: &mut CdfContext) -> Vec<u8> {
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w64_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.is_inter_cdf[0], 0)
+        .symbol(&mut cdf.y_mode_cdf[3], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_not_allowed_cdf[0], UVMode::Dc as usize)
+        .symbol(&mut cdf.tx_64x64_cdf[0], 0);
+    w.finish()
+}
+
+
+// 64x48 的无损 key frame，没有环路滤波，skip 的 DC_PRED 块重建后全是中间值
+pub fn lossless_temporal_unit() -> Vec<u8> {
+    let mut w = BitWriter::new();
+    write_sequence_header(&mut w, 64, 48);
+    w.trailing_bits();
+    let seq = w.bytes();
+
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 0, &[true]);
+    w.byte_alignment();
+    let mut cdf = CdfContext::new(0);
+    let mut tile = SymbolWriter::new(false);
+    // 无损时只有 4x4 变换，不读 tx_size
+    tile.symbol(&mut cdf.partition_w64_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_not_allowed_cdf[0], UVMode::Dc as usize);
+    let key = [w.bytes(), tile.finish()].concat();
+    [write_obu(2, &[]), write_obu(1, &seq), write_obu(6, &key)].concat()
+}
+
+
+// 三个时间单元：key frame，inter frame 写入 slot 1，再显示 slot 1
+pub fn temporal_units() -> Vec<Vec<u8>> {
+    let mut w = BitWriter::new();
+    write_sequence_header(&mut w, 64, 48);
+    w.trailing_bits();
+    let seq = w.bytes();
+
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    w.byte_alignment();
+    let mut cdf = CdfContext::new(100);
+    let key = [w.bytes(), key_frame_tile(&mut cdf)].concat();
+
+    // inter frame 从 key frame 的 tile 结束时的 CDF 开始
+    cdf.reset_counters();
+    let mut w = BitWriter::new();
+    write_inter_frame(&mut w, 1, 0b10);
+    w.put(0, 7).byte_alignment();
+    let inter = [w.bytes(), inter_frame_tile(&mut cdf)].concat();
+
+    let mut w = BitWriter::new();
+    w.bit(true).put(1, 3).trailing_bits();
+    let show_existing = w.bytes();
+
+    vec![
+        [write_obu(2, &[]), write_obu(1, &seq), write_obu(6, &key)].concat(),
+        [write_obu(2, &[]), write_obu(6, &inter)].concat(),
+        [write_obu(2, &[]), write_obu(3, &show_existing)].concat(),
+    ]
+}
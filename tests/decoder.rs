@@ -0,0 +1,227 @@
+mod common;
+
+use common::{inter_frame_tile, key_frame_tile, lossless_temporal_unit, temporal_units, write_inter_frame, write_key_frame_header, write_layer_obu, write_obu, write_scalable_sequence_header, write_sequence_header, BitWriter};
+use wav1d::{entropy::CdfContext, obu::{frame::{FrameHeader, TileInfo}, tile_group::TileGroup}, utils::enums::FrameType, Decoder, Error};
+
+
+
+fn decode(chunks: &[&[u8]]) -> Vec<(FrameType, u32)> {
+    let mut decoder = Decoder::new();
+    let mut res = Vec::new();
+    for chunk in chunks {
+        decoder.send_data(chunk).unwrap();
+        while let Some(p) = decoder.get_picture() {
+            res.push((p.frame_type, p.order_hint));
+        }
+    }
+    decoder.flush().unwrap();
+    while let Some(p) = decoder.get_picture() {
+        res.push((p.frame_type, p.order_hint));
+    }
+    res
+}
+
+
+#[test]
+fn output_order() {
+    let a = temporal_units().concat();
+    let expected = vec![(FrameType::Key, 0), (FrameType::Inter, 1), (FrameType::Inter, 1)];
+    assert_eq!(decode(&[&a]), expected);
+    let chunks: Vec<&[u8]> = a.chunks(1).collect();
+    assert_eq!(decode(&chunks), expected);
+    let chunks: Vec<&[u8]> = a.chunks(7).collect();
+    assert_eq!(decode(&chunks), expected);
+}
+
+
+#[test]
+fn temporal_unit_boundaries() {
+    let a = temporal_units().concat();
+    let mut decoder = Decoder::new();
+    decoder.send_data(&a).unwrap();
+    // 最后一个时间单元要等 flush 或下一个 temporal delimiter
+    assert_eq!(decoder.get_picture().map(|p| p.order_hint), Some(0));
+    assert_eq!(decoder.get_picture().map(|p| p.order_hint), Some(1));
+    assert!(decoder.get_picture().is_none());
+    decoder.flush().unwrap();
+    let p = decoder.get_picture().unwrap();
+    assert_eq!((p.width, p.height, p.bit_depth()), (64, 48, 8));
+    assert_eq!(p.planes.len(), 3);
+    assert_eq!((p.planes[1].width, p.planes[1].height), (32, 24));
+    assert!(decoder.get_picture().is_none());
+    assert_eq!(decoder.sequence_header().map(|s| s.max_frame_width_minus_1), Some(63));
+}
+
+
+#[test]
+fn reconstructed_pictures() {
+    let mut decoder = Decoder::new();
+    decoder.send_data(&lossless_temporal_unit()).unwrap();
+    decoder.flush().unwrap();
+    let p = decoder.get_picture().unwrap();
+    assert!(p.reconstructed);
+    assert!(p.planes.iter().all(|plane| plane.data.iter().all(|&s| s == 128)));
+    // 开启环路滤波的帧还没有完成重建
+    let mut decoder = Decoder::new();
+    decoder.send_data(&temporal_units().concat()).unwrap();
+    decoder.flush().unwrap();
+    assert!(std::iter::from_fn(|| decoder.get_picture()).all(|p| !p.reconstructed));
+}
+
+
+#[test]
+fn frame_before_sequence_header() {
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    w.byte_alignment().put(0, 8);
+    let a = [write_obu(2, &[]), write_obu(6, &w.bytes())].concat();
+    let mut decoder = Decoder::new();
+    decoder.send_data(&a).unwrap();
+    assert!(matches!(decoder.flush(), Err(Error::Conformance { section: "7.5", .. })));
+}
+
+
+#[test]
+fn invalid_tile_data() {
+    let mut w = BitWriter::new();
+    write_sequence_header(&mut w, 64, 48);
+    w.trailing_bits();
+    let seq = w.bytes();
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    w.byte_alignment().put(0xab, 8).put(0xcd, 8);
+    let a = [write_obu(2, &[]), write_obu(1, &seq), write_obu(6, &w.bytes())].concat();
+    let mut decoder = Decoder::new();
+    decoder.send_data(&a).unwrap();
+    assert!(matches!(decoder.flush(), Err(Error::Conformance { section: "8.2.4", .. })));
+    assert!(decoder.get_picture().is_none());
+}
+
+
+#[test]
+fn resync_after_error() {
+    // 第一个 temporal unit 缺少 sequence header，后面的数据要继续解码
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    w.byte_alignment().put(0, 8);
+    let mut a = [write_obu(2, &[]), write_obu(6, &w.bytes())].concat();
+    a.extend(temporal_units().concat());
+    let mut decoder = Decoder::new();
+    assert!(matches!(decoder.send_data(&a), Err(Error::Conformance { section: "7.5", .. })));
+    decoder.send_data(&[]).unwrap();
+    decoder.flush().unwrap();
+    let order: Vec<_> = std::iter::from_fn(|| decoder.get_picture()).map(|p| p.order_hint).collect();
+    assert_eq!(order, [0, 1, 1]);
+
+    // 无效的 OBU 头跳过一个字节
+    let mut a = vec![0x80];
+    a.extend(temporal_units().concat());
+    let mut decoder = Decoder::new();
+    assert!(decoder.send_data(&a).is_err());
+    decoder.send_data(&[]).unwrap();
+    decoder.flush().unwrap();
+    assert_eq!(std::iter::from_fn(|| decoder.get_picture()).count(), 3);
+}
+
+
+#[test]
+fn redundant_frame_headers() {
+    let mut units = temporal_units();
+    // 帧已经解码完成后的 redundant frame header 不会再开始一帧或再次显示
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    w.trailing_bits();
+    units[0].extend(write_obu(7, &w.bytes()));
+    let mut w = BitWriter::new();
+    w.bit(true).put(1, 3).trailing_bits();
+    units[2].extend(write_obu(7, &w.bytes()));
+    let a = units.concat();
+    assert_eq!(decode(&[&a]), vec![(FrameType::Key, 0), (FrameType::Inter, 1), (FrameType::Inter, 1)]);
+}
+
+#[test]
+fn tile_group() {
+    let fh = FrameHeader {
+        tile_info: TileInfo { tile_cols: 4, tile_rows: 2, tile_cols_log2: 2, tile_rows_log2: 1, tile_size_bytes: 2, ..Default::default() },
+        ..Default::default()
+    };
+    // tile_start_and_end_present_flag = 1, tg_start = 2, tg_end = 4
+    let mut w = BitWriter::new();
+    w.bit(true).put(2, 3).put(4, 3).byte_alignment();
+    w.put(1, 8).put(0, 8).put(1, 8).put(2, 8);
+    w.put(0, 8).put(0, 8).put(3, 8);
+    w.put(4, 8).put(5, 8);
+    let a = w.bytes();
+    let tg = TileGroup::parse(&a, &fh).unwrap();
+    assert_eq!((tg.tg_start, tg.tg_end), (2, 4));
+    assert!(!tg.is_last(&fh));
+    let tiles: Vec<_> = tg.tiles.iter().map(|t| (t.tile_num, t.tile_row, t.tile_col, t.data)).collect();
+    assert_eq!(tiles, vec![(2, 0, 2, [1u8, 2].as_slice()), (3, 0, 3, [3].as_slice()), (4, 1, 0, [4, 5].as_slice())]);
+
+    let a = [0xa8, 0x03, 0x00, 1, 2];
+    assert_eq!(TileGroup::parse(&a, &fh), Err(Error::Truncated { needed: 32, available: 16 }));
+}
+
+
+// 两个空间层、两个时间层：L2T2 全部，L1T1 基础层，L2T1 只含 temporal_id 0
+fn layered_obus() -> Vec<Vec<u8>> {
+    let mut w = BitWriter::new();
+    write_scalable_sequence_header(&mut w, 64, 48, &[0x303, 0x101, 0x301]);
+    w.trailing_bits();
+    let seq = w.bytes();
+
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    w.byte_alignment();
+    let mut cdf = CdfContext::new(100);
+    let key = [w.bytes(), key_frame_tile(&mut cdf)].concat();
+    cdf.reset_counters();
+    // 每个 inter frame 都从 key frame 保存的 CDF 开始
+    let inter = |order_hint| {
+        let mut w = BitWriter::new();
+        write_inter_frame(&mut w, order_hint, 0b10);
+        w.put(0, 7).byte_alignment();
+        [w.bytes(), inter_frame_tile(&mut cdf.clone())].concat()
+    };
+    vec![
+        write_obu(2, &[]), write_obu(1, &seq),
+        write_layer_obu(6, 0, 0, &key), write_layer_obu(6, 0, 1, &inter(0)),
+        write_obu(2, &[]),
+        write_layer_obu(6, 1, 0, &inter(1)), write_layer_obu(6, 1, 1, &inter(1)),
+    ]
+}
+
+
+#[test]
+fn operating_point() {
+    let decode = |operating_point, all_layers| {
+        let mut decoder = Decoder::new();
+        decoder.set_operating_point(operating_point);
+        decoder.set_all_layers(all_layers);
+        decoder.send_data(&layered_obus().concat()).unwrap();
+        decoder.flush().unwrap();
+        std::iter::from_fn(|| decoder.get_picture()).map(|p| (p.spatial_id, p.order_hint)).collect::<Vec<_>>()
+    };
+    assert_eq!(decode(0, true), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+    assert_eq!(decode(0, false), vec![(1, 0), (1, 1)]);
+    assert_eq!(decode(1, true), vec![(0, 0)]);
+    assert_eq!(decode(1, false), vec![(0, 0)]);
+    assert_eq!(decode(2, false), vec![(1, 0)]);
+
+    let mut decoder = Decoder::new();
+    decoder.set_operating_point(3);
+    assert_eq!(decoder.send_data(&layered_obus().concat()), Err(Error::InvalidValue { name: "operating_point", value: 3 }));
+}
+
+
+#[test]
+fn highest_layer_missing() {
+    // 第二个 temporal unit 缺少空间层 1 时输出层 0
+    let mut obus = layered_obus();
+    obus.pop();
+    let mut decoder = Decoder::new();
+    decoder.send_data(&obus.concat()).unwrap();
+    decoder.flush().unwrap();
+    let res: Vec<_> = std::iter::from_fn(|| decoder.get_picture()).map(|p| (p.spatial_id, p.order_hint)).collect();
+    assert_eq!(res, vec![(1, 0), (0, 1)]);
+}
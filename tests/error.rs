@@ -0,0 +1,39 @@
+use wav1d::{obu::header::ObuHeader, utils::{bits::{BitsReader, FromBitsReader}, enums::{MVJoint, SignUV}}, Error};
+
+
+
+#[test]
+fn truncated_read() {
+    let a = [0b10100111];
+    let mut r = BitsReader::from(a.as_slice());
+    assert_eq!(r.read_u8(5), Ok(0b10100));
+    assert_eq!(r.read_u8(4), Err(Error::Truncated { needed: 4, available: 3 }));
+    assert_eq!(r.read_position(), 5, "failed read must not consume bits");
+    assert_eq!(r.read_u8(3), Ok(0b111));
+    assert!(r.read_bit().is_err());
+}
+
+
+#[test]
+fn aligned_full_byte() {
+    let a = [0xff, 0x01];
+    let mut r = BitsReader::from(a.as_slice());
+    assert_eq!(r.read_u8(8), Ok(0xff));
+    assert_eq!(r.read_u8(8), Ok(0x01));
+}
+
+
+#[test]
+fn forbidden_bit() {
+    let a = [0b1001_0010, 0x00];
+    let mut r = BitsReader::from(a.as_slice());
+    assert_eq!(ObuHeader::from_bits_reader(&mut r).err(), Some(Error::ForbiddenBit));
+}
+
+
+#[test]
+fn invalid_enum_value() {
+    assert_eq!(MVJoint::from(7).changes_row_and_col(), Err(Error::InvalidValue { name: "mv_joint", value: 7 }));
+    assert_eq!(MVJoint::Hnzvz.changes_row_and_col(), Ok((false, true)));
+    assert_eq!(SignUV::from_cfl_alpha_signs(8), Err(Error::InvalidValue { name: "cfl_alpha_signs", value: 8 }));
+}
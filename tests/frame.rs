@@ -0,0 +1,231 @@
+mod common;
+
+use common::{write_inter_frame, write_key_frame_header, write_sequence_header, BitWriter};
+use wav1d::{obu::{frame::{FrameHeader, RefFrameState}, header::ObuHeader, sequence::SequenceHeader}, utils::{bits::{BitsReader, FromBitsReader}, consts::{IDENTITY, PRIMARY_REF_NONE, ROTZOOM, TRANSLATION}, enums::{FrameType, Interpolationfilter, ObuType, TxMode}}, Error};
+
+
+
+fn sequence_header(width: u32, height: u32) -> SequenceHeader {
+    let mut w = BitWriter::new();
+    write_sequence_header(&mut w, width, height);
+    let a = w.bytes();
+    SequenceHeader::from_bits_reader(&mut BitsReader::from(a.as_slice())).unwrap()
+}
+
+fn obu_header() -> ObuHeader {
+    ObuHeader {
+        obu_forbidden_bit: false,
+        obu_type: ObuType::FrameHeader,
+        obu_extension_flag: false,
+        obu_has_size_field: true,
+        temporal_id: 0,
+        spatial_id: 0,
+        obu_size: 0,
+    }
+}
+
+fn parse(w: &BitWriter, seq: &SequenceHeader, state: &mut RefFrameState) -> Result<FrameHeader, Error> {
+    let a = w.bytes();
+    let mut r = BitsReader::from(a.as_slice());
+    let fh = FrameHeader::from_bits_reader(&mut r, seq, &obu_header(), state)?;
+    assert_eq!(r.read_position(), w.position(), "header length");
+    Ok(fh)
+}
+
+
+#[test]
+fn key_frame() {
+    let seq = sequence_header(64, 48);
+    let mut state = RefFrameState::default();
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 5, 100, &[true]);
+    let fh = parse(&w, &seq, &mut state).unwrap();
+
+    assert_eq!(fh.frame_type, FrameType::Key);
+    assert!(fh.frame_is_intra && fh.show_frame && !fh.showable_frame && fh.error_resilient_mode);
+    assert_eq!((fh.frame_width, fh.frame_height, fh.upscaled_width), (64, 48, 64));
+    assert_eq!((fh.render_width, fh.render_height), (64, 48));
+    assert_eq!((fh.mi_cols, fh.mi_rows), (16, 12));
+    assert_eq!(fh.order_hint, 5);
+    assert_eq!(fh.primary_ref_frame, PRIMARY_REF_NONE);
+    assert_eq!(fh.refresh_frame_flags, 0xff);
+    assert_eq!((fh.tile_info.tile_cols, fh.tile_info.tile_rows), (1, 1));
+    assert_eq!(fh.tile_info.mi_col_starts, vec![0, 16]);
+    assert_eq!(fh.quantization.base_q_idx, 100);
+    assert!(!fh.coded_lossless);
+    assert_eq!(fh.loop_filter.loop_filter_level, [10, 12, 3, 4]);
+    assert_eq!(fh.loop_filter.loop_filter_ref_deltas, [1, 0, 0, 0, -1, 0, -1, -1]);
+    assert_eq!(fh.cdef.cdef_damping, 5);
+    assert_eq!(fh.cdef.cdef_y_sec_strength[0], 4);
+    assert_eq!(fh.tx_mode, TxMode::Select);
+    assert_eq!(fh.global_motion.gm_type, [IDENTITY; 8]);
+
+    state.update(&fh);
+    assert!(state.slots.iter().all(|s| s.valid && s.order_hint == 5 && s.frame_width == 64));
+}
+
+
+#[test]
+fn lossless_key_frame() {
+    let seq = sequence_header(64, 48);
+    let mut state = RefFrameState::default();
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 0, &[true]);
+    let fh = parse(&w, &seq, &mut state).unwrap();
+    assert!(fh.coded_lossless && fh.all_lossless);
+    assert_eq!(fh.lossless_array, [true; 8]);
+    assert_eq!(fh.tx_mode, TxMode::Only4X4);
+    assert_eq!(fh.loop_filter.loop_filter_level, [0; 4]);
+}
+
+
+#[test]
+fn uniform_tiles() {
+    let seq = sequence_header(1920, 1080);
+    let mut state = RefFrameState::default();
+    let mut w = BitWriter::new();
+    let mut tile_bits = vec![true, true, true, false, true, false];
+    // context_update_tile_id = 5, tile_size_bytes_minus_1 = 3
+    tile_bits.extend([true, false, true, true, true]);
+    write_key_frame_header(&mut w, 0, 60, &tile_bits);
+    let fh = parse(&w, &seq, &mut state).unwrap();
+    let ti = &fh.tile_info;
+    assert_eq!((ti.tile_cols_log2, ti.tile_rows_log2), (2, 1));
+    assert_eq!((ti.tile_cols, ti.tile_rows), (4, 2));
+    assert_eq!(ti.mi_col_starts, vec![0, 128, 256, 384, 480]);
+    assert_eq!(ti.mi_row_starts, vec![0, 144, 270]);
+    assert_eq!(ti.context_update_tile_id, 5);
+    assert_eq!(ti.tile_size_bytes, 4);
+}
+
+
+#[test]
+fn inter_frame_and_show_existing() {
+    let seq = sequence_header(64, 48);
+    let mut state = RefFrameState::default();
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    let key = parse(&w, &seq, &mut state).unwrap();
+    state.update(&key);
+
+    let mut w = BitWriter::new();
+    write_inter_frame(&mut w, 1, 0b10);
+    w.put(0, 7);                    // is_global
+    let fh = parse(&w, &seq, &mut state).unwrap();
+    assert_eq!(fh.frame_type, FrameType::Inter);
+    assert!(!fh.frame_is_intra && fh.showable_frame);
+    assert_eq!(fh.order_hint, 1);
+    assert_eq!(fh.primary_ref_frame, 0);
+    assert_eq!(fh.ref_frame_idx, [0; 7]);
+    assert_eq!(fh.order_hints[1..], [0; 7]);
+    assert_eq!(fh.ref_frame_sign_bias, [false; 8]);
+    assert_eq!((fh.frame_width, fh.frame_height), (64, 48));
+    assert!(fh.allow_high_precision_mv && fh.use_ref_frame_mvs);
+    assert_eq!(fh.interpolation_filter, Interpolationfilter::Switchable);
+    assert!(fh.delta.delta_q_present && fh.delta.delta_lf_present && fh.delta.delta_lf_multi);
+    assert_eq!((fh.delta.delta_q_res, fh.delta.delta_lf_res), (1, 2));
+    assert!(fh.reference_select && !fh.skip_mode_present);
+    // loop filter deltas are loaded from the primary reference frame
+    assert_eq!(fh.loop_filter.loop_filter_ref_deltas, key.loop_filter.loop_filter_ref_deltas);
+    state.update(&fh);
+    assert_eq!(state.slots[1].order_hint, 1);
+    assert_eq!(state.slots[0].order_hint, 0);
+
+    let mut w = BitWriter::new();
+    w.bit(true).put(1, 3);
+    let shown = parse(&w, &seq, &mut state).unwrap();
+    assert!(shown.show_existing_frame);
+    assert_eq!(shown.frame_type, FrameType::Inter);
+    assert_eq!(shown.order_hint, 1);
+    assert_eq!(shown.refresh_frame_flags, 0);
+    assert_eq!((shown.frame_width, shown.frame_height), (64, 48));
+}
+
+
+#[test]
+fn previous_segment_ids() {
+    let seq = sequence_header(64, 48);
+    let mut state = RefFrameState::default();
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    let key = parse(&w, &seq, &mut state).unwrap();
+    assert_eq!(state.load_previous_segment_ids(&key), None);
+    state.update(&key);
+    let segment_ids: Vec<u8> = (0..16 * 12).map(|i| (i % 8) as u8).collect();
+    state.save_segment_ids(&key, &segment_ids);
+
+    let mut w = BitWriter::new();
+    write_inter_frame(&mut w, 1, 0b10);
+    w.put(0, 7);
+    let mut fh = parse(&w, &seq, &mut state).unwrap();
+    assert_eq!(state.load_previous_segment_ids(&fh), Some(segment_ids.as_slice()));
+    // 大小不同时全部为 0
+    fh.mi_cols = 8;
+    assert_eq!(state.load_previous_segment_ids(&fh), None);
+}
+
+
+#[test]
+fn invalid_reference() {
+    let seq = sequence_header(64, 48);
+    let mut state = RefFrameState::default();
+    let mut w = BitWriter::new();
+    write_inter_frame(&mut w, 1, 0);
+    w.put(0, 7);
+    let a = w.bytes();
+    let res = FrameHeader::from_bits_reader(&mut BitsReader::from(a.as_slice()), &seq, &obu_header(), &mut state);
+    assert!(matches!(res, Err(Error::Conformance { .. })));
+
+    let mut w = BitWriter::new();
+    w.bit(true).put(3, 3);
+    let a = w.bytes();
+    let res = FrameHeader::from_bits_reader(&mut BitsReader::from(a.as_slice()), &seq, &obu_header(), &mut state);
+    assert!(matches!(res, Err(Error::Conformance { .. })));
+}
+
+
+#[test]
+fn failed_header_keeps_references() {
+    let seq = sequence_header(64, 48);
+    let mut state = RefFrameState::default();
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 3, 100, &[true]);
+    let key = parse(&w, &seq, &mut state).unwrap();
+    state.update(&key);
+
+    // 显示的关键帧会使所有参考槽失效，但帧头在后面被截断
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 4, 100, &[true]);
+    let a = w.bytes();
+    let res = FrameHeader::from_bits_reader(&mut BitsReader::from(&a[..2]), &seq, &obu_header(), &mut state);
+    assert!(matches!(res, Err(Error::Truncated { .. })));
+    assert!(state.slots.iter().all(|s| s.valid && s.order_hint == 3));
+}
+
+#[test]
+fn global_motion() {
+    let seq = sequence_header(64, 48);
+    let mut state = RefFrameState::default();
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    let key = parse(&w, &seq, &mut state).unwrap();
+    state.update(&key);
+
+    let mut w = BitWriter::new();
+    write_inter_frame(&mut w, 1, 0);
+    // LAST: TRANSLATION, both components decode to 3 (subexp value 6 recentered around 512)
+    w.bit(true).bit(false).bit(true)
+        .bit(false).put(6, 3)
+        .bit(false).put(6, 3);
+    // LAST2: ROTZOOM, all parameters equal to the previous (identity) values
+    w.bit(true).bit(true);
+    for _ in 0..4 {
+        w.bit(false).put(0, 3);
+    }
+    w.put(0, 5);
+    let fh = parse(&w, &seq, &mut state).unwrap();
+    let gm = &fh.global_motion;
+    assert_eq!(gm.gm_type[..4], [IDENTITY, TRANSLATION, ROTZOOM, IDENTITY]);
+    assert_eq!(gm.gm_params[1], [3 << 13, 3 << 13, 1 << 16, 0, 0, 1 << 16]);
+    assert_eq!(gm.gm_params[2], [0, 0, 1 << 16, 0, 0, 1 << 16]);
+}
@@ -0,0 +1,279 @@
+use wav1d::{
+    decoder::{intra_pred::{predict_chroma_from_luma, predict_intra, ChromaFromLuma, IntraPrediction}, Plane},
+    utils::enums::{FilterIntraMode, IntraFrameYMode, TxSize},
+};
+
+
+
+// 32x32 的平面，块放在 (8, 8)，上方一行和左边一列由 above / left 给出，左上角为 corner
+fn frame(above: impl Fn(usize) -> u16, left: impl Fn(usize) -> u16, corner: u16) -> Plane {
+    let mut plane = Plane::new(32, 32, 0);
+    for x in 8..32 {
+        plane.set(x, 7, above(x - 8));
+    }
+    for y in 8..32 {
+        plane.set(7, y, left(y - 8));
+    }
+    plane.set(7, 7, corner);
+    plane
+}
+
+fn block(tx_size: TxSize, mode: IntraFrameYMode) -> IntraPrediction {
+    IntraPrediction {
+        start_x: 8,
+        start_y: 8,
+        tx_size,
+        mode,
+        have_left: true,
+        have_above: true,
+        have_above_right: true,
+        have_below_left: true,
+        max_x: 31,
+        max_y: 31,
+        ..Default::default()
+    }
+}
+
+
+#[test]
+fn dc_prediction() {
+    let plane = frame(|_| 100, |_| 50, 0);
+    let b = block(TxSize::Tx8X16, IntraFrameYMode::Dc);
+    // (8 * 100 + 16 * 50 + 12) / 24
+    assert_eq!(predict_intra(&plane, &b, 8, false), vec![67; 128]);
+    assert_eq!(predict_intra(&plane, &IntraPrediction { have_left: false, ..b }, 8, false), vec![100; 128]);
+    assert_eq!(predict_intra(&plane, &IntraPrediction { have_above: false, ..b }, 8, false), vec![50; 128]);
+    assert_eq!(predict_intra(&plane, &IntraPrediction { have_above: false, have_left: false, ..b }, 10, false), vec![512; 128]);
+}
+
+#[test]
+fn vertical_horizontal_and_unavailable_edges() {
+    let plane = frame(|x| x as u16 * 3, |y| 200 - y as u16, 7);
+    let pred = predict_intra(&plane, &block(TxSize::Tx4X8, IntraFrameYMode::V), 8, true);
+    for i in 0..8 {
+        assert_eq!(pred[i * 4..i * 4 + 4], [0, 3, 6, 9]);
+    }
+    let pred = predict_intra(&plane, &block(TxSize::Tx8X4, IntraFrameYMode::H), 8, true);
+    for i in 0..4 {
+        assert!(pred[i * 8..i * 8 + 8].iter().all(|&p| p == 200 - i as u16));
+    }
+    // 没有上方时 AboveRow 取左边第一个样本，都没有时为 (1 << (BitDepth - 1)) - 1 和 + 1
+    let no_above = IntraPrediction { have_above: false, ..block(TxSize::Tx4X4, IntraFrameYMode::V) };
+    assert_eq!(predict_intra(&plane, &no_above, 8, false), vec![200; 16]);
+    let none = IntraPrediction { have_above: false, have_left: false, ..block(TxSize::Tx4X4, IntraFrameYMode::V) };
+    assert_eq!(predict_intra(&plane, &none, 12, false), vec![2047; 16]);
+    let none = IntraPrediction { mode: IntraFrameYMode::H, ..none };
+    assert_eq!(predict_intra(&plane, &none, 12, false), vec![2049; 16]);
+}
+
+#[test]
+fn paeth_prediction() {
+    let plane = frame(|x| [10, 20, 30, 40][x % 4], |y| [15, 25, 35, 45][y % 4], 20);
+    let pred = predict_intra(&plane, &block(TxSize::Tx4X4, IntraFrameYMode::Paeth), 8, false);
+    // base = above + left - 20，取与 base 最接近的 left / above / corner
+    assert_eq!(pred, [10, 15, 30, 40, 10, 25, 30, 40, 20, 35, 35, 40, 45, 45, 45, 45]);
+}
+
+#[test]
+fn smooth_prediction() {
+    let plane = frame(|_| 300, |_| 300, 0);
+    for mode in [IntraFrameYMode::Smooth, IntraFrameYMode::SmoothV, IntraFrameYMode::SmoothH] {
+        assert_eq!(predict_intra(&plane, &block(TxSize::Tx16X4, mode), 10, false), vec![300; 64]);
+    }
+    let plane = frame(|_| 200, |y| if y == 3 { 40 } else { 0 }, 0);
+    let pred = predict_intra(&plane, &block(TxSize::Tx4X4, IntraFrameYMode::SmoothV), 8, false);
+    // 权重 255, 149, 85, 64，底部用 LeftCol[h - 1]
+    let rows: Vec<u16> = [255, 149, 85, 64].iter().map(|&w| ((w * 200 + (256 - w) * 40 + 128) >> 8) as u16).collect();
+    for i in 0..4 {
+        assert!(pred[i * 4..i * 4 + 4].iter().all(|&p| p == rows[i]));
+    }
+    let pred = predict_intra(&plane, &block(TxSize::Tx4X4, IntraFrameYMode::Smooth), 8, false);
+    // 左上角：(255 * 200 + 1 * 40 + 255 * 0 + 1 * 200 + 256) >> 9
+    assert_eq!(pred[0], 100);
+}
+
+#[test]
+fn diagonal_prediction() {
+    let plane = frame(|x| 10 + x as u16, |y| 100 + y as u16, 5);
+    // D45 的 dx 为 64，pred[i][j] = AboveRow[i + j + 1]
+    let pred = predict_intra(&plane, &block(TxSize::Tx8X8, IntraFrameYMode::D45), 8, false);
+    for i in 0..8 {
+        for j in 0..8 {
+            assert_eq!(pred[i * 8 + j], (10 + (i + j + 1).min(15)) as u16, "({i}, {j})");
+        }
+    }
+    // D135 沿对角线复制上方、左上角和左边
+    let pred = predict_intra(&plane, &block(TxSize::Tx4X4, IntraFrameYMode::D135), 8, false);
+    assert_eq!(pred, [5, 10, 11, 12, 100, 5, 10, 11, 101, 100, 5, 10, 102, 101, 100, 5]);
+    // V 加上 angle_delta -1 为 87 度，dx 为 3
+    let b = IntraPrediction { angle_delta: -1, ..block(TxSize::Tx4X4, IntraFrameYMode::V) };
+    let pred = predict_intra(&plane, &b, 8, false);
+    let row0: Vec<u16> = (0..4).map(|j| ((10 + j) * 31 + (11 + j) + 16) >> 5).collect();
+    assert_eq!(pred[..4], row0);
+}
+
+#[test]
+fn above_right_and_below_left_availability() {
+    let plane = frame(|x| 10 * x as u16, |y| 10 * y as u16, 0);
+    let b = block(TxSize::Tx4X4, IntraFrameYMode::D45);
+    // 右上可用时 AboveRow 用到 2w 个样本
+    let pred = predict_intra(&plane, &b, 8, false);
+    assert_eq!(pred[15], 70);
+    // 不可用时重复 AboveRow[w - 1]
+    let pred = predict_intra(&plane, &IntraPrediction { have_above_right: false, ..b }, 8, false);
+    assert_eq!(pred[3 * 4 + 3], 30);
+    assert_eq!(pred[0], 10);
+    // 帧的右边界同样限制 AboveRow
+    let pred = predict_intra(&plane, &IntraPrediction { max_x: 13, ..b }, 8, false);
+    assert_eq!(pred[15], 50);
+
+    // D203 从 LeftCol 预测
+    let b = block(TxSize::Tx4X4, IntraFrameYMode::D203);
+    let with = predict_intra(&plane, &b, 8, false);
+    let without = predict_intra(&plane, &IntraPrediction { have_below_left: false, ..b }, 8, false);
+    assert!(without.iter().all(|&p| p <= 30));
+    assert!(with.iter().any(|&p| p > 30));
+    let limited = predict_intra(&plane, &IntraPrediction { max_y: 10, ..b }, 8, false);
+    assert!(limited.iter().all(|&p| p <= 20));
+}
+
+#[test]
+fn edge_filter_and_upsampling() {
+    // 滤波和上采样不改变常数边缘
+    let plane = frame(|_| 900, |_| 900, 900);
+    for (tx_size, mode, delta) in [
+        (TxSize::Tx4X4, IntraFrameYMode::D67, 2),
+        (TxSize::Tx16X16, IntraFrameYMode::D45, -3),
+        (TxSize::Tx8X8, IntraFrameYMode::D157, 1),
+        (TxSize::Tx32X32, IntraFrameYMode::D203, 3),
+        (TxSize::Tx8X4, IntraFrameYMode::D113, -2),
+    ] {
+        let b = IntraPrediction { angle_delta: delta, ..block(tx_size, mode) };
+        let pred = predict_intra(&plane, &b, 10, true);
+        assert!(pred.iter().all(|&p| p == 900), "{tx_size:?} {mode:?}");
+    }
+
+    // 4x4 的 D45 强度为 0 且不上采样，与关闭滤波一致
+    let plane = frame(|x| [0, 200, 50, 255, 0, 90, 10, 180][x % 8], |y| (y * 30) as u16, 128);
+    let b = block(TxSize::Tx4X4, IntraFrameYMode::D45);
+    assert_eq!(predict_intra(&plane, &b, 8, true), predict_intra(&plane, &b, 8, false));
+    // 16x16 的 D45 强度为 3，会平滑上方的边缘
+    let b = block(TxSize::Tx16X16, IntraFrameYMode::D45);
+    let filtered = predict_intra(&plane, &b, 8, true);
+    let unfiltered = predict_intra(&plane, &b, 8, false);
+    assert_ne!(filtered, unfiltered);
+    let spread = |pred: &[u16]| pred[..16].iter().max().unwrap() - pred[..16].iter().min().unwrap();
+    assert!(spread(&filtered) < spread(&unfiltered));
+    // 邻块为 SMOOTH 时选择另一组强度
+    let smooth = predict_intra(&plane, &IntraPrediction { smooth_neighbor: true, ..b }, 8, true);
+    assert_ne!(smooth, unfiltered);
+
+    // 4x4 的 D67 - 6 度用上采样的边缘，结果仍在边缘的取值范围内
+    let b = IntraPrediction { angle_delta: -2, ..block(TxSize::Tx4X4, IntraFrameYMode::D67) };
+    let upsampled = predict_intra(&plane, &b, 8, true);
+    assert_ne!(upsampled, predict_intra(&plane, &b, 8, false));
+    assert!(upsampled.iter().all(|&p| p <= 255));
+}
+
+#[test]
+fn filter_intra() {
+    let plane = frame(|_| 16, |_| 32, 0);
+    let b = IntraPrediction { use_filter_intra: true, filter_intra_mode: FilterIntraMode::Dc, ..block(TxSize::Tx8X8, IntraFrameYMode::Dc) };
+    let pred = predict_intra(&plane, &b, 8, false);
+    // 第一个 4x2：(-6 * 0 + 10 * 16 + 12 * 32) / 16 和 (-4 * 0 + 6 * 16 + 2 * 32 + 12 * 32) / 16
+    assert_eq!(pred[0], 34);
+    assert_eq!(pred[8], 34);
+    // 常数边缘得到常数预测
+    let plane = frame(|_| 77, |_| 77, 77);
+    for mode in [FilterIntraMode::Dc, FilterIntraMode::V, FilterIntraMode::H, FilterIntraMode::D157, FilterIntraMode::Paeth] {
+        let b = IntraPrediction { filter_intra_mode: mode, ..b };
+        assert_eq!(predict_intra(&plane, &b, 8, true), vec![77; 64], "{mode:?}");
+    }
+    // 色度不使用 filter intra
+    let chroma = IntraPrediction { plane: 1, ..b };
+    assert_eq!(predict_intra(&frame(|_| 16, |_| 32, 0), &chroma, 8, false), vec![(8 * 16 + 8 * 32 + 8) / 16; 64]);
+}
+
+// 亮度平面，每个样本由 f(x, y) 给出
+fn luma(width: usize, height: usize, f: impl Fn(usize, usize) -> u16) -> Plane {
+    let mut plane = Plane::new(width, height, 0);
+    for y in 0..height {
+        for x in 0..width {
+            plane.set(x, y, f(x, y));
+        }
+    }
+    plane
+}
+
+fn cfl(alpha: i8, subsampling_x: bool, subsampling_y: bool, max_luma_w: u32, max_luma_h: u32) -> ChromaFromLuma {
+    ChromaFromLuma { alpha, subsampling_x, subsampling_y, max_luma_w, max_luma_h }
+}
+
+#[test]
+fn chroma_from_luma_444() {
+    // 亮度均值为 100，alpha 8 时 (8 * 8 * (L - avg)) / 64 正好为 L - avg
+    let y = luma(8, 8, |x, y| 100 + x as u16 * 2 - y as u16 * 2);
+    let b = IntraPrediction { plane: 1, start_x: 4, start_y: 4, tx_size: TxSize::Tx4X4, ..Default::default() };
+    let mut pred = vec![500; 16];
+    predict_chroma_from_luma(&y, &b, &cfl(8, false, false, 8, 8), &mut pred, 10);
+    let expected: Vec<u16> = (0..16).map(|i| 500 + (i % 4) * 2 - (i / 4) * 2).collect();
+    assert_eq!(pred, expected);
+    // alpha 为负时方向相反，alpha 0 不改变 DC 预测
+    let mut pred = vec![500; 16];
+    predict_chroma_from_luma(&y, &b, &cfl(-8, false, false, 8, 8), &mut pred, 10);
+    assert_eq!((pred[0], pred[3], pred[12]), (500, 494, 506));
+    let mut pred = vec![500; 16];
+    predict_chroma_from_luma(&y, &b, &cfl(0, false, false, 8, 8), &mut pred, 10);
+    assert_eq!(pred, vec![500; 16]);
+}
+
+#[test]
+fn chroma_from_luma_subsampled() {
+    // 4:2:0 的每个色度样本对应 2x2 的亮度，左右两半分别是 0 和 40，均值 20
+    let y = luma(16, 16, |x, _| if x < 8 { 0 } else { 40 });
+    let b = IntraPrediction { plane: 2, tx_size: TxSize::Tx8X8, ..Default::default() };
+    let mut pred = vec![128; 64];
+    // (4 * (8 * 0 - 8 * 20) + 32) >> 6 = -10
+    predict_chroma_from_luma(&y, &b, &cfl(4, true, true, 16, 16), &mut pred, 8);
+    assert!(pred.iter().enumerate().all(|(i, &p)| p == if i % 8 < 4 { 118 } else { 138 }));
+    // 2x2 取平均：奇数列为 40 时每组为 20，没有交流分量
+    let y = luma(16, 16, |x, _| if x % 2 == 1 { 40 } else { 0 });
+    let mut pred = vec![128; 64];
+    predict_chroma_from_luma(&y, &b, &cfl(16, true, true, 16, 16), &mut pred, 8);
+    assert_eq!(pred, vec![128; 64]);
+
+    // 4:2:2 只在水平方向下采样
+    let y = luma(16, 8, |_, y| y as u16 * 8);
+    let b = IntraPrediction { plane: 1, tx_size: TxSize::Tx8X8, ..Default::default() };
+    let mut pred = vec![128; 64];
+    predict_chroma_from_luma(&y, &b, &cfl(8, true, false, 16, 8), &mut pred, 8);
+    for i in 0..8 {
+        // 亮度 8 * i，均值 28
+        assert!(pred[i * 8..i * 8 + 8].iter().all(|&p| p as usize == 128 + 8 * i - 28), "row {i}");
+    }
+}
+
+#[test]
+fn chroma_from_luma_padding_and_clipping() {
+    // 亮度只解码到 (8, 4)，之后的列和行复制最后一组 2x2 样本
+    let y = luma(16, 16, |x, y| if x >= 8 || y >= 4 { 1000 } else { (x + 10 * y) as u16 });
+    let b = IntraPrediction { plane: 1, tx_size: TxSize::Tx8X8, ..Default::default() };
+    let mut padded = vec![512; 64];
+    predict_chroma_from_luma(&y, &b, &cfl(8, true, true, 8, 4), &mut padded, 10);
+    let y = luma(16, 16, |x, y| {
+        let (x, y) = (if x >= 8 { 6 + x % 2 } else { x }, if y >= 4 { 2 + y % 2 } else { y });
+        (x + 10 * y) as u16
+    });
+    let mut expected = vec![512; 64];
+    predict_chroma_from_luma(&y, &b, &cfl(8, true, true, 16, 16), &mut expected, 10);
+    assert_eq!(padded, expected);
+    assert!(padded[4..8].iter().all(|&p| p == padded[3]));
+    assert_eq!(padded[2 * 8..], padded[8..8 * 7]);
+
+    // 结果裁剪到 BitDepth
+    let y = luma(8, 8, |x, _| if x < 4 { 0 } else { 4095 });
+    let b = IntraPrediction { plane: 2, tx_size: TxSize::Tx8X8, ..Default::default() };
+    let mut pred = vec![2048; 64];
+    predict_chroma_from_luma(&y, &b, &cfl(16, false, false, 8, 8), &mut pred, 12);
+    assert!(pred.iter().enumerate().all(|(i, &p)| p == if i % 8 < 4 { 0 } else { 4095 }));
+}
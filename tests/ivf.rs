@@ -0,0 +1,54 @@
+mod common;
+
+use common::write_ivf;
+use wav1d::{container::ivf::{IvfHeader, IvfReader}, obu::ObuIter, utils::enums::ObuType, Error};
+
+
+
+#[test]
+fn header() {
+    let a = write_ivf(352, 288, &[]);
+    let h = IvfHeader::parse(&a).unwrap();
+    assert_eq!((h.width, h.height, h.frame_count), (352, 288, 0));
+    assert_eq!((h.timebase_num, h.timebase_den), (1, 30));
+    assert_eq!(&h.fourcc, b"AV01");
+
+    let mut b = a.clone();
+    b[0] = b'X';
+    assert!(matches!(IvfHeader::parse(&b), Err(Error::InvalidValue { name: "ivf_signature", .. })));
+    let mut b = a.clone();
+    b[8..12].copy_from_slice(b"VP90");
+    assert!(matches!(IvfHeader::parse(&b), Err(Error::InvalidValue { name: "ivf_fourcc", .. })));
+    assert_eq!(IvfHeader::parse(&a[..20]), Err(Error::Truncated { needed: 256, available: 160 }));
+}
+
+
+#[test]
+fn frames() {
+    let td = [0x12, 0x00];
+    let a = write_ivf(64, 64, &[(0, &td), (1, &[]), (3, &[0x12, 0x00, 0x32, 0x00])]);
+    let mut reader = IvfReader::new(&a).unwrap();
+    assert_eq!(reader.header.frame_count, 3);
+
+    let f = reader.next().unwrap().unwrap();
+    assert_eq!((f.timestamp, f.data), (0, td.as_slice()));
+    let (h, p) = ObuIter::from(f.data).next().unwrap().unwrap();
+    assert_eq!((h.obu_type, p.len()), (ObuType::TemporalDelimiter, 0));
+
+    let f = reader.next().unwrap().unwrap();
+    assert_eq!((f.timestamp, f.data.len()), (1, 0));
+    let f = reader.next().unwrap().unwrap();
+    assert_eq!(f.timestamp, 3);
+    assert_eq!(ObuIter::from(f.data).count(), 2);
+    assert!(reader.next().is_none());
+}
+
+
+#[test]
+fn truncated_frame() {
+    let a = write_ivf(64, 64, &[(0, &[0x12, 0x00]), (1, &[0x12, 0x00, 0x32, 0x00])]);
+    let mut reader = IvfReader::new(&a[..a.len() - 1]).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    assert_eq!(reader.next().unwrap(), Err(Error::Truncated { needed: 32, available: 24 }));
+    assert!(reader.next().is_none());
+}
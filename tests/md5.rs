@@ -0,0 +1,44 @@
+use wav1d::output::{Crc32, Md5};
+
+
+
+fn md5(data: &[u8]) -> String {
+    let mut md5 = Md5::new();
+    md5.update(data);
+    md5.hex()
+}
+
+
+#[test]
+fn known_digests() {
+    assert_eq!(md5(b""), "d41d8cd98f00b204e9800998ecf8427e");
+    assert_eq!(md5(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+    assert_eq!(md5(b"The quick brown fox jumps over the lazy dog"), "9e107d9d372bb6826bd81d3542a419d6");
+    assert_eq!(md5(&[b'a'; 64]), "014842d480b571495a4a0363793f7367");
+}
+
+
+#[test]
+fn chunked_updates() {
+    let mut a = Md5::new();
+    for chunk in vec![b'a'; 1_000_000].chunks(777) {
+        a.update(chunk);
+    }
+    assert_eq!(a.hex(), "7707d6ae4e027c70eea2a935c2296f21");
+}
+
+
+#[test]
+fn crc32() {
+    let crc32 = |data: &[u8]| {
+        let mut c = Crc32::new();
+        c.update(data);
+        c.finalize()
+    };
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    let mut c = Crc32::new();
+    c.update(b"The quick brown fox ");
+    c.update(b"jumps over the lazy dog");
+    assert_eq!(c.finalize(), 0x414fa339);
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use common::{temporal_units, write_obu, BitWriter};
+use wav1d::{obu::metadata::{HdrCll, ItutT35, Metadata, ScalabilityStructure, TemporalGroupEntry, Timecode}, utils::enums::ScalabilityModeIdc, Decoder, Error};
+
+
+
+#[test]
+fn hdr() {
+    let mut w = BitWriter::new();
+    w.leb128(1).put(1000, 16).put(400, 16).trailing_bits();
+    assert_eq!(Metadata::parse(&w.bytes()).unwrap(), Metadata::HdrCll(HdrCll { max_cll: 1000, max_fall: 400 }));
+
+    let mut w = BitWriter::new();
+    w.leb128(2);
+    for v in [34000, 16000, 13250, 34500, 7500, 3000, 15635, 16450] {
+        w.put(v, 16);
+    }
+    w.put(1000 << 8, 32).put(50, 32).trailing_bits();
+    let Metadata::HdrMdcv(mdcv) = Metadata::parse(&w.bytes()).unwrap() else { panic!() };
+    assert_eq!(mdcv.primary_chromaticity_x, [34000, 13250, 7500]);
+    assert_eq!(mdcv.primary_chromaticity_y, [16000, 34500, 3000]);
+    assert_eq!((mdcv.white_point_chromaticity_x, mdcv.white_point_chromaticity_y), (15635, 16450));
+    assert_eq!((mdcv.luminance_max, mdcv.luminance_min), (1000 << 8, 50));
+}
+
+
+#[test]
+fn scalability() {
+    let mut w = BitWriter::new();
+    w.leb128(3).put(ScalabilityModeIdc::L2T2 as u64, 8).trailing_bits();
+    let Metadata::Scalability(s) = Metadata::parse(&w.bytes()).unwrap() else { panic!() };
+    assert_eq!((s.scalability_mode_idc, s.scalability_structure), (ScalabilityModeIdc::L2T2, None));
+
+    let mut w = BitWriter::new();
+    w.leb128(3).put(14, 8);
+    // 两个空间层，三个 flag 都置位
+    w.put(1, 2).bit(true).bit(true).bit(true).put(0, 3);
+    w.put(320, 16).put(180, 16).put(640, 16).put(360, 16);
+    w.put(0, 8).put(0, 8);
+    w.put(2, 8);
+    w.put(0, 3).bit(true).bit(false).put(0, 3);
+    w.put(1, 3).bit(false).bit(true).put(2, 3).put(1, 8).put(2, 8);
+    w.trailing_bits();
+    let Metadata::Scalability(s) = Metadata::parse(&w.bytes()).unwrap() else { panic!() };
+    assert_eq!(s.scalability_mode_idc, ScalabilityModeIdc::SS);
+    assert_eq!(s.scalability_structure, Some(ScalabilityStructure {
+        spatial_layers_cnt_minus_1: 1,
+        spatial_layer_dimensions_present_flag: true,
+        spatial_layer_description_present_flag: true,
+        temporal_group_description_present_flag: true,
+        spatial_layer_max_dimensions: vec![(320, 180), (640, 360)],
+        spatial_layer_ref_id: vec![0, 0],
+        temporal_group: vec![
+            TemporalGroupEntry { temporal_id: 0, temporal_switching_up_point_flag: true, spatial_switching_up_point_flag: false, ref_pic_diff: vec![] },
+            TemporalGroupEntry { temporal_id: 1, temporal_switching_up_point_flag: false, spatial_switching_up_point_flag: true, ref_pic_diff: vec![1, 2] },
+        ],
+    }));
+}
+
+
+#[test]
+fn itut_t35() {
+    let mut w = BitWriter::new();
+    w.leb128(4).put(0xb5, 8).put(0x00, 8).put(0x3c, 8).put(0x00, 8).trailing_bits();
+    let expected = ItutT35 { itu_t_t35_country_code: 0xb5, itu_t_t35_country_code_extension_byte: None, itu_t_t35_payload_bytes: vec![0x00, 0x3c, 0x00] };
+    assert_eq!(Metadata::parse(&w.bytes()).unwrap(), Metadata::ItutT35(expected));
+
+    // 0xFF 之后是扩展字节，trailing bits 后面可以补零
+    let data = [4, 0xff, 0x01, 0x80, 0x00, 0x80, 0x00, 0x00];
+    let expected = ItutT35 { itu_t_t35_country_code: 0xff, itu_t_t35_country_code_extension_byte: Some(1), itu_t_t35_payload_bytes: vec![0x80, 0x00] };
+    assert_eq!(Metadata::parse(&data).unwrap(), Metadata::ItutT35(expected));
+
+    assert_eq!(
+        Metadata::parse(&[4, 0xb5, 0x12, 0x00]),
+        Err(Error::Conformance { section: "5.8.2", reason: "itu_t_t35 payload without trailing bits" })
+    );
+}
+
+
+#[test]
+fn timecode() {
+    let mut w = BitWriter::new();
+    w.leb128(5).put(4, 5).bit(true).bit(false).bit(true).put(24, 9);
+    w.put(30, 6).put(15, 6).put(12, 5).put(4, 5).put(9, 4).trailing_bits();
+    assert_eq!(Metadata::parse(&w.bytes()).unwrap(), Metadata::Timecode(Timecode {
+        counting_type: 4,
+        full_timestamp_flag: true,
+        discontinuity_flag: false,
+        cnt_dropped_flag: true,
+        n_frames: 24,
+        seconds_value: Some(30),
+        minutes_value: Some(15),
+        hours_value: Some(12),
+        time_offset_length: 4,
+        time_offset_value: 9,
+    }));
+
+    // seconds_flag 和 minutes_flag 置位，hours_flag 为 0
+    let mut w = BitWriter::new();
+    w.leb128(5).put(0, 5).bit(false).bit(true).bit(false).put(3, 9);
+    w.bit(true).put(59, 6).bit(true).put(1, 6).bit(false).put(0, 5).trailing_bits();
+    let Metadata::Timecode(t) = Metadata::parse(&w.bytes()).unwrap() else { panic!() };
+    assert_eq!((t.seconds_value, t.minutes_value, t.hours_value, t.time_offset_length), (Some(59), Some(1), None, 0));
+
+    let mut w = BitWriter::new();
+    w.leb128(5).put(0, 5).bit(true).bit(false).bit(false).put(0, 9).put(60, 6).put(0, 6).put(0, 5).put(0, 5).trailing_bits();
+    assert_eq!(
+        Metadata::parse(&w.bytes()),
+        Err(Error::Conformance { section: "6.7.7", reason: "timecode value out of range" })
+    );
+}
+
+
+#[test]
+fn unknown_type() {
+    let mut w = BitWriter::new();
+    w.leb128(6).put(0xaa, 8).trailing_bits();
+    assert_eq!(Metadata::parse(&w.bytes()).unwrap(), Metadata::Unknown { metadata_type: 6, payload: vec![0xaa, 0x80] });
+    let mut w = BitWriter::new();
+    w.leb128(300).trailing_bits();
+    assert_eq!(Metadata::parse(&w.bytes()).unwrap(), Metadata::Unknown { metadata_type: 300, payload: vec![0x80] });
+}
+
+
+#[test]
+fn attached_to_picture() {
+    let mut w = BitWriter::new();
+    w.leb128(1).put(1000, 16).put(400, 16).trailing_bits();
+    let cll = write_obu(5, &w.bytes());
+    let units = temporal_units();
+    // metadata 放在第二个 temporal unit 的 temporal delimiter 后面
+    let second = [&units[1][..2], &cll, &units[1][2..]].concat();
+    let data = [units[0].clone(), second, units[2].clone()].concat();
+
+    let mut decoder = Decoder::new();
+    decoder.send_data(&data).unwrap();
+    decoder.flush().unwrap();
+    let metadata: Vec<_> = std::iter::from_fn(|| decoder.get_picture()).map(|p| p.metadata).collect();
+    let expected = Metadata::HdrCll(HdrCll { max_cll: 1000, max_fall: 400 });
+    assert_eq!(metadata, vec![vec![], vec![expected.clone()], vec![]]);
+
+    // 无效的 metadata 被跳过，不影响解码
+    let mut w = BitWriter::new();
+    w.leb128(1).put(1000, 8);
+    let truncated = write_obu(5, &w.bytes());
+    let second = [&units[1][..2], &truncated, &cll, &units[1][2..]].concat();
+    let data = [units[0].clone(), second, units[2].clone()].concat();
+    let mut decoder = Decoder::new();
+    decoder.send_data(&data).unwrap();
+    decoder.flush().unwrap();
+    let metadata: Vec<_> = std::iter::from_fn(|| decoder.get_picture()).map(|p| p.metadata).collect();
+    assert_eq!(metadata, vec![vec![], vec![expected], vec![]]);
+    let errors = decoder.take_metadata_errors();
+    assert!(matches!(errors[..], [Error::Truncated { .. }]), "{errors:?}");
+    assert!(decoder.take_metadata_errors().is_empty());
+}
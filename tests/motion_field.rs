@@ -0,0 +1,113 @@
+mod common;
+
+use common::{write_sequence_header, BitWriter};
+use wav1d::{
+    decoder::{motion_field::{get_mv_projection, motion_field_estimation, save_motion_field}, ModeInfo, ModeInfoGrid},
+    obu::{frame::{FrameHeader, RefFrameState}, sequence::SequenceHeader},
+    utils::{bits::{BitsReader, FromBitsReader}, enums::{FrameType, RefFrame, SubSize}},
+};
+
+
+
+const LAST: i8 = RefFrame::Last as i8;
+
+fn sequence_header() -> SequenceHeader {
+    let mut w = BitWriter::new();
+    write_sequence_header(&mut w, 64, 64);
+    SequenceHeader::from_bits_reader(&mut BitsReader::from(w.bytes().as_slice())).unwrap()
+}
+
+// order_hint 为 2 的 inter frame 写入 slot 0，它的参考帧都在 order_hint 0
+fn previous_frame(seq: &SequenceHeader, mv: [i32; 2]) -> RefFrameState {
+    let fh = FrameHeader {
+        frame_type: FrameType::Inter,
+        mi_rows: 16,
+        mi_cols: 16,
+        order_hint: 2,
+        refresh_frame_flags: 1,
+        ..Default::default()
+    };
+    let mut mi = ModeInfoGrid::new(16, 16);
+    let info = ModeInfo { mi_size: SubSize::Block8X8, is_inter: true, ref_frame: [LAST, -1], mv: [mv, [0, 0]], ..Default::default() };
+    mi.fill(2, 2, 2, 2, &info);
+    let mut refs = RefFrameState::default();
+    refs.update(&fh);
+    save_motion_field(seq, &fh, &mi, &mut refs);
+    refs
+}
+
+// order_hint 为 4，所有参考帧都是 slot 0
+fn current_frame() -> FrameHeader {
+    let mut fh = FrameHeader { frame_type: FrameType::Inter, mi_rows: 16, mi_cols: 16, order_hint: 4, use_ref_frame_mvs: true, ..Default::default() };
+    fh.order_hints[1..].fill(2);
+    fh
+}
+
+
+#[test]
+fn saved_per_8x8() {
+    let seq = sequence_header();
+    let refs = previous_frame(&seq, [64, 32]);
+    let slot = &refs.slots[0];
+    assert_eq!(slot.saved_ref_frames.len(), 64);
+    assert_eq!((slot.saved_ref_frames[9], slot.saved_mvs[9]), (LAST, [64, 32]));
+    assert_eq!(slot.saved_ref_frames[0], -1);
+    assert!(refs.slots[1].saved_ref_frames.is_empty());
+
+    // 超过 REFMVS_LIMIT 的不保存
+    let refs = previous_frame(&seq, [4096, 0]);
+    assert_eq!(refs.slots[0].saved_ref_frames[9], -1);
+}
+
+#[test]
+fn kept_by_show_existing_key_frame() {
+    let seq = sequence_header();
+    let mut refs = previous_frame(&seq, [64, 32]);
+    refs.slots[0].frame_type = FrameType::Key;
+    refs.save_segment_ids(&FrameHeader { refresh_frame_flags: 1, ..Default::default() }, &[3; 256]);
+    // 7.21 显示 slot 0 的 key frame 时刷新所有 slot，保存的运动场和 segment id 来自 slot 0
+    let fh = FrameHeader { show_existing_frame: true, frame_to_show_map_idx: 0, frame_type: FrameType::Key, mi_rows: 16, mi_cols: 16, refresh_frame_flags: 0xff, ..Default::default() };
+    refs.update(&fh);
+    for slot in refs.slots.iter() {
+        assert_eq!((slot.saved_ref_frames[9], slot.saved_mvs[9]), (LAST, [64, 32]));
+        assert_eq!(slot.saved_segment_ids, [3; 256]);
+    }
+    // 其他帧由 save_motion_field 重新保存
+    refs.update(&FrameHeader { refresh_frame_flags: 2, ..Default::default() });
+    assert!(refs.slots[1].saved_mvs.is_empty() && refs.slots[1].saved_segment_ids.is_empty());
+    assert_eq!(refs.slots[0].saved_mvs[9], [64, 32]);
+}
+
+#[test]
+fn projects_onto_current_frame() {
+    let seq = sequence_header();
+    let refs = previous_frame(&seq, [64, 32]);
+    let fh = current_frame();
+    let mf = motion_field_estimation(&seq, &fh, &refs);
+    // 从 (1, 1) 沿运动反方向移动一个 8x8
+    assert_eq!(mf.get(LAST, 0, 1), Some([64, 32]));
+    assert_eq!(mf.get(LAST, 1, 1), None);
+    assert_eq!(mf.get(RefFrame::Altref as i8, 0, 1), Some([64, 32]));
+
+    let fh = FrameHeader { use_ref_frame_mvs: false, ..current_frame() };
+    assert_eq!(motion_field_estimation(&seq, &fh, &refs).get(LAST, 0, 1), None);
+}
+
+#[test]
+fn intra_and_resized_references_are_skipped() {
+    let seq = sequence_header();
+    let mut refs = previous_frame(&seq, [64, 32]);
+    refs.slots[0].frame_type = FrameType::Intra;
+    assert_eq!(motion_field_estimation(&seq, &current_frame(), &refs).get(LAST, 0, 1), None);
+
+    let mut refs = previous_frame(&seq, [64, 32]);
+    refs.slots[0].mi_rows = 18;
+    assert_eq!(motion_field_estimation(&seq, &current_frame(), &refs).get(LAST, 0, 1), None);
+}
+
+#[test]
+fn mv_projection_scaling() {
+    assert_eq!(get_mv_projection([8, -8], 1, 2), [4, -4]);
+    assert_eq!(get_mv_projection([16, 0], 40, 1), [496, 0]);
+    assert_eq!(get_mv_projection([4000, -4000], 31, 1), [(1 << 14) - 1, -(1 << 14) + 1]);
+}
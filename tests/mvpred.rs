@@ -0,0 +1,123 @@
+use wav1d::{
+    decoder::{mvpred::MvBlock, ModeInfo, ModeInfoGrid, MotionFieldMvs, MvPredictor, MvStack},
+    obu::frame::FrameHeader,
+    utils::enums::{InterMode, RefFrame, SubSize},
+};
+
+
+
+const LAST: i8 = RefFrame::Last as i8;
+const BWDREF: i8 = RefFrame::Bwdref as i8;
+
+// 16x16 个 4x4 的帧，只有一个 tile
+fn frame_header() -> FrameHeader {
+    FrameHeader { mi_rows: 16, mi_cols: 16, allow_high_precision_mv: true, ..Default::default() }
+}
+
+fn inter_block(mi_size: SubSize, ref_frame: i8, mv: [i32; 2], inter_mode: InterMode) -> ModeInfo {
+    ModeInfo { mi_size, is_inter: true, ref_frame: [ref_frame, -1], mv: [mv, [0, 0]], inter_mode, ..Default::default() }
+}
+
+fn find(fh: &FrameHeader, mi: &ModeInfoGrid, motion_field: Option<&MotionFieldMvs>, block: MvBlock) -> MvStack {
+    let predictor = MvPredictor { fh, mi, motion_field, tile: [0, 16, 0, 16] };
+    predictor.find_mv_stack(&block)
+}
+
+fn block_8x8(mi_row: u32, mi_col: u32, ref_frame: [i8; 2]) -> MvBlock {
+    MvBlock { mi_row, mi_col, mi_size: SubSize::Block8X8, ref_frame }
+}
+
+
+#[test]
+fn no_neighbours_uses_global_mv() {
+    let fh = frame_header();
+    let mi = ModeInfoGrid::new(16, 16);
+    let stack = find(&fh, &mi, None, block_8x8(0, 0, [LAST, -1]));
+    assert_eq!(stack.num_mv_found, 0);
+    assert_eq!((stack.new_mv_context, stack.ref_mv_context, stack.zero_mv_context), (0, 0, 0));
+    assert_eq!(stack.ref_stack_mv[0][0], [0, 0]);
+    assert_eq!(stack.ref_stack_mv[1][0], [0, 0]);
+}
+
+#[test]
+fn spatial_neighbours_weights_and_contexts() {
+    let fh = frame_header();
+    let mut mi = ModeInfoGrid::new(16, 16);
+    // 当前块在 (4, 4)，上方、左边各一个 8x8，再往上一行有一个 4x4
+    mi.fill(2, 4, 2, 2, &inter_block(SubSize::Block8X8, LAST, [8, 4], InterMode::NewMv));
+    mi.fill(4, 2, 2, 2, &inter_block(SubSize::Block8X8, LAST, [-6, 2], InterMode::GlobalMv));
+    mi.fill(1, 5, 1, 1, &inter_block(SubSize::Block4X4, LAST, [20, 20], InterMode::NearestMv));
+    // 参考帧不同的右上角不会加入
+    mi.fill(2, 6, 2, 2, &inter_block(SubSize::Block8X8, BWDREF, [100, 100], InterMode::NearestMv));
+    let stack = find(&fh, &mi, None, block_8x8(4, 4, [LAST, -1]));
+    assert_eq!(stack.num_mv_found, 3);
+    assert_eq!(stack.ref_stack_mv[0][0], [8, 4]);
+    assert_eq!(stack.ref_stack_mv[1][0], [-6, 2]);
+    assert_eq!(stack.ref_stack_mv[2][0], [20, 20]);
+    assert_eq!(&stack.weight_stack[..3], &[644, 644, 4]);
+    assert_eq!(&stack.drl_ctx_stack[..2], &[0, 1]);
+    // 两边都有匹配，其中一个是 NEWMV
+    assert_eq!((stack.new_mv_context, stack.ref_mv_context), (4, 5));
+}
+
+#[test]
+fn top_right_only_once_decoded() {
+    let fh = frame_header();
+    let mut mi = ModeInfoGrid::new(16, 16);
+    mi.fill(0, 0, 2, 2, &inter_block(SubSize::Block8X8, LAST, [8, 8], InterMode::NearestMv));
+    let block = block_8x8(2, 0, [LAST, -1]);
+    let stack = find(&fh, &mi, None, block);
+    assert_eq!(stack.num_mv_found, 1);
+    assert_eq!((stack.new_mv_context, stack.ref_mv_context), (3, 3));
+
+    mi.fill(0, 2, 2, 2, &inter_block(SubSize::Block8X8, LAST, [16, 16], InterMode::NearestMv));
+    let stack = find(&fh, &mi, None, block);
+    assert_eq!(stack.num_mv_found, 2);
+    assert_eq!(stack.ref_stack_mv[1][0], [16, 16]);
+    assert_eq!(stack.weight_stack[1], 644);
+}
+
+#[test]
+fn compound_extra_search() {
+    let fh = frame_header();
+    let mut mi = ModeInfoGrid::new(16, 16);
+    mi.fill(2, 4, 2, 2, &inter_block(SubSize::Block8X8, LAST, [8, 8], InterMode::NearestMv));
+    mi.fill(4, 2, 2, 2, &inter_block(SubSize::Block8X8, BWDREF, [-4, -4], InterMode::NearestMv));
+    let stack = find(&fh, &mi, None, block_8x8(4, 4, [LAST, BWDREF]));
+    // 没有完全相同的参考帧对，由 RefIdMvs 和 RefDiffMvs 组合
+    assert_eq!(stack.num_mv_found, 2);
+    assert_eq!(stack.ref_stack_mv[0], [[8, 8], [-4, -4]]);
+    assert_eq!(stack.ref_stack_mv[1], [[-4, -4], [8, 8]]);
+    assert_eq!((stack.new_mv_context, stack.ref_mv_context), (0, 0));
+}
+
+#[test]
+fn temporal_candidates_and_zero_mv_context() {
+    let fh = FrameHeader { use_ref_frame_mvs: true, ..frame_header() };
+    let mi = ModeInfoGrid::new(16, 16);
+    let block = block_8x8(4, 4, [LAST, -1]);
+    let mut motion_field = MotionFieldMvs::new(16, 16);
+    // (0, 0) 处没有投影
+    assert_eq!(find(&fh, &mi, Some(&motion_field), block).zero_mv_context, 1);
+
+    motion_field.set(LAST, 2, 2, Some([2, -2]));
+    let stack = find(&fh, &mi, Some(&motion_field), block);
+    assert_eq!(stack.zero_mv_context, 0);
+    assert_eq!(stack.num_mv_found, 1);
+    assert_eq!(stack.ref_stack_mv[0][0], [2, -2]);
+    assert_eq!(stack.weight_stack[0], 2);
+
+    motion_field.set(LAST, 2, 2, Some([40, 0]));
+    assert_eq!(find(&fh, &mi, Some(&motion_field), block).zero_mv_context, 1);
+}
+
+#[test]
+fn candidates_are_clamped() {
+    let fh = frame_header();
+    let mut mi = ModeInfoGrid::new(16, 16);
+    mi.fill(0, 0, 2, 2, &inter_block(SubSize::Block8X8, LAST, [-2000, 4], InterMode::NearestMv));
+    let stack = find(&fh, &mi, None, block_8x8(0, 2, [LAST, -1]));
+    assert_eq!(stack.num_mv_found, 1);
+    // MV_BORDER + 8 像素
+    assert_eq!(stack.ref_stack_mv[0][0], [-192, 4]);
+}
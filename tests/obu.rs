@@ -0,0 +1,80 @@
+mod common;
+
+use common::BitWriter;
+use wav1d::{obu::ObuIter, utils::{bits::BitsReader, enums::ObuType}, Error};
+
+
+
+fn obu(w: &mut BitWriter, obu_type: u64, extension: Option<(u64, u64)>, payload: &[u8], has_size: bool) {
+    w.bit(false).put(obu_type, 4).bit(extension.is_some()).bit(has_size).bit(false);
+    if let Some((temporal_id, spatial_id)) = extension {
+        w.put(temporal_id, 3).put(spatial_id, 2).put(0, 3);
+    }
+    if has_size {
+        w.leb128(payload.len() as u64);
+    }
+    for &b in payload {
+        w.put(b as u64, 8);
+    }
+}
+
+
+#[test]
+fn leb128() {
+    let mut w = BitWriter::new();
+    w.leb128(0).leb128(127).leb128(128).leb128(300).leb128(u32::MAX as u64);
+    let a = w.bytes();
+    let mut r = BitsReader::from(a.as_slice());
+    assert_eq!(r.read_leb128(), Ok(0));
+    assert_eq!(r.read_leb128(), Ok(127));
+    assert_eq!(r.read_leb128(), Ok(128));
+    assert_eq!(r.read_leb128(), Ok(300));
+    assert_eq!(r.read_leb128(), Ok(u32::MAX as usize));
+
+    let a = [0x80; 9];
+    let mut r = BitsReader::from(a.as_slice());
+    assert!(matches!(r.read_leb128(), Err(Error::Conformance { .. })));
+}
+
+
+#[test]
+fn iterate() {
+    let mut w = BitWriter::new();
+    obu(&mut w, 2, None, &[], true);
+    obu(&mut w, 1, Some((2, 1)), &[1, 2, 3], true);
+    obu(&mut w, 15, None, &[9; 200], true);
+    obu(&mut w, 6, None, &[4, 5], false);
+    let a = w.bytes();
+
+    let mut iter = ObuIter::from(a.as_slice());
+    let (h, p) = iter.next().unwrap().unwrap();
+    assert_eq!((h.obu_type, p.len(), iter.offset()), (ObuType::TemporalDelimiter, 0, 0));
+
+    let (h, p) = iter.next().unwrap().unwrap();
+    assert_eq!((h.obu_type, h.temporal_id, h.spatial_id), (ObuType::SequenceHeader, 2, 1));
+    assert_eq!((p, iter.offset()), ([1u8, 2, 3].as_slice(), 2));
+
+    let (h, p) = iter.next().unwrap().unwrap();
+    assert_eq!((h.obu_type, h.obu_size, p.len(), iter.offset()), (ObuType::Unknown, 200, 200, 8));
+
+    let (h, p) = iter.next().unwrap().unwrap();
+    assert_eq!((h.obu_type, h.obu_has_size_field, h.obu_size), (ObuType::Frame, false, 2));
+    assert_eq!((p, iter.offset()), ([4u8, 5].as_slice(), 211));
+    assert!(iter.next().is_none());
+}
+
+
+#[test]
+fn truncated_payload() {
+    let mut w = BitWriter::new();
+    obu(&mut w, 2, None, &[], true);
+    obu(&mut w, 1, None, &[1, 2, 3], true);
+    let mut a = w.bytes();
+    a.pop();
+
+    let mut iter = ObuIter::from(a.as_slice());
+    assert!(iter.next().unwrap().is_ok());
+    assert_eq!(iter.next().unwrap().err(), Some(Error::Truncated { needed: 24, available: 16 }));
+    assert_eq!(iter.offset(), 2);
+    assert!(iter.next().is_none());
+}
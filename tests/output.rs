@@ -0,0 +1,129 @@
+mod common;
+
+use common::picture;
+use wav1d::{output::{hash::hex, y4m::colorspace, Crc32, HashWriter, Md5, PictureWriter, Y4mWriter, YuvWriter}, utils::enums::ChromaSamplePosition};
+
+
+
+#[test]
+fn y4m_colorspace() {
+    let cases = [
+        (8, false, (true, true), ChromaSamplePosition::Unknown, "420jpeg"),
+        (8, false, (true, true), ChromaSamplePosition::Vertical, "420mpeg2"),
+        (8, false, (true, true), ChromaSamplePosition::CoLocated, "420paldv"),
+        (10, false, (true, true), ChromaSamplePosition::Vertical, "420p10"),
+        (12, false, (true, true), ChromaSamplePosition::Unknown, "420p12"),
+        (8, false, (true, false), ChromaSamplePosition::Unknown, "422"),
+        (10, false, (true, false), ChromaSamplePosition::Unknown, "422p10"),
+        (8, false, (false, false), ChromaSamplePosition::Unknown, "444"),
+        (12, false, (false, false), ChromaSamplePosition::Unknown, "444p12"),
+        (8, true, (true, true), ChromaSamplePosition::Unknown, "mono"),
+        (10, true, (true, true), ChromaSamplePosition::Unknown, "mono10"),
+    ];
+    for (bit_depth, mono, ss, csp, tag) in cases {
+        assert_eq!(colorspace(&picture(16, 16, bit_depth, mono, ss, csp)), tag);
+    }
+}
+
+
+#[test]
+fn y4m_frames() {
+    let mut p = picture(4, 2, 8, false, (true, true), ChromaSamplePosition::Unknown);
+    p.planes[0].set(3, 1, 7);
+    p.planes[2].set(1, 0, 200);
+    let mut w = Y4mWriter::new(Vec::new(), (30, 1));
+    w.write_picture(&p).unwrap();
+    w.write_picture(&p).unwrap();
+    let a = w.into_inner();
+
+    let header = b"YUV4MPEG2 W4 H2 F30:1 Ip A0:0 C420jpeg\n";
+    let frame = [b"FRAME\n".as_slice(), &[128, 128, 128, 128, 128, 128, 128, 7], &[128, 128], &[128, 200]].concat();
+    assert_eq!(a, [header.as_slice(), &frame, &frame].concat());
+
+    let mut w = Y4mWriter::new(Vec::new(), (30, 1));
+    w.write_picture(&p).unwrap();
+    let p = picture(8, 2, 8, false, (true, true), ChromaSamplePosition::Unknown);
+    assert!(w.write_picture(&p).is_err());
+}
+
+
+#[test]
+fn yuv_frames() {
+    let mut p = picture(3, 3, 10, false, (true, false), ChromaSamplePosition::Unknown);
+    assert_eq!((p.planes[1].width, p.planes[1].height), (2, 3));
+    p.planes[0].set(0, 0, 1023);
+    let mut w = YuvWriter::new(Vec::new());
+    w.write_picture(&p).unwrap();
+    let a = w.into_inner();
+    assert_eq!(a.len(), (9 + 6 + 6) * 2);
+    assert_eq!(a[..4], [0xff, 0x03, 0x00, 0x02]);
+
+    let p = picture(3, 3, 8, true, (true, true), ChromaSamplePosition::Unknown);
+    let mut w = YuvWriter::new(Vec::new());
+    w.write_picture(&p).unwrap();
+    assert_eq!(w.into_inner(), vec![128; 9]);
+}
+
+
+#[test]
+fn bit_depth_conversion() {
+    let mut p = picture(2, 2, 10, true, (true, true), ChromaSamplePosition::Unknown);
+    p.planes[0].data = vec![0, 2, 1021, 1023];
+    let q = p.with_bit_depth(8);
+    assert_eq!((q.bit_depth(), q.color_config.high_bitdepth), (8, false));
+    assert_eq!(q.planes[0].data, [0, 1, 255, 255]);
+    let q = p.with_bit_depth(12);
+    assert!(q.color_config.twelve_bit);
+    assert_eq!(q.planes[0].data, [0, 8, 4084, 4092]);
+}
+
+
+#[test]
+fn hashes() {
+    let mut a = picture(4, 4, 8, false, (true, true), ChromaSamplePosition::Unknown);
+    let mut b = picture(4, 4, 10, false, (false, false), ChromaSamplePosition::Unknown);
+    let mut hash = HashWriter::new(true);
+    // 没有重建的图像不能计算 hash
+    assert_eq!(hash.write_picture(&a).unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert!(hash.frames().is_empty());
+    a.reconstructed = true;
+    b.reconstructed = true;
+    b.planes[1].set(2, 3, 1000);
+    let mut yuv = YuvWriter::new(Vec::new());
+    let mut frames = Vec::new();
+    for p in [&a, &b] {
+        let mut w = YuvWriter::new(Vec::new());
+        w.write_picture(p).unwrap();
+        frames.push(w.into_inner());
+        yuv.write_picture(p).unwrap();
+        hash.write_picture(p).unwrap();
+    }
+    let yuv = yuv.into_inner();
+
+    let md5 = |data: &[u8]| {
+        let mut m = Md5::new();
+        m.update(data);
+        m.finalize()
+    };
+    let crc32 = |data: &[u8]| {
+        let mut c = Crc32::new();
+        c.update(data);
+        c.finalize()
+    };
+    assert_eq!(hash.md5(), md5(&yuv));
+    assert_eq!(hash.crc32(), Some(crc32(&yuv)));
+    assert_eq!(hash.frames().len(), 2);
+    assert_eq!(hash.frames()[1].md5, md5(&frames[1]));
+    assert_eq!(hash.frames()[1].crc32, Some(crc32(&frames[1])));
+
+    assert_eq!(hash.stream_report(), format!("{}  crc32:{:08x}\n", hex(&md5(&yuv)), crc32(&yuv)));
+    let report = hash.frame_report("clip");
+    let lines: Vec<_> = report.lines().collect();
+    assert_eq!(lines[0], format!("{}  clip-4x4-0001.i420  crc32:{:08x}", hex(&md5(&frames[0])), crc32(&frames[0])));
+    assert!(lines[1].contains("  clip-4x4-0002.i420  crc32:"));
+
+    let mut hash = HashWriter::new(false);
+    hash.write_picture(&a).unwrap();
+    assert_eq!(hash.stream_report(), format!("{}\n", hex(&md5(&frames[0]))));
+    assert_eq!(hash.frames()[0].crc32, None);
+}
@@ -0,0 +1,82 @@
+use wav1d::{
+    decoder::palette::{get_palette_cache, predict_palette, wavefront, ColorMap},
+    utils::enums::TxSize,
+};
+
+
+
+fn color_map(width: usize, height: usize, indices: &[u8]) -> ColorMap {
+    ColorMap { width, height, indices: indices.to_vec() }
+}
+
+
+#[test]
+fn palette_cache() {
+    assert_eq!(get_palette_cache(&[], &[]), []);
+    assert_eq!(get_palette_cache(&[3, 9, 40], &[]), [3, 9, 40]);
+    // 合并两个有序的调色板，去掉重复的颜色
+    assert_eq!(get_palette_cache(&[10, 20, 30], &[5, 20, 25, 30, 99]), [5, 10, 20, 25, 30, 99]);
+    assert_eq!(get_palette_cache(&[1, 1, 7], &[1, 7, 7, 8]), [1, 7, 8]);
+    assert_eq!(get_palette_cache(&[200, 255], &[0, 4]), [0, 4, 200, 255]);
+}
+
+#[test]
+fn wavefront_order() {
+    let order: Vec<_> = wavefront(3, 2).collect();
+    assert_eq!(order, [(0, 0), (0, 1), (1, 0), (0, 2), (1, 1), (1, 2)]);
+    let order: Vec<_> = wavefront(2, 3).collect();
+    assert_eq!(order, [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]);
+    // 每个位置的左边、左上和上方都在它之前
+    let order: Vec<_> = wavefront(16, 8).collect();
+    assert_eq!(order.len(), 128);
+    for (k, &(r, c)) in order.iter().enumerate() {
+        let before = |p: (usize, usize)| order[..k].contains(&p);
+        assert!((c == 0 || before((r, c - 1))) && (r == 0 || before((r - 1, c))) && (r == 0 || c == 0 || before((r - 1, c - 1))));
+    }
+}
+
+#[test]
+fn color_context() {
+    // 第一行和第一列只有一个邻居，hash 为 2
+    let map = color_map(4, 4, &[3, 1, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(map.color_context(0, 1, 4), (0, [3, 0, 1, 2, 4, 5, 6, 7]));
+    assert_eq!(map.color_context(1, 0, 4), (0, [3, 0, 1, 2, 4, 5, 6, 7]));
+    // 左边 2 (+2)，左上 3 (+1)，上方 1 (+2)：得分相同时保持原来的顺序，hash 2 + 2 * 2 + 2 * 1 = 8
+    assert_eq!(map.color_context(1, 1, 4), (1, [1, 2, 3, 0, 4, 5, 6, 7]));
+    // 三个邻居相同，hash 5
+    let map = color_map(2, 2, &[1, 1, 1, 0]);
+    assert_eq!(map.color_context(1, 1, 2), (4, [1, 0, 2, 3, 4, 5, 6, 7]));
+    // 左边和上方相同，左上不同，hash 4 + 2 = 6
+    let map = color_map(2, 2, &[5, 2, 2, 0]);
+    assert_eq!(map.color_context(1, 1, 6), (3, [2, 5, 0, 1, 3, 4, 6, 7]));
+    // 左边和左上相同，hash 3 + 2 * 2 = 7
+    let map = color_map(2, 2, &[4, 6, 4, 0]);
+    assert_eq!(map.color_context(1, 1, 8), (2, [4, 6, 0, 1, 2, 3, 5, 7]));
+    // 只考虑前 n 个颜色
+    let map = color_map(2, 1, &[1, 0]);
+    assert_eq!(map.color_context(0, 1, 2), (0, [1, 0, 2, 3, 4, 5, 6, 7]));
+}
+
+#[test]
+fn extend_offscreen_part() {
+    let mut map = color_map(4, 4, &[1, 2, 9, 9, 3, 4, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9]);
+    map.extend(2, 2);
+    assert_eq!(map.indices, [1, 2, 2, 2, 3, 4, 4, 4, 3, 4, 4, 4, 3, 4, 4, 4]);
+}
+
+#[test]
+fn palette_prediction() {
+    let indices: Vec<u8> = (0..64).map(|i| ((i % 8 + i / 8) % 3) as u8).collect();
+    let map = color_map(8, 8, &indices);
+    let palette = [7, 300, 1023];
+    let pred = predict_palette(&palette, &map, 0, 0, TxSize::Tx8X8);
+    assert_eq!(pred[..4], [7, 300, 1023, 7]);
+    assert_eq!(pred[8..12], [300, 1023, 7, 300]);
+    // 变换块在调色板块中的位置
+    let pred = predict_palette(&palette, &map, 4, 4, TxSize::Tx4X4);
+    let expected: Vec<u16> = (0..16).map(|k| palette[(8 + k / 4 + k % 4) % 3]).collect();
+    assert_eq!(pred, expected);
+    let pred = predict_palette(&palette, &map, 0, 4, TxSize::Tx8X4);
+    assert_eq!(pred.len(), 32);
+    assert_eq!(pred[0], palette[indices[32] as usize]);
+}
@@ -0,0 +1,134 @@
+use wav1d::{
+    decoder::quant::{ac_q, dc_q, Quantizer},
+    obu::frame::{DeltaParams, FrameHeader, QuantizationParams, SegmentationParams},
+    utils::{consts::{QM_OFFSET, QM_TOTAL_SIZE, SEG_LVL_ALT_Q}, enums::{TxSize, TxType}},
+    Error,
+};
+
+
+
+fn frame_header(quantization: QuantizationParams) -> FrameHeader {
+    FrameHeader { quantization, ..Default::default() }
+}
+
+
+#[test]
+fn lookup_tables() {
+    assert_eq!((dc_q(0, 8), dc_q(0, 10), dc_q(0, 12)), (4, 4, 4));
+    assert_eq!((dc_q(255, 8), dc_q(255, 10), dc_q(255, 12)), (1336, 5347, 21387));
+    assert_eq!((ac_q(255, 8), ac_q(255, 10), ac_q(255, 12)), (1828, 7312, 29247));
+    assert_eq!((dc_q(100, 8), ac_q(100, 8)), (93, 112));
+    // 8 位的 ac_q 在 1..=95 之间为 qindex + 7
+    assert!((1..96).all(|q| ac_q(q, 8) == q + 7));
+    // 越界的 qindex 被裁剪
+    assert_eq!(dc_q(-20, 10), 4);
+    assert_eq!(ac_q(300, 8), 1828);
+    for bit_depth in [8, 10, 12] {
+        assert!((1..256).all(|q| dc_q(q, bit_depth) >= dc_q(q - 1, bit_depth) && ac_q(q, bit_depth) > ac_q(q - 1, bit_depth)));
+    }
+}
+
+#[test]
+fn plane_deltas() {
+    let fh = frame_header(QuantizationParams {
+        base_q_idx: 100,
+        delta_q_y_dc: -4,
+        delta_q_u_dc: 3,
+        delta_q_u_ac: -2,
+        delta_q_v_dc: 10,
+        delta_q_v_ac: 200,
+        ..Default::default()
+    });
+    let y = Quantizer::new(&fh, 0, 0, 100, 8);
+    assert_eq!((y.dc, y.ac, y.qm_level), (dc_q(96, 8), ac_q(100, 8), 15));
+    let u = Quantizer::new(&fh, 1, 0, 100, 10);
+    assert_eq!((u.dc, u.ac), (dc_q(103, 10), ac_q(98, 10)));
+    let v = Quantizer::new(&fh, 2, 0, 100, 12);
+    assert_eq!((v.dc, v.ac), (dc_q(110, 12), ac_q(255, 12)));
+}
+
+#[test]
+fn segment_and_delta_qindex() {
+    let mut segmentation = SegmentationParams { segmentation_enabled: true, ..Default::default() };
+    segmentation.feature_enabled[2][SEG_LVL_ALT_Q] = true;
+    segmentation.feature_data[2][SEG_LVL_ALT_Q] = -30;
+    let mut fh = FrameHeader {
+        quantization: QuantizationParams { base_q_idx: 80, ..Default::default() },
+        segmentation,
+        ..Default::default()
+    };
+    // 没有 delta_q 时忽略 CurrentQIndex
+    assert_eq!(Quantizer::new(&fh, 0, 0, 120, 8).ac, ac_q(80, 8));
+    assert_eq!(Quantizer::new(&fh, 0, 2, 120, 8).ac, ac_q(50, 8));
+    fh.delta = DeltaParams { delta_q_present: true, ..Default::default() };
+    assert_eq!(Quantizer::new(&fh, 0, 0, 120, 8).ac, ac_q(120, 8));
+    assert_eq!(Quantizer::new(&fh, 0, 2, 120, 8).ac, ac_q(90, 8));
+    assert_eq!(Quantizer::new(&fh, 0, 2, 20, 8).ac, ac_q(0, 8));
+}
+
+#[test]
+fn dequantize_coefficients() {
+    let quantizer = Quantizer { plane: 0, bit_depth: 8, dc: 20, ac: 30, qm_level: 15 };
+    let mut quant = vec![0; 16];
+    quant[0] = -3;
+    quant[5] = 7;
+    let dequant = quantizer.dequantize(&quant, TxSize::Tx4X4, TxType::DctDct).unwrap();
+    assert_eq!((dequant[0], dequant[5]), (-60, 210));
+    assert_eq!(dequant.iter().filter(|&&x| x != 0).count(), 2);
+
+    // 32x32 类的块除以 2，64 点的块除以 4，向 0 取整
+    let mut quant = vec![0; 1024];
+    quant[0] = -1;
+    quant[1] = 3;
+    for (tx_size, expected) in [(TxSize::Tx16X32, [-10, 45]), (TxSize::Tx64X16, [-10, 45]), (TxSize::Tx32X64, [-5, 22]), (TxSize::Tx64X64, [-5, 22])] {
+        let dequant = quantizer.dequantize(&quant, tx_size, TxType::DctDct).unwrap();
+        assert_eq!(dequant[..2], expected, "{tx_size:?}");
+    }
+}
+
+#[test]
+fn dequantize_mask_and_clamp() {
+    let quantizer = Quantizer { plane: 0, bit_depth: 8, dc: 1336, ac: 1828, qm_level: 15 };
+    // 裁剪到 BitDepth + 8 位
+    let dequant = quantizer.dequantize(&[0xFFFFF, -0xFFFFF, 100, -1], TxSize::Tx4X4, TxType::DctDct).unwrap();
+    assert_eq!(dequant, [32767, -32768, 32767, -1828]);
+    // 乘积只保留低 24 位：574 * 29247 = 0x1000000 + 10562
+    let quantizer = Quantizer { plane: 0, bit_depth: 12, dc: 4, ac: 29247, qm_level: 15 };
+    let dequant = quantizer.dequantize(&[0, 573, -574, 574], TxSize::Tx4X4, TxType::DctDct).unwrap();
+    assert_eq!(dequant, [0, (1 << 19) - 1, -10562, 10562]);
+}
+
+#[test]
+fn quantizer_matrix_levels() {
+    let mut fh = frame_header(QuantizationParams { base_q_idx: 60, using_qmatrix: true, qm_y: 4, qm_u: 7, qm_v: 15, ..Default::default() });
+    fh.seg_qm_level = [[4; 8], [7; 8], [15; 8]];
+    assert_eq!(Quantizer::new(&fh, 0, 0, 60, 8).qm_level, 4);
+    assert_eq!(Quantizer::new(&fh, 1, 3, 60, 8).qm_level, 7);
+    let v = Quantizer::new(&fh, 2, 0, 60, 8);
+    assert_eq!(v.qm_level, 15);
+    // 第 15 级为平坦矩阵
+    assert_eq!(v.dequantize(&[1, 1, 0, 0], TxSize::Tx4X4, TxType::AdstAdst).unwrap(), [v.dc, v.ac, 0, 0]);
+    let y = Quantizer::new(&fh, 0, 0, 60, 8);
+    // 一维和 identity 变换不使用量化矩阵
+    assert_eq!(y.dequantize(&[1, 1, 0, 0], TxSize::Tx4X4, TxType::Idtx).unwrap(), [y.dc, y.ac, 0, 0]);
+    assert_eq!(y.dequantize(&[1, 1, 0, 0], TxSize::Tx4X4, TxType::VDct).unwrap(), [y.dc, y.ac, 0, 0]);
+    assert!(matches!(y.dequantize(&[1, 1, 0, 0], TxSize::Tx4X4, TxType::DctDct), Err(Error::Unsupported(_))));
+}
+
+#[test]
+fn quantizer_matrix_weights() {
+    let quantizer = Quantizer { plane: 1, bit_depth: 8, dc: 20, ac: 30, qm_level: 3 };
+    // 权重 32 相当于平坦矩阵，q * qm 按 AOM_QM_BITS 四舍五入
+    let mut qm = vec![32; 16];
+    qm[0] = 64;
+    qm[1] = 17;
+    qm[2] = 16;
+    let dequant = quantizer.dequantize_with_matrix(&[1, 2, -3, 4], TxSize::Tx4X4, Some(&qm));
+    assert_eq!(dequant, [40, 2 * 16, -3 * 15, 4 * 30]);
+    assert_eq!(quantizer.dequantize_with_matrix(&[1, 2, -3, 4], TxSize::Tx4X4, None), [20, 60, -90, 120]);
+    // 64 点的变换使用 32 点的权重
+    assert_eq!(QM_OFFSET[TxSize::Tx64X64 as usize], QM_OFFSET[TxSize::Tx32X32 as usize]);
+    assert_eq!(QM_OFFSET[TxSize::Tx16X64 as usize], QM_OFFSET[TxSize::Tx16X32 as usize]);
+    let end = QM_OFFSET[TxSize::Tx32X8 as usize] as usize + 32 * 8;
+    assert_eq!(end, QM_TOTAL_SIZE as usize);
+}
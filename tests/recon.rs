@@ -0,0 +1,39 @@
+use wav1d::{
+    decoder::{intra_pred::IntraPrediction, palette::PalettePrediction, recon::reconstruct_intra, transform::inverse_transform_block, Plane, TransformBlock},
+    utils::enums::{IntraFrameYMode, TxSize, TxType},
+};
+
+
+
+#[test]
+fn palette_then_intra() {
+    let mut frame = vec![Plane::new(16, 16, 128)];
+    // 左边 4x4 是调色板块，加上 DC 残差
+    let palette = PalettePrediction { plane: 0, start_x: 0, start_y: 0, tx_size: TxSize::Tx4X4, pred: (0..16).map(|k| 100 + k).collect() };
+    let mut dequant = vec![0; 16];
+    dequant[0] = 64;
+    let residual = TransformBlock { plane: 0, start_x: 0, start_y: 0, tx_size: TxSize::Tx4X4, tx_type: TxType::DctDct, eob: 1, quant: vec![0; 16], dequant };
+    let r = inverse_transform_block(TxSize::Tx4X4, TxType::DctDct, 8, &residual.dequant);
+    assert!(r[0] > 0);
+    // 右边的 DC_PRED 块只有左边的相邻样本
+    let intra = IntraPrediction {
+        start_x: 4,
+        tx_size: TxSize::Tx4X4,
+        mode: IntraFrameYMode::Dc,
+        have_left: true,
+        max_x: 15,
+        max_y: 15,
+        ..Default::default()
+    };
+    reconstruct_intra(&mut frame, &[intra], &[palette], &[residual], 8, false);
+
+    let expected: Vec<u16> = (0..16).map(|k| (100 + k + r[k as usize]) as u16).collect();
+    for (k, &sample) in expected.iter().enumerate() {
+        assert_eq!(frame[0].get(k % 4, k / 4), sample);
+    }
+    let dc = ((0..4).map(|i| expected[i * 4 + 3] as u32).sum::<u32>() + 2) as u16 / 4;
+    assert!((0..4).all(|y| (4..8).all(|x| frame[0].get(x, y) == dc)));
+    // 没有预测的位置保持不变
+    assert_eq!(frame[0].get(8, 0), 128);
+    assert_eq!(frame[0].get(0, 4), 128);
+}
@@ -0,0 +1,48 @@
+use wav1d::{
+    decoder::tile::residual::get_scan,
+    utils::{consts::{TX_HEIGHT, TX_SIZES_ALL, TX_SIZE_SQR_UP, TX_WIDTH}, enums::{TxSize, TxType}, scan::*},
+};
+
+
+
+#[test]
+fn default_scans_match_spec() {
+    assert_eq!(DEFAULT_SCAN_4X4, [0, 1, 4, 8, 5, 2, 3, 6, 9, 12, 13, 10, 7, 11, 14, 15]);
+    assert_eq!(DEFAULT_SCAN_8X8[..16], [0, 8, 1, 2, 9, 16, 24, 17, 10, 3, 4, 11, 18, 25, 32, 40]);
+    assert_eq!(DEFAULT_SCAN_16X16[..10], [0, 16, 1, 2, 17, 32, 48, 33, 18, 3]);
+    assert_eq!(DEFAULT_SCAN_4X8[..12], [0, 1, 4, 2, 5, 8, 3, 6, 9, 12, 7, 10]);
+    assert_eq!(DEFAULT_SCAN_8X4[..12], [0, 8, 1, 16, 9, 2, 24, 17, 10, 3, 25, 18]);
+    assert_eq!(DEFAULT_SCAN_8X4[29..], [30, 23, 31]);
+    assert_eq!(DEFAULT_SCAN_16X4[..8], [0, 16, 1, 32, 17, 2, 48, 33]);
+    assert_eq!(MCOL_SCAN_4X4, [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15]);
+    assert_eq!(MCOL_SCAN_8X4[..6], [0, 8, 16, 24, 1, 9]);
+}
+
+#[test]
+fn scans_are_permutations() {
+    for tx_size in 0..TX_SIZES_ALL as u8 {
+        // 1D 变换只用于 16x16 及以下
+        let types: &[TxType] = if TX_SIZE_SQR_UP[tx_size as usize] <= TxSize::Tx16X16 as u8 { &[TxType::DctDct, TxType::Idtx, TxType::VDct, TxType::HAdst] } else { &[TxType::DctDct, TxType::Idtx] };
+        for &tx_type in types {
+            let scan = get_scan(TxSize::from(tx_size), tx_type);
+            let area = (TX_WIDTH[tx_size as usize] as usize * TX_HEIGHT[tx_size as usize] as usize).min(1024);
+            let area = if tx_size == TxSize::Tx16X64 as u8 || tx_size == TxSize::Tx64X16 as u8 { 512 } else { area };
+            let mut seen = vec![false; area];
+            for &pos in scan {
+                seen[pos as usize] = true;
+            }
+            assert_eq!(scan.len(), area);
+            assert!(seen.iter().all(|&s| s));
+        }
+    }
+}
+
+#[test]
+fn scan_follows_tx_class() {
+    assert_eq!(get_scan(TxSize::Tx8X8, TxType::VDct), &MROW_SCAN_8X8);
+    assert_eq!(get_scan(TxSize::Tx16X4, TxType::HAdst), &MCOL_SCAN_16X4);
+    assert_eq!(get_scan(TxSize::Tx8X8, TxType::Idtx), &DEFAULT_SCAN_8X8);
+    // 64 点变换只有左上 32 个系数
+    assert_eq!(get_scan(TxSize::Tx64X32, TxType::DctDct), &DEFAULT_SCAN_32X32);
+    assert_eq!(get_scan(TxSize::Tx16X64, TxType::DctDct), &DEFAULT_SCAN_16X32);
+}
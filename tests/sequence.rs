@@ -0,0 +1,140 @@
+mod common;
+
+use common::{write_sequence_header, BitWriter};
+use wav1d::{obu::sequence::SequenceHeader, utils::{bits::{BitsReader, FromBitsReader}, consts::{SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS}, enums::{ChromaSamplePosition, ColorPrimaries, MatrixCoefficients, TransferCharacteristics}}, Error};
+
+
+
+#[test]
+fn read_wide_values() {
+    let a = [0x12, 0x34, 0x56, 0x78, 0x9a];
+    let mut r = BitsReader::from(a.as_slice());
+    assert_eq!(r.read_u32(32), Ok(0x12345678));
+    let mut r = BitsReader::from(a.as_slice());
+    r.read_skip(4).unwrap();
+    assert_eq!(r.read_u16(12), Ok(0x234));
+    assert_eq!(r.read_u32(20), Ok(0x56789));
+}
+
+
+#[test]
+fn uvlc() {
+    let mut w = BitWriter::new();
+    w.uvlc(0).uvlc(5).uvlc(1000);
+    let a = w.bytes();
+    let mut r = BitsReader::from(a.as_slice());
+    assert_eq!(r.read_uvlc(), Ok(0));
+    assert_eq!(r.read_uvlc(), Ok(5));
+    assert_eq!(r.read_uvlc(), Ok(1000));
+}
+
+
+#[test]
+fn general_header() {
+    let mut w = BitWriter::new();
+    write_sequence_header(&mut w, 1920, 1080);
+    let a = w.bytes();
+    let mut r = BitsReader::from(a.as_slice());
+    let sh = SequenceHeader::from_bits_reader(&mut r).unwrap();
+
+    assert_eq!(sh.seq_profile, 0);
+    assert_eq!(sh.operating_points.len(), 1);
+    assert_eq!(sh.operating_points[0].seq_level_idx, 8);
+    assert_eq!(sh.max_frame_width_minus_1, 1919);
+    assert_eq!(sh.max_frame_height_minus_1, 1079);
+    assert!(sh.enable_order_hint && sh.enable_ref_frame_mvs);
+    assert_eq!(sh.order_hint_bits, 7);
+    assert_eq!(sh.seq_force_screen_content_tools, SELECT_SCREEN_CONTENT_TOOLS);
+    assert_eq!(sh.seq_force_integer_mv, SELECT_INTEGER_MV);
+    assert_eq!(sh.color_config.bit_depth, 8);
+    assert_eq!(sh.color_config.num_planes, 3);
+    assert!(sh.color_config.subsampling_x && sh.color_config.subsampling_y);
+    assert_eq!(sh.color_config.color_primaries, ColorPrimaries::Unspecified);
+    assert_eq!(r.read_position(), w.position());
+}
+
+
+#[test]
+fn timing_and_decoder_model() {
+    let mut w = BitWriter::new();
+    w.put(2, 3).bit(false).bit(false)
+        .bit(true)                                  // timing_info_present_flag
+        .put(1001, 32).put(60000, 32).bit(true).uvlc(1)
+        .bit(true)                                  // decoder_model_info_present_flag
+        .put(9, 5).put(90000, 32).put(4, 5).put(7, 5)
+        .bit(true)                                  // initial_display_delay_present_flag
+        .put(1, 5)                                  // two operating points
+        .put(0x103, 12).put(9, 5).bit(true)
+        .bit(true).put(999, 10).put(543, 10).bit(false)
+        .bit(true).put(3, 4)
+        .put(0x101, 12).put(4, 5)
+        .bit(false)
+        .bit(false)
+        .put(9, 4).put(9, 4).put(639, 10).put(479, 10)
+        .bit(false).bit(true).bit(false).bit(false)
+        .put(0, 5)                                  // no order hint
+        .bit(false).bit(true)                       // seq_force_screen_content_tools = 1
+        .bit(false).bit(false)                      // seq_force_integer_mv = 0
+        .bit(false).bit(false).bit(false)
+        .bit(true).bit(true)                        // 12-bit
+        .bit(false)
+        .bit(true).put(1, 8).put(13, 8).put(0, 8)   // sRGB
+        .bit(false)
+        .bit(true);
+    let a = w.bytes();
+    let mut r = BitsReader::from(a.as_slice());
+    let sh = SequenceHeader::from_bits_reader(&mut r).unwrap();
+
+    let ti = sh.timing_info.as_ref().unwrap();
+    assert_eq!((ti.num_units_in_display_tick, ti.time_scale, ti.num_ticks_per_picture_minus_1), (1001, 60000, 1));
+    let dm = sh.decoder_model_info.as_ref().unwrap();
+    assert_eq!((dm.buffer_delay_length_minus_1, dm.num_units_in_decoding_tick), (9, 90000));
+    assert_eq!(sh.operating_points.len(), 2);
+    assert_eq!(sh.operating_point_idc(0), 0x103);
+    assert_eq!(sh.operating_point_idc(1), 0x101);
+    assert_eq!(sh.operating_points[0].seq_tier, 1);
+    assert_eq!(sh.operating_points[0].operating_parameters_info.as_ref().unwrap().decoder_buffer_delay, 999);
+    assert_eq!(sh.operating_points[0].initial_display_delay_minus_1, 3);
+    assert!(sh.operating_points[1].operating_parameters_info.is_none());
+    assert_eq!(sh.max_frame_width_minus_1, 639);
+    assert_eq!(sh.order_hint_bits, 0);
+    assert_eq!(sh.seq_force_screen_content_tools, 1);
+    assert_eq!(sh.seq_force_integer_mv, 0);
+    assert_eq!(sh.color_config.bit_depth, 12);
+    assert_eq!(sh.color_config.transfer_characteristics, TransferCharacteristics::Srgb);
+    assert_eq!(sh.color_config.matrix_coefficients, MatrixCoefficients::Identity);
+    assert!(sh.color_config.color_range);
+    assert!(!sh.color_config.subsampling_x && !sh.color_config.subsampling_y);
+    assert_eq!(sh.color_config.chroma_sample_position, ChromaSamplePosition::Unknown);
+    assert!(sh.film_grain_params_present);
+}
+
+
+#[test]
+fn reduced_still_picture() {
+    let mut w = BitWriter::new();
+    w.put(0, 3).bit(true).bit(true)
+        .put(5, 5)
+        .put(7, 4).put(7, 4).put(63, 8).put(63, 8)
+        .bit(false).bit(false).bit(false)
+        .bit(false).bit(false).bit(false)
+        .bit(false).bit(true)                       // mono_chrome
+        .bit(false)
+        .bit(true)
+        .bit(false);
+    let a = w.bytes();
+    let mut r = BitsReader::from(a.as_slice());
+    let sh = SequenceHeader::from_bits_reader(&mut r).unwrap();
+    assert!(sh.reduced_still_picture_header);
+    assert_eq!(sh.operating_points[0].seq_level_idx, 5);
+    assert_eq!(sh.color_config.num_planes, 1);
+    assert!(sh.color_config.color_range);
+}
+
+
+#[test]
+fn invalid_profile() {
+    let a = [0b1110_0000, 0, 0, 0];
+    let mut r = BitsReader::from(a.as_slice());
+    assert_eq!(SequenceHeader::from_bits_reader(&mut r), Err(Error::InvalidValue { name: "seq_profile", value: 7 }));
+}
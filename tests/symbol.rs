@@ -0,0 +1,86 @@
+mod common;
+
+use common::SymbolWriter;
+use wav1d::{entropy::SymbolDecoder, utils::consts::{DEFAULT_INTRA_FRAME_Y_MODE_CDF, DEFAULT_SKIP_CDF}, Error};
+
+
+
+// 简单的线性同余序列，保证测试可重复
+fn sequence(count: usize, modulo: usize) -> Vec<usize> {
+    let mut x = 12345u32;
+    (0..count).map(|_| {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        (x >> 16) as usize % modulo
+    }).collect()
+}
+
+
+#[test]
+fn symbols_round_trip() {
+    let symbols = sequence(2000, 13);
+    let bits = sequence(2000, 2);
+    let mut w = SymbolWriter::new(false);
+    let mut cdf = DEFAULT_INTRA_FRAME_Y_MODE_CDF[0][0];
+    let mut skip = DEFAULT_SKIP_CDF[1];
+    for (&s, &b) in symbols.iter().zip(bits.iter()) {
+        w.symbol(&mut cdf, s).symbol(&mut skip, b);
+    }
+    w.literal(0x2a5, 10).bool(true);
+    let a = w.finish();
+
+    let mut cdf = DEFAULT_INTRA_FRAME_Y_MODE_CDF[0][0];
+    let mut skip = DEFAULT_SKIP_CDF[1];
+    let mut d = SymbolDecoder::init_symbol(&a, false).unwrap();
+    for (&s, &b) in symbols.iter().zip(bits.iter()) {
+        assert_eq!(d.read_symbol(&mut cdf), s);
+        assert_eq!(d.read_symbol(&mut skip), b);
+    }
+    assert_eq!(d.read_literal(10), 0x2a5);
+    assert!(d.read_bool());
+    d.exit_symbol().unwrap();
+    // 计数器最多到 32
+    assert_eq!((cdf[13], skip[2]), (32, 32));
+    assert_ne!(cdf, DEFAULT_INTRA_FRAME_Y_MODE_CDF[0][0]);
+}
+
+
+#[test]
+fn disable_cdf_update() {
+    let symbols = sequence(300, 4);
+    let mut w = SymbolWriter::new(true);
+    let mut cdf = [8192, 16384, 24576, 32768, 0];
+    for &s in symbols.iter() {
+        w.symbol(&mut cdf, s);
+    }
+    let a = w.finish();
+    let mut d = SymbolDecoder::init_symbol(&a, true).unwrap();
+    for &s in symbols.iter() {
+        assert_eq!(d.read_symbol(&mut cdf), s);
+    }
+    assert_eq!(cdf, [8192, 16384, 24576, 32768, 0]);
+}
+
+
+#[test]
+fn adaptation() {
+    let mut w = SymbolWriter::new(false);
+    let mut cdf = [16384, 32768, 0];
+    w.symbol(&mut cdf, 1);
+    let a = w.finish();
+    let mut cdf = [16384, 32768, 0];
+    let mut d = SymbolDecoder::init_symbol(&a, false).unwrap();
+    assert_eq!(d.read_symbol(&mut cdf), 1);
+    // rate = 4, cdf[0] -= 16384 >> 4
+    assert_eq!(cdf, [15360, 32768, 1]);
+}
+
+
+#[test]
+fn exit() {
+    assert_eq!(SymbolDecoder::init_symbol(&[], false).err(), Some(Error::Truncated { needed: 8, available: 0 }));
+    let mut d = SymbolDecoder::init_symbol(&[0], false).unwrap();
+    for _ in 0..10 {
+        d.read_bool();
+    }
+    assert!(matches!(d.exit_symbol(), Err(Error::Conformance { section: "8.2.4", .. })));
+}
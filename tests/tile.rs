@@ -0,0 +1,527 @@
+mod common;
+
+use common::{write_inter_frame, write_key_frame_header, write_sequence_header, BitWriter, SymbolWriter};
+use wav1d::{
+    decoder::{intra_pred::{ChromaFromLuma, IntraPrediction}, palette::PalettePrediction, ModeInfoGrid, RestorationUnit, TileDecoder, TransformBlock},
+    entropy::CdfContext,
+    obu::{frame::{FrameHeader, LrParams, RefFrameState}, header::ObuHeader, sequence::SequenceHeader, tile_group::Tile},
+    utils::{bits::{BitsReader, FromBitsReader}, enums::{CompoundType, FilterIntraMode, FrameRestorationType, InterMode, Interpolationfilter, IntraFrameYMode, MotionMode, ObuType, Partition, RefFrame, SubSize, TxMode, TxSize, TxType, UVMode}},
+    Error,
+};
+
+
+
+fn obu_header() -> ObuHeader {
+    ObuHeader {
+        obu_forbidden_bit: false,
+        obu_type: ObuType::FrameHeader,
+        obu_extension_flag: false,
+        obu_has_size_field: true,
+        temporal_id: 0,
+        spatial_id: 0,
+        obu_size: 0,
+    }
+}
+
+// width x height 的 key frame，base_q_idx 100，tx_mode 为 TX_MODE_SELECT
+fn key_frame(width: u32, height: u32) -> (SequenceHeader, FrameHeader, RefFrameState) {
+    let mut w = BitWriter::new();
+    write_sequence_header(&mut w, width, height);
+    let a = w.bytes();
+    let seq = SequenceHeader::from_bits_reader(&mut BitsReader::from(a.as_slice())).unwrap();
+    let mut w = BitWriter::new();
+    write_key_frame_header(&mut w, 0, 100, &[true]);
+    let a = w.bytes();
+    let mut state = RefFrameState::default();
+    let fh = FrameHeader::from_bits_reader(&mut BitsReader::from(a.as_slice()), &seq, &obu_header(), &mut state).unwrap();
+    (seq, fh, state)
+}
+
+// key frame 之后的 inter frame，所有参考帧都是 key frame，不使用时域 MV
+fn inter_frame(width: u32, height: u32) -> (SequenceHeader, FrameHeader) {
+    let (seq, key, mut state) = key_frame(width, height);
+    state.update(&key);
+    let mut w = BitWriter::new();
+    write_inter_frame(&mut w, 1, 0b10);
+    w.put(0, 7);
+    let a = w.bytes();
+    let mut fh = FrameHeader::from_bits_reader(&mut BitsReader::from(a.as_slice()), &seq, &obu_header(), &mut state).unwrap();
+    fh.use_ref_frame_mvs = false;
+    (seq, fh)
+}
+
+fn decode(seq: &SequenceHeader, fh: &FrameHeader, data: &[u8]) -> (Result<CdfContext, Error>, ModeInfoGrid) {
+    let mut mi = ModeInfoGrid::new(fh.mi_rows, fh.mi_cols);
+    let tile = Tile { tile_num: 0, tile_row: 0, tile_col: 0, data };
+    let mut td = TileDecoder::new(seq, fh, &tile, CdfContext::new(100), &mut mi).unwrap();
+    let res = td.decode_tile().and_then(|_| td.finish());
+    (res, mi)
+}
+
+
+#[test]
+fn horizontal_partition() {
+    let (seq, fh, _) = key_frame(64, 48);
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w64_cdf[0], Partition::Horz as usize);
+    // 第一个 64x32 块：V_PRED，angle_delta +2，色度 V_PRED，angle_delta -2，tx_depth 1
+    w.symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::V as usize)
+        .symbol(&mut cdf.angle_delta_cdf[0], 5)
+        .symbol(&mut cdf.uv_mode_cfl_not_allowed_cdf[IntraFrameYMode::V as usize], UVMode::V as usize)
+        .symbol(&mut cdf.angle_delta_cdf[0], 1)
+        .symbol(&mut cdf.tx_64x64_cdf[0], 1);
+    // 第二个 64x32 块只有上半部分在帧内，上方块 skip，上方 tx 宽度 32 小于 64
+    w.symbol(&mut cdf.skip_cdf[1], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[1][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_not_allowed_cdf[0], UVMode::Dc as usize)
+        .symbol(&mut cdf.tx_64x64_cdf[0], 0);
+    let data = w.finish();
+
+    let (res, mi) = decode(&seq, &fh, &data);
+    assert_eq!(res.unwrap(), cdf);
+    let top = mi.get(7, 15);
+    assert_eq!((top.mi_size, top.skip, top.qindex), (SubSize::Block64X32, true, 100));
+    assert_eq!((top.y_mode, top.angle_delta_y, top.uv_mode, top.angle_delta_uv), (IntraFrameYMode::V, 2, UVMode::V, -2));
+    assert_eq!(top.tx_size, TxSize::Tx32X32);
+    let bottom = mi.get(11, 0);
+    assert_eq!((bottom.mi_size, bottom.y_mode, bottom.angle_delta_y), (SubSize::Block64X32, IntraFrameYMode::Dc, 0));
+    assert_eq!(bottom.tx_size, TxSize::Tx64X32);
+    assert_eq!(mi.get(8, 0), bottom);
+    // skip 块不读 cdef_idx
+    assert_eq!(mi.cdef_idx(0, 0), -1);
+}
+
+
+#[test]
+fn cfl_and_filter_intra() {
+    // 16x16 的帧，64x64 和 32x32 都隐式 split，16x16 读取 partition
+    let (seq, fh, _) = key_frame(16, 16);
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w16_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_allowed_cdf[0], UVMode::UVCfl as usize)
+        // signU = NEG，signV = POS
+        .symbol(&mut cdf.cfl_sign_cdf, 4)
+        .symbol(&mut cdf.cfl_alpha_cdf[2], 3)
+        .symbol(&mut cdf.cfl_alpha_cdf[4], 0)
+        .symbol(&mut cdf.filter_intra_cdf[SubSize::Block16X16 as usize], 1)
+        .symbol(&mut cdf.filter_intra_mode_cdf, FilterIntraMode::D157 as usize)
+        .symbol(&mut cdf.tx_16x16_cdf[0], 2);
+    let data = w.finish();
+
+    let mut mi = ModeInfoGrid::new(fh.mi_rows, fh.mi_cols);
+    let tile = Tile { tile_num: 0, tile_row: 0, tile_col: 0, data: &data };
+    let mut td = TileDecoder::new(&seq, &fh, &tile, CdfContext::new(100), &mut mi).unwrap();
+    td.decode_tile().unwrap();
+    // 16 个 4x4 亮度变换块之后是两个 8x8 的 CFL 色度块，亮度覆盖到 (16, 16)
+    let predictions = td.intra_predictions();
+    assert_eq!(predictions.len(), 18);
+    assert!(predictions[..16].iter().all(|p| p.cfl.is_none() && p.use_filter_intra));
+    let cfl = ChromaFromLuma { alpha: -4, subsampling_x: true, subsampling_y: true, max_luma_w: 16, max_luma_h: 16 };
+    assert_eq!((predictions[16].mode, predictions[16].cfl), (IntraFrameYMode::Dc, Some(cfl)));
+    assert_eq!(predictions[17].cfl, Some(ChromaFromLuma { alpha: 1, ..cfl }));
+    assert_eq!(td.finish().unwrap(), cdf);
+    let info = mi.get(3, 3);
+    assert_eq!((info.mi_size, info.uv_mode, info.cfl_alpha_u, info.cfl_alpha_v), (SubSize::Block16X16, UVMode::UVCfl, -4, 1));
+    assert_eq!((info.use_filter_intra, info.filter_intra_mode), (true, FilterIntraMode::D157));
+    assert_eq!(info.tx_size, TxSize::Tx4X4);
+}
+
+
+#[test]
+fn intra_coefficients() {
+    let (seq, fh, _) = key_frame(16, 16);
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w16_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 0)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Paeth as usize)
+        .symbol(&mut cdf.uv_mode_cfl_allowed_cdf[IntraFrameYMode::Paeth as usize], UVMode::Dc as usize)
+        .symbol(&mut cdf.tx_16x16_cdf[0], 0);
+    // 亮度 16x16：TX_SET_INTRA_2 中的 DCT_DCT，eobPt 3 没有 eob_extra，eob 为 3
+    let coeff = &mut cdf.coeff;
+    w.symbol(&mut coeff.txb_skip_cdf[2][0], 0)
+        .symbol(&mut cdf.intra_tx_type_set2_cdf[2][IntraFrameYMode::Paeth as usize], 1)
+        .symbol(&mut coeff.eob_pt_256_cdf[0][0], 2)
+        .symbol(&mut coeff.eob_extra_cdf[2][0][0], 0);
+    // 扫描顺序 0, 16, 1：pos 1 的 level 进入 Golomb，pos 16 为 0，pos 0 为 1
+    w.symbol(&mut coeff.coeff_base_eob_cdf[2][0][1], 2);
+    for _ in 0..4 {
+        w.symbol(&mut coeff.coeff_br_cdf[2][0][7], 3);
+    }
+    w.symbol(&mut coeff.coeff_base_cdf[2][0][1], 0)
+        .symbol(&mut coeff.coeff_base_cdf[2][0][0], 1)
+        .symbol(&mut coeff.dc_sign_cdf[0][0], 1)
+        .literal(0, 1)
+        // golomb_length_bit 0, 1，golomb_data_bit 1
+        .literal(0, 1)
+        .literal(1, 1)
+        .literal(1, 1);
+    // 两个 8x8 色度块都是 all_zero
+    w.symbol(&mut coeff.txb_skip_cdf[1][7], 1)
+        .symbol(&mut coeff.txb_skip_cdf[1][7], 1);
+    let data = w.finish();
+
+    let mut mi = ModeInfoGrid::new(fh.mi_rows, fh.mi_cols);
+    let tile = Tile { tile_num: 0, tile_row: 0, tile_col: 0, data: &data };
+    let mut td = TileDecoder::new(&seq, &fh, &tile, CdfContext::new(100), &mut mi).unwrap();
+    td.decode_tile().unwrap();
+    let mut quant = vec![0; 256];
+    quant[0] = -1;
+    quant[1] = 17;
+    // base_q_idx 100 的 dc_q 为 93，ac_q 为 112
+    let mut dequant = vec![0; 256];
+    dequant[0] = -93;
+    dequant[1] = 17 * 112;
+    let expected = TransformBlock { plane: 0, start_x: 0, start_y: 0, tx_size: TxSize::Tx16X16, tx_type: TxType::DctDct, eob: 3, quant, dequant };
+    assert_eq!(td.residuals(), &[expected]);
+    // 帧左上角的块没有可用的邻居，色度为 8x8 的 DC_PRED
+    let luma = IntraPrediction { tx_size: TxSize::Tx16X16, mode: IntraFrameYMode::Paeth, max_x: 15, max_y: 15, ..Default::default() };
+    let chroma = IntraPrediction { tx_size: TxSize::Tx8X8, mode: IntraFrameYMode::Dc, max_x: 7, max_y: 7, ..Default::default() };
+    assert_eq!(td.intra_predictions(), &[luma, IntraPrediction { plane: 1, ..chroma }, IntraPrediction { plane: 2, ..chroma }]);
+    assert_eq!(td.finish().unwrap(), cdf);
+    // cdef_bits 为 0，非 skip 块的 cdef_idx 为 0
+    assert_eq!(mi.cdef_idx(0, 0), 0);
+    assert_eq!((mi.get(0, 0).y_mode, mi.get(0, 0).tx_size), (IntraFrameYMode::Paeth, TxSize::Tx16X16));
+}
+
+
+#[test]
+fn loop_restoration_units() {
+    // 每个 plane 都只有一个 restoration unit，在第一个超级块开始时读取
+    let (seq, mut fh, _) = key_frame(16, 16);
+    fh.lr = LrParams {
+        uses_lr: true,
+        frame_restoration_type: [FrameRestorationType::Wiener, FrameRestorationType::Sgrproj, FrameRestorationType::Switchable],
+        loop_restoration_size: [64, 32, 32],
+    };
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    // 亮度 Wiener：第一组 3，-7 + (3 -> -2)，15 + (20 -> +10)，第二组都等于参考值
+    w.symbol(&mut cdf.use_wiener_cdf, 1)
+        .literal(0, 1).literal(0, 1)
+        .literal(0, 1).literal(3, 2)
+        .literal(1, 1).literal(1, 1).literal(4, 5)
+        .literal(0, 1).literal(0, 1)
+        .literal(0, 1).literal(0, 2)
+        .literal(0, 1).literal(0, 3);
+    // U 用第 14 组参数，只有第一个滤波器，第二个系数由第一个推出
+    w.symbol(&mut cdf.use_sgrproj_cdf, 1)
+        .literal(14, 4)
+        .literal(0, 1).literal(1, 4);
+    // V 为 RESTORE_WIENER，色度的第一个系数不读
+    w.symbol(&mut cdf.restoration_type_cdf, 1);
+    for _ in 0..2 {
+        w.literal(0, 1).literal(0, 2).literal(0, 1).literal(0, 3);
+    }
+    w.symbol(&mut cdf.partition_w16_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_allowed_cdf[0], UVMode::Dc as usize)
+        .symbol(&mut cdf.filter_intra_cdf[SubSize::Block16X16 as usize], 0)
+        .symbol(&mut cdf.tx_16x16_cdf[0], 0);
+    let data = w.finish();
+
+    let mut mi = ModeInfoGrid::new(fh.mi_rows, fh.mi_cols);
+    let tile = Tile { tile_num: 0, tile_row: 0, tile_col: 0, data: &data };
+    let mut td = TileDecoder::new(&seq, &fh, &tile, CdfContext::new(100), &mut mi).unwrap();
+    td.decode_tile().unwrap();
+    let y = RestorationUnit { plane: 0, lr_type: FrameRestorationType::Wiener, lr_wiener: [[3, -9, 25], [3, -7, 15]], ..Default::default() };
+    let u = RestorationUnit { plane: 1, lr_type: FrameRestorationType::Sgrproj, lr_sgr_set: 14, lr_sgr_xqd: [-33, 95], ..Default::default() };
+    let v = RestorationUnit { plane: 2, lr_type: FrameRestorationType::Wiener, lr_wiener: [[0, -7, 15]; 2], ..Default::default() };
+    assert_eq!(td.restoration_units(), &[y, u, v]);
+    assert_eq!(td.finish().unwrap(), cdf);
+}
+
+
+#[test]
+fn palette_block() {
+    let (seq, mut fh, _) = key_frame(16, 16);
+    fh.allow_screen_content_tools = true;
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w16_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_allowed_cdf[0], UVMode::Dc as usize)
+        // 16x16 的 bsizeCtx 为 2，亮度两种颜色：10 和 10 + (19 + 1)
+        .symbol(&mut cdf.palette_y_mode_cdf[2][0], 1)
+        .symbol(&mut cdf.palette_y_size_cdf[2], 0)
+        .literal(10, 8)
+        .literal(0, 2)
+        .literal(19, 5)
+        .symbol(&mut cdf.palette_uv_mode_cdf[1], 1)
+        .symbol(&mut cdf.palette_uv_size_cdf[2], 1)
+        // U：100，100 + 0，100 + 50
+        .literal(100, 8)
+        .literal(1, 2)
+        .literal(0, 6)
+        .literal(50, 6)
+        // V 用差值编码：250，250 + 10 回绕到 4，4 + 0
+        .literal(1, 1)
+        .literal(2, 2)
+        .literal(250, 8)
+        .literal(10, 6)
+        .literal(0, 1)
+        .literal(0, 6);
+    // color_index_map_y 为 1，之后的索引都和邻居相同，ColorOrder[0] 为 1
+    w.literal(1, 1);
+    for i in 1..31usize {
+        for j in (i.saturating_sub(15)..=i.min(15)).rev() {
+            let ctx = if i - j == 0 || j == 0 { 0 } else { 4 };
+            w.symbol(&mut cdf.palette_size_2_y_color_cdf[ctx], 0);
+        }
+    }
+    // color_index_map_uv 为 NS(3) 的 2：v 为 1，extra_bit 为 1
+    w.literal(1, 1).literal(1, 1);
+    for i in 1..15usize {
+        for j in (i.saturating_sub(7)..=i.min(7)).rev() {
+            let ctx = if i - j == 0 || j == 0 { 0 } else { 4 };
+            w.symbol(&mut cdf.palette_size_3_uv_color_cdf[ctx], 0);
+        }
+    }
+    w.symbol(&mut cdf.tx_16x16_cdf[0], 0);
+    let data = w.finish();
+
+    let mut mi = ModeInfoGrid::new(fh.mi_rows, fh.mi_cols);
+    let tile = Tile { tile_num: 0, tile_row: 0, tile_col: 0, data: &data };
+    let mut td = TileDecoder::new(&seq, &fh, &tile, CdfContext::new(100), &mut mi).unwrap();
+    td.decode_tile().unwrap();
+    let y = PalettePrediction { plane: 0, start_x: 0, start_y: 0, tx_size: TxSize::Tx16X16, pred: vec![30; 256] };
+    let u = PalettePrediction { plane: 1, tx_size: TxSize::Tx8X8, pred: vec![150; 64], ..y.clone() };
+    let v = PalettePrediction { plane: 2, tx_size: TxSize::Tx8X8, pred: vec![4; 64], ..y.clone() };
+    assert_eq!(td.palette_predictions(), &[y, u, v]);
+    assert!(td.intra_predictions().is_empty());
+    assert_eq!(td.finish().unwrap(), cdf);
+    let info = mi.get(2, 1);
+    assert_eq!((info.palette_size_y, info.palette_size_uv, info.use_filter_intra), (2, 3, false));
+    assert_eq!(info.palette_colors_y[..2], [10, 30]);
+    assert_eq!((&info.palette_colors_u[..3], &info.palette_colors_v[..3]), (&[100, 100, 150][..], &[250, 4, 4][..]));
+}
+
+
+#[test]
+fn narrow_palette_block() {
+    // PARTITION_VERT_4 得到四个 4x16 块，只有奇数列的块带色度，色度 2x8 补成 4x8
+    let (mut seq, mut fh, _) = key_frame(16, 16);
+    seq.enable_filter_intra = false;
+    fh.allow_screen_content_tools = true;
+    fh.tx_mode = TxMode::Largest;
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w16_cdf[0], Partition::Vert4 as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.palette_y_mode_cdf[0][0], 0);
+    // 第二个块：亮度 10 和 30，U 100 和 150，V 250 和 4
+    w.symbol(&mut cdf.skip_cdf[1], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_allowed_cdf[0], UVMode::Dc as usize)
+        .symbol(&mut cdf.palette_y_mode_cdf[0][0], 1)
+        .symbol(&mut cdf.palette_y_size_cdf[0], 0)
+        .literal(10, 8)
+        .literal(0, 2)
+        .literal(19, 5)
+        .symbol(&mut cdf.palette_uv_mode_cdf[1], 1)
+        .symbol(&mut cdf.palette_uv_size_cdf[0], 0)
+        .literal(100, 8)
+        .literal(1, 2)
+        .literal(50, 6)
+        .literal(1, 1)
+        .literal(2, 2)
+        .literal(250, 8)
+        .literal(10, 6)
+        .literal(0, 1);
+    w.literal(1, 1);
+    for i in 1..19usize {
+        for j in (i.saturating_sub(15)..=i.min(3)).rev() {
+            let ctx = if i - j == 0 || j == 0 { 0 } else { 4 };
+            w.symbol(&mut cdf.palette_size_2_y_color_cdf[ctx], 0);
+        }
+    }
+    w.literal(0, 1);
+    for i in 1..11usize {
+        for j in (i.saturating_sub(7)..=i.min(3)).rev() {
+            let ctx = if i - j == 0 || j == 0 { 0 } else { 4 };
+            w.symbol(&mut cdf.palette_size_2_uv_color_cdf[ctx], 0);
+        }
+    }
+    // 后两个块不用调色板
+    w.symbol(&mut cdf.skip_cdf[1], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.palette_y_mode_cdf[0][1], 0)
+        .symbol(&mut cdf.skip_cdf[1], 1)
+        .symbol(&mut cdf.intra_frame_y_mode_cdf[0][0], IntraFrameYMode::Dc as usize)
+        .symbol(&mut cdf.uv_mode_cfl_allowed_cdf[0], UVMode::Dc as usize)
+        .symbol(&mut cdf.palette_y_mode_cdf[0][0], 0)
+        .symbol(&mut cdf.palette_uv_mode_cdf[0], 0);
+    let data = w.finish();
+
+    let mut mi = ModeInfoGrid::new(fh.mi_rows, fh.mi_cols);
+    let tile = Tile { tile_num: 0, tile_row: 0, tile_col: 0, data: &data };
+    let mut td = TileDecoder::new(&seq, &fh, &tile, CdfContext::new(100), &mut mi).unwrap();
+    td.decode_tile().unwrap();
+    let y = PalettePrediction { plane: 0, start_x: 4, start_y: 0, tx_size: TxSize::Tx4X16, pred: vec![30; 64] };
+    let u = PalettePrediction { plane: 1, start_x: 0, tx_size: TxSize::Tx4X8, pred: vec![100; 32], ..y.clone() };
+    let v = PalettePrediction { plane: 2, pred: vec![250; 32], ..u.clone() };
+    assert_eq!(td.palette_predictions(), &[y, u, v]);
+    assert_eq!(td.finish().unwrap(), cdf);
+    let info = mi.get(0, 1);
+    assert_eq!((info.mi_size, info.palette_size_y, info.palette_size_uv), (SubSize::Block4X16, 2, 2));
+    assert_eq!((&info.palette_colors_u[..2], &info.palette_colors_v[..2]), (&[100, 150][..], &[250, 4][..]));
+}
+
+
+#[test]
+fn single_reference_new_mv() {
+    let (seq, fh) = inter_frame(16, 16);
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w16_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        // delta_q_abs 1，符号为负
+        .symbol(&mut cdf.delta_q_cdf, 1)
+        .literal(1, 1);
+    for i in 0..4 {
+        w.symbol(&mut cdf.delta_lf_multi_cdf[i], 0);
+    }
+    // single_ref_p1 = 0，p3 = 1，p5 = 1 为 GOLDEN
+    w.symbol(&mut cdf.is_inter_cdf[0], 1)
+        .symbol(&mut cdf.comp_mode_cdf[1], 0)
+        .symbol(&mut cdf.single_ref_cdf[1][0], 0)
+        .symbol(&mut cdf.single_ref_cdf[1][2], 1)
+        .symbol(&mut cdf.single_ref_cdf[1][4], 1)
+        .symbol(&mut cdf.new_mv_cdf[0], 0);
+    // mv_joint MV_JOINT_HNZVNZ，行 -30，列 15
+    let mv = &mut cdf.mv[0];
+    w.symbol(&mut mv.mv_joint_cdf, 3)
+        .symbol(&mut mv.mv_sign_cdf[0], 1)
+        .symbol(&mut mv.mv_class_cdf[0], 1)
+        .symbol(&mut mv.mv_bit_cdf[0][0], 1)
+        .symbol(&mut mv.mv_fr_cdf[0], 2)
+        .symbol(&mut mv.mv_hp_cdf[0], 1)
+        .symbol(&mut mv.mv_sign_cdf[1], 0)
+        .symbol(&mut mv.mv_class_cdf[1], 0)
+        .symbol(&mut mv.mv_class0_bit_cdf[1], 1)
+        .symbol(&mut mv.mv_class0_fr_cdf[1][1], 3)
+        .symbol(&mut mv.mv_class0_hp_cdf[1], 0);
+    w.symbol(&mut cdf.inter_intra_cdf[1], 0)
+        .symbol(&mut cdf.interp_filter_cdf[3], 1)
+        .symbol(&mut cdf.interp_filter_cdf[11], 2);
+    let data = w.finish();
+
+    let (res, mi) = decode(&seq, &fh, &data);
+    assert_eq!(res.unwrap(), cdf);
+    let info = mi.get(3, 3);
+    assert!(info.is_inter && info.skip);
+    assert_eq!(info.qindex, 78);
+    assert_eq!(info.ref_frame, [RefFrame::Golden as i8, -1]);
+    assert_eq!((info.inter_mode, info.mv[0]), (InterMode::NewMv, [-30, 15]));
+    assert_eq!((info.motion_mode, info.compound_type), (MotionMode::Simple, CompoundType::Average));
+    assert_eq!(info.interp_filter, [Interpolationfilter::EightTapSmooth, Interpolationfilter::EIGHTTAPSharp]);
+    assert_eq!(info.tx_size, TxSize::Tx16X16);
+}
+
+
+#[test]
+fn var_tx_size() {
+    // 非 skip 的 inter 块：16x16 分成四个 8x8，第一个再分成四个 4x4
+    let (seq, fh) = inter_frame(16, 16);
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w16_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 0)
+        .symbol(&mut cdf.delta_q_cdf, 0);
+    for i in 0..4 {
+        w.symbol(&mut cdf.delta_lf_multi_cdf[i], 0);
+    }
+    w.symbol(&mut cdf.is_inter_cdf[0], 1)
+        .symbol(&mut cdf.comp_mode_cdf[1], 0)
+        .symbol(&mut cdf.single_ref_cdf[1][0], 0)
+        .symbol(&mut cdf.single_ref_cdf[1][2], 1)
+        .symbol(&mut cdf.single_ref_cdf[1][4], 1)
+        .symbol(&mut cdf.new_mv_cdf[0], 1)
+        .symbol(&mut cdf.zero_mv_cdf[0], 0)
+        .symbol(&mut cdf.inter_intra_cdf[1], 0);
+    // GLOBALMV 的全局运动是 IDENTITY，不读 interp_filter；16x16 的 ctx 为 12，8x8 的为 15，邻居是 4x4 时加 1
+    w.symbol(&mut cdf.txfm_split_cdf[12], 1)
+        .symbol(&mut cdf.txfm_split_cdf[15], 1)
+        .symbol(&mut cdf.txfm_split_cdf[16], 0)
+        .symbol(&mut cdf.txfm_split_cdf[16], 0)
+        .symbol(&mut cdf.txfm_split_cdf[15], 0);
+    // 所有变换块都是 all_zero
+    let coeff = &mut cdf.coeff;
+    for _ in 0..4 {
+        w.symbol(&mut coeff.txb_skip_cdf[0][1], 1);
+    }
+    for _ in 0..3 {
+        w.symbol(&mut coeff.txb_skip_cdf[1][1], 1);
+    }
+    w.symbol(&mut coeff.txb_skip_cdf[1][7], 1)
+        .symbol(&mut coeff.txb_skip_cdf[1][7], 1);
+    let data = w.finish();
+
+    let (res, mi) = decode(&seq, &fh, &data);
+    assert_eq!(res.unwrap(), cdf);
+    let info = mi.get(0, 0);
+    assert!(info.is_inter && !info.skip);
+    assert_eq!(info.inter_mode, InterMode::GlobalMv);
+    // TxSize 是最后一个没有再分的变换尺寸
+    assert_eq!(info.tx_size, TxSize::Tx8X8);
+    for (row, col, tx_size) in [(0, 0, TxSize::Tx4X4), (1, 1, TxSize::Tx4X4), (0, 2, TxSize::Tx8X8), (3, 0, TxSize::Tx8X8), (3, 3, TxSize::Tx8X8)] {
+        assert_eq!(mi.get(row, col).inter_tx_size, tx_size, "{row} {col}");
+    }
+}
+
+
+#[test]
+fn compound_wedge() {
+    let (seq, fh) = inter_frame(16, 16);
+    let mut cdf = CdfContext::new(100);
+    let mut w = SymbolWriter::new(false);
+    w.symbol(&mut cdf.partition_w16_cdf[0], Partition::None as usize)
+        .symbol(&mut cdf.skip_cdf[0], 1)
+        .symbol(&mut cdf.delta_q_cdf, 0);
+    for i in 0..4 {
+        w.symbol(&mut cdf.delta_lf_multi_cdf[i], 0);
+    }
+    // BIDIR_COMP_REFERENCE：comp_ref = 1，comp_ref_p2 = 0 为 LAST3，comp_bwdref = 0，comp_bwdref_p1 = 1 为 ALTREF2
+    w.symbol(&mut cdf.is_inter_cdf[0], 1)
+        .symbol(&mut cdf.comp_mode_cdf[1], 1)
+        .symbol(&mut cdf.comp_ref_type_cdf[2], 1)
+        .symbol(&mut cdf.comp_ref_cdf[1][0], 1)
+        .symbol(&mut cdf.comp_ref_cdf[1][2], 0)
+        .symbol(&mut cdf.comp_bwd_ref_cdf[1][0], 0)
+        .symbol(&mut cdf.comp_bwd_ref_cdf[1][1], 1)
+        .symbol(&mut cdf.compound_mode_cdf[0], (InterMode::NewNewMv as u8 - InterMode::NearestNearestMv as u8) as usize)
+        // extra search 用 GlobalMvs 补出两个候选，DrlCtxStack[0] 为 2
+        .symbol(&mut cdf.drl_mode_cdf[2], 0);
+    let mv = &mut cdf.mv[0];
+    w.symbol(&mut mv.mv_joint_cdf, 2)
+        .symbol(&mut mv.mv_sign_cdf[0], 0)
+        .symbol(&mut mv.mv_class_cdf[0], 0)
+        .symbol(&mut mv.mv_class0_bit_cdf[0], 0)
+        .symbol(&mut mv.mv_class0_fr_cdf[0][0], 0)
+        .symbol(&mut mv.mv_class0_hp_cdf[0], 1)
+        .symbol(&mut mv.mv_joint_cdf, 0);
+    w.symbol(&mut cdf.comp_group_idx_cdf[0], 1)
+        .symbol(&mut cdf.compound_type_cdf[SubSize::Block16X16 as usize], CompoundType::Wedge as usize)
+        .symbol(&mut cdf.wedge_index_cdf[SubSize::Block16X16 as usize], 5)
+        .literal(1, 1)
+        .symbol(&mut cdf.interp_filter_cdf[7], 0)
+        .symbol(&mut cdf.interp_filter_cdf[15], 0);
+    let data = w.finish();
+
+    let (res, mi) = decode(&seq, &fh, &data);
+    assert_eq!(res.unwrap(), cdf);
+    let info = mi.get(0, 0);
+    assert_eq!(info.ref_frame, [RefFrame::Last3 as i8, RefFrame::Altref2 as i8]);
+    assert_eq!((info.inter_mode, info.mv), (InterMode::NewNewMv, [[2, 0], [0, 0]]));
+    assert_eq!((info.comp_group_idx, info.compound_type), (1, CompoundType::Wedge));
+    assert_eq!((info.wedge_index, info.wedge_sign), (5, 1));
+    assert_eq!(info.interp_filter, [Interpolationfilter::EightTap; 2]);
+}
@@ -0,0 +1,207 @@
+use std::f64::consts::{FRAC_1_SQRT_2, PI, SQRT_2};
+
+use wav1d::{
+    decoder::transform::{inverse_adst16, inverse_adst4, inverse_adst8, inverse_dct, inverse_transform_block, inverse_wht},
+    utils::{
+        consts::{TRANSFORM_ROW_SHIFT, TX_HEIGHT, TX_SIZES_ALL, TX_WIDTH},
+        enums::{TxSize, TxType},
+    },
+};
+
+
+
+fn lcg(seed: &mut u64, range: i32) -> i32 {
+    *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    ((*seed >> 33) % (2 * range as u64 + 1)) as i32 - range
+}
+
+// 浮点的 DCT-II 反变换基函数，DC 项带 1/sqrt(2)
+fn dct_basis(k: usize, j: usize, n: usize) -> f64 {
+    if k == 0 { FRAC_1_SQRT_2 } else { (PI * (2 * j + 1) as f64 * k as f64 / (2 * n) as f64).cos() }
+}
+
+fn adst_basis(k: usize, j: usize, n: usize) -> f64 {
+    if n == 4 {
+        2.0 * SQRT_2 / 3.0 * (PI * (j + 1) as f64 * (2 * k + 1) as f64 / 9.0).sin()
+    } else {
+        (PI * (2 * j + 1) as f64 * (2 * k + 1) as f64 / (4 * n) as f64).sin()
+    }
+}
+
+fn max_error_1d(n: usize, transform: impl Fn(&mut [i32]), basis: impl Fn(usize, usize, usize) -> f64) -> f64 {
+    let mut seed = n as u64;
+    let mut max_error: f64 = 0.0;
+    for _ in 0..64 {
+        let input: Vec<i32> = (0..n).map(|_| lcg(&mut seed, 1000)).collect();
+        let mut t = input.clone();
+        transform(&mut t);
+        for (j, &out) in t.iter().enumerate() {
+            let expected: f64 = input.iter().enumerate().map(|(k, &x)| x as f64 * basis(k, j, n)).sum();
+            max_error = max_error.max((expected - out as f64).abs());
+        }
+    }
+    max_error
+}
+
+fn tx_size_dims(tx_size: TxSize) -> (usize, usize) {
+    (TX_WIDTH[tx_size as usize] as usize, TX_HEIGHT[tx_size as usize] as usize)
+}
+
+fn dc_only(tx_size: TxSize, dc: i32) -> Vec<i32> {
+    let (w, h) = tx_size_dims(tx_size);
+    let mut coeffs = vec![0; w.min(32) * h.min(32)];
+    coeffs[0] = dc;
+    coeffs
+}
+
+
+#[test]
+fn dct_matches_float_reference() {
+    for n in 2..=6 {
+        let error = max_error_1d(1 << n, |t| inverse_dct(t, n), dct_basis);
+        assert!(error < 8.0, "dct{} error {error}", 1 << n);
+    }
+}
+
+#[test]
+fn adst_matches_float_reference() {
+    assert!(max_error_1d(4, inverse_adst4, adst_basis) < 4.0);
+    assert!(max_error_1d(8, inverse_adst8, adst_basis) < 4.0);
+    assert!(max_error_1d(16, inverse_adst16, adst_basis) < 4.0);
+}
+
+#[test]
+fn dc_only_blocks_are_flat() {
+    for tx_size in 0..TX_SIZES_ALL as u8 {
+        let tx_size = TxSize::from(tx_size);
+        let (w, h) = tx_size_dims(tx_size);
+        let residual = inverse_transform_block(tx_size, TxType::DctDct, 8, &dc_only(tx_size, 1024));
+        assert_eq!(residual.len(), w * h);
+        assert!(residual.iter().all(|&x| x == residual[0]), "{tx_size:?}");
+        // DC 增益 1/sqrt(2) 每维，1:2 的块再乘 1/sqrt(2)
+        let rect = if w == 2 * h || h == 2 * w { FRAC_1_SQRT_2 } else { 1.0 };
+        let shift = TRANSFORM_ROW_SHIFT[tx_size as usize] + 4;
+        let expected = 1024.0 * 0.5 * rect / (1 << shift) as f64;
+        assert!((residual[0] as f64 - expected).abs() <= 1.0, "{tx_size:?} {} {expected}", residual[0]);
+    }
+}
+
+#[test]
+fn dct_2d_matches_float_reference() {
+    let mut seed = 3;
+    for tx_size in 0..TX_SIZES_ALL as u8 {
+        let tx_size = TxSize::from(tx_size);
+        let (w, h) = tx_size_dims(tx_size);
+        let (tw, th) = (w.min(32), h.min(32));
+        let coeffs: Vec<i32> = (0..tw * th).map(|_| lcg(&mut seed, 64)).collect();
+        let residual = inverse_transform_block(tx_size, TxType::DctDct, 10, &coeffs);
+        let rect = if w == 2 * h || h == 2 * w { FRAC_1_SQRT_2 } else { 1.0 };
+        let scale = rect / (1 << (TRANSFORM_ROW_SHIFT[tx_size as usize] + 4)) as f64;
+        for i in 0..h {
+            for j in 0..w {
+                let mut expected = 0.0;
+                for k in 0..th {
+                    for l in 0..tw {
+                        expected += coeffs[k * tw + l] as f64 * dct_basis(k, i, h) * dct_basis(l, j, w);
+                    }
+                }
+                let error = (expected * scale - residual[i * w + j] as f64).abs();
+                assert!(error < 2.0, "{tx_size:?} ({i}, {j}) error {error}");
+            }
+        }
+    }
+}
+
+#[test]
+fn identity_scales_each_coefficient() {
+    let mut coeffs = vec![0; 16];
+    coeffs[4 + 2] = 1024;
+    let residual = inverse_transform_block(TxSize::Tx4X4, TxType::Idtx, 8, &coeffs);
+    let mut expected = vec![0; 16];
+    // Round2(Round2(1024 * 5793, 12) * 5793, 12) >> 4
+    expected[4 + 2] = 128;
+    assert_eq!(residual, expected);
+
+    // 8 点 identity 为 x2，16 点为 x2sqrt(2)，行移位 1
+    let mut coeffs = vec![0; 128];
+    coeffs[8 * 3 + 5] = 100;
+    let residual = inverse_transform_block(TxSize::Tx8X16, TxType::Idtx, 8, &coeffs);
+    let row = (100 * 2896 + 2048) >> 12; // 71
+    let row = (row * 2 + 1) >> 1; // 71
+    let col = (row * 11586 + 2048) >> 12; // 201
+    assert_eq!(residual[3 * 8 + 5], (col + 8) >> 4);
+    assert_eq!(residual.iter().filter(|&&x| x != 0).count(), 1);
+}
+
+#[test]
+fn one_dimensional_types() {
+    // V_DCT 只有列变换，单个系数得到一列常数
+    let mut coeffs = vec![0; 64];
+    coeffs[3] = 512;
+    let residual = inverse_transform_block(TxSize::Tx8X8, TxType::VDct, 8, &coeffs);
+    for i in 0..8 {
+        for j in 0..8 {
+            assert_eq!(residual[i * 8 + j] != 0, j == 3, "({i}, {j})");
+        }
+        assert_eq!(residual[i * 8 + 3], residual[3]);
+    }
+    let residual = inverse_transform_block(TxSize::Tx8X8, TxType::HDct, 8, &dc_only(TxSize::Tx8X8, 512));
+    assert!(residual[..8].iter().all(|&x| x == residual[0] && x != 0));
+    assert!(residual[8..].iter().all(|&x| x == 0));
+}
+
+#[test]
+fn flipped_adst_mirrors_output() {
+    let mut seed = 5;
+    let coeffs: Vec<i32> = (0..128).map(|_| lcg(&mut seed, 300)).collect();
+    let (w, h) = (16, 8);
+    let base = inverse_transform_block(TxSize::Tx16X8, TxType::AdstAdst, 8, &coeffs);
+    let mirrored = |flip_lr: bool, flip_ud: bool| -> Vec<i32> {
+        (0..w * h)
+            .map(|pos| {
+                let (i, j) = (pos / w, pos % w);
+                let i = if flip_ud { h - 1 - i } else { i };
+                let j = if flip_lr { w - 1 - j } else { j };
+                base[i * w + j]
+            })
+            .collect()
+    };
+    assert_eq!(inverse_transform_block(TxSize::Tx16X8, TxType::AdstFlipadst, 8, &coeffs), mirrored(true, false));
+    assert_eq!(inverse_transform_block(TxSize::Tx16X8, TxType::FlipadstAdst, 8, &coeffs), mirrored(false, true));
+    assert_eq!(inverse_transform_block(TxSize::Tx16X8, TxType::FlipadstFlipadst, 8, &coeffs), mirrored(true, true));
+    let dct = inverse_transform_block(TxSize::Tx16X8, TxType::AdstDct, 8, &coeffs);
+    let flipped = inverse_transform_block(TxSize::Tx16X8, TxType::FlipadstDct, 8, &coeffs);
+    for i in 0..h {
+        assert_eq!(flipped[i * w..(i + 1) * w], dct[(h - 1 - i) * w..(h - i) * w]);
+    }
+}
+
+#[test]
+fn sixty_four_point_zero_out() {
+    let mut seed = 9;
+    let coeffs: Vec<i32> = (0..512).map(|_| lcg(&mut seed, 64)).collect();
+    // 64x16 的系数为 32x16，16x64 的为 16x32
+    let wide = inverse_transform_block(TxSize::Tx64X16, TxType::DctDct, 8, &coeffs);
+    let tall = inverse_transform_block(TxSize::Tx16X64, TxType::DctDct, 8, &coeffs);
+    assert_eq!((wide.len(), tall.len()), (1024, 1024));
+    assert!(wide.iter().any(|&x| x != 0) && tall.iter().any(|&x| x != 0));
+    let residual = inverse_transform_block(TxSize::Tx64X64, TxType::DctDct, 12, &dc_only(TxSize::Tx64X64, 4096));
+    assert_eq!(residual.len(), 4096);
+    assert!(residual.iter().all(|&x| x == residual[0]));
+}
+
+#[test]
+fn lossless_walsh_hadamard() {
+    let mut t = [8, 0, 0, 0];
+    inverse_wht(&mut t, 2);
+    assert_eq!(t, [1, 1, 1, 1]);
+    let mut t = [4, 4, 0, 0];
+    inverse_wht(&mut t, 0);
+    assert_eq!(t, [4, 4, 0, 0]);
+
+    // 行变换先右移 2 位，列变换不移位
+    let residual = inverse_transform_block(TxSize::Tx4X4, TxType::WhtWht, 8, &dc_only(TxSize::Tx4X4, 16));
+    assert_eq!(residual, [1; 16]);
+    // 没有舍入，所有系数为 0 的块输出 0
+    assert!(inverse_transform_block(TxSize::Tx4X4, TxType::WhtWht, 10, &[0; 16]).iter().all(|&x| x == 0));
+}
@@ -0,0 +1,130 @@
+use wav1d::{
+    decoder::{tile::residual::compute_tx_type, ModeInfo},
+    utils::{
+        consts::{
+            MODE_TO_TXFM, TX_CLASS_2D, TX_CLASS_HORIZ, TX_CLASS_VERT, TX_SET_TYPES_INTER, TX_SET_TYPES_INTRA, TX_SIZES_ALL, TX_TYPES,
+            TX_TYPE_INTER_INV_SET1, TX_TYPE_INTER_INV_SET2, TX_TYPE_INTER_INV_SET3, TX_TYPE_INTRA_INV_SET1, TX_TYPE_INTRA_INV_SET2,
+        },
+        enums::{TxSize, TxType, UVMode},
+    },
+};
+
+
+
+// get_tx_set 按 TxSize 的顺序，4x4 8x8 16x16 32x32 64x64 4x8 8x4 8x16 16x8 16x32 32x16 32x64 64x32 4x16 16x4 8x32 32x8 16x64 64x16
+const INTRA_SETS: [u8; TX_SIZES_ALL] = [1, 1, 2, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0];
+const INTRA_REDUCED_SETS: [u8; TX_SIZES_ALL] = [2, 2, 2, 0, 0, 2, 2, 2, 2, 0, 0, 0, 0, 2, 2, 0, 0, 0, 0];
+const INTER_SETS: [u8; TX_SIZES_ALL] = [1, 1, 2, 3, 0, 1, 1, 1, 1, 3, 3, 0, 0, 1, 1, 3, 3, 0, 0];
+const INTER_REDUCED_SETS: [u8; TX_SIZES_ALL] = [3, 3, 3, 3, 0, 3, 3, 3, 3, 3, 3, 0, 0, 3, 3, 3, 3, 0, 0];
+
+fn all_tx_types() -> impl Iterator<Item = TxType> {
+    (0..TX_TYPES).map(TxType::from)
+}
+
+fn intra_info(uv_mode: UVMode) -> ModeInfo {
+    ModeInfo { uv_mode, ..Default::default() }
+}
+
+fn inter_info() -> ModeInfo {
+    ModeInfo { is_inter: true, ..Default::default() }
+}
+
+
+#[test]
+fn from_u8_and_tx_class() {
+    for value in 0..=16 {
+        assert_eq!(TxType::from(value) as u8, value);
+    }
+    assert_eq!(TxType::from(17), TxType::Unknown);
+    for tx_type in all_tx_types().chain([TxType::WhtWht]) {
+        let expected = match tx_type {
+            TxType::VDct | TxType::VAdst | TxType::VFlipadst => TX_CLASS_VERT,
+            TxType::HDct | TxType::HAdst | TxType::HFlipadst => TX_CLASS_HORIZ,
+            _ => TX_CLASS_2D,
+        };
+        assert_eq!(tx_type.tx_class(), expected, "{tx_type:?}");
+    }
+}
+
+#[test]
+fn tx_set_of_every_size() {
+    for tx_size in 0..TX_SIZES_ALL {
+        let size = TxSize::from(tx_size as u8);
+        assert_eq!(size.tx_set(false, false), INTRA_SETS[tx_size], "{size:?}");
+        assert_eq!(size.tx_set(false, true), INTRA_REDUCED_SETS[tx_size], "{size:?}");
+        assert_eq!(size.tx_set(true, false), INTER_SETS[tx_size], "{size:?}");
+        assert_eq!(size.tx_set(true, true), INTER_REDUCED_SETS[tx_size], "{size:?}");
+    }
+}
+
+#[test]
+fn sets_match_inverse_maps() {
+    let intra: [&[TxType]; TX_SET_TYPES_INTRA as usize] = [&[TxType::DctDct], &TX_TYPE_INTRA_INV_SET1, &TX_TYPE_INTRA_INV_SET2];
+    let inter: [&[TxType]; TX_SET_TYPES_INTER as usize] = [&[TxType::DctDct], &TX_TYPE_INTER_INV_SET1, &TX_TYPE_INTER_INV_SET2, &TX_TYPE_INTER_INV_SET3];
+    for (is_inter, sets) in [(false, &intra[..]), (true, &inter[..])] {
+        for (set, members) in sets.iter().enumerate() {
+            for tx_type in all_tx_types() {
+                assert_eq!(tx_type.is_in_set(set as u8, is_inter), members.contains(&tx_type), "set {set} inter {is_inter} {tx_type:?}");
+            }
+            assert!(!TxType::WhtWht.is_in_set(set as u8, is_inter));
+        }
+    }
+    assert_eq!(&TX_TYPE_INTRA_INV_SET1, &[TxType::Idtx, TxType::DctDct, TxType::VDct, TxType::HDct, TxType::AdstAdst, TxType::AdstDct, TxType::DctAdst]);
+    assert_eq!(&TX_TYPE_INTER_INV_SET3, &[TxType::Idtx, TxType::DctDct]);
+}
+
+#[test]
+fn lossless_and_64_point_transforms() {
+    let info = intra_info(UVMode::V);
+    assert_eq!(compute_tx_type(0, TxSize::Tx4X4, &info, true, false, TxType::Idtx), TxType::WhtWht);
+    assert_eq!(compute_tx_type(1, TxSize::Tx4X4, &inter_info(), true, false, TxType::DctDct), TxType::WhtWht);
+    for tx_size in [TxSize::Tx64X64, TxSize::Tx32X64, TxSize::Tx64X32, TxSize::Tx16X64, TxSize::Tx64X16] {
+        assert_eq!(compute_tx_type(0, tx_size, &info, false, false, TxType::AdstAdst), TxType::DctDct);
+    }
+    // 亮度直接使用读到的 TxTypes
+    assert_eq!(compute_tx_type(0, TxSize::Tx8X8, &inter_info(), false, false, TxType::HFlipadst), TxType::HFlipadst);
+}
+
+#[test]
+fn implicit_intra_chroma_types() {
+    let expected = [
+        (UVMode::Dc, TxType::DctDct),
+        (UVMode::V, TxType::AdstDct),
+        (UVMode::H, TxType::DctAdst),
+        (UVMode::D45, TxType::DctDct),
+        (UVMode::D135, TxType::AdstAdst),
+        (UVMode::D113, TxType::AdstDct),
+        (UVMode::D157, TxType::DctAdst),
+        (UVMode::D203, TxType::DctAdst),
+        (UVMode::D67, TxType::AdstDct),
+        (UVMode::Smooth, TxType::AdstAdst),
+        (UVMode::SmoothV, TxType::AdstDct),
+        (UVMode::SmoothH, TxType::DctAdst),
+        (UVMode::Paeth, TxType::AdstAdst),
+        (UVMode::UVCfl, TxType::DctDct),
+    ];
+    for (uv_mode, tx_type) in expected {
+        assert_eq!(MODE_TO_TXFM[uv_mode as usize], tx_type);
+        let info = intra_info(uv_mode);
+        // 色度不看亮度的 TxTypes
+        assert_eq!(compute_tx_type(1, TxSize::Tx8X8, &info, false, false, TxType::Idtx), tx_type);
+        assert_eq!(compute_tx_type(2, TxSize::Tx16X16, &info, false, true, TxType::Idtx), tx_type);
+        // TX_SET_DCTONLY
+        assert_eq!(compute_tx_type(1, TxSize::Tx32X32, &info, false, false, TxType::Idtx), TxType::DctDct);
+    }
+}
+
+#[test]
+fn inter_chroma_follows_luma_within_set() {
+    let info = inter_info();
+    for tx_type in all_tx_types() {
+        // 8x8 为 TX_SET_INTER_1，包含所有类型
+        assert_eq!(compute_tx_type(1, TxSize::Tx8X8, &info, false, false, tx_type), tx_type);
+        let in_set2 = (tx_type as u8) < TxType::VAdst as u8;
+        let expected = if in_set2 { tx_type } else { TxType::DctDct };
+        assert_eq!(compute_tx_type(1, TxSize::Tx16X16, &info, false, false, tx_type), expected);
+        // reduced_tx_set 只剩 TX_SET_INTER_3
+        let expected = if matches!(tx_type, TxType::Idtx | TxType::DctDct) { tx_type } else { TxType::DctDct };
+        assert_eq!(compute_tx_type(2, TxSize::Tx4X4, &info, false, true, tx_type), expected);
+    }
+}